@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+//the precomposed latin letters with accents (or other marks) on them, and the letters they're made from, in order, so they can be binary searched
+//these are what decomposing them (to unicode's nfd) and dropping the marks turns them into, letters like ø and ł don't decompose, so they're left alone
+const BASE_LETTERS: [(char, char); 497] = [
+    ('À', 'A'), ('Á', 'A'), ('Â', 'A'), ('Ã', 'A'), ('Ä', 'A'), ('Å', 'A'), ('Ç', 'C'), ('È', 'E'),
+    ('É', 'E'), ('Ê', 'E'), ('Ë', 'E'), ('Ì', 'I'), ('Í', 'I'), ('Î', 'I'), ('Ï', 'I'), ('Ñ', 'N'),
+    ('Ò', 'O'), ('Ó', 'O'), ('Ô', 'O'), ('Õ', 'O'), ('Ö', 'O'), ('Ù', 'U'), ('Ú', 'U'), ('Û', 'U'),
+    ('Ü', 'U'), ('Ý', 'Y'), ('à', 'a'), ('á', 'a'), ('â', 'a'), ('ã', 'a'), ('ä', 'a'), ('å', 'a'),
+    ('ç', 'c'), ('è', 'e'), ('é', 'e'), ('ê', 'e'), ('ë', 'e'), ('ì', 'i'), ('í', 'i'), ('î', 'i'),
+    ('ï', 'i'), ('ñ', 'n'), ('ò', 'o'), ('ó', 'o'), ('ô', 'o'), ('õ', 'o'), ('ö', 'o'), ('ù', 'u'),
+    ('ú', 'u'), ('û', 'u'), ('ü', 'u'), ('ý', 'y'), ('ÿ', 'y'), ('Ā', 'A'), ('ā', 'a'), ('Ă', 'A'),
+    ('ă', 'a'), ('Ą', 'A'), ('ą', 'a'), ('Ć', 'C'), ('ć', 'c'), ('Ĉ', 'C'), ('ĉ', 'c'), ('Ċ', 'C'),
+    ('ċ', 'c'), ('Č', 'C'), ('č', 'c'), ('Ď', 'D'), ('ď', 'd'), ('Ē', 'E'), ('ē', 'e'), ('Ĕ', 'E'),
+    ('ĕ', 'e'), ('Ė', 'E'), ('ė', 'e'), ('Ę', 'E'), ('ę', 'e'), ('Ě', 'E'), ('ě', 'e'), ('Ĝ', 'G'),
+    ('ĝ', 'g'), ('Ğ', 'G'), ('ğ', 'g'), ('Ġ', 'G'), ('ġ', 'g'), ('Ģ', 'G'), ('ģ', 'g'), ('Ĥ', 'H'),
+    ('ĥ', 'h'), ('Ĩ', 'I'), ('ĩ', 'i'), ('Ī', 'I'), ('ī', 'i'), ('Ĭ', 'I'), ('ĭ', 'i'), ('Į', 'I'),
+    ('į', 'i'), ('İ', 'I'), ('Ĵ', 'J'), ('ĵ', 'j'), ('Ķ', 'K'), ('ķ', 'k'), ('Ĺ', 'L'), ('ĺ', 'l'),
+    ('Ļ', 'L'), ('ļ', 'l'), ('Ľ', 'L'), ('ľ', 'l'), ('Ń', 'N'), ('ń', 'n'), ('Ņ', 'N'), ('ņ', 'n'),
+    ('Ň', 'N'), ('ň', 'n'), ('Ō', 'O'), ('ō', 'o'), ('Ŏ', 'O'), ('ŏ', 'o'), ('Ő', 'O'), ('ő', 'o'),
+    ('Ŕ', 'R'), ('ŕ', 'r'), ('Ŗ', 'R'), ('ŗ', 'r'), ('Ř', 'R'), ('ř', 'r'), ('Ś', 'S'), ('ś', 's'),
+    ('Ŝ', 'S'), ('ŝ', 's'), ('Ş', 'S'), ('ş', 's'), ('Š', 'S'), ('š', 's'), ('Ţ', 'T'), ('ţ', 't'),
+    ('Ť', 'T'), ('ť', 't'), ('Ũ', 'U'), ('ũ', 'u'), ('Ū', 'U'), ('ū', 'u'), ('Ŭ', 'U'), ('ŭ', 'u'),
+    ('Ů', 'U'), ('ů', 'u'), ('Ű', 'U'), ('ű', 'u'), ('Ų', 'U'), ('ų', 'u'), ('Ŵ', 'W'), ('ŵ', 'w'),
+    ('Ŷ', 'Y'), ('ŷ', 'y'), ('Ÿ', 'Y'), ('Ź', 'Z'), ('ź', 'z'), ('Ż', 'Z'), ('ż', 'z'), ('Ž', 'Z'),
+    ('ž', 'z'), ('Ơ', 'O'), ('ơ', 'o'), ('Ư', 'U'), ('ư', 'u'), ('Ǎ', 'A'), ('ǎ', 'a'), ('Ǐ', 'I'),
+    ('ǐ', 'i'), ('Ǒ', 'O'), ('ǒ', 'o'), ('Ǔ', 'U'), ('ǔ', 'u'), ('Ǖ', 'U'), ('ǖ', 'u'), ('Ǘ', 'U'),
+    ('ǘ', 'u'), ('Ǚ', 'U'), ('ǚ', 'u'), ('Ǜ', 'U'), ('ǜ', 'u'), ('Ǟ', 'A'), ('ǟ', 'a'), ('Ǡ', 'A'),
+    ('ǡ', 'a'), ('Ǣ', 'Æ'), ('ǣ', 'æ'), ('Ǧ', 'G'), ('ǧ', 'g'), ('Ǩ', 'K'), ('ǩ', 'k'), ('Ǫ', 'O'),
+    ('ǫ', 'o'), ('Ǭ', 'O'), ('ǭ', 'o'), ('Ǯ', 'Ʒ'), ('ǯ', 'ʒ'), ('ǰ', 'j'), ('Ǵ', 'G'), ('ǵ', 'g'),
+    ('Ǹ', 'N'), ('ǹ', 'n'), ('Ǻ', 'A'), ('ǻ', 'a'), ('Ǽ', 'Æ'), ('ǽ', 'æ'), ('Ǿ', 'Ø'), ('ǿ', 'ø'),
+    ('Ȁ', 'A'), ('ȁ', 'a'), ('Ȃ', 'A'), ('ȃ', 'a'), ('Ȅ', 'E'), ('ȅ', 'e'), ('Ȇ', 'E'), ('ȇ', 'e'),
+    ('Ȉ', 'I'), ('ȉ', 'i'), ('Ȋ', 'I'), ('ȋ', 'i'), ('Ȍ', 'O'), ('ȍ', 'o'), ('Ȏ', 'O'), ('ȏ', 'o'),
+    ('Ȑ', 'R'), ('ȑ', 'r'), ('Ȓ', 'R'), ('ȓ', 'r'), ('Ȕ', 'U'), ('ȕ', 'u'), ('Ȗ', 'U'), ('ȗ', 'u'),
+    ('Ș', 'S'), ('ș', 's'), ('Ț', 'T'), ('ț', 't'), ('Ȟ', 'H'), ('ȟ', 'h'), ('Ȧ', 'A'), ('ȧ', 'a'),
+    ('Ȩ', 'E'), ('ȩ', 'e'), ('Ȫ', 'O'), ('ȫ', 'o'), ('Ȭ', 'O'), ('ȭ', 'o'), ('Ȯ', 'O'), ('ȯ', 'o'),
+    ('Ȱ', 'O'), ('ȱ', 'o'), ('Ȳ', 'Y'), ('ȳ', 'y'), ('Ḁ', 'A'), ('ḁ', 'a'), ('Ḃ', 'B'), ('ḃ', 'b'),
+    ('Ḅ', 'B'), ('ḅ', 'b'), ('Ḇ', 'B'), ('ḇ', 'b'), ('Ḉ', 'C'), ('ḉ', 'c'), ('Ḋ', 'D'), ('ḋ', 'd'),
+    ('Ḍ', 'D'), ('ḍ', 'd'), ('Ḏ', 'D'), ('ḏ', 'd'), ('Ḑ', 'D'), ('ḑ', 'd'), ('Ḓ', 'D'), ('ḓ', 'd'),
+    ('Ḕ', 'E'), ('ḕ', 'e'), ('Ḗ', 'E'), ('ḗ', 'e'), ('Ḙ', 'E'), ('ḙ', 'e'), ('Ḛ', 'E'), ('ḛ', 'e'),
+    ('Ḝ', 'E'), ('ḝ', 'e'), ('Ḟ', 'F'), ('ḟ', 'f'), ('Ḡ', 'G'), ('ḡ', 'g'), ('Ḣ', 'H'), ('ḣ', 'h'),
+    ('Ḥ', 'H'), ('ḥ', 'h'), ('Ḧ', 'H'), ('ḧ', 'h'), ('Ḩ', 'H'), ('ḩ', 'h'), ('Ḫ', 'H'), ('ḫ', 'h'),
+    ('Ḭ', 'I'), ('ḭ', 'i'), ('Ḯ', 'I'), ('ḯ', 'i'), ('Ḱ', 'K'), ('ḱ', 'k'), ('Ḳ', 'K'), ('ḳ', 'k'),
+    ('Ḵ', 'K'), ('ḵ', 'k'), ('Ḷ', 'L'), ('ḷ', 'l'), ('Ḹ', 'L'), ('ḹ', 'l'), ('Ḻ', 'L'), ('ḻ', 'l'),
+    ('Ḽ', 'L'), ('ḽ', 'l'), ('Ḿ', 'M'), ('ḿ', 'm'), ('Ṁ', 'M'), ('ṁ', 'm'), ('Ṃ', 'M'), ('ṃ', 'm'),
+    ('Ṅ', 'N'), ('ṅ', 'n'), ('Ṇ', 'N'), ('ṇ', 'n'), ('Ṉ', 'N'), ('ṉ', 'n'), ('Ṋ', 'N'), ('ṋ', 'n'),
+    ('Ṍ', 'O'), ('ṍ', 'o'), ('Ṏ', 'O'), ('ṏ', 'o'), ('Ṑ', 'O'), ('ṑ', 'o'), ('Ṓ', 'O'), ('ṓ', 'o'),
+    ('Ṕ', 'P'), ('ṕ', 'p'), ('Ṗ', 'P'), ('ṗ', 'p'), ('Ṙ', 'R'), ('ṙ', 'r'), ('Ṛ', 'R'), ('ṛ', 'r'),
+    ('Ṝ', 'R'), ('ṝ', 'r'), ('Ṟ', 'R'), ('ṟ', 'r'), ('Ṡ', 'S'), ('ṡ', 's'), ('Ṣ', 'S'), ('ṣ', 's'),
+    ('Ṥ', 'S'), ('ṥ', 's'), ('Ṧ', 'S'), ('ṧ', 's'), ('Ṩ', 'S'), ('ṩ', 's'), ('Ṫ', 'T'), ('ṫ', 't'),
+    ('Ṭ', 'T'), ('ṭ', 't'), ('Ṯ', 'T'), ('ṯ', 't'), ('Ṱ', 'T'), ('ṱ', 't'), ('Ṳ', 'U'), ('ṳ', 'u'),
+    ('Ṵ', 'U'), ('ṵ', 'u'), ('Ṷ', 'U'), ('ṷ', 'u'), ('Ṹ', 'U'), ('ṹ', 'u'), ('Ṻ', 'U'), ('ṻ', 'u'),
+    ('Ṽ', 'V'), ('ṽ', 'v'), ('Ṿ', 'V'), ('ṿ', 'v'), ('Ẁ', 'W'), ('ẁ', 'w'), ('Ẃ', 'W'), ('ẃ', 'w'),
+    ('Ẅ', 'W'), ('ẅ', 'w'), ('Ẇ', 'W'), ('ẇ', 'w'), ('Ẉ', 'W'), ('ẉ', 'w'), ('Ẋ', 'X'), ('ẋ', 'x'),
+    ('Ẍ', 'X'), ('ẍ', 'x'), ('Ẏ', 'Y'), ('ẏ', 'y'), ('Ẑ', 'Z'), ('ẑ', 'z'), ('Ẓ', 'Z'), ('ẓ', 'z'),
+    ('Ẕ', 'Z'), ('ẕ', 'z'), ('ẖ', 'h'), ('ẗ', 't'), ('ẘ', 'w'), ('ẙ', 'y'), ('ẛ', 'ſ'), ('Ạ', 'A'),
+    ('ạ', 'a'), ('Ả', 'A'), ('ả', 'a'), ('Ấ', 'A'), ('ấ', 'a'), ('Ầ', 'A'), ('ầ', 'a'), ('Ẩ', 'A'),
+    ('ẩ', 'a'), ('Ẫ', 'A'), ('ẫ', 'a'), ('Ậ', 'A'), ('ậ', 'a'), ('Ắ', 'A'), ('ắ', 'a'), ('Ằ', 'A'),
+    ('ằ', 'a'), ('Ẳ', 'A'), ('ẳ', 'a'), ('Ẵ', 'A'), ('ẵ', 'a'), ('Ặ', 'A'), ('ặ', 'a'), ('Ẹ', 'E'),
+    ('ẹ', 'e'), ('Ẻ', 'E'), ('ẻ', 'e'), ('Ẽ', 'E'), ('ẽ', 'e'), ('Ế', 'E'), ('ế', 'e'), ('Ề', 'E'),
+    ('ề', 'e'), ('Ể', 'E'), ('ể', 'e'), ('Ễ', 'E'), ('ễ', 'e'), ('Ệ', 'E'), ('ệ', 'e'), ('Ỉ', 'I'),
+    ('ỉ', 'i'), ('Ị', 'I'), ('ị', 'i'), ('Ọ', 'O'), ('ọ', 'o'), ('Ỏ', 'O'), ('ỏ', 'o'), ('Ố', 'O'),
+    ('ố', 'o'), ('Ồ', 'O'), ('ồ', 'o'), ('Ổ', 'O'), ('ổ', 'o'), ('Ỗ', 'O'), ('ỗ', 'o'), ('Ộ', 'O'),
+    ('ộ', 'o'), ('Ớ', 'O'), ('ớ', 'o'), ('Ờ', 'O'), ('ờ', 'o'), ('Ở', 'O'), ('ở', 'o'), ('Ỡ', 'O'),
+    ('ỡ', 'o'), ('Ợ', 'O'), ('ợ', 'o'), ('Ụ', 'U'), ('ụ', 'u'), ('Ủ', 'U'), ('ủ', 'u'), ('Ứ', 'U'),
+    ('ứ', 'u'), ('Ừ', 'U'), ('ừ', 'u'), ('Ử', 'U'), ('ử', 'u'), ('Ữ', 'U'), ('ữ', 'u'), ('Ự', 'U'),
+    ('ự', 'u'), ('Ỳ', 'Y'), ('ỳ', 'y'), ('Ỵ', 'Y'), ('ỵ', 'y'), ('Ỷ', 'Y'), ('ỷ', 'y'), ('Ỹ', 'Y'),
+    ('ỹ', 'y'),
+];
+//the blocks of combining marks, which are what accents are once they're decomposed (like the U+0301 in e\u{301})
+const COMBINING_MARKS: [Range<char>; 5] = ['\u{300}'..'\u{370}', '\u{1AB0}'..'\u{1B00}', '\u{1DC0}'..'\u{1E00}', '\u{20D0}'..'\u{2100}', '\u{FE20}'..'\u{FE30}'];
+
+/**
+ * returns true if c is a combining mark, like an accent that's been decomposed from the letter it was on
+ */
+pub fn is_combining_mark(c: char) -> bool {
+    return COMBINING_MARKS.iter().any(|marks| marks.contains(&c));
+}
+
+/**
+ * returns the letter c is without its accents (like e for é), or c itself if it doesn't have any
+ */
+pub fn base_letter(c: char) -> char {
+    return match BASE_LETTERS.binary_search_by(|(letter, _)| letter.cmp(&c)) {
+        Ok(index) => BASE_LETTERS[index].1,
+        Err(_) => c,
+    };
+}
+
+/**
+ * returns text without its accents, whether they're on the letters (é) or after them (e\u{301}), so résumé and re\u{301}sume\u{301} are both resume
+ */
+pub fn strip_accents(text: &str) -> String {
+    return text.chars().filter(|c| !is_combining_mark(*c)).map(base_letter).collect();
+}
+
+/**
+ * same as strip_accents, along with where each byte of the result came from in text (the start of the character it was made from)
+ */
+pub fn strip_accents_with_origins(text: &str) -> (String, Vec<usize>) {
+    //DATA
+    let mut stripped = String::with_capacity(text.len());
+    let mut origins: Vec<usize> = Vec::with_capacity(text.len());
+
+    for (start, c) in text.char_indices().filter(|(_, c)| !is_combining_mark(*c)) {
+        stripped.push(base_letter(c));
+        origins.resize(stripped.len(), start);
+    }
+    return (stripped, origins);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base_letters_are_sorted_for_the_binary_search() {
+        assert!(BASE_LETTERS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn accented_letters_have_a_base_letter() {
+        assert_eq!(['é', 'É', 'ü', 'ñ', 'ç', 'ệ'].map(base_letter), ['e', 'E', 'u', 'n', 'c', 'e']);
+        //letters without accents, ones that don't decompose, and ones that aren't latin, are left as they are
+        assert_eq!(['e', 'Z', 'ø', 'ł', 'ß', 'я', '中'].map(base_letter), ['e', 'Z', 'ø', 'ł', 'ß', 'я', '中']);
+    }
+
+    #[test]
+    fn combining_marks_are_the_decomposed_accents() {
+        assert!(['\u{301}', '\u{308}', '\u{327}', '\u{36F}', '\u{1DC0}', '\u{20D7}', '\u{FE20}'].iter().all(|c| is_combining_mark(*c)));
+        assert!(!['e', '\u{2FF}', '\u{370}', '´', '¨'].iter().any(|c| is_combining_mark(*c)));
+    }
+
+    #[test]
+    fn precomposed_and_decomposed_accents_are_both_stripped() {
+        assert_eq!(strip_accents("résumé"), "resume");
+        assert_eq!(strip_accents("re\u{301}sume\u{301}"), "resume");
+        assert_eq!(strip_accents("Müller, Mu\u{308}ller"), "Muller, Muller");
+        //even more than one mark on a letter
+        assert_eq!(strip_accents("e\u{302}\u{301}ệ\u{301}"), "ee");
+        assert_eq!(strip_accents("plain"), "plain");
+    }
+
+    #[test]
+    fn origins_point_back_at_the_original_characters() {
+        //DATA
+        let (stripped, origins) = strip_accents_with_origins("ré\u{301}su");
+
+        //r is at 0, é at 1 (the mark after it is dropped), s at 5, u at 6
+        assert_eq!(stripped, "resu");
+        assert_eq!(origins, vec![0, 1, 5, 6]);
+        //a letter that strips to more than one byte keeps pointing at where it started
+        assert_eq!(strip_accents_with_origins("éя"), ("eя".to_string(), vec![0, 2, 2]));
+    }
+}
@@ -0,0 +1,216 @@
+use std::error::Error;  //allows for some better errors
+use std::io::{self, Write};
+
+use crate::options::{self, Hint, OptionSpec, OPTIONS};
+use crate::types::TypeTable;
+
+//the name of the command being completed
+const COMMAND: &str = "grep-directory";
+
+/**
+ * a shell that completion scripts can be written for
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+impl Shell {
+    pub fn from_name(name: &str) -> Result<Shell, Box<dyn Error>> {
+        return match name {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            other => Err(format!("Invalid value '{}' for '--completions', expected bash, zsh, or fish.", other).into()),
+        };
+    }
+}
+
+/**
+ * writes the completion script for shell to out, it completes every option, and the values of the ones that take files, directories, types, or one of a few choices
+ */
+pub fn write_completions<W: Write>(shell: Shell, out: &mut W) -> io::Result<()> {
+    return match shell {
+        Shell::Bash => write_bash(out),
+        Shell::Zsh => write_zsh(out),
+        Shell::Fish => write_fish(out),
+    };
+}
+
+/**
+ * returns the names of the built in file types, for completing --type and --type-not
+ */
+fn type_names() -> Vec<String> {
+    return TypeTable::new().iter().map(|(name, _)| name.to_string()).collect();
+}
+
+/**
+ * returns the name of the value spec has to be given, None for flags, and options whose value is optional (those are completed like flags)
+ */
+fn required_value(spec: &OptionSpec) -> Option<&'static str> {
+    return spec.value.filter(|value| !options::is_optional(value));
+}
+
+/**
+ * returns the ways spec can be written, short first
+ */
+fn spec_names(spec: &OptionSpec) -> Vec<&'static str> {
+    return spec.short.into_iter().chain([spec.long]).collect();
+}
+
+fn write_bash<W: Write>(out: &mut W) -> io::Result<()> {
+    //DATA
+    let function: String = format!("_{}", COMMAND.replace('-', "_"));
+    let all: Vec<&str> = OPTIONS.iter().flat_map(spec_names).collect();
+
+    writeln!(out, "# bash completion for {}, generated by {} --completions bash", COMMAND, COMMAND)?;
+    writeln!(out, "{}() {{", function)?;
+    writeln!(out, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\" prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"")?;
+    writeln!(out, "    case \"$prev\" in")?;
+    //the options that take a value complete the value, when there's something to suggest, and nothing when there isn't
+    for spec in OPTIONS.iter().filter(|spec| required_value(spec).is_some()) {
+        //DATA
+        let action: String = match spec.hint {
+            Hint::None => String::from("return"),
+            Hint::File | Hint::Path => String::from("COMPREPLY=($(compgen -f -- \"$cur\")); return"),
+            Hint::Dir => String::from("COMPREPLY=($(compgen -d -- \"$cur\")); return"),
+            Hint::Type => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return", type_names().join(" ")),
+            Hint::Choices(choices) => format!("COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return", choices.join(" ")),
+        };
+
+        writeln!(out, "        {}) {};;", spec_names(spec).join("|"), action)?;
+    }
+    writeln!(out, "    esac")?;
+    writeln!(out, "    if [[ \"$cur\" == -* ]]; then")?;
+    writeln!(out, "        COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))", all.join(" "))?;
+    writeln!(out, "    else")?;
+    writeln!(out, "        COMPREPLY=($(compgen -f -- \"$cur\"))")?;
+    writeln!(out, "    fi")?;
+    writeln!(out, "}}")?;
+    writeln!(out, "complete -o filenames -F {} {}", function, COMMAND)?;
+    return Ok(());
+}
+
+fn write_zsh<W: Write>(out: &mut W) -> io::Result<()> {
+    //DATA
+    //descriptions are in single quotes, and inside [...], so quotes and brackets have to be escaped
+    let description = |spec: &OptionSpec| spec.description[0].replace('\'', "'\\''").replace('[', "\\[").replace(']', "\\]");
+    //values are shown as N rather than <N>
+    let message = |name: &str| name.trim_matches(['<', '>', '.']).to_string();
+
+    writeln!(out, "#compdef {}", COMMAND)?;
+    writeln!(out, "# zsh completion for {}, generated by {} --completions zsh", COMMAND, COMMAND)?;
+    writeln!(out, "_arguments -s \\")?;
+    for spec in OPTIONS {
+        //DATA
+        let value: String = match (required_value(spec), spec.hint) {
+            (None, _) => String::new(),
+            (Some(name), Hint::None) => format!(":{}: ", message(name)),
+            (Some(name), Hint::File | Hint::Path) => format!(":{}:_files", message(name)),
+            (Some(name), Hint::Dir) => format!(":{}:_files -/", message(name)),
+            (Some(name), Hint::Type) => format!(":{}:({})", message(name), type_names().join(" ")),
+            (Some(name), Hint::Choices(choices)) => format!(":{}:({})", message(name), choices.join(" ")),
+        };
+
+        //short options take their value as the next argument (+), and long ones either that way or after an = (=)
+        if let Some(short) = spec.short {
+            writeln!(out, "    '{}{}[{}]{}' \\", short, if required_value(spec).is_some() {"+"} else {""}, description(spec), value)?;
+        }
+        writeln!(out, "    '{}{}[{}]{}' \\", spec.long, if required_value(spec).is_some() {"="} else {""}, description(spec), value)?;
+    }
+    writeln!(out, "    '*:path or query:_files'")?;
+    return Ok(());
+}
+
+fn write_fish<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(out, "# fish completion for {}, generated by {} --completions fish", COMMAND, COMMAND)?;
+    for spec in OPTIONS {
+        //DATA
+        let short: String = spec.short.map(|short| format!(" -s {}", &short[1..])).unwrap_or_default();
+        //-r means it takes a value, -F completes files for it, -f doesn't, and -a lists what it can be
+        let value: String = match (required_value(spec), spec.hint) {
+            (None, _) => String::new(),
+            (Some(_), Hint::None) => String::from(" -r -f"),
+            (Some(_), Hint::File | Hint::Path) => String::from(" -r -F"),
+            (Some(_), Hint::Dir) => String::from(" -r -f -a '(__fish_complete_directories)'"),
+            (Some(_), Hint::Type) => format!(" -r -f -a '{}'", type_names().join(" ")),
+            (Some(_), Hint::Choices(choices)) => format!(" -r -f -a '{}'", choices.join(" ")),
+        };
+
+        writeln!(out, "complete -c {}{} -l {}{} -d '{}'", COMMAND, short, &spec.long[2..], value, spec.description[0].replace('\\', "\\\\").replace('\'', "\\'"))?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * returns the completion script for shell
+     */
+    fn script(shell: Shell) -> String {
+        //DATA
+        let mut out: Vec<u8> = Vec::new();
+
+        write_completions(shell, &mut out).unwrap();
+        return String::from_utf8(out).unwrap();
+    }
+
+    #[test]
+    fn shells_are_picked_by_name() {
+        assert_eq!(Shell::from_name("bash").unwrap(), Shell::Bash);
+        assert_eq!(Shell::from_name("zsh").unwrap(), Shell::Zsh);
+        assert_eq!(Shell::from_name("fish").unwrap(), Shell::Fish);
+        assert_eq!(Shell::from_name("powershell").unwrap_err().to_string(), "Invalid value 'powershell' for '--completions', expected bash, zsh, or fish.");
+    }
+
+    #[test]
+    fn every_shell_completes_every_option() {
+        //DATA
+        let (bash, zsh, fish) = (script(Shell::Bash), script(Shell::Zsh), script(Shell::Fish));
+
+        for spec in OPTIONS {
+            assert!(bash.contains(&format!(" {} ", spec.long)) || bash.contains(&format!(" {}\"", spec.long)), "bash: {}", spec.long);
+            assert!(zsh.contains(&format!("'{}[", spec.long)) || zsh.contains(&format!("'{}=[", spec.long)), "zsh: {}", spec.long);
+            assert!(fish.contains(&format!(" -l {} ", &spec.long[2..])), "fish: {}", spec.long);
+            if let Some(short) = spec.short {
+                assert!(zsh.contains(&format!("'{}[", short)) || zsh.contains(&format!("'{}+[", short)), "zsh: {}", short);
+                assert!(fish.contains(&format!(" -s {} -l {} ", &short[1..], &spec.long[2..])), "fish: {}", short);
+            }
+        }
+    }
+
+    #[test]
+    fn types_and_choices_are_suggested_as_values() {
+        //DATA
+        let types: String = type_names().join(" ");
+
+        assert!(script(Shell::Bash).contains(&format!("-t|--type) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")); return;;", types)));
+        assert!(script(Shell::Zsh).contains(&format!(":TYPE:({})'", types)));
+        assert!(script(Shell::Fish).contains(" -l sort -r -f -a 'path mtime size none' "));
+    }
+
+    #[test]
+    fn files_and_directories_are_completed_where_they_go() {
+        assert!(script(Shell::Bash).contains("--relative-to) COMPREPLY=($(compgen -d -- \"$cur\")); return;;"));
+        assert!(script(Shell::Bash).contains("--output) COMPREPLY=($(compgen -f -- \"$cur\")); return;;"));
+        assert!(script(Shell::Zsh).contains(":_files -/'"));
+        assert!(script(Shell::Fish).contains(" -l output -r -F "));
+    }
+
+    #[test]
+    fn descriptions_are_quoted_for_each_shell() {
+        //DATA
+        let zsh: String = script(Shell::Zsh);
+        let fish: String = script(Shell::Fish);
+
+        //a description with a ' in it (--word-regexp's "can't"), and with a \ in it (--regex's example)
+        assert!(zsh.contains("can'\\''t touch"), "{}", zsh);
+        assert!(fish.contains("can\\'t touch"), "{}", fish);
+        assert!(fish.contains("\"fn\\\\s+\\\\w+\\\\(\""), "{}", fish);
+        //zsh descriptions are in [...], so brackets in them (--multiline's example) are escaped
+        assert!(zsh.contains("#\\[test\\]"), "{}", zsh);
+    }
+}
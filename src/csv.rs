@@ -0,0 +1,52 @@
+use std::borrow::Cow;
+
+/**
+ * returns text as a csv field, quoted (with any quotes in it doubled) if it has commas, quotes, or line breaks in it
+ */
+pub fn field(text: &str) -> Cow<'_, str> {
+    if !text.contains([',', '"', '\n', '\r']) {
+        return Cow::Borrowed(text);
+    }
+    return Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")));
+}
+
+/**
+ * returns the fields as a csv row, without the line ending
+ */
+pub fn row<S: AsRef<str>>(fields: &[S]) -> String {
+    return fields.iter().map(|text| field(text.as_ref())).collect::<Vec<Cow<str>>>().join(",");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_fields_are_left_alone() {
+        assert!(matches!(field("just text"), Cow::Borrowed("just text")));
+        assert_eq!(field(""), "");
+        assert_eq!(field(" spaced\ttabbed 'single' é "), " spaced\ttabbed 'single' é ");
+    }
+
+    #[test]
+    fn fields_with_commas_or_line_breaks_are_quoted() {
+        assert_eq!(field("a,b"), "\"a,b\"");
+        assert_eq!(field("a\nb"), "\"a\nb\"");
+        assert_eq!(field("a\r\nb"), "\"a\r\nb\"");
+        assert_eq!(field(","), "\",\"");
+    }
+
+    #[test]
+    fn quotes_are_doubled_inside_quotes() {
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("\""), "\"\"\"\"");
+        assert_eq!(field("\"\","), "\"\"\"\"\",\"");
+    }
+
+    #[test]
+    fn rows_join_the_fields_with_commas() {
+        assert_eq!(row(&["./a,b.txt", "1", "2", "\"x\",\ny"]), "\"./a,b.txt\",1,2,\"\"\"x\"\",\ny\"");
+        assert_eq!(row(&["", ""]), ",");
+        assert_eq!(row::<&str>(&[]), "");
+    }
+}
@@ -0,0 +1,259 @@
+use std::error::Error;  //allows for some better errors
+use std::io::{self, Read};
+
+//how much of the file is read at a time
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/**
+ * what text encoding files are read in, everything is decoded to utf-8 before it's searched
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Auto,       //utf-16 if the file starts with a utf-16 byte order mark, utf-8 otherwise
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,     //every byte is the unicode character with the same value
+}
+impl Encoding {
+    /**
+     * returns the encoding with this name, like the value of --encoding
+     */
+    pub fn from_name(name: &str) -> Result<Encoding, Box<dyn Error>> {
+        return match name.to_ascii_lowercase().replace('_', "-").as_str() {
+            "auto" => Ok(Encoding::Auto),
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Encoding::Utf16Be),
+            "latin1" | "latin-1" | "iso-8859-1" => Ok(Encoding::Latin1),
+            _ => Err(format!("Invalid encoding '{}', expected auto, utf-8, utf-16le, utf-16be, or latin1.", name).into()),
+        };
+    }
+
+    /**
+     * returns the encoding the start of a file says it's in, with the length of its byte order mark,
+     * or None if it doesn't start with one
+     */
+    fn sniff(start: &[u8]) -> Option<(Encoding, usize)> {
+        return match start {
+            [0xEF, 0xBB, 0xBF, ..] => Some((Encoding::Utf8, 3)),
+            [0xFF, 0xFE, ..] => Some((Encoding::Utf16Le, 2)),
+            [0xFE, 0xFF, ..] => Some((Encoding::Utf16Be, 2)),
+            _ => None,
+        };
+    }
+
+    /**
+     * returns how many bytes text takes up in this encoding, which turns offsets in decoded text back into offsets in the file
+     */
+    pub fn encoded_len(&self, text: &str) -> usize {
+        return match self {
+            Encoding::Auto | Encoding::Utf8 => text.len(),
+            Encoding::Utf16Le | Encoding::Utf16Be => text.encode_utf16().count() * 2,
+            Encoding::Latin1 => text.chars().count(),
+        };
+    }
+}
+
+/**
+ * returns the encoding a file that starts with start should be read in, when encoding was asked for,
+ * along with how many bytes of byte order mark to skip before decoding it
+ * auto goes with whatever the byte order mark says (utf-8 if there isn't one), an explicit encoding only skips a byte order mark of its own
+ */
+pub fn decode_prefix(start: &[u8], encoding: Encoding) -> (Encoding, usize) {
+    return match Encoding::sniff(start) {
+        Some((sniffed, length)) if encoding == Encoding::Auto || encoding == sniffed => (sniffed, length),
+        _ if encoding == Encoding::Auto => (Encoding::Utf8, 0),
+        _ => (encoding, 0),
+    };
+}
+
+/**
+ * reads text in some encoding from inner, and gives it back as utf-8
+ * a byte order mark at the start is skipped, even when the encoding was picked explicitly
+ * utf-8 is passed through as is, so invalid utf-8 is an error for whatever reads it as text, invalid utf-16 is an error here
+ */
+pub struct DecodingReader<R: Read> {
+    inner: R,
+    encoding: Encoding,
+    started: bool,          //whether the byte order mark has been looked for yet
+    bom_length: usize,      //how many bytes of byte order mark were skipped
+    eof: bool,              //whether inner has run out
+    raw: Vec<u8>,           //bytes read from inner that haven't been decoded yet
+    decoded: Vec<u8>,       //utf-8 that hasn't been read out yet
+    position: usize,        //how much of decoded has been read out
+}
+impl<R: Read> DecodingReader<R> {
+    pub fn new(inner: R, encoding: Encoding) -> DecodingReader<R> {
+        return DecodingReader { inner, encoding, started: false, bom_length: 0, eof: false, raw: Vec::new(), decoded: Vec::new(), position: 0 };
+    }
+
+    /**
+     * returns the encoding that's being read, which is only known for sure once something has been read
+     */
+    pub fn encoding(&self) -> Encoding {
+        return self.encoding;
+    }
+
+    /**
+     * returns how many bytes of byte order mark were skipped at the start, once something has been read
+     */
+    pub fn bom_length(&self) -> usize {
+        return self.bom_length;
+    }
+
+    /**
+     * reads another chunk from inner into raw, noting when it runs out
+     */
+    fn fill(&mut self) -> io::Result<()> {
+        //DATA
+        let mut chunk = [0u8; CHUNK_SIZE];
+
+        let bytes_read = loop {
+            match self.inner.read(&mut chunk) {
+                Ok(bytes_read) => break bytes_read,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        };
+        self.eof = bytes_read == 0;
+        self.raw.extend_from_slice(&chunk[..bytes_read]);
+        return Ok(());
+    }
+
+    /**
+     * decodes as much of raw as can be into decoded, leaving anything that's cut off for the next chunk
+     */
+    fn decode(&mut self) -> io::Result<()> {
+        //look for a byte order mark before decoding anything, it needs the first 3 bytes (or the whole file, if it's shorter)
+        if !self.started {
+            if self.raw.len() < 3 && !self.eof {
+                return Ok(());
+            }
+            self.started = true;
+            (self.encoding, self.bom_length) = decode_prefix(&self.raw, self.encoding);
+            self.raw.drain(..self.bom_length);
+        }
+
+        self.decoded.clear();
+        self.position = 0;
+        match self.encoding {
+            Encoding::Auto | Encoding::Utf8 => self.decoded.append(&mut self.raw),
+            Encoding::Latin1 => {
+                self.decoded = self.raw.drain(..).map(char::from).collect::<String>().into_bytes();
+            },
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                //DATA
+                let big_endian: bool = self.encoding == Encoding::Utf16Be;
+                let mut units: Vec<u16> = self.raw.chunks_exact(2)
+                    .map(|pair| if big_endian {u16::from_be_bytes([pair[0], pair[1]])} else {u16::from_le_bytes([pair[0], pair[1]])})
+                    .collect();
+                let mut used: usize = units.len() * 2;
+
+                //the other half of a surrogate pair might be in the next chunk
+                if !self.eof && units.last().is_some_and(|unit| (0xD800..0xDC00).contains(unit)) {
+                    units.pop();
+                    used -= 2;
+                }
+                if self.eof && used < self.raw.len() {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-16: it ends halfway through a character"));
+                }
+                let text: String = char::decode_utf16(units).collect::<Result<String, _>>()
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("stream did not contain valid UTF-16: unpaired surrogate {:#06x}", err.unpaired_surrogate())))?;
+                self.decoded = text.into_bytes();
+                self.raw.drain(..used);
+            },
+        }
+        return Ok(());
+    }
+}
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        //decode more once everything that's been decoded has been read out, until there's something to give back or nothing left
+        while self.position == self.decoded.len() {
+            if self.eof && self.raw.is_empty() {
+                return Ok(0);
+            }
+            if !self.eof {
+                self.fill()?;
+            }
+            self.decode()?;
+        }
+
+        //DATA
+        let length: usize = buf.len().min(self.decoded.len() - self.position);
+
+        buf[..length].copy_from_slice(&self.decoded[self.position..self.position + length]);
+        self.position += length;
+        return Ok(length);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_prefix_skips_a_bom_on_its_own() {
+        assert_eq!(decode_prefix(b"\xEF\xBB\xBF", Encoding::Auto), (Encoding::Utf8, 3));
+        assert_eq!(decode_prefix(b"\xFF\xFE", Encoding::Auto), (Encoding::Utf16Le, 2));
+        assert_eq!(decode_prefix(b"\xFE\xFF", Encoding::Auto), (Encoding::Utf16Be, 2));
+    }
+
+    #[test]
+    fn decode_prefix_skips_a_bom_before_content() {
+        assert_eq!(decode_prefix(b"\xEF\xBB\xBFfn main", Encoding::Auto), (Encoding::Utf8, 3));
+        assert_eq!(decode_prefix(b"\xFF\xFEf\x00n\x00", Encoding::Auto), (Encoding::Utf16Le, 2));
+        assert_eq!(decode_prefix(b"fn main", Encoding::Auto), (Encoding::Utf8, 0));
+        assert_eq!(decode_prefix(b"", Encoding::Auto), (Encoding::Utf8, 0));
+    }
+
+    #[test]
+    fn decode_prefix_leaves_things_that_only_look_like_a_bom() {
+        //the start of a utf-8 bom, but not all of it
+        assert_eq!(decode_prefix(b"\xEF\xBB", Encoding::Auto), (Encoding::Utf8, 0));
+        assert_eq!(decode_prefix(b"\xEF\xBBx", Encoding::Auto), (Encoding::Utf8, 0));
+        //a bom for some other encoding than the one asked for is content
+        assert_eq!(decode_prefix(b"\xFF\xFEab", Encoding::Latin1), (Encoding::Latin1, 0));
+        assert_eq!(decode_prefix(b"\xFE\xFFab", Encoding::Utf16Le), (Encoding::Utf16Le, 0));
+        assert_eq!(decode_prefix(b"\xEF\xBB\xBFab", Encoding::Utf16Be), (Encoding::Utf16Be, 0));
+    }
+
+    #[test]
+    fn decode_prefix_skips_the_bom_of_the_encoding_asked_for() {
+        assert_eq!(decode_prefix(b"\xEF\xBB\xBFab", Encoding::Utf8), (Encoding::Utf8, 3));
+        assert_eq!(decode_prefix(b"\xFF\xFEa\x00", Encoding::Utf16Le), (Encoding::Utf16Le, 2));
+    }
+
+    #[test]
+    fn decoding_skips_the_bom() {
+        //DATA
+        let mut decoder = DecodingReader::new(b"\xEF\xBB\xBFab\n".as_slice(), Encoding::Auto);
+        let mut decoded = String::new();
+
+        decoder.read_to_string(&mut decoded).unwrap();
+        assert_eq!((decoded.as_str(), decoder.bom_length()), ("ab\n", 3));
+    }
+
+    #[test]
+    fn encoded_len_counts_bytes_in_the_encoding() {
+        for (encoding, expected) in [(Encoding::Utf8, 9), (Encoding::Utf16Le, 12), (Encoding::Utf16Be, 12), (Encoding::Latin1, 5)] {
+            //'é' is 2 bytes in utf-8 and 1 in latin1, '😀' is 4 bytes in utf-8 and a surrogate pair in utf-16
+            assert_eq!(encoding.encoded_len(if encoding == Encoding::Latin1 {"aébcd"} else {"aé😀bc"}), expected, "{:?}", encoding);
+        }
+    }
+
+    #[test]
+    fn decoding_utf16_across_chunks() {
+        //DATA
+        //a surrogate pair that straddles the end of the first chunk
+        let text: String = "x".repeat(CHUNK_SIZE / 2 - 2) + "😀 needle";
+        let raw: Vec<u8> = [b"\xFF\xFE".to_vec(), text.encode_utf16().flat_map(u16::to_le_bytes).collect()].concat();
+        let mut decoder = DecodingReader::new(raw.as_slice(), Encoding::Auto);
+        let mut decoded = String::new();
+
+        decoder.read_to_string(&mut decoded).unwrap();
+        assert_eq!(decoded, text);
+        assert_eq!((decoder.encoding(), decoder.bom_length()), (Encoding::Utf16Le, 2));
+    }
+}
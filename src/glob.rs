@@ -0,0 +1,224 @@
+use std::error::Error;  //allows for some better errors
+
+/**
+ * a piece of a compiled glob pattern
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Literal(char),
+    AnyChar,                                    //?
+    AnyString,                                  //*
+    Class { negated: bool, ranges: Vec<(char,char)> }, //[...]
+}
+
+/**
+ * a compiled glob pattern, supporting `*` (any string), `?` (any one character),
+ * and `[...]` character classes (with ranges like `[a-z]`, and negation like `[!0-9]`)
+ * a `\` escapes the character after it
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Glob {
+    pattern: String,
+    tokens: Vec<Token>,
+}
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Glob, Box<dyn Error>> {
+        //DATA
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut chars = pattern.chars().peekable();
+
+        //compile the pattern into tokens
+        while let Some(c) = chars.next() {
+            match c {
+                '*' => {
+                    //consecutive stars mean the same thing as one
+                    if tokens.last() != Some(&Token::AnyString) {
+                        tokens.push(Token::AnyString);
+                    }
+                },
+                '?' => tokens.push(Token::AnyChar),
+                '\\' => match chars.next() {
+                    Some(escaped) => tokens.push(Token::Literal(escaped)),
+                    None => return Err(format!("Invalid glob '{}': nothing to escape after the trailing '\\'.", pattern).into()),
+                },
+                '[' => {
+                    //DATA
+                    let mut negated = false;
+                    let mut ranges: Vec<(char,char)> = Vec::new();
+                    let mut closed = false;
+
+                    if let Some('!' | '^') = chars.peek() {
+                        negated = true;
+                        chars.next();
+                    }
+                    //a ']' right at the start of the class is just a character in the class
+                    if let Some(']') = chars.peek() {
+                        ranges.push((']', ']'));
+                        chars.next();
+                    }
+                    while let Some(c) = chars.next() {
+                        match c {
+                            ']' => {
+                                closed = true;
+                                break;
+                            },
+                            '-' if !ranges.is_empty() && chars.peek().is_some_and(|next| *next != ']') => {
+                                let start = ranges.pop().map(|(start,_)| start).unwrap_or('-');
+                                let end = chars.next().unwrap_or('-');
+                                if end < start {
+                                    return Err(format!("Invalid glob '{}': range '{}-{}' is backwards.", pattern, start, end).into());
+                                }
+                                ranges.push((start, end));
+                            },
+                            c => ranges.push((c, c)),
+                        }
+                    }
+
+                    //error handling
+                    if !closed {
+                        return Err(format!("Invalid glob '{}': unclosed '['.", pattern).into());
+                    }
+                    tokens.push(Token::Class { negated, ranges });
+                },
+                c => tokens.push(Token::Literal(c)),
+            }
+        }
+
+        //return
+        return Ok(Glob { pattern: pattern.to_string(), tokens });
+    }
+
+    /**
+     * returns the pattern this glob was compiled from
+     */
+    pub fn as_str(&self) -> &str {
+        return &self.pattern;
+    }
+
+    /**
+     * returns true if the whole of text matches the pattern
+     */
+    pub fn matches(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        return Glob::matches_from(&self.tokens, &text);
+    }
+
+    fn matches_from(tokens: &[Token], text: &[char]) -> bool {
+        //DATA
+        let (mut t, mut c) = (0, 0);
+        //where to go back to if we get stuck (the last star, and how much of text it had eaten)
+        let mut backtrack: Option<(usize, usize)> = None;
+
+        //walk through the tokens and text together, backtracking to the last star when we get stuck
+        while c < text.len() {
+            match tokens.get(t) {
+                Some(Token::AnyString) => {
+                    backtrack = Some((t, c));
+                    t += 1;
+                    continue;
+                },
+                Some(token) if Glob::token_matches(token, text[c]) => {
+                    t += 1;
+                    c += 1;
+                    continue;
+                },
+                _ => {},
+            }
+            //we're stuck, let the last star eat one more character, or give up if there isn't one
+            match backtrack {
+                Some((star_t, star_c)) => {
+                    backtrack = Some((star_t, star_c + 1));
+                    t = star_t + 1;
+                    c = star_c + 1;
+                },
+                None => return false,
+            }
+        }
+
+        //any tokens left over have to be stars, which can match nothing
+        return tokens[t.min(tokens.len())..].iter().all(|token| *token == Token::AnyString);
+    }
+
+    fn token_matches(token: &Token, c: char) -> bool {
+        return match token {
+            Token::Literal(literal) => *literal == c,
+            Token::AnyChar => true,
+            Token::AnyString => true,
+            Token::Class { negated, ranges } => ranges.iter().any(|(start,end)| *start <= c && c <= *end) != *negated,
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * returns whether text matches pattern
+     */
+    fn matches(pattern: &str, text: &str) -> bool {
+        return Glob::new(pattern).unwrap().matches(text);
+    }
+
+    #[test]
+    fn literals_match_the_whole_text() {
+        assert!(matches("Makefile", "Makefile"));
+        assert!(!matches("Makefile", "Makefile.am"));
+        assert!(!matches("Makefile", "makefile"));
+        assert!(matches("", ""));
+        assert!(!matches("", "a"));
+    }
+
+    #[test]
+    fn star_matches_any_string() {
+        assert!(matches("*.rs", "lib.rs"));
+        assert!(matches("*.rs", ".rs"));
+        assert!(!matches("*.rs", "lib.rs.bak"));
+        assert!(matches("Makefile*", "Makefile"));
+        assert!(matches("Makefile*", "Makefile.in"));
+        assert!(matches("*.min.*", "app.min.js"));
+        assert!(matches("a*b*c", "aXXbYYbZZc"));
+        assert!(!matches("a*b*c", "aXXbYY"));
+        assert!(matches("**", ""));
+    }
+
+    #[test]
+    fn question_mark_matches_one_character() {
+        assert!(matches("?.txt", "a.txt"));
+        assert!(matches("?.txt", "é.txt"));
+        assert!(!matches("?.txt", ".txt"));
+        assert!(!matches("?.txt", "ab.txt"));
+    }
+
+    #[test]
+    fn classes_match_ranges_and_can_be_negated() {
+        assert!(matches("file[0-9].log", "file7.log"));
+        assert!(!matches("file[0-9].log", "fileX.log"));
+        assert!(matches("file[!0-9].log", "fileX.log"));
+        assert!(matches("file[^0-9].log", "fileX.log"));
+        assert!(!matches("file[!0-9].log", "file7.log"));
+        assert!(matches("[abc]", "b"));
+        assert!(matches("[]x]", "]"));
+        assert!(matches("[a-]", "-"));
+        assert!(matches("*.[ch]", "main.h"));
+    }
+
+    #[test]
+    fn backslash_escapes() {
+        assert!(matches("\\*", "*"));
+        assert!(!matches("\\*", "a"));
+        assert!(matches("what\\?", "what?"));
+    }
+
+    #[test]
+    fn invalid_globs_are_errors() {
+        assert_eq!(Glob::new("[abc").err().unwrap().to_string(), "Invalid glob '[abc': unclosed '['.");
+        assert_eq!(Glob::new("[z-a]").err().unwrap().to_string(), "Invalid glob '[z-a]': range 'z-a' is backwards.");
+        assert!(Glob::new("trailing\\").is_err());
+    }
+
+    #[test]
+    fn keeps_its_pattern() {
+        assert_eq!(Glob::new("*.rs").unwrap().as_str(), "*.rs");
+    }
+}
@@ -0,0 +1,244 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use crate::inflate::Inflate;
+
+//the flags in a gzip header, for the optional fields that come after it
+const FLAG_HEADER_CRC: u8 = 0x02;
+const FLAG_EXTRA: u8 = 0x04;
+const FLAG_NAME: u8 = 0x08;
+const FLAG_COMMENT: u8 = 0x10;
+
+//the crc-32 of every byte, so checksums can be worked out a byte at a time
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    //DATA
+    let mut table: [u32; 256] = [0; 256];
+    let mut byte: usize = 0;
+
+    while byte < 256 {
+        //DATA
+        let mut crc: u32 = byte as u32;
+        let mut bit: usize = 0;
+
+        while bit < 8 {
+            crc = if crc & 1 == 1 {0xEDB88320 ^ (crc >> 1)} else {crc >> 1};
+            bit += 1;
+        }
+        table[byte] = crc;
+        byte += 1;
+    }
+
+    //return
+    return table;
+}
+
+/**
+ * returns crc (the crc-32 of what came before bytes, 0 to start with) updated with bytes, the checksum gzip and zip use
+ */
+pub fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    //DATA
+    let mut crc: u32 = !crc;
+
+    for &byte in bytes {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+    }
+
+    //return
+    return !crc;
+}
+
+/**
+ * returns true if path is a gzip file, going by its name (.gz)
+ */
+pub fn is_gzip_path(path: &Path) -> bool {
+    return path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gz"));
+}
+
+/**
+ * reads a gzip file (rfc 1952) from inner, and gives back what it decompresses to
+ * files made of more than one gzip member, one after another (like ones that were appended to), decompress to all of them in order
+ * the checksum and length at the end of each member are checked, so a corrupt file is an error rather than garbage
+ */
+pub struct GzDecoder<R: Read> {
+    inflate: Inflate<BufReader<R>>,
+    in_member: bool,    //whether the current member's header has been read, and its trailer hasn't
+    members: usize,     //how many members have been read all the way through
+    crc: u32,           //the crc-32 of the current member so far
+    size: u32,          //how much the current member has decompressed to so far (mod 2^32, like the trailer)
+}
+impl<R: Read> GzDecoder<R> {
+    pub fn new(inner: R) -> GzDecoder<R> {
+        return GzDecoder { inflate: Inflate::new(BufReader::new(inner)), in_member: false, members: 0, crc: 0, size: 0 };
+    }
+
+    /**
+     * reads a member's header, leaving the reader at the start of its deflate data
+     */
+    fn read_header(&mut self) -> io::Result<()> {
+        //DATA
+        let inner: &mut BufReader<R> = self.inflate.get_mut();
+        let mut header: [u8; 10] = [0; 10];
+
+        inner.read_exact(&mut header).map_err(|_| invalid("it's too short to be gzip"))?;
+        if header[0..2] != [0x1F, 0x8B] {
+            return Err(invalid("it isn't gzip"));
+        }
+        if header[2] != 8 {
+            return Err(invalid("it uses a compression method other than deflate"));
+        }
+        if header[3] & FLAG_EXTRA != 0 {
+            //DATA
+            let mut length: [u8; 2] = [0; 2];
+
+            inner.read_exact(&mut length)?;
+            io::copy(&mut inner.take(u16::from_le_bytes(length) as u64), &mut io::sink())?;
+        }
+        //the name and comment end in a NUL
+        for flag in [FLAG_NAME, FLAG_COMMENT] {
+            if header[3] & flag != 0 {
+                inner.read_until(0, &mut Vec::new())?;
+            }
+        }
+        if header[3] & FLAG_HEADER_CRC != 0 {
+            inner.read_exact(&mut [0; 2])?;
+        }
+        return Ok(());
+    }
+
+    /**
+     * reads a member's trailer and checks it against what the member decompressed to
+     */
+    fn read_trailer(&mut self) -> io::Result<()> {
+        //DATA
+        let mut trailer: [u8; 8] = [0; 8];
+
+        self.inflate.get_mut().read_exact(&mut trailer).map_err(|_| invalid("it ends too soon"))?;
+        if u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]) != self.crc {
+            return Err(invalid("its checksum doesn't match"));
+        }
+        if u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]) != self.size {
+            return Err(invalid("its length doesn't match"));
+        }
+        return Ok(());
+    }
+}
+impl<R: Read> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            if !self.in_member {
+                //there's always at least one member, and another one after each one that isn't at the end of the file
+                if self.members > 0 {
+                    if self.inflate.get_mut().fill_buf()?.is_empty() {
+                        return Ok(0);
+                    }
+                    self.inflate.restart();
+                }
+                self.read_header()?;
+                (self.in_member, self.crc, self.size) = (true, 0, 0);
+            }
+
+            //DATA
+            let read: usize = self.inflate.read(buf)?;
+
+            if read > 0 {
+                self.crc = crc32(self.crc, &buf[..read]);
+                self.size = self.size.wrapping_add(read as u32);
+                return Ok(read);
+            }
+            self.read_trailer()?;
+            self.in_member = false;
+            self.members += 1;
+        }
+    }
+}
+
+/**
+ * returns the error for a file that isn't valid gzip
+ */
+fn invalid(problem: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, format!("corrupt gzip file: {}", problem));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //"first\nthe needle\nlast\n" gzipped
+    const GZIPPED: [u8; 40] = [
+        0x1F, 0x8B, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x4B, 0xCB, 0x2C, 0x2A, 0x2E, 0xE1, 0x2A, 0xC9, 0x48, 0x55,
+        0xC8, 0x4B, 0x4D, 0x4D, 0xC9, 0x49, 0xE5, 0xCA, 0x49, 0x04, 0xF2, 0x01, 0x3D, 0x14, 0x09, 0x59, 0x16, 0x00, 0x00, 0x00,
+    ];
+    //"named needle\n" gzipped, with the name a.txt in the header
+    const NAMED: [u8; 39] = [
+        0x1F, 0x8B, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x02, 0xFF, 0x61, 0x2E, 0x74, 0x78, 0x74, 0x00, 0xCB, 0x4B, 0xCC, 0x4D,
+        0x4D, 0x51, 0xC8, 0x4B, 0x4D, 0x4D, 0xC9, 0x49, 0xE5, 0x02, 0x00, 0x7E, 0x63, 0x55, 0xC3, 0x0D, 0x00, 0x00, 0x00,
+    ];
+
+    fn gunzip(gzipped: &[u8]) -> io::Result<String> {
+        return io::read_to_string(GzDecoder::new(gzipped));
+    }
+
+    #[test]
+    fn crc32_matches_the_check_value() {
+        assert_eq!(crc32(0, b"123456789"), 0xCBF43926);
+        assert_eq!(crc32(0, b""), 0);
+        //it can be worked out a piece at a time
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xCBF43926);
+    }
+
+    #[test]
+    fn gzip_paths_end_in_gz() {
+        assert!(is_gzip_path(Path::new("logs/app.log.gz")));
+        assert!(is_gzip_path(Path::new("APP.GZ")));
+        assert!(!is_gzip_path(Path::new("app.log")));
+        assert!(!is_gzip_path(Path::new("gz")));
+        assert!(!is_gzip_path(Path::new("app.tgz")));
+    }
+
+    #[test]
+    fn gzip_files_are_decompressed() {
+        assert_eq!(gunzip(&GZIPPED).unwrap(), "first\nthe needle\nlast\n");
+        assert_eq!(gunzip(&NAMED).unwrap(), "named needle\n");
+    }
+
+    #[test]
+    fn members_one_after_another_are_all_decompressed() {
+        assert_eq!(gunzip(&[&GZIPPED[..], &NAMED].concat()).unwrap(), "first\nthe needle\nlast\nnamed needle\n");
+    }
+
+    #[test]
+    fn corrupt_files_say_whats_wrong() {
+        //DATA
+        let mut bad_crc: [u8; 40] = GZIPPED;
+        bad_crc[32] ^= 1;
+        let mut bad_length: [u8; 40] = GZIPPED;
+        bad_length[36] ^= 1;
+        let mut bad_method: [u8; 40] = GZIPPED;
+        bad_method[2] = 7;
+
+        for (gzipped, problem) in [
+            (&b"plain text"[..], "it isn't gzip"),
+            (&GZIPPED[..5], "it's too short to be gzip"),
+            (&bad_method, "it uses a compression method other than deflate"),
+            (&bad_crc, "its checksum doesn't match"),
+            (&bad_length, "its length doesn't match"),
+            (&GZIPPED[..34], "it ends too soon"),
+        ] {
+            //DATA
+            let error: io::Error = gunzip(gzipped).unwrap_err();
+
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", problem);
+            assert_eq!(error.to_string(), format!("corrupt gzip file: {}", problem));
+        }
+    }
+
+    #[test]
+    fn garbage_after_a_member_is_an_error() {
+        assert!(gunzip(&[&GZIPPED[..], b"junk"].concat()).is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+//how many bytes are on each row of a dump, like xxd
+const ROW_SIZE: usize = 16;
+
+/**
+ * some bytes of a file, around one or more matches, for --binary-context
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct HexDump {
+    pub offset: u64,                //of the first byte, from the start of the file
+    pub bytes: Vec<u8>,
+    pub matches: Vec<Range<u64>>,   //the byte ranges of the matches in the dump, from the start of the file
+}
+impl HexDump {
+    /**
+     * returns the rows of the dump, like xxd's, "offset: hex bytes  printable bytes", with the matched bytes passed through highlight
+     * each row is ROW_SIZE bytes long, starting from the start of the dump, except the last, which is padded so its printable bytes line up
+     */
+    pub fn rows<F: Fn(&str) -> String>(&self, highlight: F) -> Vec<String> {
+        //DATA
+        let mut rows: Vec<String> = Vec::new();
+
+        for (index, row) in self.bytes.chunks(ROW_SIZE).enumerate() {
+            //DATA
+            let row_offset: u64 = self.offset + (index * ROW_SIZE) as u64;
+            let mut hex: String = String::new();
+            let mut printable: String = String::new();
+
+            for (at, byte) in row.iter().enumerate() {
+                //DATA
+                let offset: u64 = row_offset + at as u64;
+                let matched: bool = self.matches.iter().any(|range| range.contains(&offset));
+                let character: String = if byte.is_ascii_graphic() || *byte == b' ' {(*byte as char).to_string()} else {String::from(".")};
+                let digits: String = format!("{:02x}", byte);
+
+                //the bytes are in pairs, like "6865 6c6c"
+                if at > 0 && at % 2 == 0 {
+                    hex.push(' ');
+                }
+                hex.push_str(&if matched {highlight(&digits)} else {digits});
+                printable.push_str(&if matched {highlight(&character)} else {character});
+            }
+            //a short row is padded out to the width of a full one, so the printable bytes start in the same column
+            hex.push_str(&" ".repeat(row_width(ROW_SIZE) - row_width(row.len())));
+            rows.push(format!("{:08x}: {}  {}", row_offset, hex, printable));
+        }
+
+        //return
+        return rows;
+    }
+}
+
+/**
+ * returns how many characters the hex of a row of length bytes takes up, without any highlighting
+ */
+fn row_width(length: usize) -> usize {
+    return length * 2 + length.saturating_sub(1) / 2;
+}
+
+/**
+ * collects the dumps around matches, as the bytes they're in go by, without keeping any more of them than the dumps need
+ * matches close enough together for their context to touch or overlap share a dump
+ */
+pub struct HexCollector {
+    context: usize,             //how many bytes before and after each match go in its dump
+    recent: VecDeque<u8>,       //the last context bytes, and the chunk being looked at, which the next dump could start in
+    recent_start: u64,          //the offset of the first byte of recent
+    current: Option<HexDump>,   //the dump that's still being filled in, and could have more matches added to it
+    current_end: u64,           //where the current dump ends, once it's filled in
+    dumps: Vec<HexDump>,        //the dumps that are done
+}
+impl HexCollector {
+    pub fn new(context: usize) -> HexCollector {
+        return HexCollector { context, recent: VecDeque::new(), recent_start: 0, current: None, current_end: 0, dumps: Vec::new() };
+    }
+
+    /**
+     * adds the next chunk of bytes, and the byte ranges of the matches in it (from the start of the file), which have to be in order
+     */
+    pub fn push(&mut self, chunk: &[u8], matches: &[Range<u64>]) {
+        self.recent.extend(chunk);
+        for range in matches {
+            //DATA
+            let start: u64 = range.start.saturating_sub(self.context as u64).max(self.recent_start);
+            let end: u64 = range.end + self.context as u64;
+
+            match &mut self.current {
+                Some(dump) if start <= self.current_end => {
+                    dump.matches.push(range.clone());
+                    self.current_end = self.current_end.max(end);
+                },
+                _ => {
+                    self.fill();
+                    self.dumps.extend(self.current.take());
+                    self.current = Some(HexDump { offset: start, bytes: Vec::new(), matches: vec![range.clone()] });
+                    self.current_end = end;
+                },
+            }
+        }
+        self.fill();
+
+        //only the last context bytes can be the start of a dump now
+        let excess: usize = self.recent.len().saturating_sub(self.context);
+        self.recent.drain(..excess);
+        self.recent_start += excess as u64;
+    }
+
+    /**
+     * returns true if the current dump still needs bytes that haven't been pushed yet
+     */
+    pub fn wants_more(&self) -> bool {
+        return self.current.as_ref().is_some_and(|dump| dump.offset + (dump.bytes.len() as u64) < self.current_end);
+    }
+
+    /**
+     * copies what the current dump still needs of recent into it
+     */
+    fn fill(&mut self) {
+        if let Some(dump) = &mut self.current {
+            //DATA
+            //it's always filled up to the end of recent, or all the way, so the bytes it needs are never ones that are already gone
+            let from: u64 = (dump.offset + dump.bytes.len() as u64).max(self.recent_start);
+            let until: u64 = self.current_end.min(self.recent_start + self.recent.len() as u64).max(from);
+
+            dump.bytes.extend(self.recent.range((from - self.recent_start) as usize..(until - self.recent_start) as usize));
+        }
+    }
+
+    /**
+     * returns all of the dumps, once there aren't any more bytes
+     */
+    pub fn finish(mut self) -> Vec<HexDump> {
+        self.fill();
+        self.dumps.extend(self.current.take());
+        return self.dumps;
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)] //the matches are lists of ranges, and plenty of them only have one
+mod tests {
+    use super::*;
+
+    /**
+     * returns the dumps of context bytes around matches in bytes, pushing them chunk_size bytes at a time
+     */
+    fn collect(bytes: &[u8], matches: &[Range<u64>], context: usize, chunk_size: usize) -> Vec<HexDump> {
+        //DATA
+        let mut collector = HexCollector::new(context);
+
+        for (index, chunk) in bytes.chunks(chunk_size).enumerate() {
+            //DATA
+            let chunk_range: Range<u64> = (index * chunk_size) as u64..(index * chunk_size + chunk.len()) as u64;
+            //a match goes with the chunk it starts in
+            let in_chunk: Vec<Range<u64>> = matches.iter().filter(|range| chunk_range.contains(&range.start)).cloned().collect();
+
+            collector.push(chunk, &in_chunk);
+        }
+        return collector.finish();
+    }
+
+    #[test]
+    fn rows_look_like_xxd() {
+        //DATA
+        let dump = HexDump { offset: 0x1c, bytes: b"\x1c\x1d\x1e\x1fneedle\xff\xff\xff\xff".to_vec(), matches: vec![0x20..0x26] };
+
+        assert_eq!(dump.rows(|text| text.to_string()), vec!["0000001c: 1c1d 1e1f 6e65 6564 6c65 ffff ffff       ....needle...."]);
+        assert_eq!(dump.rows(|text| format!("[{}]", text)), vec!["0000001c: 1c1d 1e1f [6e][65] [65][64] [6c][65] ffff ffff       ....[n][e][e][d][l][e]...."]);
+    }
+
+    #[test]
+    fn rows_are_16_bytes_from_the_start_of_the_dump() {
+        //DATA
+        let dump = HexDump { offset: 5, bytes: (b'a'..=b'z').collect(), matches: Vec::new() };
+
+        assert_eq!(dump.rows(|text| text.to_string()), vec![
+            "00000005: 6162 6364 6566 6768 696a 6b6c 6d6e 6f70  abcdefghijklmnop",
+            "00000015: 7172 7374 7576 7778 797a                 qrstuvwxyz",
+        ]);
+        //an odd number of bytes still lines up
+        assert_eq!(HexDump { offset: 0, bytes: b"a b".to_vec(), matches: Vec::new() }.rows(|text| text.to_string()), vec!["00000000: 6120 62                                  a b"]);
+        assert_eq!(HexDump::default().rows(|text| text.to_string()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn each_match_gets_its_context() {
+        //DATA
+        let bytes: Vec<u8> = (0..100).collect();
+
+        assert_eq!(collect(&bytes, &[40..42], 3, 100), vec![HexDump { offset: 37, bytes: (37..45).collect(), matches: vec![40..42] }]);
+        //the context stops at the start and the end of the file
+        assert_eq!(collect(&bytes, &[1..2, 98..99], 3, 100), vec![
+            HexDump { offset: 0, bytes: (0..5).collect(), matches: vec![1..2] },
+            HexDump { offset: 95, bytes: (95..100).collect(), matches: vec![98..99] },
+        ]);
+    }
+
+    #[test]
+    fn matches_whose_context_touches_share_a_dump() {
+        //DATA
+        let bytes: Vec<u8> = (0..100).collect();
+
+        //10 bytes apart, with 5 bytes of context each, they touch, with 4 they don't
+        assert_eq!(collect(&bytes, &[20..22, 32..34], 5, 100), vec![HexDump { offset: 15, bytes: (15..39).collect(), matches: vec![20..22, 32..34] }]);
+        assert_eq!(collect(&bytes, &[20..22, 32..34], 4, 100).len(), 2);
+        //even when the first one's context reaches past the second's
+        assert_eq!(collect(&bytes, &[20..30, 22..24], 2, 100), vec![HexDump { offset: 18, bytes: (18..32).collect(), matches: vec![20..30, 22..24] }]);
+    }
+
+    #[test]
+    fn dumps_dont_depend_on_how_the_bytes_are_chunked() {
+        //DATA
+        let bytes: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let matches: [Range<u64>; 4] = [5..9, 300..302, 310..320, 990..995];
+        let whole: Vec<HexDump> = collect(&bytes, &matches, 8, bytes.len());
+
+        assert_eq!(whole.len(), 3);
+        assert_eq!(whole[1], HexDump { offset: 292, bytes: bytes[292..328].to_vec(), matches: vec![300..302, 310..320] });
+        for chunk_size in [1, 3, 7, 16, 301] {
+            assert_eq!(collect(&bytes, &matches, 8, chunk_size), whole, "chunks of {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn only_the_context_is_kept_while_there_isnt_a_dump() {
+        //DATA
+        let mut collector = HexCollector::new(4);
+
+        collector.push(&[0; 1000], &[]);
+        assert_eq!((collector.recent.len(), collector.recent_start), (4, 996));
+        collector.push(b"needle", &[1000..1006]);
+        assert!(collector.wants_more());
+        collector.push(b"abcd", &[]);
+        assert!(!collector.wants_more());
+        assert_eq!(collector.finish(), vec![HexDump { offset: 996, bytes: b"\0\0\0\0needleabcd".to_vec(), matches: vec![1000..1006] }]);
+    }
+}
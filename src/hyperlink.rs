@@ -0,0 +1,139 @@
+use std::path::Path;
+
+use crate::winpath;
+
+/**
+ * returns text wrapped in an OSC 8 escape sequence, so terminals that support it make it a link to url (the rest just print text)
+ */
+pub fn wrap(text: &str, url: &str) -> String {
+    return format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text);
+}
+
+/**
+ * returns the file:// url of path, made absolute first (relative to the current directory), or None if it can't be
+ */
+pub fn file_url(path: &Path) -> Option<String> {
+    //DATA
+    let absolute = std::path::absolute(path).ok()?;
+
+    if cfg!(windows) {
+        return windows_file_url(&absolute.to_string_lossy());
+    }
+    return unix_file_url(absolute.as_os_str().as_encoded_bytes());
+}
+
+/**
+ * returns the file:// url of an absolute unix path, given as its bytes (which don't have to be valid utf-8), like file:///home/me/notes%20v2.txt
+ */
+pub fn unix_file_url(path: &[u8]) -> Option<String> {
+    if path.first() != Some(&b'/') {
+        return None;
+    }
+    return Some(format!("file://{}", percent_encode(path)));
+}
+
+/**
+ * returns the file:// url of an absolute windows path, like file:///C:/Users/me/notes.txt for C:\Users\me\notes.txt,
+ * or file://server/share/notes.txt for the network share \\server\share\notes.txt
+ * verbatim paths (\\?\C:\...) are made regular first, urls can't say they're verbatim
+ */
+pub fn windows_file_url(path: &str) -> Option<String> {
+    //DATA
+    let path: String = winpath::strip_verbatim(path).replace('\\', "/");
+    let bytes: &[u8] = path.as_bytes();
+
+    if let Some(share) = path.strip_prefix("//").filter(|share| !share.starts_with('?')) {
+        return Some(format!("file://{}", percent_encode(share.as_bytes())));
+    }
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'/' {
+        //the colon after the drive letter is left as it is, that's how file urls write drives
+        return Some(format!("file:///{}:{}", bytes[0] as char, percent_encode(&bytes[2..])));
+    }
+    return None;
+}
+
+/**
+ * returns the bytes with everything but letters, digits, -._~ and / percent-encoded (spaces, non-ascii, and anything with a meaning in urls)
+ */
+fn percent_encode(bytes: &[u8]) -> String {
+    //DATA
+    let mut encoded = String::with_capacity(bytes.len());
+
+    for &byte in bytes {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    //return
+    return encoded;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapping_puts_the_url_in_an_osc_8_sequence() {
+        assert_eq!(wrap("a.txt", "file:///a.txt"), "\x1b]8;;file:///a.txt\x1b\\a.txt\x1b]8;;\x1b\\");
+    }
+
+    #[test]
+    fn unix_paths_are_percent_encoded() {
+        assert_eq!(unix_file_url(b"/home/me/notes.txt").unwrap(), "file:///home/me/notes.txt");
+        assert_eq!(unix_file_url(b"/home/me/notes v2.txt").unwrap(), "file:///home/me/notes%20v2.txt");
+        assert_eq!(unix_file_url("/tmp/résumé.txt".as_bytes()).unwrap(), "file:///tmp/r%C3%A9sum%C3%A9.txt");
+        assert_eq!(unix_file_url(b"/a#b?c%d&e.txt").unwrap(), "file:///a%23b%3Fc%25d%26e.txt");
+        assert_eq!(unix_file_url(b"/keep-these._~").unwrap(), "file:///keep-these._~");
+    }
+
+    #[test]
+    fn unix_paths_dont_have_to_be_utf8() {
+        assert_eq!(unix_file_url(b"/bad\xff.txt").unwrap(), "file:///bad%FF.txt");
+    }
+
+    #[test]
+    fn relative_unix_paths_have_no_url() {
+        assert_eq!(unix_file_url(b"notes.txt"), None);
+        assert_eq!(unix_file_url(b""), None);
+    }
+
+    #[test]
+    fn windows_drives_keep_their_colon() {
+        assert_eq!(windows_file_url(r"C:\Users\me\notes.txt").unwrap(), "file:///C:/Users/me/notes.txt");
+        assert_eq!(windows_file_url(r"d:\my notes\é.txt").unwrap(), "file:///d:/my%20notes/%C3%A9.txt");
+        assert_eq!(windows_file_url("C:/mixed\\seps.txt").unwrap(), "file:///C:/mixed/seps.txt");
+    }
+
+    #[test]
+    fn windows_shares_use_the_server_as_the_host() {
+        assert_eq!(windows_file_url(r"\\server\share\notes.txt").unwrap(), "file://server/share/notes.txt");
+    }
+
+    #[test]
+    fn windows_verbatim_paths_are_made_regular() {
+        assert_eq!(windows_file_url(r"\\?\C:\Users\notes.txt").unwrap(), "file:///C:/Users/notes.txt");
+        assert_eq!(windows_file_url(r"\\?\UNC\server\share\notes.txt").unwrap(), "file://server/share/notes.txt");
+        //too long to be regular, and there's no way to say verbatim in a url
+        assert_eq!(windows_file_url(&format!(r"\\?\C:\{}", "d".repeat(winpath::MAX_PATH))), None);
+    }
+
+    #[test]
+    fn relative_windows_paths_have_no_url() {
+        for path in [r"notes.txt", r"dir\notes.txt", r"\notes.txt", "C:notes.txt"] {
+            assert_eq!(windows_file_url(path), None, "{}", path);
+        }
+    }
+
+    #[test]
+    fn file_urls_are_absolute() {
+        //DATA
+        let url: String = file_url(Path::new("notes v2.txt")).unwrap();
+
+        assert!(url.starts_with("file://"), "{}", url);
+        assert!(url.ends_with("/notes%20v2.txt"), "{}", url);
+        assert!(!url.contains("/./"), "{}", url);
+    }
+}
@@ -0,0 +1,342 @@
+use std::error::Error;  //allows for some better errors
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::glob::Glob;
+
+/**
+ * a piece of an ignore pattern, between slashes
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Glob(Glob),     //matches one file or directory name
+    AnyDirs,        //** (any number of directories, including none)
+}
+
+/**
+ * one pattern (line) of an ignore file
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pattern: String,        //the line it came from
+    negated: bool,          //a pattern starting with a !, re-includes what an earlier pattern ignored
+    dir_only: bool,         //pattern/, only matches directories
+    segments: Vec<Segment>, //matched against the path relative to the ignore file's directory
+}
+impl Rule {
+    /**
+     * parses a line of an ignore file, returning None for blank lines, comments, and patterns that aren't valid
+     */
+    fn parse(line: &str) -> Option<Rule> {
+        //DATA
+        let mut line: &str = line;
+        let mut negated: bool = false;
+        let mut dir_only: bool = false;
+
+        //trailing spaces don't count unless they're escaped
+        while line.ends_with(' ') && !line.ends_with("\\ ") {
+            line = &line[..line.len() - 1];
+        }
+        let pattern: String = line.to_string();
+        //#'s start comments, and !'s negate, unless they're escaped
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        if let Some(rest) = line.strip_prefix('!') {
+            negated = true;
+            line = rest;
+        }
+        //a trailing slash means only directories match, it isn't part of the path
+        if let Some(rest) = line.strip_suffix('/') {
+            dir_only = true;
+            line = rest;
+        }
+        //a slash anywhere else anchors the pattern to the ignore file's directory, without one it matches a name at any depth
+        let anchored: bool = line.contains('/');
+        line = line.strip_prefix('/').unwrap_or(line);
+        if line.is_empty() {
+            return None;
+        }
+
+        //DATA
+        let mut segments: Vec<Segment> = if anchored {Vec::new()} else {vec![Segment::AnyDirs]};
+        for segment in line.split('/') {
+            segments.push(match segment {
+                "**" => Segment::AnyDirs,
+                segment => Segment::Glob(Glob::new(segment).ok()?),
+            });
+        }
+
+        //return
+        return Some(Rule { pattern, negated, dir_only, segments });
+    }
+
+    /**
+     * returns the line of the ignore file this rule came from
+     */
+    pub fn as_str(&self) -> &str {
+        return &self.pattern;
+    }
+
+    /**
+     * returns true if this rule re-includes what it matches, instead of ignoring it
+     */
+    pub fn is_negated(&self) -> bool {
+        return self.negated;
+    }
+
+    /**
+     * returns true if this rule matches the path made of components (relative to the ignore file's directory)
+     */
+    fn matches(&self, components: &[&str], is_dir: bool) -> bool {
+        return (is_dir || !self.dir_only) && Rule::matches_from(&self.segments, components);
+    }
+
+    fn matches_from(segments: &[Segment], components: &[&str]) -> bool {
+        return match segments.split_first() {
+            None => components.is_empty(),
+            //** can eat any number of components, try them all
+            Some((Segment::AnyDirs, rest)) => (0..=components.len()).any(|skip| Rule::matches_from(rest, &components[skip..])),
+            Some((Segment::Glob(glob), rest)) => components.split_first().is_some_and(|(first, components)| glob.matches(first) && Rule::matches_from(rest, components)),
+        };
+    }
+}
+
+/**
+ * the patterns of an ignore file (gitignore syntax), which apply to the paths under the directory it's for
+ * when more than one pattern matches a path, the last one wins
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gitignore {
+    root: PathBuf,      //the directory the patterns are relative to
+    source: PathBuf,    //the file the patterns came from
+    rules: Vec<Rule>,
+}
+impl Gitignore {
+    /**
+     * reads the ignore file at path, its patterns are relative to root
+     */
+    pub fn from_file(path: &Path, root: &Path) -> Result<Gitignore, Box<dyn Error>> {
+        return Ok(Gitignore::new(&fs::read_to_string(path)?, path, root));
+    }
+
+    /**
+     * parses the contents of an ignore file, source is where they came from, and the patterns are relative to root
+     * lines that aren't valid patterns are skipped, like git does
+     */
+    pub fn new(contents: &str, source: &Path, root: &Path) -> Gitignore {
+        return Gitignore {
+            root: root.to_path_buf(),
+            source: source.to_path_buf(),
+            rules: contents.lines().filter_map(|line| Rule::parse(line.trim_end_matches('\r'))).collect(),
+        };
+    }
+
+    /**
+     * returns the file the patterns came from
+     */
+    pub fn source(&self) -> &Path {
+        return &self.source;
+    }
+
+    /**
+     * returns the last rule that matches path (which should be under root), or None if none of them do (or it isn't under root)
+     */
+    pub fn matched(&self, path: &Path, is_dir: bool) -> Option<&Rule> {
+        //DATA
+        let relative: &Path = path.strip_prefix(&self.root).ok()?;
+        let components: Vec<String> = relative.components().map(|component| component.as_os_str().to_string_lossy().to_string()).collect();
+        let components: Vec<&str> = components.iter().map(String::as_str).collect();
+
+        return self.rules.iter().rev().find(|rule| rule.matches(&components, is_dir));
+    }
+}
+
+/**
+ * returns the ignore file and rule that decide whether path is ignored, or None if nothing matches it
+ * ignores are in order of precedence, the later ones (like ones in deeper directories) win over the earlier ones
+ * the path is only ignored if the rule isn't negated
+ */
+pub fn ignored_by<'a>(ignores: &'a [Gitignore], path: &Path, is_dir: bool) -> Option<(&'a Gitignore, &'a Rule)> {
+    return ignores.iter().rev().find_map(|ignore| ignore.matched(path, is_dir).map(|rule| (ignore, rule)));
+}
+
+/**
+ * the ignore files that apply while walking a directory tree:
+ * the .gitignore files of the directories the walk is in (deeper ones win over the ones above them),
+ * then the extra ignore files, which win over all of those, the later ones winning over the earlier ones
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoreStack {
+    gitignores: Vec<Gitignore>,
+    extra: Vec<Gitignore>,
+}
+impl IgnoreStack {
+    pub fn new(extra: Vec<Gitignore>) -> IgnoreStack {
+        return IgnoreStack { gitignores: Vec::new(), extra };
+    }
+
+    /**
+     * adds the .gitignore of a directory the walk is going into
+     */
+    pub fn push(&mut self, gitignore: Gitignore) {
+        self.gitignores.push(gitignore);
+    }
+
+    /**
+     * removes the .gitignore that was added last, once the walk leaves its directory
+     */
+    pub fn pop(&mut self) -> Option<Gitignore> {
+        return self.gitignores.pop();
+    }
+
+    /**
+     * returns the .gitignore that was added last
+     */
+    pub fn last(&self) -> Option<&Gitignore> {
+        return self.gitignores.last();
+    }
+
+    /**
+     * returns the ignore file and rule that decide whether path is ignored, or None if nothing matches it
+     */
+    pub fn ignored_by(&self, path: &Path, is_dir: bool) -> Option<(&Gitignore, &Rule)> {
+        return ignored_by(&self.extra, path, is_dir).or_else(|| ignored_by(&self.gitignores, path, is_dir));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * returns the gitignore with contents, in the directory /r
+     */
+    fn gitignore(contents: &str) -> Gitignore {
+        return Gitignore::new(contents, Path::new("/r/.gitignore"), Path::new("/r"));
+    }
+
+    /**
+     * returns true if the gitignore with contents ignores path (relative to its directory)
+     */
+    fn ignores(contents: &str, path: &str, is_dir: bool) -> bool {
+        return gitignore(contents).matched(&Path::new("/r").join(path), is_dir).is_some_and(|rule| !rule.is_negated());
+    }
+
+    #[test]
+    fn blank_lines_and_comments_are_skipped() {
+        //DATA
+        let gitignore: Gitignore = gitignore("\n   \n# a comment\n#*.rs\n*.log\n");
+
+        assert_eq!(gitignore.rules.len(), 1);
+        assert_eq!(gitignore.rules[0].as_str(), "*.log");
+        assert!(!ignores("#*.rs", "#a.rs", false));
+    }
+
+    #[test]
+    fn escapes_keep_their_meaning() {
+        assert!(ignores("\\#notes", "#notes", false));
+        assert!(ignores("\\!important", "!important", false));
+        assert!(!ignores("\\!important", "important", false));
+        //trailing spaces are dropped, unless they're escaped
+        assert!(ignores("a.txt   ", "a.txt", false));
+        assert!(ignores("a\\ ", "a ", false));
+        assert!(!ignores("a\\ ", "a", false));
+    }
+
+    #[test]
+    fn crlf_line_endings_are_ignored() {
+        assert!(ignores("*.log\r\nbuild/\r\n", "x.log", false));
+        assert!(ignores("*.log\r\nbuild/\r\n", "build", true));
+    }
+
+    #[test]
+    fn names_without_slashes_match_at_any_depth() {
+        assert!(ignores("*.log", "a.log", false));
+        assert!(ignores("*.log", "deep/down/a.log", false));
+        assert!(ignores("target", "target", true));
+        assert!(ignores("target", "sub/target", false));
+        assert!(!ignores("*.log", "a.log.txt", false));
+    }
+
+    #[test]
+    fn a_leading_slash_anchors_to_the_directory() {
+        assert!(ignores("/target", "target", true));
+        assert!(!ignores("/target", "sub/target", true));
+        assert!(ignores("/*.log", "a.log", false));
+        assert!(!ignores("/*.log", "sub/a.log", false));
+    }
+
+    #[test]
+    fn a_slash_in_the_middle_anchors_too() {
+        assert!(ignores("doc/*.txt", "doc/a.txt", false));
+        assert!(!ignores("doc/*.txt", "sub/doc/a.txt", false));
+        //* doesn't cross slashes
+        assert!(!ignores("doc/*.txt", "doc/more/a.txt", false));
+    }
+
+    #[test]
+    fn a_trailing_slash_only_matches_directories() {
+        assert!(ignores("build/", "build", true));
+        assert!(ignores("build/", "sub/build", true));
+        assert!(!ignores("build/", "build", false));
+    }
+
+    #[test]
+    fn double_stars_match_any_number_of_directories() {
+        //at the start
+        assert!(ignores("**/foo", "foo", false));
+        assert!(ignores("**/foo", "a/b/foo", false));
+        //in the middle, including none
+        assert!(ignores("a/**/b", "a/b", false));
+        assert!(ignores("a/**/b", "a/x/y/b", false));
+        assert!(!ignores("a/**/b", "x/a/b", false));
+        //at the end, everything inside
+        assert!(ignores("a/**", "a/x", false));
+        assert!(ignores("a/**", "a/x/y", false));
+    }
+
+    #[test]
+    fn negation_re_includes_and_the_last_match_wins() {
+        assert!(!ignores("*.log\n!keep.log", "keep.log", false));
+        assert!(ignores("*.log\n!keep.log", "other.log", false));
+        //a later pattern wins over an earlier negation
+        assert!(ignores("!keep.log\n*.log", "keep.log", false));
+        assert_eq!(gitignore("*.log\n!keep.log").matched(Path::new("/r/keep.log"), false).map(Rule::as_str), Some("!keep.log"));
+    }
+
+    #[test]
+    fn glob_syntax_works_in_patterns() {
+        assert!(ignores("file?.txt", "file1.txt", false));
+        assert!(!ignores("file?.txt", "file10.txt", false));
+        assert!(ignores("[ab].txt", "b.txt", false));
+        assert!(!ignores("[ab].txt", "c.txt", false));
+    }
+
+    #[test]
+    fn invalid_patterns_are_skipped() {
+        //DATA
+        let gitignore: Gitignore = gitignore("[unclosed\n/\n!\n*.log");
+
+        assert_eq!(gitignore.rules.iter().map(Rule::as_str).collect::<Vec<&str>>(), vec!["*.log"]);
+    }
+
+    #[test]
+    fn paths_outside_the_directory_dont_match() {
+        assert_eq!(gitignore("*.log").matched(Path::new("/other/a.log"), false), None);
+    }
+
+    #[test]
+    fn deeper_gitignores_win_over_the_ones_above() {
+        //DATA
+        let mut stack = IgnoreStack::new(Vec::new());
+
+        stack.push(gitignore("*.log"));
+        stack.push(Gitignore::new("!keep.log", Path::new("/r/sub/.gitignore"), Path::new("/r/sub")));
+        assert!(stack.ignored_by(Path::new("/r/sub/keep.log"), false).is_some_and(|(_, rule)| rule.is_negated()));
+        assert!(stack.ignored_by(Path::new("/r/keep.log"), false).is_some_and(|(_, rule)| !rule.is_negated()));
+        stack.pop();
+        assert_eq!(stack.last().map(Gitignore::source), Some(Path::new("/r/.gitignore")));
+        assert!(stack.ignored_by(Path::new("/r/sub/keep.log"), false).is_some_and(|(_, rule)| !rule.is_negated()));
+    }
+}
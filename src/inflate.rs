@@ -0,0 +1,461 @@
+use std::io::{self, BufRead, Read};
+
+//how far back a match can reach, so how much of the output has to be kept around
+const WINDOW_SIZE: usize = 32 * 1024;
+//the lengths length codes 257 to 285 start at, and how many extra bits they have
+const LENGTH_BASES: [u16; 29] = [3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258];
+const LENGTH_EXTRA_BITS: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+//the same, for distance codes 0 to 29
+const DISTANCE_BASES: [u16; 30] = [1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13];
+//the order the lengths of the code length code are stored in, in a dynamic block
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/**
+ * returns the error for deflate data that doesn't make sense
+ */
+fn invalid(problem: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, format!("corrupt compressed data: {}", problem));
+}
+
+/**
+ * a canonical huffman code, as how many codes there are of each length, and the symbols in code order
+ */
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+impl Huffman {
+    /**
+     * builds the code where symbol i has a code lengths[i] bits long (0 means it isn't used)
+     */
+    fn new(lengths: &[u8]) -> io::Result<Huffman> {
+        //DATA
+        let mut counts: [u16; 16] = [0; 16];
+        let mut offsets: [u16; 16] = [0; 16];
+        let mut symbols: Vec<u16> = vec![0; lengths.len()];
+        let mut left: i32 = 1; //how many codes of the current length are still free
+
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        //more codes than there's room for can't be decoded, too few is fine (a block can use just one distance)
+        for count in &counts[1..] {
+            left = left * 2 - *count as i32;
+            if left < 0 {
+                return Err(invalid("a huffman code has too many codes"));
+            }
+        }
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        for (symbol, &length) in lengths.iter().enumerate().filter(|(_, length)| **length != 0) {
+            symbols[offsets[length as usize] as usize] = symbol as u16;
+            offsets[length as usize] += 1;
+        }
+
+        //return
+        return Ok(Huffman { counts, symbols });
+    }
+
+    /**
+     * returns the fixed codes for literals and lengths, and for distances, that blocks of type 1 use
+     */
+    fn fixed() -> (Huffman, Huffman) {
+        //DATA
+        let mut literals: [u8; 288] = [8; 288];
+
+        literals[144..256].fill(9);
+        literals[256..280].fill(7);
+        //these lengths are valid, so building them can't fail
+        return (Huffman::new(&literals).unwrap(), Huffman::new(&[5; 30]).unwrap());
+    }
+}
+
+/**
+ * reads the bits of deflate data, least significant first, a byte at a time, so nothing past the end of the data is read
+ */
+struct BitReader<R: BufRead> {
+    inner: R,
+    bits: u32,      //bits that have been read, but not used yet
+    count: u32,     //how many of them there are
+}
+impl<R: BufRead> BitReader<R> {
+    fn byte(&mut self) -> io::Result<u8> {
+        //DATA
+        let mut byte: [u8; 1] = [0];
+
+        if self.inner.read(&mut byte)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "corrupt compressed data: it ends too soon"));
+        }
+        return Ok(byte[0]);
+    }
+
+    /**
+     * returns the next count bits (up to 16), as a number
+     */
+    fn bits(&mut self, count: u32) -> io::Result<u32> {
+        while self.count < count {
+            self.bits |= (self.byte()? as u32) << self.count;
+            self.count += 8;
+        }
+
+        //DATA
+        let value: u32 = self.bits & ((1 << count) - 1);
+
+        self.bits >>= count;
+        self.count -= count;
+        return Ok(value);
+    }
+
+    /**
+     * skips to the start of the next byte, stored blocks start on one
+     */
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+
+    /**
+     * returns the next symbol, decoded with code, a bit at a time
+     */
+    fn decode(&mut self, code: &Huffman) -> io::Result<u16> {
+        //DATA
+        let mut value: i32 = 0;    //the bits read so far
+        let mut first: i32 = 0;    //the first code of the current length
+        let mut index: i32 = 0;    //where the codes of the current length start in symbols
+
+        for length in 1..16 {
+            //DATA
+            let count: i32 = code.counts[length] as i32;
+
+            value |= self.bits(1)? as i32;
+            if value - first < count {
+                return Ok(code.symbols[(index + value - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            value <<= 1;
+        }
+        return Err(invalid("a code that isn't in the huffman code"));
+    }
+}
+
+/**
+ * where decoding a deflate stream is up to
+ */
+enum State {
+    Header,         //the next thing is a block header
+    Stored(usize),  //in a stored block, with this many bytes left
+    Codes,          //in a block of huffman codes
+    Done,           //the last block has ended
+}
+
+/**
+ * reads raw deflate data (rfc 1951) from inner, and gives back what it decompresses to, as it goes
+ * nothing after the end of the data is read from inner, so whatever comes after it (like a gzip trailer) can still be read from get_mut
+ */
+pub struct Inflate<R: BufRead> {
+    input: BitReader<R>,
+    state: State,
+    last: bool,                 //whether the current block is the last one
+    literals: Huffman,          //the codes of the current block
+    distances: Huffman,
+    window: Vec<u8>,            //the last WINDOW_SIZE bytes of output, that matches copy from
+    written: usize,             //how much has been output, matches can't reach back before the start
+    copy_length: usize,         //how much of the current match is left to copy
+    copy_distance: usize,
+}
+impl<R: BufRead> Inflate<R> {
+    pub fn new(inner: R) -> Inflate<R> {
+        //DATA
+        let (literals, distances) = Huffman::fixed();
+
+        return Inflate { input: BitReader { inner, bits: 0, count: 0 }, state: State::Header, last: false, literals, distances, window: vec![0; WINDOW_SIZE], written: 0, copy_length: 0, copy_distance: 0 };
+    }
+
+    /**
+     * returns the reader the deflate data is read from
+     */
+    pub fn get_mut(&mut self) -> &mut R {
+        return &mut self.input.inner;
+    }
+
+    /**
+     * starts decoding a new deflate stream from where the last one ended
+     */
+    pub fn restart(&mut self) {
+        self.input.align();
+        self.state = State::Header;
+        self.last = false;
+        self.written = 0;
+        self.copy_length = 0;
+    }
+
+    /**
+     * reads the lengths of the codes of a dynamic block, and builds them
+     */
+    fn read_dynamic_codes(&mut self) -> io::Result<(Huffman, Huffman)> {
+        //DATA
+        let literal_count: usize = self.input.bits(5)? as usize + 257;
+        let distance_count: usize = self.input.bits(5)? as usize + 1;
+        let code_length_count: usize = self.input.bits(4)? as usize + 4;
+        let mut code_lengths: [u8; 19] = [0; 19];
+        let mut lengths: Vec<u8> = Vec::with_capacity(literal_count + distance_count);
+
+        for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[symbol] = self.input.bits(3)? as u8;
+        }
+        let code_length_code: Huffman = Huffman::new(&code_lengths)?;
+        //the lengths of both codes are run length encoded together
+        while lengths.len() < literal_count + distance_count {
+            //DATA
+            let (length, repeat): (u8, u32) = match self.input.decode(&code_length_code)? {
+                symbol @ 0..=15 => (symbol as u8, 1),
+                16 => match lengths.last() {
+                    Some(&previous) => (previous, 3 + self.input.bits(2)?),
+                    None => return Err(invalid("a repeated code length with nothing before it")),
+                },
+                17 => (0, 3 + self.input.bits(3)?),
+                _ => (0, 11 + self.input.bits(7)?),
+            };
+
+            if lengths.len() + repeat as usize > literal_count + distance_count {
+                return Err(invalid("too many code lengths"));
+            }
+            lengths.extend(std::iter::repeat_n(length, repeat as usize));
+        }
+        if lengths[256] == 0 {
+            return Err(invalid("a block without an end"));
+        }
+
+        //return
+        return Ok((Huffman::new(&lengths[..literal_count])?, Huffman::new(&lengths[literal_count..])?));
+    }
+
+    /**
+     * adds byte to the output, so later matches can copy it
+     */
+    fn output(&mut self, byte: u8) {
+        self.window[self.written % WINDOW_SIZE] = byte;
+        self.written += 1;
+    }
+}
+impl<R: BufRead> Read for Inflate<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        //DATA
+        let mut filled: usize = 0;
+
+        while filled < buf.len() {
+            //the rest of a match comes before anything else
+            if self.copy_length > 0 {
+                //DATA
+                let byte: u8 = self.window[(self.written - self.copy_distance) % WINDOW_SIZE];
+
+                self.output(byte);
+                buf[filled] = byte;
+                filled += 1;
+                self.copy_length -= 1;
+                continue;
+            }
+            match self.state {
+                State::Done => break,
+                State::Header if self.last => self.state = State::Done,
+                State::Header => {
+                    self.last = self.input.bits(1)? == 1;
+                    match self.input.bits(2)? {
+                        0 => {
+                            self.input.align();
+                            //DATA
+                            let length: u32 = self.input.bits(16)?;
+
+                            if self.input.bits(16)? != !length & 0xFFFF {
+                                return Err(invalid("a stored block's length doesn't match its check"));
+                            }
+                            self.state = State::Stored(length as usize);
+                        },
+                        1 => {
+                            (self.literals, self.distances) = Huffman::fixed();
+                            self.state = State::Codes;
+                        },
+                        2 => {
+                            (self.literals, self.distances) = self.read_dynamic_codes()?;
+                            self.state = State::Codes;
+                        },
+                        _ => return Err(invalid("a block of an unknown type")),
+                    }
+                },
+                State::Stored(0) => self.state = State::Header,
+                State::Stored(left) => {
+                    //DATA
+                    let byte: u8 = self.input.byte()?;
+
+                    self.output(byte);
+                    buf[filled] = byte;
+                    filled += 1;
+                    self.state = State::Stored(left - 1);
+                },
+                State::Codes => match self.input.decode(&self.literals)? {
+                    literal @ 0..=255 => {
+                        self.output(literal as u8);
+                        buf[filled] = literal as u8;
+                        filled += 1;
+                    },
+                    256 => self.state = State::Header,
+                    symbol => {
+                        //DATA
+                        let length_code: usize = symbol as usize - 257;
+                        if length_code >= LENGTH_BASES.len() {
+                            return Err(invalid("a length code that doesn't exist"));
+                        }
+                        let length: usize = LENGTH_BASES[length_code] as usize + self.input.bits(LENGTH_EXTRA_BITS[length_code] as u32)? as usize;
+                        let distance_code: usize = self.input.decode(&self.distances)? as usize;
+                        if distance_code >= DISTANCE_BASES.len() {
+                            return Err(invalid("a distance code that doesn't exist"));
+                        }
+                        let distance: usize = DISTANCE_BASES[distance_code] as usize + self.input.bits(DISTANCE_EXTRA_BITS[distance_code] as u32)? as usize;
+
+                        if distance > self.written {
+                            return Err(invalid("a match that reaches back before the start"));
+                        }
+                        self.copy_length = length;
+                        self.copy_distance = distance;
+                    },
+                },
+            }
+        }
+
+        //return
+        return Ok(filled);
+    }
+}
+
+/**
+ * reads from inner, but fails once more than limit bytes have come out of it, so a small archive can't decompress to something huge
+ */
+pub struct Bounded<R: Read> {
+    inner: R,
+    limit: u64,
+    read: u64,      //how much has been read so far
+}
+impl<R: Read> Bounded<R> {
+    pub fn new(inner: R, limit: u64) -> Bounded<R> {
+        return Bounded { inner, limit, read: 0 };
+    }
+}
+impl<R: Read> Read for Bounded<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        //DATA
+        let read: usize = self.inner.read(buf)?;
+
+        self.read += read as u64;
+        if self.read > self.limit {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("it decompresses to more than {} bytes", self.limit)));
+        }
+        return Ok(read);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //"hello hello hello\n", compressed as a stored block, and with the fixed codes
+    const STORED: [u8; 23] = [0x01, 0x12, 0x00, 0xED, 0xFF, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x20, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x20, 0x68, 0x65, 0x6C, 0x6C, 0x6F, 0x0A];
+    const FIXED: [u8; 11] = [0xCB, 0x48, 0xCD, 0xC9, 0xC9, 0x57, 0xC8, 0x40, 0x90, 0x5C, 0x00];
+    //dynamic_text(), compressed with codes of its own
+    const DYNAMIC: [u8; 92] = [
+        0xED, 0xD1, 0xBB, 0x0D, 0xC0, 0x20, 0x10, 0x04, 0xD1, 0xDC, 0x55, 0x6C, 0x01, 0x0E, 0xFC, 0xEF, 0x07, 0x89, 0x45, 0x20, 0x9D, 0xC1, 0x02, 0x24,
+        0xDA, 0xB7, 0xDD, 0xC2, 0xC5, 0x97, 0xCF, 0x8B, 0x46, 0x52, 0x26, 0x16, 0x94, 0x80, 0x1E, 0x89, 0x4C, 0x7A, 0x21, 0x42, 0x12, 0xCE, 0x18, 0xA9,
+        0x47, 0xB4, 0x72, 0x13, 0xA3, 0x54, 0xDF, 0xBE, 0xC0, 0x75, 0x54, 0x3E, 0x74, 0x7D, 0x92, 0x9F, 0xAD, 0x3A, 0xB6, 0xE9, 0xD8, 0xAE, 0x63, 0x87,
+        0x8E, 0x9D, 0x3A, 0x76, 0xE9, 0x98, 0x0D, 0xB0, 0x01, 0x36, 0xC0, 0x06, 0xD8, 0x00, 0x1B, 0x60, 0x03, 0x7E, 0xF6, 0x02,
+    ];
+
+    fn dynamic_text() -> String {
+        return (0..40).map(|line| format!("line {} of the needle file, with some words that repeat\n", line % 7)).collect();
+    }
+
+    /**
+     * returns what compressed decompresses to, read through a buffer of buffer_size bytes at a time
+     */
+    fn inflate(compressed: &[u8], buffer_size: usize) -> io::Result<Vec<u8>> {
+        //DATA
+        let mut inflate = Inflate::new(compressed);
+        let mut out: Vec<u8> = Vec::new();
+        let mut buffer: Vec<u8> = vec![0; buffer_size];
+
+        loop {
+            match inflate.read(&mut buffer)? {
+                0 => return Ok(out),
+                read => out.extend_from_slice(&buffer[..read]),
+            }
+        }
+    }
+
+    #[test]
+    fn stored_blocks_are_copied() {
+        assert_eq!(inflate(&STORED, 4096).unwrap(), b"hello hello hello\n");
+    }
+
+    #[test]
+    fn fixed_codes_are_decoded() {
+        assert_eq!(inflate(&FIXED, 4096).unwrap(), b"hello hello hello\n");
+    }
+
+    #[test]
+    fn dynamic_codes_are_decoded() {
+        assert_eq!(inflate(&DYNAMIC, 4096).unwrap(), dynamic_text().as_bytes());
+    }
+
+    #[test]
+    fn output_can_be_read_a_little_at_a_time() {
+        //matches get split up across reads
+        for buffer_size in [1, 3, 7] {
+            assert_eq!(inflate(&DYNAMIC, buffer_size).unwrap(), dynamic_text().as_bytes(), "{}", buffer_size);
+            assert_eq!(inflate(&FIXED, buffer_size).unwrap(), b"hello hello hello\n", "{}", buffer_size);
+        }
+    }
+
+    #[test]
+    fn nothing_after_the_data_is_read() {
+        //DATA
+        let mut input: Vec<u8> = FIXED.to_vec();
+        input.extend_from_slice(b"trailer");
+        let mut inflate = Inflate::new(&input[..]);
+        let mut out: Vec<u8> = Vec::new();
+
+        inflate.read_to_end(&mut out).unwrap();
+        assert_eq!(out, b"hello hello hello\n");
+        assert_eq!(inflate.get_mut().fill_buf().unwrap(), b"trailer");
+    }
+
+    #[test]
+    fn corrupt_data_is_an_error() {
+        //DATA
+        //a block type of 3 doesn't exist, and a stored block's length has to match its complement
+        let mut bad_length: [u8; 23] = STORED;
+        bad_length[3] = 0;
+
+        for compressed in [&[0x07][..], &bad_length] {
+            assert_eq!(inflate(compressed, 4096).unwrap_err().kind(), io::ErrorKind::InvalidData, "{:?}", compressed);
+        }
+    }
+
+    #[test]
+    fn data_that_stops_too_soon_is_an_error() {
+        for compressed in [&DYNAMIC[..40], &FIXED[..5], &STORED[..10], &[]] {
+            assert_eq!(inflate(compressed, 4096).unwrap_err().kind(), io::ErrorKind::UnexpectedEof, "{:?}", compressed);
+        }
+    }
+
+    #[test]
+    fn bounded_fails_past_the_limit() {
+        //DATA
+        let mut exact = Bounded::new(&b"12345"[..], 5);
+        let mut over = Bounded::new(&b"123456"[..], 5);
+
+        assert_eq!(io::read_to_string(&mut exact).unwrap(), "12345");
+        assert_eq!(io::read_to_string(&mut over).unwrap_err().to_string(), "it decompresses to more than 5 bytes");
+    }
+}
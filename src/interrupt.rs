@@ -0,0 +1,56 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+//set when the user presses Ctrl-C, the search checks it and stops early
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+//the exit code for being interrupted, 128 + SIGINT, like shells use
+pub const EXIT_CODE: i32 = 130;
+
+/**
+ * returns true once the user has pressed Ctrl-C (after install was called)
+ */
+pub fn interrupted() -> bool {
+    return INTERRUPTED.load(Ordering::Relaxed);
+}
+
+/**
+ * makes Ctrl-C stop the search early (so what was found so far, and the stats, are still printed) instead of killing the process
+ * pressing it a second time still kills the process right away, in case something is stuck (like reading stdin)
+ */
+#[cfg(unix)]
+pub fn install() {
+    //SIGINT is 2 everywhere that matters
+    const SIGINT: i32 = 2;
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        fn _exit(status: i32) -> !;
+    }
+    extern "C" fn on_interrupt(_: i32) {
+        //only atomics and _exit are safe to use in a signal handler
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            unsafe { _exit(EXIT_CODE) };
+        }
+    }
+    unsafe { signal(SIGINT, on_interrupt) };
+}
+#[cfg(windows)]
+pub fn install() {
+    //CTRL_C_EVENT, CTRL_BREAK_EVENT is left alone so it still kills the process
+    const CTRL_C_EVENT: u32 = 0;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: Option<extern "system" fn(u32) -> i32>, add: i32) -> i32;
+        fn ExitProcess(code: u32) -> !;
+    }
+    extern "system" fn on_interrupt(event: u32) -> i32 {
+        if event != CTRL_C_EVENT {
+            return 0;
+        }
+        if INTERRUPTED.swap(true, Ordering::Relaxed) {
+            unsafe { ExitProcess(EXIT_CODE as u32) };
+        }
+        return 1;
+    }
+    unsafe { SetConsoleCtrlHandler(Some(on_interrupt), 1) };
+}
+#[cfg(not(any(unix, windows)))]
+pub fn install() {}
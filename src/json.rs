@@ -0,0 +1,80 @@
+use std::fmt::Write;
+use std::path::Path;
+
+/**
+ * returns text as a json string, quoted and escaped
+ */
+pub fn string(text: &str) -> String {
+    //DATA
+    let mut escaped = String::with_capacity(text.len() + 2);
+
+    escaped.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{8}' => escaped.push_str("\\b"),
+            '\u{c}' => escaped.push_str("\\f"),
+            //the rest of the control characters can only be written as escapes
+            c if c < ' ' => {let _ = write!(escaped, "\\u{:04x}", c as u32);},
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+
+    //return
+    return escaped;
+}
+
+/**
+ * returns the field called name for a path, like "path":"..." if it's valid utf-8,
+ * or "path_bytes":[...] (its exact bytes, as numbers) if it isn't, since json strings can't hold those
+ */
+pub fn path_field(name: &str, path: &Path, shown_as: &str) -> String {
+    return match path.to_str() {
+        Some(_) => format!("\"{}\":{}", name, string(shown_as)),
+        None => format!("\"{}_bytes\":[{}]", name, path.as_os_str().as_encoded_bytes().iter().map(u8::to_string).collect::<Vec<String>>().join(",")),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strings_are_quoted_and_escaped() {
+        assert_eq!(string(""), "\"\"");
+        assert_eq!(string("say \"hi\" \\ there"), "\"say \\\"hi\\\" \\\\ there\"");
+        assert_eq!(string("a\nb\rc\td\u{8}e\u{c}f"), "\"a\\nb\\rc\\td\\be\\ff\"");
+    }
+
+    #[test]
+    fn other_control_characters_are_unicode_escapes() {
+        assert_eq!(string("\u{0}\u{1}\u{1f}"), "\"\\u0000\\u0001\\u001f\"");
+        //delete isn't one json has to escape
+        assert_eq!(string("\u{7f}"), "\"\u{7f}\"");
+    }
+
+    #[test]
+    fn everything_else_is_kept_as_is() {
+        assert_eq!(string("é 日本 🦀 / '"), "\"é 日本 🦀 / '\"");
+    }
+
+    #[test]
+    fn utf8_paths_are_strings_as_shown() {
+        assert_eq!(path_field("path", Path::new("./a\"b.txt"), "./a\"b.txt"), "\"path\":\"./a\\\"b.txt\"");
+        assert_eq!(path_field("raw_path", Path::new("/r/a.txt"), "a.txt"), "\"raw_path\":\"a.txt\"");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn other_paths_are_their_bytes() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        assert_eq!(path_field("path", Path::new(OsStr::from_bytes(b"a\xff")), "a\u{FFFD}"), "\"path_bytes\":[97,255]");
+    }
+}
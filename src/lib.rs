@@ -1,222 +1,4181 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::ffi::{OsStr, OsString};
 use std::error::Error;  //allows for some better errors
-use std::fs;            //the library that will allow us to parse files
+use std::fs::{self, File};   //the library that will allow us to parse files
+use std::io::{self, BufRead, BufReader, IsTerminal, Read, Write};
+use std::ops::Range;
+use std::borrow::Cow;
 use std::path::{Path, PathBuf};    //the library that will allow us to get more info about files and directories      
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub mod accents;
+pub mod completions;
+pub mod csv;
+pub mod encoding;
+pub mod glob;
+pub mod gzip;
+pub mod hexdump;
+pub mod hyperlink;
+pub mod ignore;
+pub mod inflate;
+pub mod interrupt;
+pub mod json;
+pub mod man;
+pub mod matcher;
+pub mod options;
+pub mod preprocess;
+pub mod regex;
+pub mod tar;
+pub mod template;
+pub mod types;
+pub mod winpath;
+pub mod zip;
+use completions::Shell;
+use encoding::{DecodingReader, Encoding};
+use glob::Glob;
+use gzip::GzDecoder;
+use hexdump::{HexCollector, HexDump};
+use ignore::{Gitignore, IgnoreStack};
+use inflate::Bounded;
+pub use matcher::Matcher;
+use preprocess::Preprocessor;
+use regex::Regex;
+use template::{Field, Template};
+use types::TypeTable;
+
+//the exit statuses, and what they mean, for the help and the man page
+pub const EXIT_STATUSES: [(i32, &str); 4] = [(0, "if any file matched"), (1, "if no files matched"), (2, "if there was an error (including files or directories that couldn't be read, when nothing matched)"), (interrupt::EXIT_CODE, "if it was interrupted (by Ctrl-C)")];
+//the features --version lists, and whether this build has them, everything's built in, but some of it only works on some platforms
+const FEATURES: [(&str, bool); 8] = [("regex", true), ("encoding", true), ("gzip", true), ("zip", true), ("tar", true), ("pre", true), ("one-file-system", cfg!(any(unix, windows))), ("windows-paths", cfg!(windows))];
+//environment variable with default options, applied before the ones on the command line
+const OPTIONS_ENV_VAR: &str = "GREP_DIRECTORY_OPTS";
+//environment variable that brings back the old default of listing the files that match, the same as --compat-v0
+const COMPAT_ENV_VAR: &str = "GREP_DIRECTORY_COMPAT_V0";
+//how much of the start of a file is checked for a NUL to see if it's binary
+const BINARY_CHECK_SIZE: usize = 8 * 1024;
+//the most a compressed file can decompress to, without --max-filesize, so it can't fill up memory (or take forever)
+const DECOMPRESSED_SIZE_LIMIT: u64 = 1024 * 1024 * 1024;
+//path that means "read from stdin" instead of a file or directory
+const STDIN_PATH: &str = "-";
+//ANSI escape codes used in --color mode
+const COLOR_PATH: &str = "\x1b[35m";       //magenta
+const COLOR_MATCH: &str = "\x1b[1;31m";    //bold red
+const COLOR_RESET: &str = "\x1b[0m";
+
+/**
+ * when to color the output
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,   //only when the output is going to a terminal, and NO_COLOR isn't set
+    Always,
+    Never,
+}
+impl ColorChoice {
+    /**
+     * returns true if the output should be colored, terminal is whether the output is going to one
+     */
+    pub fn enabled(&self, terminal: bool) -> bool {
+        return match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => terminal && env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty()),
+        };
+    }
+}
+
+/**
+ * what order the files are searched (and printed) in
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortBy {
+    None,       //whatever order the directories list them in, which is the fastest
+    Path,
+    Modified,   //oldest first
+    Size,       //smallest first
+}
+impl SortBy {
+    /**
+     * sorts paths in this order, files whose metadata can't be read go after the ones that can, ties are broken by path
+     */
+    pub fn sort(&self, paths: &mut [PathBuf]) {
+        match self {
+            SortBy::None => {},
+            SortBy::Path => paths.sort(),
+            SortBy::Modified => paths.sort_by_cached_key(|path| (fs::metadata(path).and_then(|metadata| metadata.modified()).map_err(|_| ()), path.clone())),
+            SortBy::Size => paths.sort_by_cached_key(|path| (fs::metadata(path).map(|metadata| metadata.len()).map_err(|_| ()), path.clone())),
+        }
+    }
+}
+
+/**
+ * what to do with binary files, ones with a NUL byte near the start
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryMode {
+    Skip,       //don't search them
+    Text,       //search them like any other file
+    Binary,     //search them, but only say whether they matched instead of printing their lines
+}
+
+/**
+ * whether matching is case sensitive, --smart-case picks one of these based on the patterns
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    Sensitive,
+    Insensitive,
+}
+
+/**
+ * how an occurrence of a query has to line up with the text around it to count as a match
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchKind {
+    Substring,  //anywhere
+    Word,       //not touching any other word characters (letters, digits, and underscores)
+    Line,       //the whole line, minus the line ending
+    Anchored { anchor: Anchor, word: bool, ignore_whitespace: bool },   //at the start and/or end of the line (past any whitespace there, if ignore_whitespace is set), and a whole word too if word is set
+}
+impl MatchKind {
+    /**
+     * returns true if the occurrence (a byte range of line) counts as a match of this kind
+     */
+    pub fn fits(&self, line: &str, occurrence: &Range<usize>) -> bool {
+        //DATA
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+        return match self {
+            MatchKind::Substring => true,
+            MatchKind::Word => !line[..occurrence.start].chars().next_back().is_some_and(is_word_char)
+                && !line[occurrence.end..].chars().next().is_some_and(is_word_char),
+            MatchKind::Line => occurrence.start == 0 && occurrence.end == line.len(),
+            MatchKind::Anchored { anchor, word, ignore_whitespace } => {
+                //DATA
+                let (start, end) = if *ignore_whitespace {(line.len() - line.trim_start().len(), line.trim_end().len())} else {(0, line.len())};
+
+                (!word || MatchKind::Word.fits(line, occurrence))
+                    && (*anchor == Anchor::End || occurrence.start == start)
+                    && (*anchor == Anchor::Start || occurrence.end == end)
+            },
+        };
+    }
+}
+
+/**
+ * which end of the line a match has to be at, with --anchor
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    Start,
+    End,
+    Both,
+}
+
+/**
+ * what gets printed for the files that are searched, only one can be picked
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputMode {
+    FilesWithMatches,   //the names of the files that contain the query (the default with --compat-v0, or with --invert-match or --null, which only list files)
+    FilesWithoutMatch,  //the names of the files that don't contain the query
+    Files,              //the names of all the files that would be searched, without searching them
+    Count,              //the names of the files that contain the query, with how many times they contain it
+    CountMatches,       //the total number of times all the files contain the query
+    Lines,              //every line that contains the query, with its file name and line number (the default)
+}
+
+/**
+ * what --filenames matches the query against
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameTarget {
+    Name,   //just the file's name, like lib.rs
+    Path,   //the whole path it was found at, like ./src/lib.rs
+}
+
+/**
+ * how the results are written out, whatever the output mode is
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,   //for people to read (the default)
+    Json,   //one json document, with a result for each file (or line), and the stats at the end
+    JsonLines,  //a json object on each line, printed as soon as it's found, with the stats in the last one
+    Csv,    //a header row, then a row for each file (or line)
+    Vimgrep,    //a "path:line:column:line" line for each match, for vim's quickfix list (and other editors)
+    Template,   //a line for each result, laid out by the --format template
+}
+impl OutputFormat {
+    /**
+     * returns true for the formats that are json, one way or another
+     */
+    pub fn is_json(&self) -> bool {
+        return matches!(self, OutputFormat::Json | OutputFormat::JsonLines);
+    }
+}
 
-const VALID_OPTIONS: [&str; 9] = [
-    "-c", "--case-insensitive",
-    //"-f", "--filter",
-    "-r", "--recursive",
-    "-v", "--verbose",
-    "-h", "--help", "help",
-];
 pub struct Config {
     pub query: String,
-    pub path: String,
-    pub case_sensitive: bool,
+    pub patterns: Vec<String>,
+    pub regex: bool,
+    pub encoding: Encoding,     //files are decoded from this before they're searched, columns are in the decoded text, byte offsets are in the file's own bytes
+    pub binary_mode: BinaryMode,
+    pub binary_context: Option<usize>,  //with --binary, how many bytes around each match to print a hex dump of
+    pub search_zip: bool,       //search what compressed files decompress to, instead of skipping them as binary
+    pub pre: Option<String>,    //a command whose output is searched instead of each file, with the file's path as its argument
+    pub pre_globs: Vec<Glob>,   //only files whose names match one of these go through the command, when there are any
+    pub pre_timeout: Option<Duration>,
+    pub strict_utf8: bool,      //files that aren't valid utf-8 are errors (and skipped), instead of having the invalid bytes replaced
+    pub multiline: bool,        //match against the whole contents of each file, so matches can span lines
+    pub matcher: Matcher,       //finds the patterns in each line, built once the rest of the options are known
+    pub all_of: bool,           //a file only matches if it contains every pattern, not just one of them
+    pub required: Vec<Matcher>, //with --all-of, a matcher for each pattern, which all have to match somewhere in a file
+    pub not_patterns: Vec<String>,  //files that contain any of these don't match
+    pub excluded: Option<Matcher>,  //finds the --not patterns, if there are any
+    pub paths: Vec<PathBuf>,
+    pub case_mode: CaseMode,
+    pub match_kind: MatchKind,
+    pub ignore_accents: bool,   //match letters whatever accents they have, like e for é
+    pub anchor: Option<Anchor>,     //which end of the line matches have to be at, on top of match_kind
+    pub anchor_ignore_whitespace: bool,
+    pub fuzzy: usize,           //how many edits away from the query text can be and still match, 0 means it has to match exactly
     pub filter: bool,
     pub filter_for: Vec<String>,
+    pub includes: Vec<Glob>,
+    pub excludes: Vec<Glob>,
+    pub types: TypeTable,       //the file types -t and -T can use
+    pub type_list: bool,
+    pub exclude_dirs: HashSet<String>,
     pub recurse: bool,
+    pub hidden: bool,
+    pub follow: bool,
+    pub no_ignore: bool,        //with -r, search files that .gitignore files say to ignore too
+    pub ignore_files: Vec<(PathBuf, String)>,   //the extra ignore files, and what's in them, their patterns are relative to each path searched
+    pub one_file_system: bool,  //with -r, don't go into directories on other filesystems (mounts)
+    pub dedupe: bool,           //search files that can be reached by more than one path (links, or paths given twice) only once
+    pub max_depth: Option<usize>,
+    pub min_depth: usize,
     pub verbose: bool,
+    pub quiet: bool,
+    pub invert_match: bool,
+    pub output_mode: OutputMode,
+    pub format: OutputFormat,
+    pub filenames: Option<NameTarget>,  //in files mode, only list the files whose names (or paths) match the query
+    pub also_match_names: bool, //files whose names match the query match too, whatever's in them
+    pub include_zero: bool,
+    pub column: bool,
+    pub byte_offset: bool,
+    pub only_matching: bool,
+    pub null: bool,
+    pub heading: Option<bool>,  //None means only when the output is going to a terminal
+    pub plain: Option<bool>,    //whether to leave out the banner, headers, and indentation, None means only when the output isn't going to a terminal
+    pub template: Option<Template>, //what each result looks like with --format
+    pub output: Option<(PathBuf, File)>,    //the file to write the results to instead of stdout, and its canonical path (so it isn't searched itself)
+    pub with_filename: Option<bool>,    //None means unless the only path is a file
+    pub absolute_paths: bool,   //print results with absolute, canonical paths, instead of as they were found
+    pub relative_to: Option<PathBuf>,   //print results relative to this (canonical) directory, instead of as they were found
+    pub stats: bool,
+    pub sort: SortBy,
+    pub before_context: usize,
+    pub after_context: usize,
+    pub replace: Option<String>,    //what the matches in printed lines are replaced with, the files themselves aren't changed
+    pub passthru: bool,         //print every line of the files with matches, not just the matching ones
+    pub max_count: Option<usize>,
+    pub max_filesize: Option<u64>,
+    pub min_size: Option<u64>,  //only search files at least this big
+    pub max_size: Option<u64>,  //only search files at most this big, unlike max_filesize, they're filtered out before the search, like with --files
+    pub newer_than: Option<SystemTime>, //only search files modified at or after this
+    pub older_than: Option<SystemTime>, //only search files modified before this
+    pub threads: usize,
+    pub color: ColorChoice,
+    pub hyperlinks: ColorChoice,    //when to make file names links (always is on, never is off), auto means whenever there's color
     pub help: bool,
+    pub completions: Option<Shell>, //the shell to print a completion script for, instead of searching
+    pub generate_man: bool,
+    pub version: bool,
 }
 impl Config {
-    pub fn new(args: &[String]) -> Result<Config, Box<dyn Error>> {
+    /**
+     * builds a config from the command line arguments, with the default options from the GREP_DIRECTORY_OPTS environment variable
+     * the arguments don't have to be valid utf-8 (so paths can be anything the os allows), but options and the query do
+     */
+    pub fn new<S: AsRef<OsStr>>(args: &[S]) -> Result<Config, Box<dyn Error>> {
+        return Config::new_with_env(args, env::var(OPTIONS_ENV_VAR).ok().as_deref());
+    }
+
+    /**
+     * same as new, but with the contents of the GREP_DIRECTORY_OPTS environment variable passed in instead of read from the environment
+     */
+    pub fn new_with_env<S: AsRef<OsStr>>(args: &[S], env_options: Option<&str>) -> Result<Config, Box<dyn Error>> {
+        return Config::new_with_defaults(args, env_options, default_config_file().as_deref());
+    }
+
+    /**
+     * same as new_with_env, but with the path of the config file passed in too (None means there's no config file)
+     */
+    pub fn new_with_defaults<S: AsRef<OsStr>>(args: &[S], env_options: Option<&str>, config_file: Option<&Path>) -> Result<Config, Box<dyn Error>> {
         //DATA
-        let mut config: Config = Config { query: String::new(), path: String::new(), case_sensitive: false, filter: false, filter_for: Vec::new(), recurse: false, verbose: false, help: false };
-        let options: Vec<String>;
-        let path:String;
+        let mut config: Config = Config { query: String::new(), patterns: Vec::new(), regex: false, encoding: Encoding::Auto, binary_mode: BinaryMode::Skip, binary_context: None, search_zip: false, pre: None, pre_globs: Vec::new(), pre_timeout: None, strict_utf8: false, multiline: false, matcher: Matcher::Literal { patterns: Vec::new(), kind: MatchKind::Substring }, all_of: false, required: Vec::new(), not_patterns: Vec::new(), excluded: None, paths: Vec::new(), case_mode: CaseMode::Sensitive, match_kind: MatchKind::Substring, ignore_accents: false, anchor: None, anchor_ignore_whitespace: false, fuzzy: 0, filter: false, filter_for: Vec::new(), includes: Vec::new(), excludes: Vec::new(), types: TypeTable::new(), type_list: false, exclude_dirs: HashSet::new(), recurse: false, hidden: false, follow: false, no_ignore: false, ignore_files: Vec::new(), one_file_system: false, dedupe: true, max_depth: None, min_depth: 0, verbose: false, quiet: false, invert_match: false, output_mode: OutputMode::FilesWithMatches, format: OutputFormat::Text, filenames: None, also_match_names: false, include_zero: false, column: false, byte_offset: false, only_matching: false, null: false, heading: None, plain: None, template: None, output: None, with_filename: None, absolute_paths: false, relative_to: None, stats: false, sort: SortBy::Path, before_context: 0, after_context: 0, replace: None, passthru: false, max_count: None, max_filesize: None, min_size: None, max_size: None, newer_than: None, older_than: None, threads: thread::available_parallelism().map_or(1, |threads| threads.get()), color: ColorChoice::Auto, hyperlinks: ColorChoice::Auto, help: false, completions: None, generate_man: false, version: false };
+        let options: Vec<(String,Option<String>)>;
+        let mut positionals: Vec<OsString>;
+        let mut paths: Vec<OsString> = Vec::new();
         let query:String;
+        let mut smart_case: bool = false;
+        let mut allow_blank_query: bool = false; //queries and patterns that are just whitespace are errors, unless --allow-blank-query says they aren't
+        let mut compat_v0: bool = env::var_os(COMPAT_ENV_VAR).is_some_and(|compat| !compat.is_empty()); //whether to list files by default, like before matching lines were printed
+        let mut explicit_case: bool = false; //explicit case flags win over --smart-case
+        let mut output_mode_option: Option<String> = None; //the option that picked the output mode, to name it if another one conflicts
+        let mut format_option: Option<String> = None; //the same, for the output format
+        let mut output: Option<String> = None;  //the file given with --output, it's only created once the rest of the options are known to be good
+        let mut type_includes: Vec<String> = Vec::new(); //file types from -t and -T, looked up once every option has been parsed
+        let mut type_excludes: Vec<String> = Vec::new();
+        let mut type_adds: Vec<String> = Vec::new(); //added before anything's looked up, so -t can come before the --type-add it uses
+
+        //parse default options from the environment, they come first so options on the command line win
+        let mut env_args: Vec<String> = vec![String::new()]; //parse_arguments skips the first argument
+        env_args.extend(env_options.unwrap_or_default().split_whitespace().map(String::from));
+        let (env_options, env_positionals) = Config::parse_arguments(&env_args).map_err(|err| format!("in {}: {}", OPTIONS_ENV_VAR, err))?;
+        if !env_positionals.is_empty() {
+            return Err(format!("in {}: only options are allowed, got '{}'", OPTIONS_ENV_VAR, env_positionals.join(OsStr::new(" ")).to_string_lossy()).into());
+        }
+        Config::validate_options(&env_options).map_err(|err| format!("in {}: {}", OPTIONS_ENV_VAR, err))?;
 
         //parse args
-        match Config::parse_arguments(&args) {
-            Ok( (a,b,c) ) => (options, path, query) = (a,b,c),
+        match Config::parse_arguments(args) {
+            Ok( (a,b) ) => (options, positionals) = (a,b),
             Err(err) => return Err(err),
         }
 
         //ensure everything is valid
-        //throw error if any options aren't valid
-        if options.iter().any(|o| !VALID_OPTIONS.contains(&o.as_str())) {
-            return Err("One or more invalid options.".into());
-        }
-        //throw error if path doesn't exist
-        if !Path::new(&path).exists() {
-            return Err("Invalid path.".into());
-        }
+        Config::validate_options(&options)?;
 
-        //assign path and query
-        config.path = path; 
-        config.query = query; 
+        //load defaults from the config file, unless told not to, they come before the environment and command line
+        let file_options: Vec<(String,Option<String>)> = match config_file {
+            Some(path) if !env_options.iter().chain(options.iter()).any(|(o,_)| o == "--no-config") => read_config_file(path),
+            _ => Vec::new(),
+        };
+        let options: Vec<(String,Option<String>)> = file_options.into_iter().chain(env_options).chain(options).collect();
         //modify config based on options
-        options.iter().for_each(|option| {
+        for (option,value) in &options {
             match option.as_str() {
-                "-c" | "--case-insensitive" => config.case_sensitive = true,
+                "--case-insensitive" => {
+                    eprintln!("warning: --case-insensitive is deprecated, use -i/--ignore-case instead");
+                    config.case_mode = CaseMode::Insensitive;
+                    explicit_case = true;
+                },
+                "-i" | "--ignore-case" => {
+                    config.case_mode = CaseMode::Insensitive;
+                    explicit_case = true;
+                },
+                "-s" | "--case-sensitive" => {
+                    config.case_mode = CaseMode::Sensitive;
+                    explicit_case = true;
+                },
+                "--smart-case" => smart_case = true,
+                "-E" | "--regex" => config.regex = true,
+                "-F" | "--fixed-strings" => config.regex = false,
+                "-U" | "--multiline" => config.multiline = true,
+                "-a" | "--text" => config.binary_mode = BinaryMode::Text,
+                "--binary" => config.binary_mode = BinaryMode::Binary,
+                "--binary-context" => config.binary_context = Some(parse_number(option, value)?),
+                "-z" | "--search-zip" => config.search_zip = true,
+                "--strict-utf8" => config.strict_utf8 = true,
+                "--allow-blank-query" => allow_blank_query = true,
+                "-w" | "--word-regexp" => config.match_kind = MatchKind::Word,
+                "-x" | "--line-regexp" => config.match_kind = MatchKind::Line,
+                "--fuzzy" => config.fuzzy = parse_number(option, value)?,
+                "--anchor" => {
+                    config.anchor = match value.as_deref().unwrap_or_default() {
+                        "start" => Some(Anchor::Start),
+                        "end" => Some(Anchor::End),
+                        "both" => Some(Anchor::Both),
+                        other => return Err(format!("Invalid value '{}' for '{}', expected start, end, or both.", other, option).into()),
+                    };
+                },
+                "--anchor-ignore-whitespace" => config.anchor_ignore_whitespace = true,
+                "--ignore-accents" => config.ignore_accents = true,
+                "-f" | "--filter" => {
+                    config.filter = true;
+                    //extensions are compared case-insensitively, "noext" (or an empty entry) matches files without an extension
+                    config.filter_for = value.as_deref().unwrap_or_default().split(',')
+                        .map(|ext| ext.trim().trim_start_matches('.').to_ascii_lowercase())
+                        .map(|ext| if ext == "noext" {String::new()} else {ext})
+                        .collect();
+                },
                 "-r" | "--recursive" => config.recurse = true,
+                "--hidden" => config.hidden = true,
+                "--follow" => config.follow = true,
+                "--no-ignore" => config.no_ignore = true,
+                "--ignore-file" => {
+                    let path = value.as_deref().unwrap_or_default();
+                    let contents = fs::read_to_string(path).map_err(|err| format!("Couldn't read ignore file '{}': {}", path, err))?;
+                    config.ignore_files.push((PathBuf::from(path), contents));
+                },
+                "--one-file-system" => {
+                    config.one_file_system = true;
+                    if !cfg!(any(unix, windows)) {
+                        eprintln!("warning: --one-file-system isn't supported on this platform, ignoring it");
+                    }
+                },
+                "--no-dedupe" => config.dedupe = false,
+                "--max-depth" => config.max_depth = Some(parse_number(option, value)?),
+                "--min-depth" => config.min_depth = parse_number(option, value)?,
                 "-v" | "--verbose" => config.verbose = true,
+                "-q" | "--quiet" => config.quiet = true,
+                "-I" | "--invert-match" => config.invert_match = true,
+                "--compat-v0" => compat_v0 = true,
+                "-l" | "--files-with-matches" => config.set_output_mode(OutputMode::FilesWithMatches, option, &mut output_mode_option)?,
+                "-L" | "--files-without-match" => config.set_output_mode(OutputMode::FilesWithoutMatch, option, &mut output_mode_option)?,
+                "--files" => config.set_output_mode(OutputMode::Files, option, &mut output_mode_option)?,
+                "--also-match-names" => config.also_match_names = true,
+                "--filenames" => {
+                    config.set_output_mode(OutputMode::Files, option, &mut output_mode_option)?;
+                    config.filenames = match value.as_deref() {
+                        None => Some(NameTarget::Name),
+                        Some("path") => Some(NameTarget::Path),
+                        Some(other) => return Err(format!("Invalid value '{}' for '{}', expected path (or no value, to match just the file name).", other, option).into()),
+                    };
+                },
+                "-c" | "--count" => config.set_output_mode(OutputMode::Count, option, &mut output_mode_option)?,
+                "--count-matches" => config.set_output_mode(OutputMode::CountMatches, option, &mut output_mode_option)?,
+                "-n" | "--line-number" => config.set_output_mode(OutputMode::Lines, option, &mut output_mode_option)?,
+                "--include-zero" => config.include_zero = true,
+                "--column" => config.column = true,
+                "-b" | "--byte-offset" => config.byte_offset = true,
+                "-o" | "--only-matching" => config.only_matching = true,
+                "--passthru" => config.passthru = true,
+                "--replace" => config.replace = value.clone(),
+                "-0" | "--null" => config.null = true,
+                "--json" => config.set_format(OutputFormat::Json, option, &mut format_option)?,
+                "--jsonl" => config.set_format(OutputFormat::JsonLines, option, &mut format_option)?,
+                "--csv" => config.set_format(OutputFormat::Csv, option, &mut format_option)?,
+                "--vimgrep" => config.set_format(OutputFormat::Vimgrep, option, &mut format_option)?,
+                "--format" => {
+                    config.set_format(OutputFormat::Template, option, &mut format_option)?;
+                    config.template = Some(Template::new(value.as_deref().unwrap_or_default())?);
+                },
+                "--heading" => config.heading = Some(true),
+                "--no-heading" => config.heading = Some(false),
+                "--plain" => config.plain = Some(true),
+                "--no-plain" => config.plain = Some(false),
+                "-H" | "--with-filename" => config.with_filename = Some(true),
+                "--no-filename" => config.with_filename = Some(false),
+                "--absolute-paths" => config.absolute_paths = true,
+                "--relative-to" => {
+                    //DATA
+                    let base: &str = value.as_deref().unwrap_or_default();
+
+                    //it's canonicalized like the results are, so symlinks and trailing separators don't stop them from lining up
+                    config.relative_to = Some(fs::canonicalize(base).map_err(|err| format!("Invalid path '{}' for '{}': {}", base, option, err))?);
+                },
+                "--output" => {
+                    match value.as_deref() {
+                        Some(path) if !path.is_empty() => output = Some(path.to_string()),
+                        _ => return Err(format!("Option '{}' requires a file name.", option).into()),
+                    }
+                },
+                "--stats" => config.stats = true,
+                "-A" | "--after-context" => config.after_context = parse_number(option, value)?,
+                "-B" | "--before-context" => config.before_context = parse_number(option, value)?,
+                "-C" | "--context" => {
+                    config.after_context = parse_number(option, value)?;
+                    config.before_context = config.after_context;
+                },
+                "-m" | "--max-count" => {
+                    match parse_number(option, value)? {
+                        0 => return Err(format!("Option '{}' must be at least 1.", option).into()),
+                        max_count => config.max_count = Some(max_count),
+                    }
+                },
                 "-h" | "--help" => config.help = true,
+                "--generate-man" => config.generate_man = true,
+                "--completions" => config.completions = Some(Shell::from_name(value.as_deref().unwrap_or_default())?),
+                "-V" | "--version" => config.version = true,
+                "-p" | "--path" => paths.push(OsString::from(value.clone().unwrap_or_default())),
+                "-e" | "--regexp" => {
+                    //an empty pattern would match every file, which is never what the user meant
+                    match value.as_deref() {
+                        Some(pattern) if !pattern.is_empty() => config.patterns.push(pattern.to_string()),
+                        _ => return Err(format!("Option '{}' requires a non-empty pattern.", option).into()),
+                    }
+                },
+                "--pattern-file" => config.patterns.extend(read_pattern_file(value.as_deref().unwrap_or_default())?),
+                "--all-of" => config.all_of = true,
+                "--not" => {
+                    //an empty pattern is in every file, so nothing would ever match
+                    match value.as_deref() {
+                        Some(pattern) if !pattern.is_empty() => config.not_patterns.push(pattern.to_string()),
+                        _ => return Err(format!("Option '{}' requires a non-empty pattern.", option).into()),
+                    }
+                },
+                "--include" => config.includes.push(parse_file_name_glob(option, value)?),
+                "--exclude" => config.excludes.push(parse_file_name_glob(option, value)?),
+                "-t" | "--type" => type_includes.push(value.clone().unwrap_or_default()),
+                "-T" | "--type-not" => type_excludes.push(value.clone().unwrap_or_default()),
+                "--type-list" => config.type_list = true,
+                "--type-add" => type_adds.push(value.clone().unwrap_or_default()),
+                "--exclude-dir" => {config.exclude_dirs.insert(value.clone().unwrap_or_default());},
+                "--threads" => {
+                    match parse_number(option, value)? {
+                        0 => return Err(format!("Option '{}' must be at least 1.", option).into()),
+                        threads => config.threads = threads,
+                    }
+                },
+                "--color" => {
+                    config.color = match value.as_deref().unwrap_or_default() {
+                        "auto" => ColorChoice::Auto,
+                        "always" => ColorChoice::Always,
+                        "never" => ColorChoice::Never,
+                        other => return Err(format!("Invalid value '{}' for '{}', expected auto, always, or never.", other, option).into()),
+                    };
+                },
+                "--hyperlinks" => {
+                    config.hyperlinks = match value.as_deref().unwrap_or_default() {
+                        "auto" => ColorChoice::Auto,
+                        "on" => ColorChoice::Always,
+                        "off" => ColorChoice::Never,
+                        other => return Err(format!("Invalid value '{}' for '{}', expected on, off, or auto.", other, option).into()),
+                    };
+                },
+                "--encoding" => config.encoding = Encoding::from_name(value.as_deref().unwrap_or_default())?,
+                "--max-filesize" => config.max_filesize = Some(parse_size(option, value)?),
+                "--min-size" => config.min_size = Some(parse_size(option, value)?),
+                "--max-size" => config.max_size = Some(parse_size(option, value)?),
+                "--newer-than" => config.newer_than = Some(parse_time(option, value)?),
+                "--older-than" => config.older_than = Some(parse_time(option, value)?),
+                "--pre" => {
+                    config.pre = match value.as_deref() {
+                        Some(command) if !command.is_empty() => Some(command.to_string()),
+                        _ => return Err(format!("Option '{}' requires a command.", option).into()),
+                    };
+                },
+                "--pre-glob" => config.pre_globs.push(parse_file_name_glob(option, value)?),
+                "--pre-timeout" => config.pre_timeout = Some(parse_seconds(option, value)?),
+                "--sort" => {
+                    config.sort = match value.as_deref().unwrap_or_default() {
+                        "path" => SortBy::Path,
+                        "mtime" => SortBy::Modified,
+                        "size" => SortBy::Size,
+                        "none" => SortBy::None,
+                        other => return Err(format!("Invalid value '{}' for '{}', expected path, mtime, size, or none.", other, option).into()),
+                    };
+                },
                 _ => {},
             }
-        });
+        }
+
+        //file types are just names for lists of globs, so they're included and excluded the same way --include and --exclude are
+        for definition in &type_adds {
+            config.types.add(definition)?;
+        }
+        for name in &type_includes {
+            for glob in config.types.globs(name)? {
+                config.includes.push(Glob::new(glob)?);
+            }
+        }
+        for name in &type_excludes {
+            for glob in config.types.globs(name)? {
+                config.excludes.push(Glob::new(glob)?);
+            }
+        }
+
+        //a bare "help" or "version", with nothing else to go on, means --help or --version, rather than a path that doesn't exist
+        if paths.is_empty() && config.patterns.is_empty() && config.output_mode != OutputMode::Files && positionals.len() == 1 {
+            match positionals[0].to_str() {
+                Some("help") => config.help = true,
+                Some("version") => config.version = true,
+                _ => {},
+            }
+        }
+
+        //without any --path options, the first positional argument is the path
+        //everything else is the query, multiple query words are joined with a single space
+        if paths.is_empty() && !positionals.is_empty() {
+            paths.push(positionals.remove(0));
+        }
+        query = positionals.iter()
+            .map(|word| word.to_str().ok_or("The query isn't valid UTF-8."))
+            .collect::<Result<Vec<&str>,&str>>()?.join(" ");
+
+        //a query of just spaces or tabs matches almost every file, which is almost never what the user meant
+        //(spaces around other text are still searched for as given)
+        if !allow_blank_query {
+            if !query.is_empty() && query.trim().is_empty() {
+                return Err("The query is blank (just whitespace), so it would match almost every file, use --allow-blank-query if that's what you meant.".into());
+            }
+            if let Some(pattern) = config.patterns.iter().find(|pattern| pattern.trim().is_empty()) {
+                return Err(format!("The pattern {:?} is blank (just whitespace), so it would match almost every file, use --allow-blank-query if that's what you meant.", pattern).into());
+            }
+        }
+
+        //without an output mode, the matching lines are printed, like grep does (or the files are listed, like before that, with --compat-v0)
+        //--invert-match and --null are only for listing files, so they still list them
+        if output_mode_option.is_none() && !compat_v0 && !config.invert_match && !config.null {
+            config.output_mode = OutputMode::Lines;
+        }
+
+        //columns, context, passthru, replacements, only matching, and vimgrep are only printed with lines, so they print lines unless another output mode was picked
+        if config.column || config.only_matching || config.passthru || config.replace.is_some() || config.before_context > 0 || config.after_context > 0 || config.format == OutputFormat::Vimgrep {
+            match output_mode_option {
+                None => config.output_mode = OutputMode::Lines,
+                Some(option) if config.output_mode != OutputMode::Lines => return Err(format!("--column, --only-matching, --passthru, --replace, --vimgrep, and the context options can't be used with '{}', they only work when printing lines.", option).into()),
+                Some(_) => {},
+            }
+        }
+        //only the matches are printed with --only-matching, so there's nowhere to put context
+        if config.only_matching && (config.before_context > 0 || config.after_context > 0 || config.passthru) {
+            return Err("--only-matching can't be used with --passthru or the context options.".into());
+        }
+
+        //nulls separate file names, so there have to be nothing but file names
+        if config.null && !matches!(config.output_mode, OutputMode::FilesWithMatches | OutputMode::FilesWithoutMatch | OutputMode::Files) {
+            return Err("--null only works when listing files, it can't be used with the count or line output modes.".into());
+        }
+        if config.null && config.format != OutputFormat::Text {
+            return Err(format!("--null can't be used with '{}', they're different ways of formatting the output.", format_option.unwrap_or_default()).into());
+        }
+        //csv is for spreadsheets, which can't do anything with headings or color codes
+        if config.format == OutputFormat::Csv && (config.heading == Some(true) || config.color == ColorChoice::Always) {
+            return Err("--csv can't be used with --heading or --color always, csv has a row for each result, without any decoration.".into());
+        }
+        //vimgrep's lines are just the matches, in the one form editors know how to read
+        if config.format == OutputFormat::Vimgrep && (config.before_context > 0 || config.after_context > 0 || config.passthru || config.byte_offset) {
+            return Err("--vimgrep can't be used with --passthru, --byte-offset, or the context options, every line is just \"path:line:column:text\" for a match.".into());
+        }
+        //the name tag has nowhere to go in a vimgrep line, and --files (or --filenames) doesn't search contents for names to be matched on top of
+        if config.also_match_names && (config.format == OutputFormat::Vimgrep || config.output_mode == OutputMode::Files) {
+            return Err("--also-match-names can't be used with --vimgrep, --files, or --filenames.".into());
+        }
+        //a template is for results, context lines aren't results
+        if config.format == OutputFormat::Template && (config.before_context > 0 || config.after_context > 0 || config.passthru) {
+            return Err("--format can't be used with --passthru or the context options, the template is only filled in for results.".into());
+        }
+        if config.absolute_paths && config.relative_to.is_some() {
+            return Err("--absolute-paths can't be used with --relative-to, they're different ways of printing paths.".into());
+        }
+        if let Some((min_size, max_size)) = config.min_size.zip(config.max_size).filter(|(min_size, max_size)| min_size > max_size) {
+            return Err(format!("--min-size ({}) can't be greater than --max-size ({}).", min_size, max_size).into());
+        }
+        if let (Some(newer_than), Some(older_than)) = (config.newer_than, config.older_than) {
+            if newer_than >= older_than {
+                return Err("--newer-than has to be before --older-than, or no file could be both.".into());
+            }
+        }
+        if config.binary_context.is_some() && config.binary_mode != BinaryMode::Binary {
+            return Err("--binary-context is about how --binary prints the matches in binary files, so it can't be used without it.".into());
+        }
+        if config.pre.is_none() && (!config.pre_globs.is_empty() || config.pre_timeout.is_some()) {
+            return Err("--pre-glob and --pre-timeout are about which files --pre runs on, and for how long, so they can't be used without it.".into());
+        }
+        if config.null && config.byte_offset {
+            return Err("--null can't be used with --byte-offset, only the file names are printed.".into());
+        }
+
+        //counts are of the places the query is, there's nothing to count for the places it isn't
+        if config.invert_match && (config.counts_matches() || config.output_mode == OutputMode::Lines) {
+            return Err("--invert-match only works when listing files, it can't be used with --count, --count-matches, or --line-number.".into());
+        }
+
+        //throw error if there's no depth that's both deep enough and shallow enough
+        if let Some(max_depth) = config.max_depth.filter(|max_depth| *max_depth < config.min_depth) {
+            return Err(format!("--min-depth ({}) can't be greater than --max-depth ({}).", config.min_depth, max_depth).into());
+        }
+
+        //help, version, completions, the man page, and the type list don't search anything, so they don't need a path or query
+        if !config.help && !config.version && !config.type_list && config.completions.is_none() && !config.generate_man {
+            //throw error if path or query is missing
+            if paths.is_empty() || paths.iter().any(|path| path.is_empty()) {
+                return Err("No/invalid path given".into());
+            }
+            //--files doesn't search anything, so a query would just be ignored, unless it's for --filenames to match the names against
+            if config.output_mode == OutputMode::Files && config.filenames.is_none() {
+                if !query.is_empty() || !config.patterns.is_empty() {
+                    return Err("--files lists files without searching them, so it can't be given a query or patterns".into());
+                }
+            }
+            //the query is optional when patterns were given with -e
+            else if query.is_empty() && config.patterns.is_empty() {
+                return Err("No/invalid query given".into());
+            }
+            //throw error if any path doesn't exist
+            if let Some(path) = paths.iter().find(|path| *path != STDIN_PATH && !long_path(PathBuf::from(path)).exists()) {
+                return Err(format!("Invalid path '{}'.", path.to_string_lossy()).into());
+            }
+        }
+
+        //assign paths and query
+        config.paths = paths.into_iter().map(PathBuf::from).collect(); 
+        //the query is searched for along with any -e patterns, a file matches if it contains any of them
+        if !query.is_empty() {
+            config.patterns.insert(0, query.clone());
+        }
+        config.query = query; 
+
+        //lines are searched one at a time, a line break in a pattern could never match
+        if !config.multiline && config.patterns.iter().any(|pattern| pattern.contains('\n')) {
+            return Err("The query contains a line break, which can only match across lines with --multiline.".into());
+        }
+
+        //with smart case, the patterns decide: any uppercase character makes the search case sensitive
+        if smart_case && !explicit_case {
+            config.case_mode = if config.patterns.iter().any(|pattern| pattern.chars().any(char::is_uppercase)) {CaseMode::Sensitive} else {CaseMode::Insensitive};
+        }
+
+        //anchoring is on top of -w, it's about where the match is in the line, and -x already says exactly where that is
+        match config.anchor {
+            Some(_) if config.match_kind == MatchKind::Line => return Err("--anchor can't be used with -x, which already matches whole lines.".into()),
+            Some(_) if config.multiline => return Err("--anchor can't be used with --multiline, it anchors matches to the lines they're on, a line at a time.".into()),
+            Some(anchor) => config.match_kind = MatchKind::Anchored { anchor, word: config.match_kind == MatchKind::Word, ignore_whitespace: config.anchor_ignore_whitespace },
+            None if config.anchor_ignore_whitespace => return Err("--anchor-ignore-whitespace is about where --anchor lets matches be, so it can't be used without it.".into()),
+            None => {},
+        }
+
+        //accents are ignored by comparing the text without them, which regexes (and fuzzy matching) don't do
+        if config.ignore_accents && (config.regex || config.fuzzy > 0) {
+            return Err("--ignore-accents can't be used with --regex or --fuzzy, it only works for plain text.".into());
+        }
+
+        //the patterns are compiled once, here, rather than for every file (or line)
+        //fuzzy matching is only for plain text, a line at a time, since it's slow enough already
+        if config.fuzzy > 0 {
+            if config.regex || config.multiline {
+                return Err("--fuzzy can't be used with --regex or --multiline, it only matches plain text, a line at a time.".into());
+            }
+            //a pattern that's mostly edits matches almost anything
+            if let Some(pattern) = config.patterns.iter().chain(&config.not_patterns).find(|pattern| pattern.chars().count() <= config.fuzzy * 2) {
+                return Err(format!("The pattern '{}' is too short for --fuzzy {}, it has to be more than {} characters long, or it would match almost everything.", pattern, config.fuzzy, config.fuzzy * 2).into());
+            }
+        }
+        config.matcher = config.build_matcher(&config.patterns)?;
+        //with just one pattern, containing all of them is the same as containing any of them
+        if config.all_of && config.patterns.len() > 1 {
+            config.required = config.patterns.iter().map(|pattern| config.build_matcher(&[pattern])).collect::<Result<Vec<Matcher>, Box<dyn Error>>>()?;
+        }
+        if !config.not_patterns.is_empty() {
+            config.excluded = Some(config.build_matcher(&config.not_patterns)?);
+        }
+
+        //the output file is made here, rather than when the search starts, so not being able to make it is an error with the options
+        if let Some(path) = output {
+            //DATA
+            let file: File = File::create(&path).map_err(|err| format!("Couldn't create the output file '{}': {}", path, err))?;
+            let canonical: PathBuf = fs::canonicalize(&path).map_err(|err| format!("Couldn't find the output file '{}': {}", path, err))?;
+
+            config.output = Some((canonical, file));
+        }
 
         //return
         Ok(config)
     }
 
-    fn parse_arguments(args: &[String]) -> Result<(Vec<String>,String,String),Box<dyn Error>> { //query, 
+    /**
+     * returns true if path should be listed by --filenames, because its name (or whole path) matches the patterns (and --all-of and --not agree)
+     * with --invert-match, it's the paths that don't that are listed, and without --filenames, every path is
+     */
+    pub fn name_matches(&self, path: &Path) -> bool {
+        return match self.filenames {
+            None => true,
+            Some(target) => self.name_is_match(path, target) != self.invert_match,
+        };
+    }
+
+    /**
+     * returns true if the name of path (or the whole path) matches the patterns, and --all-of and --not agree
+     */
+    fn name_is_match(&self, path: &Path, target: NameTarget) -> bool {
+        //DATA
+        let name: String = match target {
+            NameTarget::Name => path.file_name().map_or_else(|| display_path(path), |name| name.to_string_lossy().to_string()),
+            NameTarget::Path => display_path(path),
+        };
+        let mut requirements = FileRequirements::new(&self.required, self.excluded.as_ref());
+
+        requirements.check(&name);
+        return self.matcher.is_match(&name) && requirements.satisfied();
+    }
+
+    /**
+     * returns true if the file at path is at least --min-size, and at most --max-size, bytes big
+     * one whose size can't be read is kept, so searching it can fail
+     */
+    fn size_in_range(&self, path: &Path) -> bool {
         //DATA
-        let options: Vec<String> = args.into_iter().filter(|s| s.starts_with("-")).map(|s| s.clone()).collect();
-        let mut args_iter = args[0..].into_iter().filter(|s| !s.starts_with("-")).map(|s| s.clone());
-        args_iter.next(); //skip first argument
-        let path:String = args_iter.next().unwrap_or(String::new()).clone();
-        let query:String = args_iter.collect::<String>().clone();
+        let size: Option<u64> = fs::metadata(path).map(|metadata| metadata.len()).ok();
 
-        //error handling
-        if path.is_empty() {
-            return Err("No/invalid path given".into());
-        } 
-        if query.is_empty() {
-            return Err("No/invalid query given".into());
+        if path.as_os_str() == STDIN_PATH {
+            return true;
         }
+        return size.is_none_or(|size| self.min_size.is_none_or(|min_size| size >= min_size) && self.max_size.is_none_or(|max_size| size <= max_size));
+    }
 
-        //return
-        return Ok((options,path,query));
+    /**
+     * returns true if the file at path was modified at or after --newer-than, and before --older-than
+     * a file modified in the future (like after a clock was changed) is newer than any time, and one whose time can't be read is kept, so searching it can fail
+     */
+    fn modified_in_window(&self, path: &Path) -> bool {
+        //DATA
+        let modified: Option<SystemTime> = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+
+        if path.as_os_str() == STDIN_PATH {
+            return true;
+        }
+        return modified.is_none_or(|modified| self.newer_than.is_none_or(|newer_than| modified >= newer_than) && self.older_than.is_none_or(|older_than| modified < older_than));
     }
-}
 
-pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    //DATA
-    let paths_to_grep: Vec<PathBuf>;
-    let base_path = Path::new(&config.path);
+    /**
+     * returns true if the file at path is searched as what the --pre command prints for it, rather than as it is
+     */
+    fn preprocesses(&self, path: &Path) -> bool {
+        return self.pre.is_some() && path.as_os_str() != STDIN_PATH && (self.pre_globs.is_empty() || matches_any_glob(path, &self.pre_globs).is_some());
+    }
 
-    //if user asked for help, give instructions
-    if config.help {
-        help();
-        return Ok(());
+    /**
+     * returns true if path is an archive whose files are searched, rather than the archive itself
+     */
+    fn is_archive(&self, path: &Path) -> bool {
+        return self.search_zip && path.as_os_str() != STDIN_PATH && (zip::is_zip_path(path) || tar::is_tar_path(path));
+    }
+
+    /**
+     * returns true if the file at name in an archive should be searched, going by the same --filter, --include, and --exclude rules as files in directories
+     */
+    fn wants_entry(&self, name: &Path) -> bool {
+        if self.filter && !matches_filter(name, &self.filter_for) {
+            return false;
+        }
+        if !self.includes.is_empty() && matches_any_glob(name, &self.includes).is_none() {
+            return false;
+        }
+        return matches_any_glob(name, &self.excludes).is_none();
+    }
+
+    /**
+     * returns a matcher for patterns, with the options that say how to match them (case, whole words or lines, regex, and how fuzzy)
+     */
+    fn build_matcher<S: AsRef<str>>(&self, patterns: &[S]) -> Result<Matcher, Box<dyn Error>> {
+        if self.fuzzy > 0 {
+            return Ok(Matcher::fuzzy(patterns, self.case_mode, self.match_kind, self.fuzzy));
+        }
+        if self.ignore_accents {
+            return Ok(Matcher::ignoring_accents(patterns, self.case_mode, self.match_kind));
+        }
+        return Matcher::new(patterns, self.case_mode, self.match_kind, self.regex);
     }
 
-    //fill paths_to_grep based on what config.path points to, and the value of config.recurse
-    if !base_path.is_dir() { //it's a file
-        paths_to_grep = vec![PathBuf::from(base_path)];
+    /**
+     * returns true if the output should be just the results, like grep's, without the banner, the headers, or the indentation
+     * that's the default when stdout isn't a terminal (so scripts can parse it), and always the case with --null and the formats that aren't text
+     */
+    pub fn plain(&self) -> bool {
+        return self.null || self.format != OutputFormat::Text || self.plain.unwrap_or_else(|| !self.output_is_terminal());
     }
-    else if config.recurse { //it's a directory, recurse
-        paths_to_grep = list_files_recurse(base_path);
+
+    /**
+     * returns true if the results are going to a terminal, rather than a file or a pipe
+     */
+    pub fn output_is_terminal(&self) -> bool {
+        return self.output.is_none() && io::stdout().is_terminal();
     }
-    else { //it's a directory, don't recurse
-        paths_to_grep = list_files(base_path);
+
+    /**
+     * returns true if the output mode needs to know how many matches each file has, not just whether it has any
+     */
+    pub fn counts_matches(&self) -> bool {
+        return matches!(self.output_mode, OutputMode::Count | OutputMode::CountMatches);
     }
 
-    //look through all paths_to_grep
-    println!("Files containing query: ");
-    paths_to_grep.iter().for_each(|path| {
-        //DATA
-        let contains_query:bool;
-        let path_as_string:String = path.to_string_lossy().to_string();
-        
-        //find out if the file contains the query
-        if config.case_sensitive {
-            contains_query = search(&config.query, &path).unwrap_or_else(|err| {
-                if config.verbose {eprintln!("Error searching {:?}: {}",path_as_string, err);}
-                false
-            });
+    /**
+     * returns path as it should be printed in the results, made absolute (with symlinks resolved) with --absolute-paths,
+     * or relative to the --relative-to directory (if it's under it, otherwise it's absolute too)
+     * on windows, it also uses the same separators as the paths the user gave
+     */
+    pub fn output_path<'p>(&self, path: &'p Path) -> Cow<'p, Path> {
+        if path.as_os_str() == STDIN_PATH {
+            return Cow::Borrowed(path);
+        }
+        //files that were deleted since they were found can't be canonicalized, so they're just joined to the current directory
+        let path: Cow<'p, Path> = if self.absolute_paths || self.relative_to.is_some() {
+            Cow::Owned(fs::canonicalize(path).or_else(|_| std::path::absolute(path)).unwrap_or_else(|_| path.to_path_buf()))
         } else {
-            contains_query = search_case_insensitive(&config.query, &path).unwrap_or_else(|err| {
-                if config.verbose {eprintln!("Error searching {:?}: {}",path_as_string, err);}
-                false
-            });
+            Cow::Borrowed(path)
+        };
+        //the base is canonical too, so the prefix is stripped a component at a time
+        let path: Cow<'p, Path> = match self.relative_to.as_ref().and_then(|base| path.strip_prefix(base).ok()) {
+            Some(relative) if relative.as_os_str().is_empty() => Cow::Owned(PathBuf::from(".")),
+            Some(relative) => Cow::Owned(relative.to_path_buf()),
+            None => path,
+        };
+
+        //on windows, paths are shown with the separators the user typed, and without the verbatim prefix where it isn't needed
+        #[cfg(windows)]
+        let path: Cow<'p, Path> = match path.to_str() {
+            Some(text) => {
+                //DATA
+                let separator: char = self.paths.iter().find_map(|root| root.to_str().and_then(winpath::typed_separator)).unwrap_or('\\');
+
+                Cow::Owned(PathBuf::from(winpath::with_separators(&winpath::strip_verbatim(text), separator).into_owned()))
+            },
+            None => path,
+        };
+
+        //return
+        return path;
+    }
+
+    /**
+     * sets the output format, throwing an error if a different one was already picked by another option
+     */
+    fn set_format(&mut self, format: OutputFormat, option: &str, picked_by: &mut Option<String>) -> Result<(),Box<dyn Error>> {
+        if let Some(previous) = picked_by.as_deref().filter(|_| self.format != format) {
+            return Err(format!("'{}' and '{}' can't be used together, only one output format can be picked.", previous, option).into());
         }
+        self.format = format;
+        *picked_by = Some(option.to_string());
+        return Ok(());
+    }
 
-        //if it does, print the file name
-        if contains_query {
-            println!("\t{}",path_as_string);
+    /**
+     * sets the output mode, throwing an error if a different one was already picked by another option
+     */
+    fn set_output_mode(&mut self, output_mode: OutputMode, option: &str, picked_by: &mut Option<String>) -> Result<(),Box<dyn Error>> {
+        if let Some(previous) = picked_by.as_deref().filter(|_| self.output_mode != output_mode) {
+            return Err(format!("'{}' and '{}' can't be used together, only one output mode can be picked.", previous, option).into());
         }
-    });
+        self.output_mode = output_mode;
+        *picked_by = Some(option.to_string());
+        return Ok(());
+    }
 
-    return Ok(());
+    /**
+     * throws an error naming every option that isn't valid, with the closest valid option as a suggestion
+     */
+    fn validate_options(options: &[(String,Option<String>)]) -> Result<(),Box<dyn Error>> {
+        //DATA
+        let invalid_options: Vec<String> = options.iter()
+            .filter(|(o,_)| !options::is_valid(o))
+            .map(|(o,_)| match closest_option(o) {
+                Some(suggestion) => format!("unknown option '{}', did you mean '{}'?", o, suggestion),
+                None => format!("unknown option '{}'", o),
+            })
+            .collect();
+
+        //throw error if any options aren't valid
+        if !invalid_options.is_empty() {
+            return Err(invalid_options.join("; ").into());
+        }
+
+        //return
+        return Ok(());
+    }
+
+    /**
+     * expands bundled short flags (e.g. "-rv" into "-r" and "-v"), long options are left as they are
+     * like getopt, the rest of a bundle after a flag that takes a value is its value, so "-m1" is "-m 1", and "-rC2" is "-r -C 2"
+     */
+    #[allow(clippy::type_complexity)]
+    fn expand_short_flags(option: &str) -> Result<Vec<(String,Option<String>)>,Box<dyn Error>> {
+        //DATA
+        let mut expanded: Vec<(String,Option<String>)> = Vec::new();
+
+        //long options, and single short flags, don't need to be expanded
+        if option.starts_with("--") || option.chars().count() <= 2 {
+            return Ok(vec![(option.to_string(), None)]);
+        }
+
+        //expand the bundle one character at a time, every character (up to a value) must be a valid short flag
+        for (index, c) in option.char_indices().skip(1) {
+            let short = format!("-{}", c);
+            if !options::is_valid(&short) {
+                return Err(format!("Unknown flag '{}' in '{}'.", c, option).into());
+            }
+            if options::takes_value(&short) {
+                //DATA
+                let value: &str = &option[index + c.len_utf8()..];
+
+                expanded.push((short, Some(value.to_string()).filter(|value| !value.is_empty())));
+                break;
+            }
+            expanded.push((short, None));
+        }
+
+        //return
+        return Ok(expanded);
+    }
+
+    /**
+     * walks through the arguments, sorting them into options (with their values, if they take one), and positional arguments
+     */
+    #[allow(clippy::type_complexity)]
+    fn parse_arguments<S: AsRef<OsStr>>(args: &[S]) -> Result<(Vec<(String,Option<String>)>,Vec<OsString>),Box<dyn Error>> {
+        //DATA
+        let mut options: Vec<(String,Option<String>)> = Vec::new();
+        let mut positionals: Vec<OsString> = Vec::new();
+        let mut args_iter = args.iter().map(AsRef::as_ref).skip(1); //skip first argument
+
+        while let Some(arg) = args_iter.next() {
+            //everything after a literal "--" is positional, even if it starts with a '-'
+            if arg == "--" {
+                positionals.extend(args_iter.by_ref().map(OsStr::to_os_string));
+                break;
+            }
+            if !arg.as_encoded_bytes().starts_with(b"-") || arg == STDIN_PATH {
+                positionals.push(arg.to_os_string());
+                continue;
+            }
+            //positional arguments can be anything, but options have to be utf-8
+            let Some(arg) = arg.to_str() else {
+                return Err(format!("Invalid option '{}', options have to be valid UTF-8.", arg.to_string_lossy()).into());
+            };
+            //it's a long option with an attached value, like --filter=rs,toml
+            if let Some((option, value)) = arg.strip_prefix("--").and_then(|a| a.split_once('=')) {
+                let option = format!("--{}", option);
+                if options::is_valid(&option) && !options::takes_value(&option) && !options::takes_optional_value(&option) {
+                    return Err(format!("Option '{}' doesn't take a value.", option).into());
+                }
+                options.push((option, Some(value.to_string())));
+                continue;
+            }
+            //it's an option, options that take a value consume the next argument
+            for (option, attached) in Config::expand_short_flags(arg)? {
+                if let Some(value) = attached {
+                    options.push((option, Some(value)));
+                } else if options::takes_value(&option) {
+                    match args_iter.next().map(|value| value.to_str().ok_or(value)) {
+                        //a value that is an option was probably meant to be one (like a forgotten value before it), use --option=value to get around this
+                        //anything else starting with a '-' is just a value, like the pattern in "-e -foo", or the "-" in "--replace -"
+                        Some(Ok(value)) if is_known_option(value) => {
+                            return Err(format!("Option '{}' requires a value, but got the option '{}', use '{}={}' if that's the value.",
+                                option, value, options::find(&option).map_or(option.as_str(), |spec| spec.long), value).into());
+                        },
+                        Some(Ok(value)) => options.push((option, Some(value.to_string()))),
+                        Some(Err(value)) => return Err(format!("The value '{}' for '{}' isn't valid UTF-8, paths that aren't can be given as the first argument instead.", value.to_string_lossy(), option).into()),
+                        None => return Err(format!("Option '{}' requires a value.", option).into()),
+                    }
+                } else {
+                    options.push((option, None));
+                }
+            }
+        }
+
+        //return
+        return Ok((options,positionals));
+    }
 }
 
-pub fn help() {
-    println!("                              grep-directory.exe");
-    println!("                              By Anthony Rubick\n");
-    println!("search through all files in a directory for a given string\n");
-
-    println!("USAGE:\n\tgrep-directory.exe [OPTIONS]... [PATH] \"[QUERY]\"\n");
-
-    println!("OPTIONS:");
-    println!("\t-c\t--case-insensitive\t\t\tis query case sensitive (default: yes)");
-    //println!("\t-f\t--filter <EXTENSIONS>...\t\tComma separated list of extensions, will only count lines of files with these extensions");
-    println!("\t-r,\t--recursive\t\t\t\tSearch through subdirectories");
-    println!("\t-v,\t--verbose\t\t\t\tinclude all error messages in output");
-    println!("\t-h,\t-help\t\t\t\t\tPrints help information\n");
-    
-    println!("PATH:\n\tPath to search in, first argument without a '-'\n");
-    
-    println!("QUERY:\n\tString to search for, all the stuff after the path\n\twrap in \"'s if it contains spaces\n");
+/**
+ * returns true if arg is an option, written the way it could be on the command line (like "-v", "--verbose", or "--filter=rs")
+ */
+fn is_known_option(arg: &str) -> bool {
+    return options::is_valid(arg) || arg.strip_prefix("--").and_then(|arg| arg.split_once('=')).is_some_and(|(option, _)| options::is_valid(&format!("--{}", option)));
+}
+
+/**
+ * returns where the config file is, $XDG_CONFIG_HOME/grep-directory/config or ~/.config/grep-directory/config (%APPDATA%\grep-directory\config on windows)
+ */
+fn default_config_file() -> Option<PathBuf> {
+    //DATA
+    let config_dir: PathBuf;
 
+    //find the config directory
+    if let Some(dir) = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        config_dir = PathBuf::from(dir);
+    } else if cfg!(windows) {
+        config_dir = PathBuf::from(env::var_os("APPDATA")?);
+    } else {
+        config_dir = PathBuf::from(env::var_os("HOME")?).join(".config");
+    }
+
+    //return
+    return Some(config_dir.join("grep-directory").join("config"));
 }
 
-pub fn search<'a> (query: &'a str, path: &'a Path) -> Result<bool,Box<dyn Error>> {
+/**
+ * reads the options in the config file at path, one per line, like "recursive" or "filter rs,toml"
+ * blank lines and lines starting with a '#' are skipped, and unknown options are warned about rather than being errors,
+ * so old config files keep working. a missing config file just means there are no options
+ */
+fn read_config_file(path: &Path) -> Vec<(String,Option<String>)> {
     //DATA
     let contents:String;
-    
+    let mut options: Vec<(String,Option<String>)> = Vec::new();
+
     //read file
     match fs::read_to_string(path) {
         Ok(val) => contents = val,
-        Err(e) => return Err(e.into()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return options,
+        Err(e) => {
+            eprintln!("warning: couldn't read config file {:?}: {}", path, e);
+            return options;
+        },
+    }
+
+    //parse options out of contents
+    for line in contents.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        //DATA
+        let (name, value) = match line.split_once(char::is_whitespace) {
+            Some((name, value)) => (name, Some(value.trim().to_string())),
+            None => (line, None),
+        };
+        let option = format!("--{}", name.trim_start_matches('-'));
+
+        //warn about, and skip, anything that isn't a valid option
+        //options with an optional value (like "filenames", or "filenames path") are fine either way
+        if !options::is_valid(&option) {
+            eprintln!("warning: unknown option '{}' in config file {:?}, ignoring it", name, path);
+        } else if options::takes_value(&option) && value.is_none() {
+            eprintln!("warning: option '{}' in config file {:?} requires a value, ignoring it", name, path);
+        } else if value.is_some() && !options::takes_value(&option) && !options::takes_optional_value(&option) {
+            eprintln!("warning: option '{}' in config file {:?} doesn't take a value, ignoring it", name, path);
+        } else {
+            options.push((option, value));
+        }
     }
 
-    //parse contents for query, case sensitive
-    //return true if found, false otherwise
-    return Ok(contents.contains(&query));
+    //return
+    return options;
 }
 
-pub fn search_case_insensitive<'a> (query: &'a str, path: &'a Path) -> Result<bool,Box<dyn Error>> {
+/**
+ * reads the patterns in the file at path, one per line
+ * trailing whitespace is trimmed, and blank lines and lines starting with a '#' are skipped
+ */
+fn read_pattern_file(path: &str) -> Result<Vec<String>,Box<dyn Error>> {
     //DATA
     let contents:String;
-    
+    let patterns:Vec<String>;
+
     //read file
     match fs::read_to_string(path) {
         Ok(val) => contents = val,
-        Err(e) => return Err(e.into()),
+        Err(e) => return Err(format!("Couldn't read pattern file '{}': {}", path, e).into()),
+    }
+
+    //parse patterns out of contents
+    patterns = contents.lines()
+        .map(|line| line.trim_end())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+
+    //error handling
+    if patterns.is_empty() {
+        return Err(format!("Pattern file '{}' doesn't contain any patterns.", path).into());
     }
 
-    //parse contents for query, case sensitive
-    //return true if found, false otherwise
-    return Ok(contents.to_ascii_lowercase().contains(&query.to_ascii_lowercase()));
+    //return
+    return Ok(patterns);
 }
 
 /**
- * returns a vector containing paths to all files in path and subdirectories of path
+ * parses the value of option as a non-negative whole number
  */
-fn list_files_recurse(path: &Path) -> Vec<PathBuf> {
-    let mut vec = Vec::new();
-    _list_files_recurse(&mut vec,&path);
-    vec
+fn parse_number(option: &str, value: &Option<String>) -> Result<usize,Box<dyn Error>> {
+    let value = value.as_deref().unwrap_or_default();
+    return value.trim().parse::<usize>().map_err(|_| format!("Invalid value '{}' for '{}', expected a non-negative whole number.", value, option).into());
 }
-fn _list_files_recurse(vec: &mut Vec<PathBuf>, path: &Path) {
-    if path.is_dir() {
-        let paths = fs::read_dir(&path).unwrap();
-        for path_result in paths {
-            let full_path = path_result.unwrap().path();
-            if full_path.is_dir() {
-                _list_files_recurse(vec, &full_path);
-            } else {
-                vec.push(full_path);
-            }
-        }
-    }
+
+/**
+ * parses the value of option as a size in bytes, with an optional K, M, or G suffix (powers of 1024), e.g. 10M
+ */
+fn parse_size(option: &str, value: &Option<String>) -> Result<u64,Box<dyn Error>> {
+    //DATA
+    let value = value.as_deref().unwrap_or_default().trim();
+    let (number, multiplier) = match value.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&value[..index], 1024),
+        Some((index, 'm' | 'M')) => (&value[..index], 1024 * 1024),
+        Some((index, 'g' | 'G')) => (&value[..index], 1024 * 1024 * 1024),
+        _ => (value, 1),
+    };
+
+    //parse the number, and apply the suffix
+    return number.parse::<u64>().ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| format!("Invalid value '{}' for '{}', expected a size like 512, 10K, 10M, or 1G.", value, option).into());
 }
+
 /**
- * returns a vector containing paths to all files in path, but not subdirectories of path
+ * parses the value of option as a point in time, either a duration before now (a whole number of s, m, h, d, or w, e.g. 30m or 2d),
+ * or a date, like 2024-01-01, which can have a time after it, like 2024-01-01T12:30 (or 12:30:45), in UTC
  */
-fn list_files(path: &Path) -> Vec<PathBuf> {
-    let mut vec = Vec::new();
-    if path.is_dir() {
-        let paths = fs::read_dir(&path).unwrap();
-        for path_results in paths {
-            let full_path = path_results.unwrap().path();
-            if !full_path.is_dir() {
-                vec.push(full_path);
+fn parse_time(option: &str, value: &Option<String>) -> Result<SystemTime,Box<dyn Error>> {
+    //DATA
+    let value = value.as_deref().unwrap_or_default().trim();
+    let invalid = || -> Box<dyn Error> {format!("Invalid value '{}' for '{}', expected a duration like 30m, 12h, 2d, or 1w, or a date like 2024-01-01 (or 2024-01-01T12:30).", value, option).into()};
+    let unit_seconds: Option<u64> = match value.chars().last() {
+        Some('s') => Some(1),
+        Some('m') => Some(60),
+        Some('h') => Some(60 * 60),
+        Some('d') => Some(24 * 60 * 60),
+        Some('w') => Some(7 * 24 * 60 * 60),
+        _ => None,
+    };
+
+    //a duration is how long before now
+    if let Some(unit_seconds) = unit_seconds {
+        //DATA
+        let seconds: u64 = value[..value.len() - 1].parse::<u64>().ok().and_then(|number| number.checked_mul(unit_seconds)).ok_or_else(invalid)?;
+
+        return SystemTime::now().checked_sub(Duration::from_secs(seconds)).ok_or_else(|| format!("Invalid value '{}' for '{}', that's too long ago.", value, option).into());
+    }
+
+    //DATA
+    //a date is midnight at the start of it, unless it has a time too
+    let (date, time) = value.split_once(['T', ' ']).unwrap_or((value, "00:00"));
+    let date: Vec<i64> = date.split('-').map(|number| number.parse::<i64>()).collect::<Result<Vec<i64>, _>>().map_err(|_| invalid())?;
+    let time: Vec<i64> = time.split(':').map(|number| number.parse::<i64>()).collect::<Result<Vec<i64>, _>>().map_err(|_| invalid())?;
+    let (&[year, month, day], &[hour, minute, ref second @ ..]) = (&date[..], &time[..]) else {
+        return Err(invalid());
+    };
+    let second: i64 = match second {
+        [] => 0,
+        [second] => *second,
+        _ => return Err(invalid()),
+    };
+
+    if !(1..=9999).contains(&year) || !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) || !(0..24).contains(&hour) || !(0..60).contains(&minute) || !(0..60).contains(&second) {
+        return Err(invalid());
+    }
+    let seconds: i64 = days_since_epoch(year, month, day) * 24 * 60 * 60 + hour * 60 * 60 + minute * 60 + second;
+    return match seconds {
+        0.. => UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64)),
+        _ => UNIX_EPOCH.checked_sub(Duration::from_secs(seconds.unsigned_abs())),
+    }.ok_or_else(|| format!("Invalid value '{}' for '{}', that date can't be compared to the times files were modified on this system.", value, option).into());
+}
+
+/**
+ * returns how many days the month (1 to 12) of year has
+ */
+fn days_in_month(year: i64, month: i64) -> i64 {
+    return match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+}
+
+/**
+ * returns how many days after 1970-01-01 the date is (negative for ones before it), in the gregorian calendar
+ */
+fn days_since_epoch(year: i64, month: i64, day: i64) -> i64 {
+    //DATA
+    //years are counted from march, so the leap day is at the end of them, and in 400 year eras, which all have the same number of days
+    let year: i64 = if month <= 2 {year - 1} else {year};
+    let era: i64 = year.div_euclid(400);
+    let year_of_era: i64 = year - era * 400;
+    let day_of_year: i64 = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era: i64 = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    //return
+    return era * 146097 + day_of_era - 719468;
+}
+
+/**
+ * parses the value of option as a (positive) number of seconds, which can have a fraction, e.g. 2.5
+ */
+fn parse_seconds(option: &str, value: &Option<String>) -> Result<Duration,Box<dyn Error>> {
+    let value = value.as_deref().unwrap_or_default();
+    return value.trim().parse::<f64>().ok()
+        .filter(|seconds| *seconds > 0.0)
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| format!("Invalid value '{}' for '{}', expected a number of seconds, like 10 or 2.5.", value, option).into());
+}
+
+/**
+ * parses the value of option as a glob that's matched against file names,
+ * so it can't contain a directory separator
+ */
+fn parse_file_name_glob(option: &str, value: &Option<String>) -> Result<Glob,Box<dyn Error>> {
+    let value = value.as_deref().unwrap_or_default();
+    if value.contains('/') {
+        return Err(format!("Invalid value '{}' for '{}', globs are matched against file names, so they can't contain directory separators.", value, option).into());
+    }
+    return Glob::new(value);
+}
+
+/**
+ * returns the valid option closest to option, as long as it's close enough to plausibly be a typo
+ */
+fn closest_option(option: &str) -> Option<&'static str> {
+    //DATA
+    let max_distance = 1.max(option.chars().count() / 3);
+
+    //every short flag is one edit away from every other short flag, so suggesting one would just be noise
+    if option.chars().count() <= 2 {
+        return None;
+    }
+
+    return options::names()
+        .map(|valid| (levenshtein(option, valid), valid))
+        .filter(|(distance,_)| *distance <= max_distance)
+        .min_by_key(|(distance,_)| *distance)
+        .map(|(_,valid)| valid);
+}
+
+/**
+ * returns the edit distance (insertions, deletions, and substitutions) between a and b
+ */
+fn levenshtein(a: &str, b: &str) -> usize {
+    //DATA
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    let mut current_row: Vec<usize> = vec![0; b.len() + 1];
+
+    //classic two row dynamic programming approach
+    for (i, a_char) in a.chars().enumerate() {
+        current_row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution_cost = if a_char == *b_char {0} else {1};
+            current_row[j+1] = (previous_row[j] + substitution_cost)
+                .min(previous_row[j+1] + 1)
+                .min(current_row[j] + 1);
+        }
+        std::mem::swap(&mut previous_row, &mut current_row);
+    }
+
+    //return
+    return previous_row[b.len()];
+}
+
+/**
+ * what run found, so callers (like main, for the exit code) can tell if anything matched
+ */
+#[derive(Debug, Default)]
+pub struct MatchSummary {
+    pub matched_files: usize,   //the number of files that were listed (or that contain the query, when counting)
+    pub total_matches: usize,   //with --count or --count-matches, how many matches there were in all the files put together
+    pub stats: Stats,
+    pub interrupted: bool,      //whether Ctrl-C stopped the search before it was done
+    pub file_errors: Vec<PathError>,    //the files that couldn't be searched
+    pub walk_errors: Vec<PathError>,    //the directories that couldn't be read
+    pub results_written: usize, //how many results (files, lines, or counts) were printed
+}
+impl MatchSummary {
+    /**
+     * returns a line summing up the errors, like "2 files couldn't be read (permission denied: 1, invalid data: 1)",
+     * for the files and then the directories, leaving out the ones without any
+     */
+    pub fn error_summary(&self) -> Vec<String> {
+        //DATA
+        let summarize = |errors: &[PathError], what: &str| -> Option<String> {
+            if errors.is_empty() {
+                return None;
+            }
+            //count each kind of error, the most common first
+            let mut kinds: Vec<(String, usize)> = Vec::new();
+            for error in errors {
+                let kind: String = error.error.kind().to_string();
+                match kinds.iter_mut().find(|(existing, _)| *existing == kind) {
+                    Some((_, count)) => *count += 1,
+                    None => kinds.push((kind, 1)),
+                }
+            }
+            kinds.sort_by(|(kind_a, count_a), (kind_b, count_b)| count_b.cmp(count_a).then(kind_a.cmp(kind_b)));
+            return Some(format!("{} {} couldn't be read ({})", errors.len(), what, kinds.iter().map(|(kind, count)| format!("{}: {}", kind, count)).collect::<Vec<String>>().join(", ")));
+        };
+
+        //return
+        return [summarize(&self.file_errors, if self.file_errors.len() == 1 {"file"} else {"files"}), summarize(&self.walk_errors, if self.walk_errors.len() == 1 {"directory"} else {"directories"})]
+            .into_iter().flatten().collect();
+    }
+}
+
+/**
+ * the numbers --stats prints at the end of a run
+ */
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Stats {
+    pub files_searched: usize,
+    pub files_matched: usize,   //files that contain the query, even when listing the ones that don't
+    pub total_matches: usize,   //all the matches in all the files (with --stats, files are searched all the way through even when just listing them)
+    pub files_skipped: usize,   //files that were too big, binary, or couldn't be read
+    pub skipped_by_date: usize, //files that weren't modified between --newer-than and --older-than, so they weren't searched at all
+    pub skipped_by_size: usize, //files that weren't between --min-size and --max-size, so they weren't searched at all
+    pub empty_files: usize,     //files that were empty, so they weren't searched (they're still listed as not containing the query)
+    pub walk_errors: usize,     //directories (or entries in them) that couldn't be read while listing files
+    pub elapsed: Duration,
+}
+
+/**
+ * a file that couldn't be searched, or a directory (or an entry in one) that couldn't be read while listing files,
+ * either way, everything else carries on without it
+ */
+#[derive(Debug)]
+pub struct PathError {
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+/**
+ * a line that contains the query, or a line of context around one
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineMatch {
+    pub line_number: usize,         //starting from 1
+    pub byte_offset: u64,           //of the start of the line, from the start of the file
+    pub line: String,               //without the line ending
+    pub matches: Vec<Range<usize>>, //the byte ranges of the matches in line, empty for context lines
+    pub encoding: Encoding,         //what the file was in, so offsets in line can be turned into offsets in the file
+    pub raw: Option<Vec<u8>>,       //the line as it is in the file, when invalid utf-8 in it was replaced (which moves everything after it)
+}
+impl LineMatch {
+    /**
+     * returns the column the first match starts at, starting from 1, counted in characters (not bytes)
+     */
+    pub fn column(&self) -> usize {
+        return self.column_at(self.matches.first().map_or(0, |first| first.start));
+    }
+
+    /**
+     * returns the column of the byte index in line, starting from 1, counted in characters (not bytes)
+     */
+    pub fn column_at(&self, index: usize) -> usize {
+        return self.line[..index].chars().count() + 1;
+    }
+
+    /**
+     * returns the byte offset of the first match, from the start of the file
+     */
+    pub fn match_offset(&self) -> u64 {
+        return self.offset_at(self.matches.first().map_or(0, |first| first.start));
+    }
+
+    /**
+     * returns the byte offset of the byte index in line, from the start of the file (in the file's own encoding)
+     */
+    pub fn offset_at(&self, index: usize) -> u64 {
+        return self.byte_offset + match &self.raw {
+            Some(raw) => raw_offset(raw, index),
+            None => self.encoding.encoded_len(&self.line[..index]),
+        } as u64;
+    }
+}
+
+/**
+ * what searching one file found
+ */
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FileMatches {
+    pub match_count: usize,
+    pub lines: Vec<LineMatch>,  //only filled in when printing lines
+    pub binary: bool,           //whether the file looked binary
+    pub empty: bool,            //whether the file was empty, so it wasn't even read
+    pub name_match: bool,       //whether the file's name matched, with --also-match-names
+    pub hex_dumps: Vec<HexDump>,    //the bytes around the matches in a binary file, with --binary-context
+}
+impl FileMatches {
+    /**
+     * returns true if the file matched, by its contents, or by its name
+     */
+    pub fn is_match(&self) -> bool {
+        return self.match_count > 0 || self.name_match;
+    }
+}
+
+pub fn run(config: Config) -> Result<MatchSummary, Box<dyn Error>> {
+    //the results go to the --output file if there is one, and stdout otherwise
+    let output: Box<dyn Write> = match config.output.as_ref().map(|(_, file)| file.try_clone()).transpose()? {
+        Some(file) => Box::new(file),
+        None => Box::new(io::stdout().lock()),
+    };
+    //lines are written one at a time, so buffer them rather than writing each by itself
+    let mut out = io::BufWriter::new(output);
+    let summary = run_with_output(config, &mut out)?;
+    out.flush()?;
+    return Ok(summary);
+}
+
+/**
+ * same as run, but the results are written to out instead of stdout
+ */
+pub fn run_with_output<W: Write>(config: Config, out: &mut W) -> Result<MatchSummary, Box<dyn Error>> {
+    //DATA
+    let start: Instant = Instant::now();
+    let mut summary: MatchSummary = MatchSummary::default();
+    let mut paths_to_grep: Vec<PathBuf> = Vec::new();
+    let mut seen: HashSet<FileId> = HashSet::new(); //files already in paths_to_grep, so files under more than one path are only searched once
+    let output_id: Option<FileId> = config.output.as_ref().map(|(path, _)| FileId::of(path));
+
+    //if user asked for help, give instructions
+    if config.help {
+        write_help(out)?;
+        return Ok(summary);
+    }
+    //if user asked for the version, print it
+    if config.version {
+        write_version(out)?;
+        return Ok(summary);
+    }
+    //if user asked for the man page, print it
+    if config.generate_man {
+        man::write_man(out)?;
+        return Ok(summary);
+    }
+    //if user asked for a completion script, print it
+    if let Some(shell) = config.completions {
+        completions::write_completions(shell, out)?;
+        return Ok(summary);
+    }
+    //if user asked for the file types, list them
+    if config.type_list {
+        for (name, globs) in config.types.iter() {
+            writeln!(out, "{}: {}", name, globs.join(", "))?;
+        }
+        return Ok(summary);
+    }
+
+    //say what's being searched for, and where, unless the output is just the results
+    if !config.quiet && !config.plain() {
+        write_banner(&config, out)?;
+    }
+
+    for base_path in &config.paths {
+        //DATA
+        let mut files: Vec<PathBuf>;
+        let mut walk_errors: Vec<PathError> = Vec::new();
+
+        //paths that are too long for windows have to be verbatim to be read
+        let base_path: &PathBuf = &long_path(base_path.clone());
+
+        //the path existed when the config was made, but it could have been deleted since
+        if base_path.as_os_str() != STDIN_PATH && !base_path.exists() {
+            return Err(format!("Invalid path '{}', it doesn't exist anymore.", base_path.to_string_lossy()).into());
+        }
+
+        //fill files based on what base_path points to, and the value of config.recurse
+        if base_path.as_os_str() == STDIN_PATH { //it's stdin, there's nothing to list
+            files = vec![base_path.clone()];
+        }
+        else if config.min_depth > 0 && (!config.recurse || !base_path.is_dir()) { //everything here is at depth 0, so it's all too shallow
+            files = Vec::new();
+        }
+        else if !base_path.is_dir() { //it's a file
+            files = vec![base_path.clone()];
+        }
+        else if config.recurse { //it's a directory, recurse
+            files = list_files_recurse(base_path, &config, &mut walk_errors);
+        }
+        else { //it's a directory, don't recurse
+            files = list_files(base_path, &config, &mut walk_errors);
+        }
+
+        //directories that couldn't be read are skipped, the rest are still searched
+        for walk_error in &walk_errors {
+            if config.verbose {eprintln!("Error reading {:?}: {}",display_path(&walk_error.path), walk_error.error);}
+        }
+        summary.stats.walk_errors += walk_errors.len();
+        summary.walk_errors.append(&mut walk_errors);
+
+        //only keep files with one of the extensions the user filtered for
+        //with --search-zip, archives are kept either way, it's the files in them that are filtered
+        if config.filter && base_path.is_dir() {
+            files.retain(|path| config.is_archive(path) || matches_filter(path, &config.filter_for));
+        }
+        //only keep files whose names match one of the include globs
+        if !config.includes.is_empty() && base_path.is_dir() {
+            files.retain(|path| config.is_archive(path) || matches_any_glob(path, &config.includes).is_some());
+        }
+        //remove files whose names match one of the exclude globs, even if they matched an include glob
+        if !config.excludes.is_empty() && base_path.is_dir() {
+            files.retain(|path| match matches_any_glob(path, &config.excludes) {
+                Some(glob) => {
+                    if config.verbose {eprintln!("Skipping {:?}: excluded by '{}'",display_path(path), glob.as_str());}
+                    false
+                },
+                None => true,
+            });
+        }
+
+        //add files to paths_to_grep, skipping any we've already seen under another path
+        //they're put in order first, so which of the paths to a file is kept doesn't depend on what order the directories listed them in
+        config.sort.sort(&mut files);
+        for file in files {
+            //the output file is being written to, searching it would find the results of this search
+            if output_id.as_ref().is_some_and(|output_id| *output_id == FileId::of(&file)) {
+                if config.verbose {eprintln!("Skipping {:?}: it's the --output file",display_path(&file));}
+            } else if !config.dedupe || seen.insert(FileId::of(&file)) {
+                paths_to_grep.push(file);
+            } else if config.verbose {
+                eprintln!("Skipping {:?}: the same file as one that's already being searched",display_path(&file));
+            }
+        }
+    }
+
+    //only keep files modified between --newer-than and --older-than (going by their metadata, so they aren't opened)
+    if config.newer_than.is_some() || config.older_than.is_some() {
+        //DATA
+        let candidates: usize = paths_to_grep.len();
+
+        paths_to_grep.retain(|path| {
+            //DATA
+            let keep: bool = config.modified_in_window(path);
+
+            if !keep && config.verbose {eprintln!("Skipping {:?}: not modified between --newer-than and --older-than",display_path(path));}
+            keep
+        });
+        summary.stats.skipped_by_date += candidates - paths_to_grep.len();
+    }
+
+    //and only keep files between --min-size and --max-size
+    if config.min_size.is_some() || config.max_size.is_some() {
+        //DATA
+        let candidates: usize = paths_to_grep.len();
+
+        paths_to_grep.retain(|path| {
+            //DATA
+            let keep: bool = config.size_in_range(path);
+
+            if !keep && config.verbose {eprintln!("Skipping {:?}: not between --min-size and --max-size",display_path(path));}
+            keep
+        });
+        summary.stats.skipped_by_size += candidates - paths_to_grep.len();
+    }
+
+    //put the files in order, so the output is the same every time
+    config.sort.sort(&mut paths_to_grep);
+
+    //with nothing to search, say so, instead of printing an empty list
+    if paths_to_grep.is_empty() {
+        if !config.quiet {
+            eprintln!("No files to search under {}", config.paths.iter().map(|path| format!("'{}'", display_path(path))).collect::<Vec<String>>().join(", "));
+        }
+        summary.stats.elapsed = start.elapsed();
+        Printer::new(out, &config, false).stats(&summary.stats)?;
+        return Ok(summary);
+    }
+
+    //DATA
+    //when the only thing being searched is one file, everyone already knows which file the results are from (unless it's an archive of them)
+    let with_filename: bool = config.with_filename.unwrap_or(config.paths.len() != 1 || config.paths[0].is_dir() || config.is_archive(&config.paths[0]));
+    let mut printer: Printer<W> = Printer::new(out, &config, with_filename);
+    let mut write_error: Option<io::Error> = None; //if writing the output fails, there's no point in searching any more
+
+    //in files mode, just list everything that would have been searched (whose names match, with --filenames)
+    if config.output_mode == OutputMode::Files {
+        printer.header(true)?;
+        for path in paths_to_grep.iter().filter(|path| config.name_matches(path)) {
+            summary.matched_files += 1;
+            if config.quiet {
+                break;
+            }
+            printer.file_name(path, None, "")?;
+        }
+        summary.stats.elapsed = start.elapsed();
+        printer.stats(&summary.stats)?;
+        return Ok(summary);
+    }
+
+    //whether the files to print are the ones that contain the query, --invert-match flips what counts as containing it
+    let print_matching: bool = match config.output_mode {
+        OutputMode::FilesWithMatches | OutputMode::Files | OutputMode::Count | OutputMode::CountMatches | OutputMode::Lines => !config.invert_match,
+        OutputMode::FilesWithoutMatch => config.invert_match,
+    };
+
+    //look through all paths_to_grep
+    printer.header(print_matching)?;
+    for walk_error in &summary.walk_errors {
+        printer.error(walk_error)?;
+    }
+    search_paths(&paths_to_grep, &config, |path, file_matches| {
+        //files that were skipped, or couldn't be searched, are never printed
+        let file_matches: FileMatches = match file_matches {
+            Ok(Some(file_matches)) => file_matches,
+            Ok(None) => {
+                summary.stats.files_skipped += 1;
+                return true;
+            },
+            Err(error) => {
+                summary.stats.files_skipped += 1;
+                if let Err(err) = printer.error(&error) {
+                    write_error = Some(err);
+                    return false;
+                }
+                summary.file_errors.push(error);
+                return true;
+            },
+        };
+        if file_matches.empty {
+            summary.stats.empty_files += 1;
+        } else {
+            summary.stats.files_searched += 1;
+        }
+        summary.stats.total_matches += file_matches.match_count;
+        if file_matches.is_match() {
+            summary.stats.files_matched += 1;
+        }
+        if config.counts_matches() {
+            summary.total_matches += file_matches.match_count;
+        }
+        if file_matches.is_match() == print_matching {
+            summary.matched_files += 1;
+            //in quiet mode only the exit code matters, so there's no point in looking any further (unless there's a total to add up)
+            if config.quiet && config.output_mode != OutputMode::CountMatches && !config.stats {
+                return false;
+            }
+        }
+        if config.quiet {
+            return true;
+        }
+
+        //print whatever this mode prints about the file
+        match printer.file(path, &file_matches, print_matching) {
+            Ok(()) => true,
+            Err(err) => {
+                write_error = Some(err);
+                false
+            },
+        }
+    });
+    if let Some(err) = write_error {
+        return Err(err.into());
+    }
+
+    //with --count-matches, the total is all that's printed
+    printer.total(summary.total_matches)?;
+    summary.stats.elapsed = start.elapsed();
+    printer.stats(&summary.stats)?;
+    summary.results_written = printer.results;
+    summary.interrupted = interrupt::interrupted();
+
+    return Ok(summary);
+}
+
+/**
+ * prints the results of a search to out, in whatever way config says to
+ * keeps track of what's been printed so far, for the things that depend on it (like the "--" between groups of context lines)
+ */
+struct Printer<'a, W: Write> {
+    out: &'a mut W,
+    config: &'a Config,
+    color: bool,
+    hyperlinks: bool,                       //whether file names are links to the files, for terminals that support them
+    with_filename: bool,                    //whether lines and counts say which file they're from
+    heading: bool,                          //whether lines are grouped under the name of their file
+    plain: bool,                            //whether to leave out the headers and indentation
+    printed_files: bool,                    //whether the lines of any files have been printed yet, with headings
+    last_printed: Option<(PathBuf, usize)>, //the file and number of the last line printed, with context
+    printed_lines: bool,                    //whether any lines have been printed yet, with context
+    started: bool,                          //whether the start of the document (the start of the json, or the csv header) has been printed yet
+    json_results: usize,                    //how many results have been printed in the json document
+    results: usize,                         //how many results (files, lines, counts, or rows) have been printed, context lines aren't results
+}
+impl<'a, W: Write> Printer<'a, W> {
+    fn new(out: &'a mut W, config: &'a Config, with_filename: bool) -> Printer<'a, W> {
+        //separating with nulls (or json) is for other programs, which don't want color codes, or headings
+        let color: bool = config.color.enabled(config.output_is_terminal()) && !config.null && config.format == OutputFormat::Text;
+        //headings are easier for people to read, but harder for other programs, so by default they're only used in a terminal
+        //links are escape codes too, so they're left out wherever color is
+        let hyperlinks: bool = match config.hyperlinks {
+            ColorChoice::Always => !config.null && config.format == OutputFormat::Text,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => color,
+        };
+        let heading: bool = with_filename && config.format == OutputFormat::Text && config.heading.unwrap_or_else(|| config.output_is_terminal());
+        return Printer { out, config, color, hyperlinks, with_filename, heading, plain: config.plain(), printed_files: false, last_printed: None, printed_lines: false, started: false, json_results: 0, results: 0 };
+    }
+
+    /**
+     * returns path as it's printed next to results, colored, and made a link to the file when hyperlinks are on
+     */
+    fn path(&self, path: &Path) -> String {
+        //DATA
+        let painted: String = paint(&display_path(path), COLOR_PATH, self.color);
+
+        //stdin isn't a file that can be opened
+        if !self.hyperlinks || path.as_os_str() == STDIN_PATH {
+            return painted;
+        }
+        //paths printed relative to --relative-to are relative to it, not the current directory (and joining an absolute path to it is just that path)
+        let target: Cow<Path> = match &self.config.relative_to {
+            Some(base) => Cow::Owned(base.join(path)),
+            None => Cow::Borrowed(path),
+        };
+        return match hyperlink::file_url(&target) {
+            Some(url) => hyperlink::wrap(&painted, &url),
+            None => painted,
+        };
+    }
+
+    /**
+     * prints the line that goes before the results, if this mode has one
+     * print_matching is whether the files that will be listed contain the query
+     */
+    fn header(&mut self, print_matching: bool) -> io::Result<()> {
+        if self.config.quiet {
+            return Ok(());
+        }
+        //json and csv start the document instead (and json lines don't have anything to start)
+        if self.config.format == OutputFormat::Json {
+            return self.json_start();
+        }
+        if self.config.format == OutputFormat::Csv {
+            return self.csv_start();
+        }
+        //the header is just decoration, which plain output (and separating with nulls) doesn't want
+        if self.plain {
+            return Ok(());
+        }
+        return match self.config.output_mode {
+            OutputMode::Files if self.config.filenames.is_some() => writeln!(self.out, "Files {}named like query: ", if self.config.invert_match {"not "} else {""}),
+            OutputMode::Files => writeln!(self.out, "Files to search: "),
+            OutputMode::Count => writeln!(self.out, "Matches per file: "),
+            OutputMode::CountMatches | OutputMode::Lines => Ok(()),
+            OutputMode::FilesWithMatches | OutputMode::FilesWithoutMatch => writeln!(self.out, "Files {}containing query: ", if print_matching {""} else {"not "}),
+        };
+    }
+
+    /**
+     * prints the name of the file at raw (the path it was found at) in a list of files, followed by extra (like the count, or byte offset) if there is one, then tag
+     */
+    fn file_name(&mut self, raw: &Path, extra: Option<String>, tag: &str) -> io::Result<()> {
+        //DATA
+        let config: &Config = self.config;
+        let path: &Path = &config.output_path(raw);
+
+        //in json, this is only for listing files to search, so there isn't anything else to say about them
+        if self.config.format.is_json() {
+            return self.json_result(None, &self.json_path_fields(path, raw));
+        }
+        if self.config.format == OutputFormat::Csv {
+            return self.csv_row(&[self.csv_paths(path, raw), vec![String::new()]].concat());
+        }
+        if self.config.format == OutputFormat::Template {
+            return self.template_result(Some(path), None, None, None);
+        }
+        self.results += 1;
+        //with --null, the name is printed exactly as it is, with a null after it instead of a newline
+        if self.config.null {
+            if path.as_os_str() == STDIN_PATH {
+                self.out.write_all(display_path(path).as_bytes())?;
+            } else {
+                self.out.write_all(path.as_os_str().as_encoded_bytes())?;
+            }
+            return self.out.write_all(b"\0");
+        }
+        //plain output isn't indented, so it's one name per line, like grep -l
+        let indent: &str = if self.plain {""} else {"\t"};
+        return match extra {
+            Some(extra) => writeln!(self.out, "{}{}:{}{}", indent, self.path(path), extra, tag),
+            None => writeln!(self.out, "{}{}{}", indent, self.path(path), tag),
+        };
+    }
+
+    /**
+     * prints what this mode prints about the file at raw (the path it was found at), given what was found in it
+     * print_matching is whether the files being listed are the ones that contain the query
+     */
+    fn file(&mut self, raw: &Path, file_matches: &FileMatches, print_matching: bool) -> io::Result<()> {
+        //DATA
+        let config: &Config = self.config;
+        let path: &Path = &config.output_path(raw);
+
+        if self.config.format.is_json() {
+            return self.json_file(path, raw, file_matches, print_matching);
+        }
+        if self.config.format == OutputFormat::Csv {
+            return self.csv_file(path, raw, file_matches, print_matching);
+        }
+        if self.config.format == OutputFormat::Vimgrep {
+            return self.vimgrep_file(path, file_matches);
+        }
+        if self.config.format == OutputFormat::Template {
+            return self.template_file(path, file_matches, print_matching);
+        }
+        match self.config.output_mode {
+            OutputMode::Count => if file_matches.is_match() || self.config.include_zero {
+                if self.with_filename {
+                    self.file_name(raw, Some(format!(" {}", file_matches.match_count)), name_tag(file_matches))?;
+                } else {
+                    writeln!(self.out, "{}{}{}", if self.plain {""} else {"\t"}, file_matches.match_count, name_tag(file_matches))?;
+                    self.results += 1;
+                }
+            },
+            OutputMode::CountMatches => {},
+            //with --binary, a binary file's lines aren't kept, so all there is to say is whether it matched
+            //and with --binary-context, the bytes around the matches, with "--" between the dumps that aren't next to each other
+            OutputMode::Lines if file_matches.binary && self.config.binary_mode == BinaryMode::Binary => if file_matches.match_count > 0 {
+                writeln!(self.out, "binary file {} matches", self.path(path))?;
+                for (index, dump) in file_matches.hex_dumps.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(self.out, "--")?;
+                    }
+                    for row in dump.rows(|text| paint(text, COLOR_MATCH, self.color)) {
+                        writeln!(self.out, "{}", row)?;
+                    }
+                }
+                self.results += 1;
+            },
+            OutputMode::Lines => {
+                //with headings, the file's name goes above its lines, and there's a blank line between files
+                //a file whose name matched gets its name printed (tagged) even if it doesn't have any lines
+                if self.heading && (!file_matches.lines.is_empty() || file_matches.name_match) {
+                    if self.printed_files {
+                        writeln!(self.out)?;
+                    }
+                    writeln!(self.out, "{}{}", self.path(path), name_tag(file_matches))?;
+                    self.printed_files = true;
+                    self.printed_lines = false; //the blank line already separates this from the last file's lines
+                    if file_matches.lines.is_empty() {
+                        self.results += 1;
+                    }
+                } else if file_matches.name_match {
+                    writeln!(self.out, "{}{}", self.path(path), name_tag(file_matches))?;
+                    self.results += 1;
+                }
+                for line in &file_matches.lines {
+                    self.line(path, line)?;
+                }
+            },
+            OutputMode::FilesWithMatches | OutputMode::FilesWithoutMatch | OutputMode::Files => if file_matches.is_match() == print_matching {
+                //with --byte-offset, files that contain the query are printed with the offset of their first match
+                let offset: Option<String> = file_matches.lines.first().filter(|_| self.config.byte_offset).map(|first| first.match_offset().to_string());
+                self.file_name(raw, offset, name_tag(file_matches))?;
+            },
+        }
+        return Ok(());
+    }
+
+    /**
+     * prints a line that contains the query (or a line of context) from the file at path
+     */
+    fn line(&mut self, path: &Path, line: &LineMatch) -> io::Result<()> {
+        //DATA
+        let config: &Config = self.config;
+        //with headings, the file's name is already above its lines
+        let path_as_string: String = if self.heading || !self.with_filename {String::new()} else {self.path(path)};
+        let (separator, context_separator) = if self.heading || !self.with_filename {("", "")} else {(":", "-")};
+
+        //with context, groups of lines that aren't right after each other (or are in different files) are separated by a "--"
+        if config.before_context > 0 || config.after_context > 0 {
+            if self.printed_lines && (self.last_printed != Some((path.to_path_buf(), line.line_number - 1))) {
+                writeln!(self.out, "--")?;
+            }
+            self.printed_lines = true;
+            self.last_printed = Some((path.to_path_buf(), line.line_number));
+        }
+        //context lines are printed like "path-number-line", so they're easy to tell apart from matches
+        if line.matches.is_empty() {
+            return writeln!(self.out, "{}{}{}-{}", path_as_string, context_separator, line.line_number, line.line);
+        }
+        //with --only-matching, every match gets a line of its own, with just the matched text as it is in the file (or its replacement)
+        if config.only_matching {
+            for range in line.matches.iter().filter(|range| !range.is_empty()) {
+                let column: String = if config.column {format!("{}:", line.column_at(range.start))} else {String::new()};
+                let offset: String = if config.byte_offset {format!("{}:", line.offset_at(range.start))} else {String::new()};
+                let text: String = match &config.replace {
+                    Some(replacement) => config.matcher.replacement(&line.line, range, replacement),
+                    None => line.line[range.clone()].to_string(),
+                };
+                writeln!(self.out, "{}{}{}:{}{}{}", path_as_string, separator, line.line_number, column, offset, paint(&text, COLOR_MATCH, self.color))?;
+                self.results += 1;
+            }
+            return Ok(());
+        }
+        //with --column and --byte-offset, the column and then the offset go after the line number
+        //with --replace, the replacements are highlighted instead of the matches
+        let column: String = if config.column {format!("{}:", line.column())} else {String::new()};
+        let offset: String = if config.byte_offset {format!("{}:", line.match_offset())} else {String::new()};
+        let text: String = match &config.replace {
+            Some(replacement) => replace_matches(&line.line, &line.matches, |range| config.matcher.replacement(&line.line, range, replacement), self.color),
+            None => highlight_matches(&line.line, &line.matches, self.color),
+        };
+        self.results += 1;
+        return writeln!(self.out, "{}{}{}:{}{}{}", path_as_string, separator, line.line_number, column, offset, text);
+    }
+
+    /**
+     * prints the total number of matches, if this mode prints one
+     */
+    fn total(&mut self, total_matches: usize) -> io::Result<()> {
+        //in json, the total is in the stats
+        if self.config.output_mode != OutputMode::CountMatches || self.config.quiet || self.config.format.is_json() {
+            return Ok(());
+        }
+        if self.config.format == OutputFormat::Csv {
+            return self.csv_row(&[total_matches.to_string()]);
+        }
+        if self.config.format == OutputFormat::Template {
+            return self.template_result(None, None, None, Some(total_matches));
+        }
+        self.results += 1;
+        if self.plain {
+            return writeln!(self.out, "{}", total_matches);
+        }
+        return writeln!(self.out, "Total matches: {}", total_matches);
+    }
+
+    /**
+     * prints the stats, if --stats was given, to stderr in quiet mode so they don't get in the way of the exit status
+     */
+    fn stats(&mut self, stats: &Stats) -> io::Result<()> {
+        //DATA
+        let summary: String = format!("Stats:\n\tfiles searched: {}\n\tfiles matched: {}\n\ttotal matches: {}\n\tfiles skipped: {}\n\tskipped by date: {}\n\tskipped by size: {}\n\tempty files: {}\n\tunreadable directories: {}\n\telapsed: {:.3?}",
+            stats.files_searched, stats.files_matched, stats.total_matches, stats.files_skipped, stats.skipped_by_date, stats.skipped_by_size, stats.empty_files, stats.walk_errors, stats.elapsed);
+
+        //json always ends with the stats, it's what closes the document
+        if self.config.format.is_json() && !self.config.quiet {
+            return self.json_end(stats);
+        }
+        //csv always has its header, even without any rows
+        if self.config.format == OutputFormat::Csv && !self.config.quiet {
+            self.csv_start()?;
+        }
+        if !self.config.stats {
+            return Ok(());
+        }
+        //the stats aren't rows (or matches), so they'd get in the way of the csv (or vimgrep)
+        if self.config.quiet || self.config.format != OutputFormat::Text {
+            eprintln!("{}", summary);
+            return Ok(());
+        }
+        return writeln!(self.out, "{}", summary);
+    }
+
+    /**
+     * prints what this mode prints about the file at path with the --format template, a line for each file, or for each matching line when printing lines
+     * (with --only-matching, for each match)
+     */
+    fn template_file(&mut self, path: &Path, file_matches: &FileMatches, print_matching: bool) -> io::Result<()> {
+        match self.config.output_mode {
+            OutputMode::Count => if file_matches.is_match() || self.config.include_zero {
+                self.template_result(Some(path), None, None, Some(file_matches.match_count))?;
+            },
+            OutputMode::CountMatches => {},
+            OutputMode::Lines if file_matches.binary && self.config.binary_mode == BinaryMode::Binary => if file_matches.match_count > 0 {
+                self.template_result(Some(path), None, None, Some(file_matches.match_count))?;
+            },
+            OutputMode::Lines => for line in file_matches.lines.iter().filter(|line| !line.matches.is_empty()) {
+                if self.config.only_matching {
+                    for range in line.matches.iter().filter(|range| !range.is_empty()) {
+                        self.template_result(Some(path), Some(line), Some(range), Some(line.matches.len()))?;
+                    }
+                } else {
+                    self.template_result(Some(path), Some(line), line.matches.first(), Some(line.matches.len()))?;
+                }
+            },
+            OutputMode::FilesWithMatches | OutputMode::FilesWithoutMatch | OutputMode::Files => if file_matches.is_match() == print_matching {
+                self.template_result(Some(path), None, None, Some(file_matches.match_count))?;
+            },
+        }
+        return Ok(());
+    }
+
+    /**
+     * prints the --format template filled in for one result, the placeholders for what the result doesn't have (like {line}, when listing files) are left empty
+     * range is the match that {column}, {byte_offset}, and {match} are about
+     */
+    fn template_result(&mut self, path: Option<&Path>, line: Option<&LineMatch>, range: Option<&Range<usize>>, count: Option<usize>) -> io::Result<()> {
+        //DATA
+        let config: &Config = self.config;
+        let Some(template) = &config.template else {
+            return Ok(());
+        };
+        let matched: Option<(&LineMatch, &Range<usize>)> = line.zip(range);
+        let rendered: String = template.render(|field| match field {
+            Field::Path => path.map(display_path).unwrap_or_default(),
+            Field::Line => line.map(|line| line.line_number.to_string()).unwrap_or_default(),
+            Field::Column => matched.map(|(line, range)| line.column_at(range.start).to_string()).unwrap_or_default(),
+            Field::ByteOffset => matched.map(|(line, range)| line.offset_at(range.start).to_string()).unwrap_or_default(),
+            Field::Text => line.map(|line| match &config.replace {
+                Some(replacement) => replace_matches(&line.line, &line.matches, |range| config.matcher.replacement(&line.line, range, replacement), false),
+                None => line.line.clone(),
+            }).unwrap_or_default(),
+            Field::Match => matched.map(|(line, range)| match &config.replace {
+                Some(replacement) => config.matcher.replacement(&line.line, range, replacement),
+                None => line.line[range.clone()].to_string(),
+            }).unwrap_or_default(),
+            Field::Count => count.map(|count| count.to_string()).unwrap_or_default(),
+        });
+
+        self.results += 1;
+        return writeln!(self.out, "{}", rendered);
+    }
+
+    /**
+     * prints a "path:line:column:text" line for each match in the file at path, two matches on a line are two lines, with different columns
+     * the path always comes first (even when searching just one file), since that's what editors expect, and the text is the whole line
+     * (with --only-matching, just the match, and with --replace, with the replacements)
+     * binary files (with --binary) don't have lines to jump to, so nothing is printed for them
+     */
+    fn vimgrep_file(&mut self, path: &Path, file_matches: &FileMatches) -> io::Result<()> {
+        //DATA
+        let config: &Config = self.config;
+        let path_as_string: String = display_path(path);
+
+        for line in &file_matches.lines {
+            //DATA
+            let whole_line: String = match &config.replace {
+                Some(replacement) => replace_matches(&line.line, &line.matches, |range| config.matcher.replacement(&line.line, range, replacement), false),
+                None => line.line.clone(),
+            };
+
+            for range in line.matches.iter().filter(|range| !range.is_empty() || !config.only_matching) {
+                //DATA
+                let text: String = match (&config.replace, config.only_matching) {
+                    (Some(replacement), true) => config.matcher.replacement(&line.line, range, replacement),
+                    (None, true) => line.line[range.clone()].to_string(),
+                    (_, false) => whole_line.clone(),
+                };
+
+                writeln!(self.out, "{}:{}:{}:{}", path_as_string, line.line_number, line.column_at(range.start), text)?;
+                self.results += 1;
+            }
+        }
+        return Ok(());
+    }
+
+    /**
+     * prints the header row of the csv, if it hasn't been already, the columns depend on the output mode
+     */
+    fn csv_start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        //with --relative-to, the path the file was found at is also printed, after the one it was rebased to
+        let path_columns: &str = if self.config.relative_to.is_some() {"path,raw_path"} else {"path"};
+        return match self.config.output_mode {
+            OutputMode::Lines => writeln!(self.out, "{},line_number,column,match_count,line_text", path_columns),
+            OutputMode::CountMatches => writeln!(self.out, "match_count"),
+            OutputMode::FilesWithMatches | OutputMode::FilesWithoutMatch | OutputMode::Files | OutputMode::Count => writeln!(self.out, "{},match_count", path_columns),
+        };
+    }
+
+    /**
+     * returns the path columns of a csv row, the path as it's printed, and with --relative-to, raw (the path it was found at)
+     */
+    fn csv_paths(&self, path: &Path, raw: &Path) -> Vec<String> {
+        if self.config.relative_to.is_some() {
+            return vec![display_path(path), display_path(raw)];
+        }
+        return vec![display_path(path)];
+    }
+
+    /**
+     * prints a row of the csv
+     */
+    fn csv_row<S: AsRef<str>>(&mut self, fields: &[S]) -> io::Result<()> {
+        self.csv_start()?;
+        self.results += 1;
+        return writeln!(self.out, "{}", csv::row(fields));
+    }
+
+    /**
+     * prints what this mode prints about the file at path as csv rows, a row for each file, or for each line when printing lines
+     * context lines don't have a column, and have a match count of 0
+     */
+    fn csv_file(&mut self, path: &Path, raw: &Path, file_matches: &FileMatches, print_matching: bool) -> io::Result<()> {
+        //DATA
+        let paths: Vec<String> = self.csv_paths(path, raw);
+
+        match self.config.output_mode {
+            OutputMode::Count => if file_matches.is_match() || self.config.include_zero {
+                self.csv_row(&[paths, vec![file_matches.match_count.to_string()]].concat())?;
+            },
+            OutputMode::CountMatches => {},
+            OutputMode::Lines if file_matches.binary && self.config.binary_mode == BinaryMode::Binary => if file_matches.match_count > 0 {
+                self.csv_row(&[paths, vec![String::new(), String::new(), file_matches.match_count.to_string(), String::new()]].concat())?;
+            },
+            OutputMode::Lines => for line in &file_matches.lines {
+                //DATA
+                let column: String = if line.matches.is_empty() {String::new()} else {line.column().to_string()};
+                let text: String = match &self.config.replace {
+                    Some(replacement) => replace_matches(&line.line, &line.matches, |range| self.config.matcher.replacement(&line.line, range, replacement), false),
+                    None => line.line.clone(),
+                };
+
+                self.csv_row(&[paths.clone(), vec![line.line_number.to_string(), column, line.matches.len().to_string(), text]].concat())?;
+            },
+            OutputMode::FilesWithMatches | OutputMode::FilesWithoutMatch | OutputMode::Files => if file_matches.is_match() == print_matching {
+                self.csv_row(&[paths, vec![file_matches.match_count.to_string()]].concat())?;
+            },
+        }
+        return Ok(());
+    }
+
+    /**
+     * prints the start of the json document, if it hasn't been already
+     */
+    fn json_start(&mut self) -> io::Result<()> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        return write!(self.out, "{{\"results\":[");
+    }
+
+    /**
+     * prints a result in the json document, fields are the json fields of the object, without the braces
+     * kind is what kind of line it's for (match or context), when printing lines, which is also the type of json lines records (file otherwise)
+     * json lines are flushed right away, so whatever's reading them sees each one as soon as it's found
+     */
+    fn json_result(&mut self, kind: Option<&str>, fields: &str) -> io::Result<()> {
+        self.results += 1;
+        if self.config.format == OutputFormat::JsonLines {
+            writeln!(self.out, "{{\"type\":\"{}\",{}}}", kind.unwrap_or("file"), fields)?;
+            return self.out.flush();
+        }
+        self.json_start()?;
+        match kind {
+            Some(kind) => write!(self.out, "{}\n{{\"kind\":\"{}\",{}}}", if self.json_results > 0 {","} else {""}, kind, fields)?,
+            None => write!(self.out, "{}\n{{{}}}", if self.json_results > 0 {","} else {""}, fields)?,
+        }
+        self.json_results += 1;
+        return Ok(());
+    }
+
+    /**
+     * prints an error record for a file or directory that couldn't be read, only json lines has them, the other formats sum them up at the end
+     */
+    fn error(&mut self, error: &PathError) -> io::Result<()> {
+        if self.config.format != OutputFormat::JsonLines || self.config.quiet {
+            return Ok(());
+        }
+        writeln!(self.out, "{{\"type\":\"error\",{},\"kind\":{},\"message\":{}}}",
+            json::path_field("path", &error.path, &display_path(&error.path)), json::string(&error.error.kind().to_string()), json::string(&error.error.to_string()))?;
+        return self.out.flush();
+    }
+
+    /**
+     * returns the path fields of a json result, "path" as it's printed, and with --relative-to, "raw_path" (the path it was found at)
+     */
+    fn json_path_fields(&self, path: &Path, raw: &Path) -> String {
+        if self.config.relative_to.is_some() {
+            return format!("{},{}", json::path_field("path", path, &display_path(path)), json::path_field("raw_path", raw, &display_path(raw)));
+        }
+        return json::path_field("path", path, &display_path(path));
+    }
+
+    /**
+     * prints what this mode prints about the file at path as json results, a result for each file, or for each line when printing lines
+     */
+    fn json_file(&mut self, path: &Path, raw: &Path, file_matches: &FileMatches, print_matching: bool) -> io::Result<()> {
+        //DATA
+        let config: &Config = self.config;
+        let path_field: String = self.json_path_fields(path, raw);
+        //with --also-match-names, what matched, the name, the contents, or both
+        let matched_by: String = if config.also_match_names {
+            format!(",\"matched_by\":[{}]", [(file_matches.name_match, "\"name\""), (file_matches.match_count > 0, "\"content\"")].iter()
+                .filter(|(matched, _)| *matched).map(|(_, kind)| *kind).collect::<Vec<&str>>().join(","))
+        } else {
+            String::new()
+        };
+
+        match config.output_mode {
+            OutputMode::Count => if file_matches.is_match() || config.include_zero {
+                self.json_result(None, &format!("{},\"match_count\":{}{}", path_field, file_matches.match_count, matched_by))?;
+            },
+            OutputMode::CountMatches => {},
+            OutputMode::Lines if file_matches.binary && config.binary_mode == BinaryMode::Binary => if file_matches.match_count > 0 {
+                self.json_result(None, &format!("{},\"binary\":true,\"match_count\":{}{}", path_field, file_matches.match_count, matched_by))?;
+            },
+            //each line is a result of its own, context lines don't have matches (or a column), and a file whose name matched has a name result before them
+            OutputMode::Lines => for line in file_matches.name_match.then_some(None).into_iter().chain(file_matches.lines.iter().map(Some)) {
+                let Some(line) = line else {
+                    self.json_result(Some("name"), &format!("{}{}", path_field, matched_by))?;
+                    continue;
+                };
+                //DATA
+                let matches: Vec<String> = line.matches.iter().map(|range| {
+                    let replacement: String = config.replace.as_ref()
+                        .map(|replacement| format!(",\"replacement\":{}", json::string(&config.matcher.replacement(&line.line, range, replacement))))
+                        .unwrap_or_default();
+                    format!("{{\"start\":{},\"end\":{},\"text\":{}{}}}", range.start, range.end, json::string(&line.line[range.clone()]), replacement)
+                }).collect();
+                let column: String = if line.matches.is_empty() {String::new()} else {format!(",\"column\":{}", line.column())};
+
+                //the offset of the first match, like -b prints (which is the start of the line, for context lines)
+                self.json_result(Some(if line.matches.is_empty() {"context"} else {"match"}), &format!("{},\"line_number\":{}{},\"byte_offset\":{},\"line_text\":{},\"matches\":[{}]",
+                    path_field, line.line_number, column, line.match_offset(), json::string(&line.line), matches.join(",")))?;
+            },
+            OutputMode::FilesWithMatches | OutputMode::FilesWithoutMatch | OutputMode::Files => if file_matches.is_match() == print_matching {
+                let offset: String = file_matches.lines.first().filter(|_| config.byte_offset).map(|first| format!(",\"byte_offset\":{}", first.match_offset())).unwrap_or_default();
+                self.json_result(None, &format!("{}{}{}", path_field, offset, matched_by))?;
+            },
+        }
+        return Ok(());
+    }
+
+    /**
+     * prints the end of the json document, with the stats, or the summary record for json lines
+     */
+    fn json_end(&mut self, stats: &Stats) -> io::Result<()> {
+        //DATA
+        let stats: String = format!("{{\"files_searched\":{},\"files_matched\":{},\"total_matches\":{},\"files_skipped\":{},\"skipped_by_date\":{},\"skipped_by_size\":{},\"empty_files\":{},\"unreadable_directories\":{},\"elapsed_seconds\":{}}}",
+            stats.files_searched, stats.files_matched, stats.total_matches, stats.files_skipped, stats.skipped_by_date, stats.skipped_by_size, stats.empty_files, stats.walk_errors, stats.elapsed.as_secs_f64());
+
+        if self.config.format == OutputFormat::JsonLines {
+            writeln!(self.out, "{{\"type\":\"summary\",\"stats\":{}}}", stats)?;
+            return self.out.flush();
+        }
+        self.json_start()?;
+        return writeln!(self.out, "\n],\"stats\":{}}}", stats);
+    }
+}
+
+//what was found in a file, None if it was skipped, or an error if it couldn't be searched
+type SearchResult = Result<Option<FileMatches>, PathError>;
+
+/**
+ * searches every path in paths, spread across config.threads threads, and calls on_result with each path and what was found in it,
+ * in the same order as paths, and always on this thread. if on_result returns false, the search stops
+ */
+fn search_paths<F: FnMut(&Path, SearchResult) -> bool>(paths: &[PathBuf], config: &Config, mut on_result: F) {
+    //with one thread, just search everything in order, right here
+    //either way, Ctrl-C stops the search after the files that are already being searched
+    if config.threads <= 1 || paths.len() <= 1 {
+        for path in paths {
+            for (path, file_matches) in search_file(path, config) {
+                if interrupt::interrupted() || !on_result(&path, file_matches) {
+                    return;
+                }
+            }
+        }
+        return;
+    }
+
+    //DATA
+    let next_index = AtomicUsize::new(0); //the next path a worker should search
+    let stop = AtomicBool::new(false);
+    let (sender, receiver) = mpsc::channel::<(usize, Vec<(PathBuf, SearchResult)>)>();
+    let mut results: Vec<Option<Vec<(PathBuf, SearchResult)>>> = (0..paths.len()).map(|_| None).collect();
+    let mut next_to_report: usize = 0;
+
+    thread::scope(|scope| {
+        //each worker takes the next unsearched path until there are none left, and sends back what it found
+        for _ in 0..config.threads.min(paths.len()) {
+            let sender = sender.clone();
+            let (next_index, stop) = (&next_index, &stop);
+            scope.spawn(move || {
+                while !stop.load(Ordering::Relaxed) && !interrupt::interrupted() {
+                    let index = next_index.fetch_add(1, Ordering::Relaxed);
+                    if index >= paths.len() || sender.send((index, search_file(&paths[index], config))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender); //so the receiver knows when every worker is done
+
+        //results can arrive out of order, so hold on to them until everything before them has been reported
+        for (index, searched) in receiver {
+            results[index] = Some(searched);
+            while let Some(searched) = results.get_mut(next_to_report).and_then(Option::take) {
+                for (path, file_matches) in searched {
+                    if !on_result(&path, file_matches) {
+                        stop.store(true, Ordering::Relaxed);
+                        return;
+                    }
+                }
+                next_to_report += 1;
+            }
+        }
+    });
+}
+
+/**
+ * searches the file at path (or stdin), and returns what was found, with the path to report it at
+ * that's just path itself, except for zip and tar archives with --search-zip, which have a result for each file in them
+ */
+fn search_file(path: &Path, config: &Config) -> Vec<(PathBuf, SearchResult)> {
+    if config.is_archive(path) && zip::is_zip_path(path) {
+        return search_zip(path, config);
+    }
+    if config.is_archive(path) {
+        return search_tar(path, config);
+    }
+    return vec![(path.to_path_buf(), search_path(path, config))];
+}
+
+/**
+ * searches the file at path (or stdin), see search_contents
+ */
+fn search_path(path: &Path, config: &Config) -> SearchResult {
+    return add_name_match(path, config, search_contents(path, config));
+}
+
+/**
+ * returns what was found in the file at path, with --also-match-names, a file whose name matches is a match even if it was skipped, or couldn't be read
+ */
+fn add_name_match(path: &Path, config: &Config, searched: SearchResult) -> SearchResult {
+    //DATA
+    let name_match: bool = config.also_match_names && path.as_os_str() != STDIN_PATH && config.name_is_match(path, NameTarget::Name);
+
+    if !name_match {
+        return searched;
+    }
+    return Ok(Some(match searched {
+        Ok(Some(found)) => FileMatches { name_match, ..found },
+        _ => FileMatches { name_match, ..FileMatches::default() },
+    }));
+}
+
+/**
+ * searches each file in the zip archive at path that the filters let through, as if it was a file of its own at archive!name
+ * archives in the archive are searched as they are (so they're usually skipped as binary), they aren't opened in turn
+ * an archive that can't be read is an error for the archive, and a file in it that can't be (like an encrypted one) is an error for that file
+ */
+fn search_zip(path: &Path, config: &Config) -> Vec<(PathBuf, SearchResult)> {
+    //DATA
+    let mut archive: BufReader<File> = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(error) => return vec![(path.to_path_buf(), search_result(path, Err(error.into()), config))],
+    };
+    let entries: Vec<zip::Entry> = match zip::read_entries(&mut archive) {
+        Ok(entries) => entries,
+        Err(error) => return vec![(path.to_path_buf(), search_result(path, Err(error.into()), config))],
+    };
+    let mut results: Vec<(PathBuf, SearchResult)> = Vec::new();
+
+    for entry in entries.iter().filter(|entry| !entry.is_dir() && config.wants_entry(Path::new(&entry.name))) {
+        if interrupt::interrupted() {
+            break;
+        }
+        results.push(search_entry(path, &entry.name, entry.size, || zip::open_entry(&mut archive, entry), config));
+    }
+
+    //return
+    return results;
+}
+
+/**
+ * searches each regular file in the tar archive at path (gzipped or not) that the filters let through, as if it was a file of its own at archive!name
+ * tar archives have no index, so it's one pass through the archive, reading each file as it comes, other kinds of entries (like links, or devices) are skipped
+ * an archive that can't be read is an error for the archive, and since there's no finding the rest of it after that, the search of it ends there
+ */
+fn search_tar(path: &Path, config: &Config) -> Vec<(PathBuf, SearchResult)> {
+    //DATA
+    let reader: Box<dyn Read> = match File::open(path) {
+        Ok(file) if tar::is_gzipped(path) => Box::new(GzDecoder::new(file)),
+        Ok(file) => Box::new(BufReader::new(file)),
+        Err(error) => return vec![(path.to_path_buf(), search_result(path, Err(error.into()), config))],
+    };
+    let mut archive: tar::Archive<Box<dyn Read>> = tar::Archive::new(reader);
+    let mut results: Vec<(PathBuf, SearchResult)> = Vec::new();
+
+    while !interrupt::interrupted() {
+        //DATA
+        let entry: tar::Entry = match archive.next_entry() {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(error) => {
+                results.push((path.to_path_buf(), search_result(path, Err(error.into()), config)));
+                break;
+            },
+        };
+
+        if !entry.is_file() {
+            if config.verbose && !entry.is_dir() {eprintln!("Skipping {:?}: not a regular file",display_path(&archive_entry_path(path, &entry.name)));}
+            continue;
+        }
+        if config.wants_entry(Path::new(&entry.name)) {
+            results.push(search_entry(path, &entry.name, entry.size, || Ok(&mut archive), config));
+        }
+    }
+
+    //return
+    return results;
+}
+
+/**
+ * returns the path a file in the archive at path is reported at, archive!name
+ */
+fn archive_entry_path(path: &Path, name: &str) -> PathBuf {
+    //DATA
+    let mut entry_path: OsString = path.as_os_str().to_os_string();
+
+    entry_path.push("!");
+    entry_path.push(name);
+    return PathBuf::from(entry_path);
+}
+
+/**
+ * searches the file at name (size bytes big) in the archive at path, which open opens, and returns what was found, with the path to report it at
+ * it's skipped if it's larger than --max-filesize
+ */
+fn search_entry<R: Read, F: FnOnce() -> io::Result<R>>(path: &Path, name: &str, size: u64, open: F, config: &Config) -> (PathBuf, SearchResult) {
+    //DATA
+    let entry_path: PathBuf = archive_entry_path(path, name);
+    let searched: SearchResult = if config.max_filesize.is_some_and(|max_filesize| size > max_filesize) {
+        if config.verbose {eprintln!("Skipping {:?}: larger than the max filesize",display_path(&entry_path));}
+        Ok(None)
+    } else {
+        //the size the archive says it is could be a lie, so what it decompresses to is limited too
+        let opened = open().map(|reader| Bounded::new(reader, config.max_filesize.unwrap_or(DECOMPRESSED_SIZE_LIMIT)));
+        search_result(&entry_path, opened.map_err(Box::from).and_then(|reader| search_opened(reader, config)), config)
+    };
+
+    //return
+    return (entry_path.clone(), add_name_match(&entry_path, config, searched));
+}
+
+/**
+ * returns how many times the file at path (or stdin) contains any of the patterns (and the lines they're on, when printing lines),
+ * when just listing files it stops at the first one
+ * returns None for files that are skipped, and an error for ones that can't be read, either way the reason is printed in verbose mode
+ */
+fn search_contents(path: &Path, config: &Config) -> SearchResult {
+    //DATA
+    let path_as_string:String = display_path(path);
+    //with --pre, the files it runs on are searched as what it prints, which could be anything, even for an empty file
+    let preprocess: bool = config.preprocesses(path);
+    //with --search-zip, gzip files are searched as what they decompress to, which is what --max-filesize is about
+    let gzip: bool = !preprocess && config.search_zip && path.as_os_str() != STDIN_PATH && gzip::is_gzip_path(path);
+
+    //only search regular files (or symlinks to them), reading a fifo or a device could wait forever, or never end
+    //and skip files that are too big, without reading them
+    if path.as_os_str() != STDIN_PATH {
+        match fs::metadata(path) {
+            Ok(metadata) if !metadata.is_file() => {
+                if config.verbose {eprintln!("Skipping {:?}: not a regular file",path_as_string);}
+                return Ok(None);
+            },
+            Ok(metadata) if !gzip && config.max_filesize.is_some_and(|max_filesize| metadata.len() > max_filesize) => {
+                if config.verbose {eprintln!("Skipping {:?}: larger than the max filesize",path_as_string);}
+                return Ok(None);
+            },
+            //empty files have no lines, so there's nothing to read, unless the whole (empty) contents could match
+            Ok(metadata) if metadata.len() == 0 && !preprocess && !(config.multiline && config.matcher.is_match("")) => {
+                return Ok(Some(FileMatches { empty: true, ..FileMatches::default() }));
+            },
+            _ => {},
+        }
+    }
+
+    if let Some(command) = config.pre.as_deref().filter(|_| preprocess) {
+        return search_result(path, search_preprocessed(command, path, config), config);
+    }
+
+    //DATA
+    let opened = open_reader(path).map(|reader| -> Box<dyn Read> {
+        if gzip {Box::new(Bounded::new(GzDecoder::new(reader), config.max_filesize.unwrap_or(DECOMPRESSED_SIZE_LIMIT)))} else {reader}
+    });
+
+    //return
+    return search_result(path, opened.and_then(|reader| search_opened(reader, config)), config);
+}
+
+/**
+ * runs command on the file at path, and returns how many times what it prints contains any of the patterns, see search_opened
+ * the command failing (or taking longer than --pre-timeout) is an error, unless it was stopped early because the rest of what it prints wasn't needed
+ */
+fn search_preprocessed(command: &str, path: &Path, config: &Config) -> Result<Option<FileMatches>, Box<dyn Error>> {
+    //DATA
+    let mut preprocessor: Preprocessor = Preprocessor::spawn(command, path, config.pre_timeout)?;
+    let found = search_opened(&mut preprocessor, config);
+
+    //the preprocessor failing is what went wrong, even if it also made what it printed unreadable
+    preprocessor.finish()?;
+    return found;
+}
+
+/**
+ * returns how many times what reader reads contains any of the patterns (and the lines they're on, when printing lines), see search_contents
+ * returns None if it's binary, and skipped
+ */
+fn search_opened<R: Read>(reader: R, config: &Config) -> Result<Option<FileMatches>, Box<dyn Error>> {
+    //DATA
+    //listing files only needs to know if there's at least one match, unless --stats wants all of them counted
+    //a template with {count}, and csv's match_count column, print the count even when listing files
+    let counts: bool = config.counts_matches() || config.stats || config.format == OutputFormat::Csv || config.template.as_ref().is_some_and(|template| template.uses(Field::Count));
+    let max_count: Option<usize> = if counts || config.output_mode == OutputMode::Lines {config.max_count} else {Some(1)};
+    //count how many times the file (or stdin) contains any of the patterns, keeping the lines if they're going to be printed
+    //with --byte-offset, we need to know where the matches are, even when just listing files
+    let keep_lines: bool = config.output_mode == OutputMode::Lines || (config.byte_offset && !config.counts_matches());
+    //passthru is just context that goes on forever, so files without matches still end up with no lines to print
+    let context: (usize, usize) = match config.output_mode {
+        OutputMode::Lines if config.passthru => (usize::MAX, usize::MAX),
+        OutputMode::Lines => (config.before_context, config.after_context),
+        _ => (0, 0),
+    };
+    let mut requirements = FileRequirements::new(&config.required, config.excluded.as_ref());
+    let mut decoder = DecodingReader::new(reader, config.encoding);
+    let (binary, reader) = check_binary(&mut decoder)?;
+
+    //binary files are skipped unless --text or --binary say otherwise, and can't be expected to be valid utf-8 if they aren't
+    if binary && config.binary_mode == BinaryMode::Skip {
+        return Ok(None);
+    }
+    let keep_lines: bool = keep_lines && !(binary && config.binary_mode == BinaryMode::Binary);
+    //with --binary-context, binary files get a hex dump around each match instead
+    let hex_context: Option<usize> = config.binary_context.filter(|_| binary && config.binary_mode == BinaryMode::Binary && config.output_mode == OutputMode::Lines);
+    //invalid utf-8 is replaced with U+FFFD, so the rest of the file can still be searched (and printed), unless --strict-utf8 says not to
+    let lossy: bool = binary || config.binary_mode == BinaryMode::Text || !config.strict_utf8;
+    //checking for binary read the start of the file, so the decoder knows what encoding it's in by now
+    let encoding: Encoding = reader.get_ref().1.encoding();
+    let mut found: FileMatches = if config.multiline {
+        scan_multiline(reader, |contents| config.matcher.find_matches(contents), max_count, context, keep_lines, hex_context, lossy, encoding, &mut requirements)?
+    } else {
+        scan_lines(reader, |line| config.matcher.find_matches(line), max_count, context, keep_lines, hex_context, lossy, encoding, &mut requirements)?
+    };
+    //byte offsets are counted in the file's own bytes, so they're from the start of the file once the skipped byte order mark is counted
+    for line in &mut found.lines {
+        line.byte_offset += decoder.bom_length() as u64;
+    }
+
+    //return
+    return Ok(Some(FileMatches { binary, ..found }));
+}
+
+/**
+ * returns what searching the file at path came to, given how it went, printing why it was skipped (or couldn't be searched) in verbose mode
+ */
+fn search_result(path: &Path, result: Result<Option<FileMatches>, Box<dyn Error>>, config: &Config) -> SearchResult {
+    //DATA
+    let path_as_string: String = display_path(path);
+
+    return match result {
+        Ok(Some(found)) => Ok(Some(found)),
+        Ok(None) => {
+            if config.verbose {eprintln!("Skipping {:?}: binary file",path_as_string);}
+            Ok(None)
+        },
+        //files listed by the walk can be deleted before they're searched (like build output, or rotated logs), they're just skipped
+        Err(err) if err.downcast_ref::<io::Error>().is_some_and(|err| err.kind() == io::ErrorKind::NotFound) => Ok(None),
+        Err(err) => {
+            if config.verbose {eprintln!("Error searching {:?}: {}",path_as_string, err);}
+            //the errors that aren't io errors are from decoding the file
+            let error: io::Error = match err.downcast::<io::Error>() {
+                Ok(error) => *error,
+                Err(err) => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+            };
+            Err(PathError { path: path.to_path_buf(), error })
+        },
+    };
+}
+
+//a reader with what was already read from it put back in front
+type Rewound<R> = io::Chain<io::Cursor<Vec<u8>>, R>;
+
+/**
+ * reads the start of reader to see if it's binary (has a NUL in the first BINARY_CHECK_SIZE bytes), and returns a reader that still starts from the beginning
+ */
+fn check_binary<R: Read>(mut reader: R) -> Result<(bool, Rewound<R>),Box<dyn Error>> {
+    //DATA
+    let mut start: Vec<u8> = Vec::with_capacity(BINARY_CHECK_SIZE);
+
+    (&mut reader).take(BINARY_CHECK_SIZE as u64).read_to_end(&mut start)?;
+    return Ok((start.contains(&0), io::Cursor::new(start).chain(reader)));
+}
+
+/**
+ * writes the version to out, and the features it was built with, like "+regex" (or "-one-file-system" for one this platform doesn't have)
+ */
+pub fn write_version<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(out, "grep-directory {}", env!("CARGO_PKG_VERSION"))?;
+    writeln!(out, "features: {}", FEATURES.iter().map(|(feature, enabled)| format!("{}{}", if *enabled {'+'} else {'-'}, feature)).collect::<Vec<String>>().join(" "))?;
+    return Ok(());
+}
+
+/**
+ * writes what's being searched for, and where, to out
+ */
+fn write_banner<W: Write>(config: &Config, out: &mut W) -> io::Result<()> {
+    //--files doesn't search for anything, except with --filenames
+    if config.output_mode != OutputMode::Files || config.filenames.is_some() {
+        //DATA
+        let patterns: String = config.patterns.join(if config.all_of {" and "} else {" or "});
+
+        if config.not_patterns.is_empty() {
+            writeln!(out, "Searching for {}", patterns)?;
+        } else {
+            writeln!(out, "Searching for {}, but not {}", patterns, config.not_patterns.join(" or "))?;
+        }
+    }
+    writeln!(out, "In Path {}", config.paths.iter().map(|path| path.to_string_lossy()).collect::<Vec<_>>().join(", "))?;
+    return Ok(());
+}
+
+/**
+ * prints the help to stdout, stopping quietly if stdout is closed (like when it's piped into head)
+ */
+pub fn help() {
+    let _ = write_help(&mut io::stdout().lock());
+}
+
+/**
+ * writes the help to out
+ */
+pub fn write_help<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(out, "                              grep-directory.exe")?;
+    writeln!(out, "                              By Anthony Rubick\n")?;
+    writeln!(out, "search through all files in a directory for a given string\n")?;
+
+    writeln!(out, "USAGE:\n\tgrep-directory.exe [OPTIONS]... [PATH] \"[QUERY]\"")?;
+    writeln!(out, "\tgrep-directory.exe [OPTIONS]... --path [PATH]... \"[QUERY]\"\n")?;
+
+    writeln!(out, "OPTIONS:")?;
+    options::write_options(out)?;
+    writeln!(out, "\tshort flags can be combined, e.g. -rv is the same as -r -v, and the rest of a bundle after a flag that takes a value is its value, e.g. -m1 or -rC2")?;
+    writeln!(out, "\tvalues can be given as --option value, or --option=value, which also works for values that are options themselves, e.g. --regexp=-v\n")?;
+    
+    writeln!(out, "CONFIG FILE:\n\t~/.config/grep-directory/config, one option per line without the dashes, e.g. \"recursive\" or \"filter rs,toml\"\n\tapplied before GREP_DIRECTORY_OPTS and the command line\n")?;
+
+    writeln!(out, "ENVIRONMENT:\n\tGREP_DIRECTORY_OPTS\tDefault options, applied before the ones on the command line, e.g. \"-r --verbose\"")?;
+    writeln!(out, "\tGREP_DIRECTORY_COMPAT_V0\tWhen set (to anything but nothing), the same as --compat-v0\n")?;
+
+    writeln!(out, "PATH:\n\tPath to search in, first argument without a '-'\n\tuse '-' to search stdin instead\n\targuments after a \"--\" are never treated as options, even if they start with a '-'\n")?;
+    
+    writeln!(out, "QUERY:\n\tString to search for, all the stuff after the path\n\tmultiple words are joined with a single space, wrap in \"'s to keep other whitespace\n")?;
+
+    writeln!(out, "EXIT STATUS:\n\t{}\n", EXIT_STATUSES.iter().map(|(code, meaning)| format!("{} {}", code, meaning)).collect::<Vec<String>>().join(", "))?;
+
+    return Ok(());
+}
+
+pub fn search<'a> (query: &'a str, path: &'a Path) -> Result<bool,Box<dyn Error>> {
+    return search_with(&Matcher::literal(&[query], CaseMode::Sensitive, MatchKind::Substring), path);
+}
+
+pub fn search_case_insensitive<'a> (query: &'a str, path: &'a Path) -> Result<bool,Box<dyn Error>> {
+    return search_with(&Matcher::literal(&[query], CaseMode::Insensitive, MatchKind::Substring), path);
+}
+
+/**
+ * returns true if the file at path has any matches for matcher
+ */
+pub fn search_with(matcher: &Matcher, path: &Path) -> Result<bool,Box<dyn Error>> {
+    return search_reader_with(matcher, fs::File::open(path)?);
+}
+
+/**
+ * same as search, but reads the contents from anything that implements Read (like stdin) instead of a file
+ */
+pub fn search_reader<R: Read>(query: &str, reader: R) -> Result<bool,Box<dyn Error>> {
+    return search_reader_with(&Matcher::literal(&[query], CaseMode::Sensitive, MatchKind::Substring), reader);
+}
+
+/**
+ * returns true if the contents of reader have any matches for matcher
+ */
+pub fn search_reader_with<R: Read>(matcher: &Matcher, reader: R) -> Result<bool,Box<dyn Error>> {
+    //stop reading as soon as anything is found
+    return Ok(scan_lines(reader, |line| matcher.find_matches(line), Some(1), (0, 0), false, None, false, Encoding::Utf8, &mut FileRequirements::new(&[], None))?.match_count > 0);
+}
+
+/**
+ * what has to be true of a whole file for it to match, beyond it containing one of the patterns:
+ * with --all-of, every required matcher has to match somewhere in it, and with --not, the excluded matcher can't match anywhere in it
+ * lines are checked as they're read, so the file can stop being read as soon as that's known
+ */
+struct FileRequirements<'a> {
+    required: &'a [Matcher],
+    seen: Vec<bool>,    //which of the required matchers have matched so far
+    excluded: Option<&'a Matcher>,
+    failed: bool,       //whether the excluded matcher has matched
+}
+impl<'a> FileRequirements<'a> {
+    fn new(required: &'a [Matcher], excluded: Option<&'a Matcher>) -> FileRequirements<'a> {
+        return FileRequirements { required, seen: vec![false; required.len()], excluded, failed: false };
+    }
+
+    /**
+     * checks text (a line, or the whole contents with --multiline) for the required matchers that haven't matched yet, and the excluded one
+     */
+    fn check(&mut self, text: &str) {
+        for (matcher, seen) in self.required.iter().zip(self.seen.iter_mut()) {
+            if !*seen {
+                *seen = matcher.is_match(text);
+            }
+        }
+        self.failed = self.failed || self.excluded.is_some_and(|excluded| excluded.is_match(text));
+    }
+
+    /**
+     * returns true if the rest of the file can't change whether it matches, so it doesn't need to be read just for that
+     * nothing's settled while there's an excluded matcher, until it matches, since it could be on the very last line
+     */
+    fn settled(&self) -> bool {
+        return self.failed || (self.excluded.is_none() && self.satisfied());
+    }
+
+    /**
+     * returns true if the file can't match, whatever the rest of it has in it
+     */
+    fn failed(&self) -> bool {
+        return self.failed;
+    }
+
+    /**
+     * returns true if what's been checked so far is enough for the file to match
+     */
+    fn satisfied(&self) -> bool {
+        return !self.failed && self.seen.iter().all(|seen| *seen);
+    }
+}
+
+/**
+ * counts the matches find finds in each line of reader, keeping the lines with matches (and their context) if keep_lines is set
+ * if max_count is given, it stops after the line with the max_count'th match (and its context), unless requirements still need more of the file
+ * a file that doesn't meet the requirements has no matches, however many find found in it
+ * invalid utf-8 is an error, unless lossy is set, then it's replaced with U+FFFD
+ * reader gives utf-8 decoded from encoding, which is what byte offsets are counted in
+ */
+#[allow(clippy::too_many_arguments)]
+fn scan_lines<R: Read, F: Fn(&str) -> Vec<Range<usize>>>(reader: R, find: F, max_count: Option<usize>, context: (usize, usize), keep_lines: bool, hex_context: Option<usize>, lossy: bool, encoding: Encoding, requirements: &mut FileRequirements) -> Result<FileMatches,Box<dyn Error>> {
+    //DATA
+    let mut collector = LineCollector::new(context);
+    let mut hex: Option<HexCollector> = hex_context.map(HexCollector::new);
+    let mut count: usize = 0;
+    let mut reader = BufReader::new(reader);
+    let mut raw_line: Vec<u8> = Vec::new();
+    let mut line_number: usize = 0;
+    let mut byte_offset: u64 = 0;
+
+    //scan contents for queries, line by line, keeping track of where each line starts
+    loop {
+        raw_line.clear();
+        let bytes_read = reader.read_until(b'\n', &mut raw_line)?;
+        if bytes_read == 0 {
+            break;
+        }
+        let mut line: String = decode_utf8(&raw_line, lossy)?;
+        //replacing invalid utf-8 only ever makes the line longer, so if it's the same length, offsets in it are offsets in the file
+        let raw: Option<Vec<u8>> = (line.len() != raw_line.len()).then(|| raw_line.clone());
+        line_number += 1;
+        let line_start = byte_offset;
+        //utf-8 is read as is, so what was read is what's in the file (even where it isn't valid)
+        byte_offset += if encoding == Encoding::Utf8 {bytes_read} else {encoding.encoded_len(&line)} as u64;
+
+        //the line ending (\n or \r\n, or a \r at the very end of the file) isn't part of the line
+        //byte offsets still count it, so they're where the line is in the file
+        if line.ends_with('\n') {
+            line.pop();
+        }
+        if line.ends_with('\r') {
+            line.pop();
+        }
+
+        //once we've found enough matches, only the context after the last one is left, and whatever the requirements still need
+        //after Ctrl-C, what was found in the file so far is all there is
+        let found_enough: bool = max_count.is_some_and(|max_count| count >= max_count);
+        let hex_done: bool = hex.as_ref().is_none_or(|hex| !hex.wants_more());
+        if (found_enough && collector.after_left == 0 && hex_done && requirements.settled()) || requirements.failed() || interrupt::interrupted() {
+            break;
+        }
+        if !requirements.settled() {
+            requirements.check(&line);
+        }
+
+        //DATA
+        let matches: Vec<Range<usize>> = if found_enough {Vec::new()} else {find(&line)};
+
+        count += matches.len();
+        if let Some(hex) = &mut hex {
+            hex.push(&raw_line, &raw_ranges(&raw_line, line_start, &matches));
+        }
+        if keep_lines && collector.wants(&matches) {
+            collector.push(LineMatch { line_number, byte_offset: line_start, line: line.clone(), matches, encoding, raw });
+        }
+    }
+
+    if !requirements.satisfied() {
+        return Ok(FileMatches::default());
+    }
+    //the last line can take the count past max_count
+    let hex_dumps: Vec<HexDump> = hex.map(HexCollector::finish).unwrap_or_default();
+    return Ok(FileMatches { match_count: max_count.map_or(count, |max_count| count.min(max_count)), lines: collector.lines, binary: false, empty: false, name_match: false, hex_dumps });
+}
+
+/**
+ * same as scan_lines, but find (and the requirements) are given the whole contents of reader at once, so matches can span lines
+ * a match that spans lines is kept as a part of a match on each of the lines it covers, but only counted once
+ */
+#[allow(clippy::too_many_arguments)]
+fn scan_multiline<R: Read, F: Fn(&str) -> Vec<Range<usize>>>(mut reader: R, find: F, max_count: Option<usize>, context: (usize, usize), keep_lines: bool, hex_context: Option<usize>, lossy: bool, encoding: Encoding, requirements: &mut FileRequirements) -> Result<FileMatches,Box<dyn Error>> {
+    //DATA
+    let mut raw_contents: Vec<u8> = Vec::new();
+    reader.read_to_end(&mut raw_contents)?;
+    let contents: String = decode_utf8(&raw_contents, lossy)?;
+
+    requirements.check(&contents);
+    if !requirements.satisfied() {
+        return Ok(FileMatches::default());
+    }
+    let mut matches: Vec<Range<usize>> = find(&contents);
+    if let Some(max_count) = max_count {
+        matches.truncate(max_count);
+    }
+    let match_count: usize = matches.len();
+    let hex_dumps: Vec<HexDump> = match hex_context {
+        Some(hex_context) => {
+            //DATA
+            let mut hex = HexCollector::new(hex_context);
+
+            hex.push(&raw_contents, &raw_ranges(&raw_contents, 0, &matches));
+            hex.finish()
+        },
+        None => Vec::new(),
+    };
+    if !keep_lines || matches.is_empty() {
+        return Ok(FileMatches { match_count, lines: Vec::new(), binary: false, empty: false, name_match: false, hex_dumps });
+    }
+    let mut collector = LineCollector::new(context);
+    let mut first: usize = 0; //the first match that doesn't end before the current line
+    let mut line_start: usize = 0;
+    let mut byte_offset: u64 = 0; //where the current line starts in the file, which differs from line_start when it isn't utf-8
+
+    //split the contents back into lines, giving each one the parts of the matches that are on it
+    //replacing invalid utf-8 never adds or removes a \n, so the lines of raw_contents line up with them
+    for (index, (raw_line, raw)) in contents.split_inclusive('\n').zip(raw_contents.split_inclusive(|byte| *byte == b'\n')).enumerate() {
+        //DATA
+        let next_line_start: usize = line_start + raw_line.len();
+        //the line ending (\n or \r\n, or a \r at the very end of the file) isn't part of the line
+        let line: &str = raw_line.strip_suffix('\n').unwrap_or(raw_line);
+        let line: &str = line.strip_suffix('\r').unwrap_or(line);
+        let line_end: usize = line_start + line.len();
+
+        //matches are in order and don't overlap, so the ones that ended before this line are done with
+        //(an empty match "ends" where it starts)
+        while matches.get(first).is_some_and(|found| found.start.max(found.end.saturating_sub(1)) < line_start) {
+            first += 1;
+        }
+        if first == matches.len() && collector.after_left == 0 {
+            break;
+        }
+
+        //the parts of the matches on this line, relative to the start of it, the line ending is left out
+        let on_line: Vec<Range<usize>> = matches[first..].iter()
+            .take_while(|found| found.start < next_line_start)
+            .map(|found| {
+                let start = found.start.max(line_start).min(line_end);
+                start - line_start..found.end.min(line_end).max(start) - line_start
+            })
+            .collect();
+        if collector.wants(&on_line) {
+            collector.push(LineMatch { line_number: index + 1, byte_offset, line: line.to_string(), matches: on_line, encoding, raw: (raw_line.len() != raw.len()).then(|| raw.to_vec()) });
+        }
+        line_start = next_line_start;
+        byte_offset += if encoding == Encoding::Utf8 {raw.len()} else {encoding.encoded_len(raw_line)} as u64;
+    }
+
+    return Ok(FileMatches { match_count, lines: collector.lines, binary: false, empty: false, name_match: false, hex_dumps });
+}
+
+/**
+ * returns the byte ranges in raw (which starts at start in the file) of matches, which are ranges in its lossy decoding, from the start of the file
+ */
+fn raw_ranges(raw: &[u8], start: u64, matches: &[Range<usize>]) -> Vec<Range<u64>> {
+    return matches.iter().map(|found| start + raw_offset(raw, found.start) as u64..start + raw_offset(raw, found.end) as u64).collect();
+}
+
+/**
+ * returns where in raw the byte at offset in its lossy decoding came from, each invalid sequence in raw is one U+FFFD in the decoding
+ */
+fn raw_offset(raw: &[u8], offset: usize) -> usize {
+    //DATA
+    let mut decoded: usize = 0;
+    let mut at: usize = 0;
+
+    for chunk in raw.utf8_chunks() {
+        if offset <= decoded + chunk.valid().len() {
+            return at + offset - decoded;
+        }
+        decoded += chunk.valid().len();
+        at += chunk.valid().len();
+        if !chunk.invalid().is_empty() {
+            //offsets in the middle of the replacement can only be its start
+            if offset < decoded + char::REPLACEMENT_CHARACTER.len_utf8() {
+                return at;
+            }
+            decoded += char::REPLACEMENT_CHARACTER.len_utf8();
+            at += chunk.invalid().len();
+        }
+    }
+
+    //return
+    return at;
+}
+
+/**
+ * returns bytes as a string, invalid utf-8 is an error, unless lossy is set, then it's replaced with U+FFFD
+ */
+fn decode_utf8(bytes: &[u8], lossy: bool) -> Result<String,Box<dyn Error>> {
+    if lossy {
+        return Ok(String::from_utf8_lossy(bytes).into_owned());
+    }
+    return std::str::from_utf8(bytes).map(str::to_string).map_err(|_| "stream did not contain valid UTF-8".into());
+}
+
+/**
+ * collects the lines with matches, in order, along with the lines of context around them
+ * lines that are in the context of more than one match are only collected once
+ */
+struct LineCollector {
+    lines: Vec<LineMatch>,
+    before: usize,
+    after: usize,
+    recent: VecDeque<LineMatch>,    //lines that might be needed as context before the next match
+    after_left: usize,              //how many more lines are needed as context after the last match
+}
+impl LineCollector {
+    fn new(context: (usize, usize)) -> LineCollector {
+        let (before, after) = context;
+        return LineCollector { lines: Vec::new(), before, after, recent: VecDeque::new(), after_left: 0 };
+    }
+
+    /**
+     * returns true if a line with these matches might be collected, so lines that won't be don't need to be copied
+     */
+    fn wants(&self, matches: &[Range<usize>]) -> bool {
+        return !matches.is_empty() || self.after_left > 0 || self.before > 0;
+    }
+
+    fn push(&mut self, line: LineMatch) {
+        //lines without matches are context if they're right after a match, and might be context for the next match if they aren't
+        if line.matches.is_empty() {
+            if self.after_left > 0 {
+                self.after_left -= 1;
+                self.lines.push(line);
+            } else if self.before > 0 {
+                if self.recent.len() == self.before {
+                    self.recent.pop_front();
+                }
+                self.recent.push_back(line);
+            }
+            return;
+        }
+        self.lines.extend(self.recent.drain(..));
+        self.lines.push(line);
+        self.after_left = self.after;
+    }
+}
+
+/**
+ * returns the byte ranges of every non-overlapping occurrence of any of the queries in line that fits kind, case sensitive
+ * when occurrences overlap, the one that starts first (or the longest, if they start at the same place) wins
+ */
+pub fn find_matches<S: AsRef<str>>(queries: &[S], line: &str, kind: MatchKind) -> Vec<Range<usize>> {
+    //DATA
+    let mut occurrences: Vec<Range<usize>> = Vec::new();
+
+    //find every occurrence of every query, including overlapping ones, so an occurrence that doesn't fit kind can't hide one that does
+    for query in queries.iter().map(|query| query.as_ref()).filter(|query| !query.is_empty()) {
+        let mut start: usize = 0;
+        while let Some(index) = line[start..].find(query) {
+            let occurrence = start + index..start + index + query.len();
+            if kind.fits(line, &occurrence) {
+                occurrences.push(occurrence);
+            }
+            //move forward one character
+            start += index + line[start + index..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    //return
+    return select_matches(occurrences);
+}
+
+/**
+ * returns the byte ranges of every non-overlapping match of any of the regexes in line
+ * when matches overlap, the one that starts first (or the longest, if they start at the same place) wins
+ */
+pub fn find_regex_matches(regexes: &[Regex], line: &str) -> Vec<Range<usize>> {
+    return select_matches(regexes.iter().flat_map(|regex| regex.find_iter(line)).collect());
+}
+
+/**
+ * returns the first, longest, occurrence of every overlapping group of occurrences, in order
+ */
+fn select_matches(mut occurrences: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    //DATA
+    let mut matches: Vec<Range<usize>> = Vec::new();
+
+    occurrences.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+    for occurrence in occurrences {
+        //empty matches (like from "^") don't overlap anything, but the same one twice only counts once
+        if matches.last().is_none_or(|last| last.end <= occurrence.start && *last != occurrence) {
+            matches.push(occurrence);
+        }
+    }
+
+    return matches;
+}
+
+/**
+ * returns line with every one of the matches (byte ranges, like from find_matches) wrapped in color codes, if color is enabled
+ */
+pub fn highlight_matches(line: &str, matches: &[Range<usize>], color: bool) -> String {
+    //DATA
+    let mut highlighted = String::with_capacity(line.len());
+    let mut last_end: usize = 0;
+
+    //nothing to do without color
+    if !color {
+        return line.to_string();
+    }
+
+    //copy the line over, splicing color codes around each match
+    for range in matches {
+        highlighted.push_str(&line[last_end..range.start]);
+        highlighted.push_str(&paint(&line[range.clone()], COLOR_MATCH, color));
+        last_end = range.end;
+    }
+    highlighted.push_str(&line[last_end..]);
+
+    //return
+    return highlighted;
+}
+
+/**
+ * returns line with each of the matches swapped out for what replace returns for it, highlighted if color is enabled
+ */
+pub fn replace_matches<F: Fn(&Range<usize>) -> String>(line: &str, matches: &[Range<usize>], replace: F, color: bool) -> String {
+    //DATA
+    let mut replaced = String::with_capacity(line.len());
+    let mut last_end: usize = 0;
+
+    //copy the line over, splicing the replacements in where the matches were
+    for range in matches {
+        replaced.push_str(&line[last_end..range.start]);
+        replaced.push_str(&paint(&replace(range), COLOR_MATCH, color));
+        last_end = range.end;
+    }
+    replaced.push_str(&line[last_end..]);
+
+    //return
+    return replaced;
+}
+
+/**
+ * returns text wrapped in the given ANSI color code, if color is enabled
+ */
+fn paint(text: &str, color_code: &str, color: bool) -> String {
+    if !color {
+        return text.to_string();
+    }
+    return format!("{}{}{}", color_code, text, COLOR_RESET);
+}
+
+/**
+ * opens path for reading, STDIN_PATH reads from stdin instead
+ */
+fn open_reader(path: &Path) -> Result<Box<dyn Read>,Box<dyn Error>> {
+    if path.as_os_str() == STDIN_PATH {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    return Ok(Box::new(fs::File::open(path)?));
+}
+
+/**
+ * returns path in the verbatim form (\\?\) on windows if it's too long to be used otherwise, as is everywhere else
+ */
+#[cfg(windows)]
+fn long_path(path: PathBuf) -> PathBuf {
+    if path.as_os_str().len() < winpath::MAX_PATH {
+        return path;
+    }
+    return std::path::absolute(&path).ok()
+        .and_then(|absolute| absolute.to_str().and_then(winpath::to_verbatim))
+        .map_or(path, PathBuf::from);
+}
+#[cfg(not(windows))]
+fn long_path(path: PathBuf) -> PathBuf {
+    return path;
+}
+
+/**
+ * returns the tag printed after the names of files whose names matched, with --also-match-names
+ */
+fn name_tag(file_matches: &FileMatches) -> &'static str {
+    return if file_matches.name_match {" [name]"} else {""};
+}
+
+/**
+ * returns path as it should be shown to the user
+ */
+fn display_path(path: &Path) -> String {
+    if path.as_os_str() == STDIN_PATH {
+        return String::from("<stdin>");
+    }
+    return path.to_string_lossy().to_string();
+}
+
+/**
+ * returns true if the extension of path is one of the extensions in filter_for (case-insensitive),
+ * files without an extension match the empty string
+ */
+fn matches_filter(path: &Path, filter_for: &[String]) -> bool {
+    let extension = path.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+    return filter_for.contains(&extension);
+}
+
+/**
+ * returns the first of the globs that matches the file name of path, if any do
+ */
+fn matches_any_glob<'a>(path: &Path, globs: &'a [Glob]) -> Option<&'a Glob> {
+    let file_name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    return globs.iter().find(|glob| glob.matches(&file_name));
+}
+
+/**
+ * returns true if path is a dotfile or dot-directory, like .git or .env
+ */
+fn is_hidden(path: &Path) -> bool {
+    return path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+}
+
+/**
+ * returns a vector containing paths to all files in path and subdirectories of path,
+ * down to config.max_depth levels of subdirectories (0 being just the files in path),
+ * skipping files less than config.min_depth levels deep, directories named in config.exclude_dirs,
+ * and hidden files and directories unless config.hidden is set
+ * symlinks (to files or directories) are only followed if config.follow is set, and each directory is only visited once, so symlink loops end
+ * with config.one_file_system, directories on a different filesystem than path aren't gone into
+ * directories that can't be read are added to errors and skipped, the rest of the walk carries on without them
+ * files and directories that the .gitignore files in path (and its subdirectories) ignore are skipped, unless config.no_ignore is set,
+ * as are ones that config.ignore_files ignore (relative to path), which win over the .gitignore files
+ */
+fn list_files_recurse(path: &Path, config: &Config, errors: &mut Vec<PathError>) -> Vec<PathBuf> {
+    let mut vec = Vec::new();
+    let mut visited = HashSet::from([fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())]);
+    let filesystem: Option<u64> = if config.one_file_system {filesystem_id(path)} else {None};
+    let mut ignores = IgnoreStack::new(config.ignore_files.iter().map(|(source, contents)| Gitignore::new(contents, source, path)).collect());
+    _list_files_recurse(&mut vec,path,0,config,&mut visited,filesystem,&mut ignores,errors);
+    vec
+}
+#[allow(clippy::too_many_arguments)]
+fn _list_files_recurse(vec: &mut Vec<PathBuf>, path: &Path, depth: usize, config: &Config, visited: &mut HashSet<PathBuf>, filesystem: Option<u64>, ignores: &mut IgnoreStack, errors: &mut Vec<PathError>) {
+    if path.is_dir() {
+        //directories that can't be read (like ones without permission) are skipped, along with everything in them
+        let paths = match fs::read_dir(path) {
+            Ok(paths) => paths,
+            Err(error) => {
+                errors.push(PathError { path: path.to_path_buf(), error });
+                return;
+            },
+        };
+        //a .gitignore applies to everything under the directory it's in, and wins over the ones in the directories above
+        let gitignore_path: PathBuf = path.join(".gitignore");
+        let has_gitignore: bool = !config.no_ignore && gitignore_path.is_file();
+        if has_gitignore {
+            match Gitignore::from_file(&gitignore_path, path) {
+                Ok(gitignore) => ignores.push(gitignore),
+                Err(err) => if config.verbose {eprintln!("Error reading {:?}: {}",display_path(&gitignore_path), err);},
+            }
+        }
+        //entries are walked in name order, so the path a directory that's reachable more than once (with --follow) is walked under
+        //doesn't depend on the order the filesystem lists them in
+        let mut entries: Vec<io::Result<fs::DirEntry>> = paths.collect();
+        entries.sort_by_key(|entry| entry.as_ref().ok().map(fs::DirEntry::file_name));
+        for path_result in entries {
+            //after Ctrl-C, there's no point in finding any more files
+            if interrupt::interrupted() {
+                break;
+            }
+            let entry = match path_result {
+                Ok(entry) => entry,
+                Err(error) => {
+                    errors.push(PathError { path: path.to_path_buf(), error });
+                    continue;
+                },
+            };
+            let full_path = long_path(entry.path());
+            let is_symlink = entry.file_type().is_ok_and(|file_type| file_type.is_symlink());
+            //skip hidden files and directories, unless asked not to
+            if !config.hidden && is_hidden(&full_path) {
+                continue;
+            }
+            //skip broken symlinks, there's nothing to search
+            if is_symlink && !full_path.exists() {
+                if config.verbose {eprintln!("Skipping {:?}: broken symlink",display_path(&full_path));}
+                continue;
+            }
+            //skip what the ignore files ignore, ignored directories aren't even gone into
+            let is_dir: bool = full_path.is_dir();
+            if let Some((ignore_file, rule)) = ignores.ignored_by(&full_path, is_dir).filter(|(_, rule)| !rule.is_negated()) {
+                if config.verbose {eprintln!("Skipping {:?}: ignored by '{}' in {:?}",display_path(&full_path), rule.as_str(), display_path(ignore_file.source()));}
+                continue;
+            }
+            if is_dir {
+                //only go into symlinked directories when following symlinks
+                if is_symlink && !config.follow {
+                    continue;
+                }
+                //when following symlinks, never go into a directory we've already been in (that's a loop)
+                if config.follow && !visited.insert(fs::canonicalize(&full_path).unwrap_or_else(|_| full_path.clone())) {
+                    continue;
+                }
+                //with --one-file-system, don't go into directories other filesystems are mounted on
+                if filesystem.is_some() && filesystem_id(&full_path) != filesystem {
+                    if config.verbose {eprintln!("Skipping {:?}: on a different filesystem",display_path(&full_path));}
+                    continue;
+                }
+                //don't descend past the max depth, or into excluded directories (so they're never even read)
+                let excluded = full_path.file_name().is_some_and(|name| config.exclude_dirs.contains(name.to_string_lossy().as_ref()));
+                if !excluded && config.max_depth.is_none_or(|max_depth| depth < max_depth) {
+                    _list_files_recurse(vec, &full_path, depth + 1, config, visited, filesystem, ignores, errors);
+                }
+            } else if depth >= config.min_depth {
+                //symlinked files are only searched when following symlinks, like symlinked directories
+                if is_symlink && !config.follow {
+                    if config.verbose {eprintln!("Skipping {:?}: symlink, use --follow to search it",display_path(&full_path));}
+                    continue;
+                }
+                vec.push(full_path);
+            }
+        }
+        //this directory's .gitignore doesn't apply to the ones after it
+        if has_gitignore && ignores.last().is_some_and(|gitignore| gitignore.source() == gitignore_path) {
+            ignores.pop();
+        }
+    }
+}
+/**
+ * what tells files apart, so the same file reached by different paths (like through hard links or symlinks) is only searched once
+ * on unix that's the device and inode, elsewhere it's the canonical path (which can't tell hard links apart)
+ */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum FileId {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Inode(u64, u64),
+    Path(PathBuf),
+}
+impl FileId {
+    #[cfg(unix)]
+    fn of(path: &Path) -> FileId {
+        use std::os::unix::fs::MetadataExt;
+        if path.as_os_str() != STDIN_PATH {
+            if let Ok(metadata) = fs::metadata(path) {
+                return FileId::Inode(metadata.dev(), metadata.ino());
+            }
+        }
+        return FileId::Path(path.to_path_buf());
+    }
+    #[cfg(not(unix))]
+    fn of(path: &Path) -> FileId {
+        return FileId::Path(fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    }
+}
+
+/**
+ * returns an id for the filesystem path is on, the device id on unix, or the drive (or volume) on windows
+ * returns None if it can't be found, or there's no way to tell on this platform
+ */
+#[cfg(unix)]
+fn filesystem_id(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    return fs::metadata(path).ok().map(|metadata| metadata.dev());
+}
+#[cfg(windows)]
+fn filesystem_id(path: &Path) -> Option<u64> {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+    use std::path::Component;
+    //best effort: directories with the same drive or volume prefix are on the same filesystem
+    let path = fs::canonicalize(path).ok()?;
+    let Some(Component::Prefix(prefix)) = path.components().next() else {return None;};
+    let mut hasher = DefaultHasher::new();
+    prefix.as_os_str().to_ascii_lowercase().hash(&mut hasher);
+    return Some(hasher.finish());
+}
+#[cfg(not(any(unix, windows)))]
+fn filesystem_id(_path: &Path) -> Option<u64> {
+    return None;
+}
+
+/**
+ * returns a vector containing paths to all files in path, but not subdirectories of path
+ * hidden files are skipped unless config.hidden is set, what can't be read is added to errors and skipped
+ */
+fn list_files(path: &Path, config: &Config, errors: &mut Vec<PathError>) -> Vec<PathBuf> {
+    let mut vec = Vec::new();
+    if path.is_dir() {
+        let paths = match fs::read_dir(path) {
+            Ok(paths) => paths,
+            Err(error) => {
+                errors.push(PathError { path: path.to_path_buf(), error });
+                return vec;
+            },
+        };
+        for path_results in paths {
+            let full_path = match path_results {
+                Ok(entry) => entry.path(),
+                Err(error) => {
+                    errors.push(PathError { path: path.to_path_buf(), error });
+                    continue;
+                },
+            };
+            if !full_path.is_dir() && (config.hidden || !is_hidden(&full_path)) {
+                vec.push(full_path);
             }
         }
     }
     return vec;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * builds a config from args (after the program name), without the environment or a config file, so the tests don't depend on either
+     */
+    fn config(args: &[&str]) -> Result<Config, Box<dyn Error>> {
+        //DATA
+        let mut all_args: Vec<&str> = vec!["grep-directory"];
+
+        all_args.extend(args);
+        return Config::new_with_defaults(&all_args, None, None);
+    }
+
+    #[test]
+    fn query_words_are_joined_with_a_space() {
+        assert_eq!(config(&["src", "hello", "world"]).unwrap().query, "hello world");
+        assert_eq!(config(&["src", "one", "two", "three"]).unwrap().query, "one two three");
+    }
+
+    #[test]
+    fn query_keeps_whitespace_inside_one_argument() {
+        assert_eq!(config(&["src", "two  spaces\tand a tab"]).unwrap().query, "two  spaces\tand a tab");
+        assert_eq!(config(&["src", "a  b", "c"]).unwrap().query, "a  b c");
+    }
+
+    #[test]
+    fn query_is_the_first_pattern() {
+        assert_eq!(config(&["src", "hello", "world"]).unwrap().patterns, vec!["hello world"]);
+    }
+
+    #[test]
+    fn arguments_after_a_double_dash_are_positional() {
+        assert_eq!(config(&["src", "--", "-DFOO"]).unwrap().query, "-DFOO");
+        assert_eq!(config(&["src", "--", "-v", "--recursive"]).unwrap().query, "-v --recursive");
+        assert!(!config(&["src", "--", "-r", "x"]).unwrap().recurse);
+        assert!(config(&["src", "-DFOO"]).is_err());
+    }
+
+    #[test]
+    fn short_flags_can_be_bundled_in_any_order() {
+        for bundle in ["-rv", "-vr"] {
+            //DATA
+            let config: Config = config(&[bundle, "src", "x"]).unwrap();
+
+            assert!(config.recurse && config.verbose, "{}", bundle);
+        }
+    }
+
+    #[test]
+    fn bundle_with_an_unknown_flag_names_it() {
+        assert_eq!(config(&["-rX", "src", "x"]).err().unwrap().to_string(), "Unknown flag 'X' in '-rX'.");
+        assert!(config(&["--rv", "src", "x"]).is_err());
+    }
+
+    #[test]
+    fn rest_of_a_bundle_is_the_value_of_a_flag_that_takes_one() {
+        assert_eq!(config(&["-m1", "src", "x"]).unwrap().max_count, Some(1));
+        assert_eq!(config(&["-C12", "src", "x"]).unwrap().before_context, 12);
+
+        //DATA
+        let bundled: Config = config(&["-rA2", "src", "x"]).unwrap();
+        assert!(bundled.recurse);
+        assert_eq!(bundled.after_context, 2);
+
+        //it's the value even if it starts with something that'd be a flag on its own
+        assert_eq!(config(&["-ervx", "src"]).unwrap().patterns, vec!["rvx"]);
+        //a flag that takes a value at the end of a bundle takes the next argument instead
+        assert_eq!(config(&["-rm", "3", "src", "x"]).unwrap().max_count, Some(3));
+    }
+
+    #[test]
+    fn filter_extensions_are_normalized() {
+        //DATA
+        let config: Config = config(&["--filter", "RS, .toml,noext,", "src", "x"]).unwrap();
+
+        assert!(config.filter);
+        assert_eq!(config.filter_for, vec!["rs", "toml", "", ""]);
+        assert!(matches_filter(Path::new("src/lib.RS"), &config.filter_for));
+        assert!(matches_filter(Path::new("Makefile"), &config.filter_for));
+        assert!(!matches_filter(Path::new("README.md"), &config.filter_for));
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("--recusive", "--recursive"), 1);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+        //it counts characters, not bytes
+        assert_eq!(levenshtein("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn closest_option_suggests_near_typos_only() {
+        assert_eq!(closest_option("--recusive"), Some("--recursive"));
+        assert_eq!(closest_option("--verbos"), Some("--verbose"));
+        assert_eq!(closest_option("--completely-made-up"), None);
+        //short flags are all too close to each other to suggest one
+        assert_eq!(closest_option("-Y"), None);
+    }
+
+    #[test]
+    fn unknown_options_are_all_named_with_suggestions() {
+        //DATA
+        let error: String = config(&["--recusive", "--verbos", "--zzzzzzzz", "src", "x"]).err().unwrap().to_string();
+
+        assert!(error.contains("unknown option '--recusive', did you mean '--recursive'?"), "{}", error);
+        assert!(error.contains("unknown option '--verbos', did you mean '--verbose'?"), "{}", error);
+        assert!(error.contains("unknown option '--zzzzzzzz'"), "{}", error);
+    }
+
+    #[test]
+    fn help_works_without_a_path_or_query() {
+        for args in [&["prog", "help"][..], &["prog", "-h"], &["prog", "--help"], &["prog", "--help", "extra"], &["prog", "-r", "-h"]] {
+            assert!(Config::new_with_defaults(args, None, None).unwrap().help, "{:?}", args);
+        }
+    }
+
+    #[test]
+    fn help_is_only_a_bare_word_on_its_own() {
+        //with something else, "help" is a path like any other
+        assert_eq!(Config::new_with_defaults(&["prog", "help", "me"], None, None).err().unwrap().to_string(), "Invalid path 'help'.");
+        assert_eq!(Config::new_with_defaults(&["prog", "-p", "src", "help"], None, None).unwrap().query, "help");
+    }
+
+    #[test]
+    fn version_works_without_a_path_or_query() {
+        assert!(Config::new_with_defaults(&["prog", "--version"], None, None).unwrap().version);
+        assert!(Config::new_with_defaults(&["prog", "-V"], None, None).unwrap().version);
+        assert!(Config::new_with_defaults(&["prog", "version"], None, None).unwrap().version);
+        assert!(Config::new_with_defaults(&["prog"], None, None).is_err());
+    }
+
+    #[test]
+    fn version_lists_the_features() {
+        //DATA
+        let mut out: Vec<u8> = Vec::new();
+
+        write_version(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("grep-directory {}\nfeatures: +regex +encoding +gzip +zip +tar +pre {}one-file-system {}windows-paths\n",
+            env!("CARGO_PKG_VERSION"), if cfg!(any(unix, windows)) {'+'} else {'-'}, if cfg!(windows) {'+'} else {'-'}));
+    }
+
+    #[test]
+    fn values_can_follow_an_equals_sign_or_be_the_next_argument() {
+        assert_eq!(config(&["--filter=rs,toml", "src", "x"]).unwrap().filter_for, vec!["rs", "toml"]);
+        assert_eq!(config(&["--filter", "rs,toml", "src", "x"]).unwrap().filter_for, vec!["rs", "toml"]);
+        //only the first = splits the option from its value
+        assert_eq!(config(&["--regexp=a=b", "src"]).unwrap().patterns, vec!["a=b"]);
+        assert_eq!(config(&["--replace", "x=y", "src", "x"]).unwrap().replace.as_deref(), Some("x=y"));
+    }
+
+    #[test]
+    fn missing_values_are_errors() {
+        assert_eq!(config(&["src", "x", "--max-depth"]).err().unwrap().to_string(), "Option '--max-depth' requires a value.");
+        assert_eq!(config(&["--verbose=yes", "src", "x"]).err().unwrap().to_string(), "Option '--verbose' doesn't take a value.");
+    }
+
+    #[test]
+    fn only_values_that_are_options_are_rejected() {
+        assert_eq!(config(&["-e", "-foo", "src"]).unwrap().patterns, vec!["-foo"]);
+        assert_eq!(config(&["--replace", "-", "src", "x"]).unwrap().replace.as_deref(), Some("-"));
+        assert_eq!(config(&["-e", "->", "src"]).unwrap().patterns, vec!["->"]);
+        assert_eq!(config(&["-e", "-v", "src"]).err().unwrap().to_string(), "Option '-e' requires a value, but got the option '-v', use '--regexp=-v' if that's the value.");
+        assert!(config(&["--max-depth", "--filter=rs", "src", "x"]).is_err());
+        assert_eq!(config(&["--regexp=-v", "src"]).unwrap().patterns, vec!["-v"]);
+    }
+
+    #[test]
+    fn every_path_option_is_a_path_and_the_positionals_are_the_query() {
+        //DATA
+        let config: Config = config(&["-p", "src", "--path", "Cargo.toml", "two", "words"]).unwrap();
+
+        assert_eq!(config.paths, vec![PathBuf::from("src"), PathBuf::from("Cargo.toml")]);
+        assert_eq!(config.query, "two words");
+    }
+
+    #[test]
+    fn stdin_path_is_allowed_without_existing() {
+        assert_eq!(config(&["-", "x"]).unwrap().paths, vec![PathBuf::from("-")]);
+    }
+
+    #[test]
+    fn search_reader_searches_anything_readable() {
+        assert!(search_reader("ERROR", "ok\nan ERROR here\n".as_bytes()).unwrap());
+        assert!(!search_reader("ERROR", "ok\nan error here\n".as_bytes()).unwrap());
+        assert!(!search_reader("ERROR", io::empty()).unwrap());
+        assert!(search_reader_with(&Matcher::literal(&["error"], CaseMode::Insensitive, MatchKind::Substring), "an ERROR\n".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn search_wrappers_search_files() {
+        //DATA
+        let path: &Path = Path::new("Cargo.toml");
+
+        assert!(search("[package]", path).unwrap());
+        assert!(!search("[PACKAGE]", path).unwrap());
+        assert!(search_case_insensitive("[PACKAGE]", path).unwrap());
+        assert!(search_with(&Matcher::literal(&["nope", "version"], CaseMode::Sensitive, MatchKind::Word), path).unwrap());
+        assert!(search("x", Path::new("no such file")).is_err());
+    }
+
+    /**
+     * same as config, but with env_options as the contents of GREP_DIRECTORY_OPTS
+     */
+    fn config_with_env(env_options: &str, args: &[&str]) -> Result<Config, Box<dyn Error>> {
+        //DATA
+        let mut all_args: Vec<&str> = vec!["grep-directory"];
+
+        all_args.extend(args);
+        return Config::new_with_defaults(&all_args, Some(env_options), None);
+    }
+
+    /**
+     * writes contents to a file of its own in the temp directory, named after the test that needs it, and returns its path
+     */
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        //DATA
+        let path: PathBuf = env::temp_dir().join(format!("grep-directory-unit-{}-{}", std::process::id(), name));
+
+        fs::write(&path, contents).unwrap();
+        return path;
+    }
+
+    #[test]
+    fn env_options_are_defaults() {
+        //DATA
+        let config: Config = config_with_env("-r --verbose  --max-depth=3", &["src", "x"]).unwrap();
+
+        assert!(config.recurse && config.verbose);
+        assert_eq!(config.max_depth, Some(3));
+        assert!(!config_with_env("", &["src", "x"]).unwrap().recurse);
+    }
+
+    #[test]
+    fn command_line_wins_over_env_options() {
+        assert_eq!(config_with_env("-i", &["-s", "src", "x"]).unwrap().case_mode, CaseMode::Sensitive);
+        assert_eq!(config_with_env("--color always", &["--color", "never", "src", "x"]).unwrap().color, ColorChoice::Never);
+        assert_eq!(config_with_env("--max-depth 1", &["--max-depth", "4", "src", "x"]).unwrap().max_depth, Some(4));
+    }
+
+    #[test]
+    fn env_option_errors_say_where_they_came_from() {
+        assert!(config_with_env("--recusive", &["src", "x"]).err().unwrap().to_string().starts_with("in GREP_DIRECTORY_OPTS: unknown option '--recusive'"));
+        assert!(config_with_env("--max-depth", &["src", "x"]).err().unwrap().to_string().starts_with("in GREP_DIRECTORY_OPTS: Option '--max-depth' requires a value"));
+        assert_eq!(config_with_env("-r src", &["src", "x"]).err().unwrap().to_string(), "in GREP_DIRECTORY_OPTS: only options are allowed, got 'src'");
+        //the same mistake on the command line doesn't mention the environment
+        assert!(!config_with_env("", &["--recusive", "src", "x"]).err().unwrap().to_string().contains("GREP_DIRECTORY_OPTS"));
+    }
+
+    #[test]
+    fn config_file_has_an_option_per_line() {
+        //DATA
+        let path: PathBuf = temp_file("config-file-options", "# defaults\n\nrecursive\n  --verbose  \nexclude-dir target\nfilenames\nfilenames path\n");
+
+        assert_eq!(read_config_file(&path), vec![
+            (String::from("--recursive"), None),
+            (String::from("--verbose"), None),
+            (String::from("--exclude-dir"), Some(String::from("target"))),
+            (String::from("--filenames"), None),
+            (String::from("--filenames"), Some(String::from("path"))),
+        ]);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn config_file_skips_lines_it_does_not_understand() {
+        //DATA
+        let path: PathBuf = temp_file("config-file-skips", "recusive\nmax-depth\nverbose yes\nhidden\n");
+
+        assert_eq!(read_config_file(&path), vec![(String::from("--hidden"), None)]);
+        assert_eq!(read_config_file(Path::new("no such config file")), Vec::new());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn config_file_comes_before_env_options_and_the_command_line() {
+        //DATA
+        let path: PathBuf = temp_file("config-file-precedence", "max-depth 1\ncolor always\nhidden\n");
+        let from_file: Config = Config::new_with_defaults(&["prog", "src", "x"], None, Some(&path)).unwrap();
+        let from_env: Config = Config::new_with_defaults(&["prog", "src", "x"], Some("--max-depth 2"), Some(&path)).unwrap();
+        let from_args: Config = Config::new_with_defaults(&["prog", "--max-depth", "3", "src", "x"], Some("--max-depth 2"), Some(&path)).unwrap();
+        let no_config: Config = Config::new_with_defaults(&["prog", "--no-config", "src", "x"], None, Some(&path)).unwrap();
+
+        assert_eq!((from_file.max_depth, from_file.color, from_file.hidden), (Some(1), ColorChoice::Always, true));
+        assert_eq!((from_env.max_depth, from_env.color), (Some(2), ColorChoice::Always));
+        assert_eq!(from_args.max_depth, Some(3));
+        assert_eq!((no_config.max_depth, no_config.color, no_config.hidden), (None, ColorChoice::Auto, false));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn case_flags_pick_the_matching_search() {
+        //case sensitive (what search does) is the default, and the last of -i and -s wins
+        for (args, case_mode) in [
+            (&[][..], CaseMode::Sensitive),
+            (&["-i"], CaseMode::Insensitive),
+            (&["--ignore-case"], CaseMode::Insensitive),
+            (&["--case-insensitive"], CaseMode::Insensitive),
+            (&["-s"], CaseMode::Sensitive),
+            (&["--case-sensitive"], CaseMode::Sensitive),
+            (&["-i", "-s"], CaseMode::Sensitive),
+            (&["-s", "-i"], CaseMode::Insensitive),
+            (&["--case-insensitive", "--case-sensitive"], CaseMode::Sensitive),
+        ] {
+            //DATA
+            let mut all_args: Vec<&str> = args.to_vec();
+            all_args.extend(["src", "Query"]);
+            let config: Config = config(&all_args).unwrap();
+
+            assert_eq!(config.case_mode, case_mode, "{:?}", args);
+            match case_mode {
+                CaseMode::Sensitive => assert!(matches!(config.matcher, Matcher::Literal { .. }), "{:?}", args),
+                CaseMode::Insensitive => assert!(matches!(config.matcher, Matcher::LiteralCaseInsensitive { .. }), "{:?}", args),
+            }
+        }
+    }
+
+    #[test]
+    fn case_insensitive_is_not_count() {
+        //-c used to be the short form of --case-insensitive, it's --count now
+        assert_eq!(config(&["-c", "src", "x"]).unwrap().case_mode, CaseMode::Sensitive);
+        assert_eq!(config(&["-c", "src", "x"]).unwrap().output_mode, OutputMode::Count);
+    }
+
+    #[test]
+    fn smart_case_is_decided_once_from_every_pattern() {
+        assert_eq!(config(&["--smart-case", "src", "foo"]).unwrap().case_mode, CaseMode::Insensitive);
+        assert_eq!(config(&["--smart-case", "src", "Foo"]).unwrap().case_mode, CaseMode::Sensitive);
+        //any pattern with an uppercase character makes it case sensitive
+        assert_eq!(config(&["--smart-case", "-e", "foo", "-e", "BAR", "src"]).unwrap().case_mode, CaseMode::Sensitive);
+        assert_eq!(config(&["--smart-case", "-s", "src", "foo"]).unwrap().case_mode, CaseMode::Sensitive);
+        assert_eq!(config(&["--smart-case", "-i", "src", "Foo"]).unwrap().case_mode, CaseMode::Insensitive);
+    }
+
+    /**
+     * parses value the way --max-filesize does
+     */
+    fn size(value: &str) -> Result<u64, Box<dyn Error>> {
+        return parse_size("--max-filesize", &Some(value.to_string()));
+    }
+
+    #[test]
+    fn blank_queries_are_rejected() {
+        for query in [" ", "\t", "  \t "] {
+            assert_eq!(config(&["src", query]).err().unwrap().to_string(), "The query is blank (just whitespace), so it would match almost every file, use --allow-blank-query if that's what you meant.", "{:?}", query);
+        }
+        assert_eq!(config(&["-e", " ", "src"]).err().unwrap().to_string(), "The pattern \" \" is blank (just whitespace), so it would match almost every file, use --allow-blank-query if that's what you meant.");
+    }
+
+    #[test]
+    fn blank_queries_can_be_allowed() {
+        assert_eq!(config(&["--allow-blank-query", "src", "\t"]).unwrap().patterns, vec!["\t"]);
+        assert_eq!(config(&["--allow-blank-query", "-e", " ", "src"]).unwrap().patterns, vec![" "]);
+    }
+
+    #[test]
+    fn spaces_around_a_query_are_kept() {
+        assert_eq!(config(&["src", " foo "]).unwrap().patterns, vec![" foo "]);
+        assert_eq!(config(&["-e", "\tfoo", "src"]).unwrap().patterns, vec!["\tfoo"]);
+    }
+
+    #[test]
+    fn parse_size_takes_bare_bytes() {
+        assert_eq!(size("0").unwrap(), 0);
+        assert_eq!(size("512").unwrap(), 512);
+        assert_eq!(size(" 512 ").unwrap(), 512);
+    }
+
+    #[test]
+    fn parse_size_takes_suffixes_in_either_case() {
+        assert_eq!(size("10K").unwrap(), 10 * 1024);
+        assert_eq!(size("10k").unwrap(), 10 * 1024);
+        assert_eq!(size("3M").unwrap(), 3 * 1024 * 1024);
+        assert_eq!(size("1g").unwrap(), 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn parse_size_rejects_anything_else() {
+        for value in ["", "K", "-1", "1.5M", "10KB", "10T", "ten", "99999999999999999999G"] {
+            assert_eq!(size(value).err().unwrap().to_string(), format!("Invalid value '{}' for '--max-filesize', expected a size like 512, 10K, 10M, or 1G.", value.trim()), "{}", value);
+        }
+        assert!(parse_size("--max-filesize", &None).is_err());
+    }
+
+    #[test]
+    fn min_and_max_size_use_the_same_sizes() {
+        //DATA
+        let sized: Config = config(&["--min-size", "1K", "--max-size", "2k", "src", "needle"]).unwrap();
+
+        assert_eq!((sized.min_size, sized.max_size), (Some(1024), Some(2048)));
+        assert!(config(&["--min-size", "1K", "--max-size", "1024", "src", "needle"]).is_ok());
+        assert_eq!(config(&["--min-size", "1025", "--max-size", "1K", "src", "needle"]).err().unwrap().to_string(), "--min-size (1025) can't be greater than --max-size (1024).");
+    }
+
+    #[test]
+    fn run_returns_the_total_match_count() {
+        //DATA
+        let path: PathBuf = temp_file("count-matches.rs", "unwrap( unwrap(\nnone\nunwrap(\n");
+        let mut out: Vec<u8> = Vec::new();
+        let summary: MatchSummary = run_with_output(config(&["--count-matches", path.to_str().unwrap(), "unwrap("]).unwrap(), &mut out).unwrap();
+
+        assert_eq!(summary.total_matches, 3);
+        assert_eq!(String::from_utf8(out).unwrap(), "3\n");
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn run_returns_the_stats() {
+        //DATA
+        let path: PathBuf = temp_file("stats.txt", "hit\nhit\nnone\n");
+        let mut out: Vec<u8> = Vec::new();
+        let summary: MatchSummary = run_with_output(config(&["--stats", path.to_str().unwrap(), "hit"]).unwrap(), &mut out).unwrap();
+
+        assert_eq!((summary.stats.files_searched, summary.stats.files_matched, summary.stats.total_matches), (1, 1, 2));
+        assert!(summary.stats.elapsed > Duration::ZERO);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn sorting_puts_files_without_metadata_last() {
+        //DATA
+        let small: PathBuf = temp_file("sort-small.txt", "x");
+        let big: PathBuf = temp_file("sort-big.txt", "xxxx");
+        let missing: PathBuf = env::temp_dir().join("grep-directory-unit-no-such-file");
+
+        for sort in [SortBy::Size, SortBy::Modified] {
+            //DATA
+            let mut paths: Vec<PathBuf> = vec![missing.clone(), big.clone(), small.clone()];
+
+            sort.sort(&mut paths);
+            assert_eq!(paths.last(), Some(&missing), "{:?}", sort);
+        }
+        fs::remove_file(small).unwrap();
+        fs::remove_file(big).unwrap();
+    }
+
+    #[test]
+    fn output_paths_are_left_alone_by_default() {
+        //DATA
+        let config: Config = config(&["src", "x"]).unwrap();
+
+        assert_eq!(config.output_path(Path::new("src/lib.rs")), Path::new("src/lib.rs"));
+        assert_eq!(config.output_path(Path::new(STDIN_PATH)), Path::new(STDIN_PATH));
+    }
+
+    #[test]
+    fn absolute_output_paths_are_canonical() {
+        //DATA
+        let config: Config = config(&["--absolute-paths", "src", "x"]).unwrap();
+
+        assert_eq!(config.output_path(Path::new("src/../src/lib.rs")), fs::canonicalize("src/lib.rs").unwrap());
+        //a file that's gone can't be canonicalized, so it's joined to the current directory instead
+        assert_eq!(config.output_path(Path::new("src/gone.rs")), env::current_dir().unwrap().join("src/gone.rs"));
+        assert_eq!(config.output_path(Path::new(STDIN_PATH)), Path::new(STDIN_PATH));
+    }
+
+    #[test]
+    fn relative_output_paths_strip_the_base() {
+        for base in ["src", "src/", "./src/../src", "src/."] {
+            //DATA
+            let config: Config = config(&["--relative-to", base, "src", "x"]).unwrap();
+
+            assert_eq!(config.output_path(Path::new("src/lib.rs")), Path::new("lib.rs"), "{}", base);
+            assert_eq!(config.output_path(Path::new("./src/../src/lib.rs")), Path::new("lib.rs"), "{}", base);
+            assert_eq!(config.output_path(Path::new("src")), Path::new("."), "{}", base);
+        }
+    }
+
+    #[test]
+    fn relative_output_paths_outside_the_base_are_absolute() {
+        //DATA
+        let config: Config = config(&["--relative-to", "src", "src", "x"]).unwrap();
+
+        assert_eq!(config.output_path(Path::new("Cargo.toml")), fs::canonicalize("Cargo.toml").unwrap());
+        assert_eq!(config.output_path(Path::new(STDIN_PATH)), Path::new(STDIN_PATH));
+    }
+
+    #[test]
+    fn relative_output_paths_only_strip_whole_components() {
+        //DATA
+        let root: PathBuf = env::temp_dir().join(format!("grep-directory-unit-{}-relative", std::process::id()));
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("ab")).unwrap();
+        fs::write(root.join("ab/file.txt"), "").unwrap();
+        let config: Config = config(&["--relative-to", root.join("a").to_str().unwrap(), "src", "x"]).unwrap();
+
+        //ab starts with a, but it isn't in it
+        assert_eq!(config.output_path(&root.join("ab/file.txt")), fs::canonicalize(root.join("ab/file.txt")).unwrap());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn relative_output_paths_see_through_symlinks() {
+        //DATA
+        let root: PathBuf = env::temp_dir().join(format!("grep-directory-unit-{}-relative-links", std::process::id()));
+        fs::create_dir_all(root.join("real/sub")).unwrap();
+        fs::write(root.join("real/sub/file.txt"), "").unwrap();
+        std::os::unix::fs::symlink(root.join("real"), root.join("link")).unwrap();
+        let through_link: Config = config(&["--relative-to", root.join("link").to_str().unwrap(), "src", "x"]).unwrap();
+        let real: Config = config(&["--relative-to", root.join("real").to_str().unwrap(), "src", "x"]).unwrap();
+
+        //both sides are canonicalized, so it doesn't matter which of them went through the link
+        for config in [&through_link, &real] {
+            assert_eq!(config.output_path(&root.join("real/sub/file.txt")), Path::new("sub/file.txt"));
+            assert_eq!(config.output_path(&root.join("link/sub/file.txt")), Path::new("sub/file.txt"));
+        }
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn relative_to_must_exist() {
+        assert!(config(&["--relative-to", "no-such-dir", "src", "x"]).err().unwrap().to_string().starts_with("Invalid path 'no-such-dir' for '--relative-to': "));
+    }
+
+    #[test]
+    fn files_that_disappear_before_theyre_searched_are_skipped() {
+        //DATA
+        let kept: PathBuf = temp_file("vanishing-kept.txt", "needle\n");
+        let vanished: PathBuf = temp_file("vanishing-gone.txt", "needle\n");
+        let config: Config = config(&["-p", kept.to_str().unwrap(), "-p", vanished.to_str().unwrap(), "--threads", "1", "needle"]).unwrap();
+        let mut results: Vec<(PathBuf, bool)> = Vec::new();
+
+        //listed, then deleted before it's searched
+        fs::remove_file(&vanished).unwrap();
+        search_paths(&[kept.clone(), vanished.clone()], &config, |path, result| {
+            results.push((path.to_path_buf(), result.expect("a file that's gone isn't an error").is_some()));
+            true
+        });
+        assert_eq!(results, vec![(kept.clone(), true), (vanished, false)]);
+        fs::remove_file(kept).unwrap();
+    }
+
+    #[test]
+    fn other_errors_are_still_reported() {
+        //DATA
+        let config: Config = config(&["src", "needle"]).unwrap();
+        let denied: Box<dyn Error> = Box::new(io::Error::new(io::ErrorKind::PermissionDenied, "denied"));
+        let gone: Box<dyn Error> = Box::new(io::Error::new(io::ErrorKind::NotFound, "gone"));
+
+        assert_eq!(search_result(Path::new("a"), Err(denied), &config).err().map(|error| error.error.kind()), Some(io::ErrorKind::PermissionDenied));
+        assert!(search_result(Path::new("a"), Err(gone), &config).is_ok_and(|found| found.is_none()));
+    }
+
+    #[test]
+    fn a_root_that_disappears_is_an_error() {
+        //DATA
+        let root: PathBuf = env::temp_dir().join(format!("grep-directory-unit-{}-vanishing-root", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let config: Config = config(&[root.to_str().unwrap(), "needle"]).unwrap();
+
+        fs::remove_dir(&root).unwrap();
+        assert_eq!(run_with_output(config, &mut Vec::new()).err().unwrap().to_string(), format!("Invalid path '{}', it doesn't exist anymore.", root.display()));
+    }
+
+    #[test]
+    fn plain_can_be_picked_and_other_formats_always_are() {
+        assert!(config(&["--plain", "--no-plain", "--plain", "src", "needle"]).unwrap().plain());
+        assert!(!config(&["--no-plain", "src", "needle"]).unwrap().plain());
+        for format in ["--json", "--csv", "--vimgrep", "-0"] {
+            assert!(config(&["--no-plain", format, "src", "needle"]).unwrap().plain(), "{}", format);
+        }
+    }
+
+    fn path_error(path: &str, kind: io::ErrorKind) -> PathError {
+        return PathError { path: PathBuf::from(path), error: io::Error::new(kind, "failed") };
+    }
+
+    #[test]
+    fn error_summary_counts_each_kind_most_common_first() {
+        //DATA
+        let summary = MatchSummary {
+            file_errors: vec![path_error("a", io::ErrorKind::InvalidData), path_error("b", io::ErrorKind::PermissionDenied), path_error("c", io::ErrorKind::PermissionDenied)],
+            walk_errors: vec![path_error("d", io::ErrorKind::PermissionDenied)],
+            ..MatchSummary::default()
+        };
+
+        assert_eq!(summary.error_summary(), vec!["3 files couldn't be read (permission denied: 2, invalid data: 1)", "1 directory couldn't be read (permission denied: 1)"]);
+    }
+
+    #[test]
+    fn error_summary_breaks_ties_by_kind_and_leaves_out_whats_empty() {
+        //DATA
+        let summary = MatchSummary {
+            walk_errors: vec![path_error("a", io::ErrorKind::PermissionDenied), path_error("b", io::ErrorKind::InvalidData)],
+            ..MatchSummary::default()
+        };
+
+        assert_eq!(summary.error_summary(), vec!["2 directories couldn't be read (invalid data: 1, permission denied: 1)"]);
+        assert!(MatchSummary::default().error_summary().is_empty());
+    }
+
+    #[test]
+    fn run_returns_the_files_that_couldnt_be_read() {
+        //DATA
+        let good: PathBuf = temp_file("errors-good.txt", "needle\n");
+        let bad: PathBuf = env::temp_dir().join(format!("grep-directory-unit-{}-errors-bad.txt", std::process::id()));
+        fs::write(&bad, b"needle \xff\n").unwrap();
+        let mut out: Vec<u8> = Vec::new();
+        let summary: MatchSummary = run_with_output(config(&["--strict-utf8", "-p", good.to_str().unwrap(), "-p", bad.to_str().unwrap(), "needle"]).unwrap(), &mut out).unwrap();
+
+        assert_eq!(summary.matched_files, 1);
+        assert_eq!(summary.file_errors.iter().map(|error| (error.path.clone(), error.error.kind())).collect::<Vec<_>>(), vec![(bad.clone(), io::ErrorKind::InvalidData)]);
+        assert!(String::from_utf8(out).unwrap().contains("needle"));
+        fs::remove_file(good).unwrap();
+        fs::remove_file(bad).unwrap();
+    }
+
+    //a stdout that's been closed, after taking the first few bytes
+    struct ClosedPipe {
+        room: usize,
+    }
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.room == 0 {
+                return Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"));
+            }
+            //DATA
+            let written: usize = buf.len().min(self.room);
+
+            self.room -= written;
+            return Ok(written);
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            return Ok(());
+        }
+    }
+
+    #[test]
+    fn a_closed_output_is_a_broken_pipe_error() {
+        //DATA
+        let path: PathBuf = temp_file("broken-pipe.txt", &"needle\n".repeat(100));
+
+        for room in [0, 10] {
+            //DATA
+            let error: Box<dyn Error> = run_with_output(config(&[path.to_str().unwrap(), "needle"]).unwrap(), &mut ClosedPipe { room }).err().unwrap();
+
+            assert_eq!(error.downcast_ref::<io::Error>().map(|error| error.kind()), Some(io::ErrorKind::BrokenPipe));
+        }
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn paths_in_one_directory_are_on_the_same_filesystem() {
+        //DATA
+        let path: PathBuf = temp_file("filesystem.txt", "");
+
+        assert!(filesystem_id(&path).is_some());
+        assert_eq!(filesystem_id(&path), filesystem_id(&env::temp_dir()));
+        assert_eq!(filesystem_id(&env::temp_dir().join("grep-directory-unit-no-such-file")), None);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn proc_is_on_a_different_filesystem() {
+        //proc is always mounted separately, when it's there at all
+        if Path::new("/proc/self").exists() {
+            assert_ne!(filesystem_id(Path::new("/proc")), filesystem_id(&env::temp_dir()));
+        }
+    }
+
+    /**
+     * returns the time value parses to, as seconds since 1970 (negative for before then)
+     */
+    fn parsed_time(value: &str) -> Result<i64, String> {
+        //DATA
+        let time: SystemTime = parse_time("--newer-than", &Some(value.to_string())).map_err(|error| error.to_string())?;
+
+        return Ok(match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(before) => -(before.duration().as_secs() as i64),
+        });
+    }
+
+    #[test]
+    fn dates_are_counted_from_1970_in_utc() {
+        assert_eq!(days_since_epoch(1970, 1, 1), 0);
+        assert_eq!(days_since_epoch(1969, 12, 31), -1);
+        assert_eq!(days_since_epoch(2000, 3, 1), 11017);
+        assert_eq!(parsed_time("2024-01-01"), Ok(1_704_067_200));
+        assert_eq!(parsed_time("2024-01-01T12:30"), Ok(1_704_067_200 + 12 * 60 * 60 + 30 * 60));
+        assert_eq!(parsed_time("2024-01-01 12:30:45"), Ok(1_704_067_200 + 12 * 60 * 60 + 30 * 60 + 45));
+        assert_eq!(parsed_time("1969-12-31"), Ok(-24 * 60 * 60));
+    }
+
+    #[test]
+    fn only_real_dates_are_parsed() {
+        assert_eq!((days_in_month(2024, 2), days_in_month(2023, 2), days_in_month(1900, 2), days_in_month(2000, 2)), (29, 28, 28, 29));
+        assert!(parsed_time("2024-02-29").is_ok());
+        for value in ["2023-02-29", "2024-04-31", "2024-00-10", "2024-01-01T24:00", "2024-01-01T12:60", "2024-01-01T12:30:45:00", "2024-01", "0000-01-01", "01/01/2024"] {
+            assert!(parsed_time(value).unwrap_err().starts_with(&format!("Invalid value '{}' for '--newer-than', expected a duration", value)), "{}", value);
+        }
+    }
+
+    #[test]
+    fn durations_are_before_now() {
+        //DATA
+        let now: i64 = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+
+        for (value, seconds) in [("0s", 0), ("90s", 90), ("30m", 30 * 60), ("12h", 12 * 60 * 60), ("2d", 2 * 24 * 60 * 60), ("1w", 7 * 24 * 60 * 60)] {
+            //DATA
+            let parsed: i64 = parsed_time(value).unwrap();
+
+            assert!((now - seconds - parsed).abs() <= 1, "{}", value);
+        }
+        assert!(parsed_time("2y").is_err() && parsed_time("d").is_err() && parsed_time("-2d").is_err());
+        assert!(parsed_time(&format!("{}w", u64::MAX)).is_err());
+    }
+
+    #[test]
+    fn anchored_matches_are_at_the_ends_of_the_line() {
+        //DATA
+        let anchored = |anchor: Anchor| MatchKind::Anchored { anchor, word: false, ignore_whitespace: false };
+        let line: &str = "use a; use";
+
+        assert!(anchored(Anchor::Start).fits(line, &(0..3)));
+        assert!(!anchored(Anchor::Start).fits(line, &(7..10)));
+        assert!(anchored(Anchor::End).fits(line, &(7..10)));
+        assert!(!anchored(Anchor::End).fits(line, &(0..3)));
+        assert!(anchored(Anchor::Both).fits(line, &(0..10)));
+        assert!(!anchored(Anchor::Both).fits(line, &(0..3)) && !anchored(Anchor::Both).fits(line, &(7..10)));
+        //an empty line only has room for an empty occurrence
+        assert!(anchored(Anchor::Both).fits("", &(0..0)));
+    }
+
+    #[test]
+    fn anchored_matches_can_skip_whitespace_and_be_words() {
+        //DATA
+        let trimmed = MatchKind::Anchored { anchor: Anchor::Both, word: false, ignore_whitespace: true };
+        let word = MatchKind::Anchored { anchor: Anchor::Start, word: true, ignore_whitespace: false };
+
+        assert!(trimmed.fits("\t use a;  ", &(2..8)));
+        assert!(!trimmed.fits("\t use a;  ", &(0..8)));
+        assert!(!MatchKind::Anchored { anchor: Anchor::Both, word: false, ignore_whitespace: false }.fits("\t use a;  ", &(2..8)));
+        assert!(word.fits("use a", &(0..3)));
+        assert!(!word.fits("user", &(0..3)));
+    }
+
+    #[test]
+    fn anchor_goes_on_top_of_the_match_kind() {
+        assert_eq!(config(&["--anchor", "start", "src", "use"]).unwrap().match_kind, MatchKind::Anchored { anchor: Anchor::Start, word: false, ignore_whitespace: false });
+        assert_eq!(config(&["-w", "--anchor-ignore-whitespace", "--anchor", "both", "src", "use"]).unwrap().match_kind, MatchKind::Anchored { anchor: Anchor::Both, word: true, ignore_whitespace: true });
+        assert_eq!(config(&["--anchor", "end", "-i", "src", "use"]).unwrap().case_mode, CaseMode::Insensitive);
+
+        //DATA
+        let errors: Vec<String> = [
+            vec!["--anchor", "middle", "src", "use"],
+            vec!["--anchor", "start", "-x", "src", "use"],
+            vec!["--anchor", "start", "--multiline", "src", "use"],
+            vec!["--anchor-ignore-whitespace", "src", "use"],
+        ].iter().map(|args| config(args).err().unwrap().to_string()).collect();
+
+        assert_eq!(errors, vec![
+            "Invalid value 'middle' for '--anchor', expected start, end, or both.",
+            "--anchor can't be used with -x, which already matches whole lines.",
+            "--anchor can't be used with --multiline, it anchors matches to the lines they're on, a line at a time.",
+            "--anchor-ignore-whitespace is about where --anchor lets matches be, so it can't be used without it.",
+        ]);
+    }
+}
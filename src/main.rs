@@ -1,16 +1,22 @@
 use std::env;           //the library that will allow us to do stuff and stuff
+use std::error::Error;
+use std::ffi::OsString;
+use std::io;
 use std::process;       //allows for some better error handling
 
-mod lib;
-use crate::lib::Config;
+use grep_directory as lib;
+use lib::{interrupt, Config};
+
+//printed after an error, instead of the whole help, so the error (and any suggestion in it) isn't buried
+const USAGE_HINT: &str = "Run with --help for usage.";
 
 fn main() {
-    let args: Vec<String> = env::args().collect(); //read the argument values, and collect them into a string vector
+    let args: Vec<OsString> = env::args_os().collect(); //read the argument values, and collect them into a vector, they don't have to be valid utf-8
 
     let config = Config::new(&args).unwrap_or_else(|err| {
         eprintln!("Problem parsing arguments: {}", err); //use the eprintln! macro to output to standard error
-        lib::help();
-        process::exit(1);
+        eprintln!("{}", USAGE_HINT);
+        process::exit(2);
     });
     /*
      * Using unwrap_or_else allows us to define some custom, non-panic! error handling. 
@@ -19,13 +25,53 @@ fn main() {
      * which is an anonymous function we define and pass as an argument to unwrap_or_else.
     */
 
-    println!("Searching for {}", config.query);
-    println!("In Path {}", config.path);
+    //help, version, completions, the man page, and the type list don't search anything, so they always succeed
+    let informational = config.help || config.version || config.type_list || config.completions.is_some() || config.generate_man;
+    //with --verbose, every error was already printed as it happened
+    let summarize_errors = !config.verbose && !config.quiet;
+    //with --output, the results aren't on the screen, so say where they went
+    let output_path = config.output.as_ref().filter(|_| !config.quiet).map(|(path, _)| path.clone());
+
+    //Ctrl-C stops the search, instead of killing it, so what was found so far still gets printed
+    interrupt::install();
+
+    //handling errors in run with a match, the exit code tells scripts whether anything matched
+    let result = lib::run(config);
+
+    //sum up what couldn't be read, instead of staying quiet about it
+    if let Some(summary) = result.as_ref().ok().filter(|_| summarize_errors) {
+        for line in summary.error_summary() {
+            eprintln!("{}, rerun with --verbose for details", line);
+        }
+    }
+
+    if let (Some(summary), Some(path)) = (result.as_ref().ok(), output_path) {
+        eprintln!("Wrote {} result{} to {:?}", summary.results_written, if summary.results_written == 1 {""} else {"s"}, path);
+    }
+
+    match result {
+        Ok(summary) if summary.interrupted => {
+            eprintln!("Interrupted, the results are incomplete");
+            process::exit(interrupt::EXIT_CODE);
+        },
+        Ok(summary) if informational || summary.matched_files > 0 => process::exit(0),
+        //like grep, nothing matching isn't a clean "no" if some of what should've been searched couldn't be
+        Ok(summary) if !summary.file_errors.is_empty() || !summary.walk_errors.is_empty() => process::exit(2),
+        Ok(_) => process::exit(1),
+        Err(e) => {
+            exit_on_write_error(e.as_ref());
+            eprintln!("Application error: {}", e); //use the eprintln! macro to output to standard error
+            eprintln!("{}", USAGE_HINT);
+            process::exit(2);
+        },
+    }
+}
 
-    //handling errors in run with an if let
-    if let Err(e) = lib::run(config) {
-        eprintln!("Application error: {}", e); //use the eprintln! macro to output to standard error
-        lib::help();
-        process::exit(1);
+/**
+ * exits quietly if err is from stdout being closed (like when the output is piped into head), which just means nobody wants the rest of it
+ */
+fn exit_on_write_error(err: &(dyn Error + 'static)) {
+    if err.downcast_ref::<io::Error>().is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe) {
+        process::exit(0);
     }
-}
\ No newline at end of file
+}
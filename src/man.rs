@@ -0,0 +1,191 @@
+use std::io::{self, Write};
+
+use crate::options::{self, OPTIONS};
+use crate::EXIT_STATUSES;
+
+//examples for the man page, the command, and what it does
+const EXAMPLES: [(&str, &str); 6] = [
+    ("grep-directory -r src 'fn main'", "Print every line under src (and its subdirectories) that contains \"fn main\"."),
+    ("grep-directory -l -r . TODO", "List the files under the current directory that contain TODO."),
+    ("grep-directory -r -t rust -E . 'unsafe\\s*\\{'", "Print the lines of rust files that open an unsafe block."),
+    ("grep-directory -c -r --exclude-dir target . unwrap", "Count the unwraps in each file, without going into target directories."),
+    ("grep-directory -r -0 . TODO | xargs -0 wc -l", "Count the lines of every file that contains TODO, even ones with spaces in their names."),
+    ("grep-directory -r --vimgrep . FIXME > quickfix.txt", "Write every FIXME where vim's :cfile can jump to them."),
+];
+
+/**
+ * returns text escaped for roff, so backslashes and dashes are printed as they are, and lines starting with . or ' aren't read as requests
+ */
+fn escape(text: &str) -> String {
+    //DATA
+    let escaped: String = text.replace('\\', "\\e").replace('-', "\\-");
+
+    if escaped.starts_with(['.', '\'']) {
+        return format!("\\&{}", escaped);
+    }
+    return escaped;
+}
+
+/**
+ * writes the man page, in roff, to out, the options come straight from the same table as the help, so the two always agree
+ */
+pub fn write_man<W: Write>(out: &mut W) -> io::Result<()> {
+    writeln!(out, ".TH GREP\\-DIRECTORY 1 \"\" \"grep-directory {}\" \"User Commands\"", env!("CARGO_PKG_VERSION"))?;
+
+    writeln!(out, ".SH NAME")?;
+    writeln!(out, "grep\\-directory \\- search through all files in a directory for a given string")?;
+
+    writeln!(out, ".SH SYNOPSIS")?;
+    writeln!(out, ".B grep\\-directory")?;
+    writeln!(out, "[\\fIOPTIONS\\fR]... \\fIPATH\\fR \\fIQUERY\\fR")?;
+    writeln!(out, ".br")?;
+    writeln!(out, ".B grep\\-directory")?;
+    writeln!(out, "[\\fIOPTIONS\\fR]... \\fB\\-\\-path\\fR \\fIPATH\\fR... \\fIQUERY\\fR")?;
+
+    writeln!(out, ".SH DESCRIPTION")?;
+    writeln!(out, "Searches the files in \\fIPATH\\fR (a file, a directory, or \\- for stdin) for \\fIQUERY\\fR, and prints the lines that contain it.")?;
+    writeln!(out, "Multiple words of \\fIQUERY\\fR are joined with a single space.")?;
+
+    writeln!(out, ".SH OPTIONS")?;
+    for spec in OPTIONS {
+        //DATA
+        let short: String = spec.short.map(|short| format!("\\fB{}\\fR, ", escape(short))).unwrap_or_default();
+        let value: String = match spec.value {
+            Some(value) if options::is_optional(value) => format!("[=\\fI{}\\fR]", escape(value.trim_matches(['[', '=', ']']))),
+            Some(value) => format!(" \\fI{}\\fR", escape(value)),
+            None => String::new(),
+        };
+
+        writeln!(out, ".TP")?;
+        writeln!(out, "{}\\fB{}\\fR{}", short, escape(spec.long), value)?;
+        writeln!(out, "{}", spec.description.iter().map(|line| escape(line)).collect::<Vec<String>>().join("\n.br\n"))?;
+    }
+
+    writeln!(out, ".SH EXIT STATUS")?;
+    for (code, meaning) in EXIT_STATUSES {
+        writeln!(out, ".TP")?;
+        writeln!(out, ".B {}", code)?;
+        writeln!(out, "{}", escape(meaning))?;
+    }
+
+    writeln!(out, ".SH EXAMPLES")?;
+    for (command, what_it_does) in EXAMPLES {
+        writeln!(out, ".TP")?;
+        writeln!(out, "\\fB{}\\fR", escape(command))?;
+        writeln!(out, "{}", escape(what_it_does))?;
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    /**
+     * returns the man page
+     */
+    fn man_page() -> String {
+        //DATA
+        let mut out: Vec<u8> = Vec::new();
+
+        write_man(&mut out).unwrap();
+        return String::from_utf8(out).unwrap();
+    }
+
+    /**
+     * returns the words of a shell command, up to any pipe or redirection, with single quotes taken off
+     */
+    fn shell_words(command: &str) -> Vec<String> {
+        //DATA
+        let mut words: Vec<String> = Vec::new();
+        let mut word: Option<String> = None;
+        let mut quoted: bool = false;
+
+        for c in command.chars() {
+            match c {
+                '\'' => {
+                    quoted = !quoted;
+                    word.get_or_insert_with(String::new);
+                },
+                ' ' if !quoted => words.extend(word.take()),
+                '|' | '>' if !quoted => break,
+                c => word.get_or_insert_with(String::new).push(c),
+            }
+        }
+        words.extend(word);
+        return words;
+    }
+
+    #[test]
+    fn escaping_keeps_backslashes_and_dashes_as_they_are() {
+        assert_eq!(escape(r"a-b\c"), r"a\-b\ec");
+        assert_eq!(escape("plain"), "plain");
+    }
+
+    #[test]
+    fn lines_starting_with_a_dot_or_quote_arent_requests() {
+        assert_eq!(escape(".hidden files"), "\\&.hidden files");
+        assert_eq!(escape("'quoted'"), "\\&'quoted'");
+        assert_eq!(escape("not .first"), "not .first");
+    }
+
+    #[test]
+    fn sections_are_in_order() {
+        //DATA
+        let page: String = man_page();
+        let sections: Vec<&str> = page.lines().filter_map(|line| line.strip_prefix(".SH ")).collect();
+
+        assert!(page.starts_with(".TH GREP\\-DIRECTORY 1 "));
+        assert_eq!(sections, vec!["NAME", "SYNOPSIS", "DESCRIPTION", "OPTIONS", "EXIT STATUS", "EXAMPLES"]);
+    }
+
+    #[test]
+    fn every_option_is_in_the_options_section_exactly_once() {
+        //DATA
+        let page: String = man_page();
+        let options: &str = page.split(".SH OPTIONS\n").nth(1).unwrap().split(".SH ").next().unwrap();
+        //the line after each .TP is what the option is called
+        let tags: Vec<&str> = options.split(".TP\n").skip(1).map(|entry| entry.lines().next().unwrap()).collect();
+
+        assert_eq!(tags.len(), OPTIONS.len());
+        for spec in OPTIONS {
+            //DATA
+            //the \fR right after the name keeps --type from matching --type-not
+            let long: String = format!("\\fB{}\\fR", escape(spec.long));
+
+            assert_eq!(tags.iter().filter(|tag| tag.contains(&long)).count(), 1, "{}", spec.long);
+            if let Some(short) = spec.short {
+                assert_eq!(tags.iter().filter(|tag| tag.starts_with(&format!("\\fB{}\\fR, ", escape(short)))).count(), 1, "{}", short);
+            }
+        }
+    }
+
+    #[test]
+    fn every_exit_status_is_listed() {
+        //DATA
+        let page: String = man_page();
+
+        for (code, meaning) in EXIT_STATUSES {
+            assert!(page.contains(&format!(".TP\n.B {}\n{}\n", code, escape(meaning))), "{}", code);
+        }
+    }
+
+    #[test]
+    fn examples_are_valid_commands() {
+        for (command, _) in EXAMPLES {
+            //DATA
+            let words: Vec<String> = shell_words(command);
+
+            assert_eq!(words[0], "grep-directory");
+            //run from the crate root, where src and . are there to search
+            assert!(Config::new(&words).is_ok(), "{}: {:?}", command, Config::new(&words).err());
+        }
+    }
+
+    #[test]
+    fn shell_words_understands_the_quoting_the_examples_use() {
+        assert_eq!(shell_words("a -r 'fn main' b | c"), vec!["a", "-r", "fn main", "b"]);
+        assert_eq!(shell_words("a '' b > out"), vec!["a", "", "b"]);
+    }
+}
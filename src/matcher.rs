@@ -0,0 +1,546 @@
+use std::error::Error;  //allows for some better errors
+use std::ops::Range;
+
+use crate::accents::{is_combining_mark, strip_accents, strip_accents_with_origins};
+use crate::regex::Regex;
+use crate::{find_matches, find_regex_matches, select_matches, CaseMode, MatchKind};
+
+/**
+ * finds the matches of the patterns in a line, built once from the config and shared by every file that's searched
+ */
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Literal { patterns: Vec<String>, kind: MatchKind },
+    LiteralCaseInsensitive { patterns: Vec<String>, kind: MatchKind },  //the patterns are already case folded
+    Regex(Vec<Regex>),
+    Fuzzy { patterns: Vec<Vec<char>>, max_distance: usize, case_insensitive: bool, kind: MatchKind },   //the patterns are already case folded, when case insensitive
+    LiteralIgnoreAccents { patterns: Vec<String>, case_insensitive: bool, kind: MatchKind },    //the patterns are already stripped of accents (and case folded, when case insensitive)
+}
+impl Matcher {
+    /**
+     * builds a matcher for the patterns, as regexes if regex is set (throwing an error if any of them are invalid), or as plain text if it isn't
+     */
+    pub fn new<S: AsRef<str>>(patterns: &[S], case_mode: CaseMode, kind: MatchKind, regex: bool) -> Result<Matcher, Box<dyn Error>> {
+        //case insensitivity is the same as a (?i) at the start of each regex
+        if regex {
+            return Ok(Matcher::Regex(patterns.iter()
+                .map(|pattern| Regex::with_options(pattern.as_ref(), case_mode == CaseMode::Insensitive, kind))
+                .collect::<Result<Vec<Regex>, Box<dyn Error>>>()?));
+        }
+        return Ok(Matcher::literal(patterns, case_mode, kind));
+    }
+
+    /**
+     * builds a matcher for the patterns as plain text
+     */
+    pub fn literal<S: AsRef<str>>(patterns: &[S], case_mode: CaseMode, kind: MatchKind) -> Matcher {
+        return match case_mode {
+            CaseMode::Sensitive => Matcher::Literal { patterns: patterns.iter().map(|pattern| pattern.as_ref().to_string()).collect(), kind },
+            CaseMode::Insensitive => Matcher::LiteralCaseInsensitive { patterns: patterns.iter().map(|pattern| fold_case(pattern.as_ref())).collect(), kind },
+        };
+    }
+
+    /**
+     * builds a matcher for the patterns as plain text, that ignores accents, so resume matches résumé (and re\u{301}sume\u{301})
+     */
+    pub fn ignoring_accents<S: AsRef<str>>(patterns: &[S], case_mode: CaseMode, kind: MatchKind) -> Matcher {
+        //DATA
+        let case_insensitive: bool = case_mode == CaseMode::Insensitive;
+
+        return Matcher::LiteralIgnoreAccents {
+            patterns: patterns.iter().map(|pattern| strip_accents(pattern.as_ref())).map(|pattern| if case_insensitive {fold_case(&pattern)} else {pattern}).collect(),
+            case_insensitive,
+            kind,
+        };
+    }
+
+    /**
+     * builds a matcher for text within max_distance edits of the patterns (see find_fuzzy_matches), which is plain text, a line at a time
+     */
+    pub fn fuzzy<S: AsRef<str>>(patterns: &[S], case_mode: CaseMode, kind: MatchKind, max_distance: usize) -> Matcher {
+        //DATA
+        let case_insensitive: bool = case_mode == CaseMode::Insensitive;
+
+        return Matcher::Fuzzy {
+            patterns: patterns.iter().map(|pattern| pattern.as_ref().chars().map(|c| if case_insensitive {fold_one(c)} else {c}).collect()).collect(),
+            max_distance,
+            case_insensitive,
+            kind,
+        };
+    }
+
+    /**
+     * returns the byte ranges of every non-overlapping match in line
+     */
+    pub fn find_matches(&self, line: &str) -> Vec<Range<usize>> {
+        return match self {
+            Matcher::Literal { patterns, kind } => find_matches(patterns, line, *kind),
+            //ascii lowercasing doesn't change where anything is, so the ranges work for the original line too
+            Matcher::LiteralCaseInsensitive { patterns, kind } if line.is_ascii() => find_matches(patterns, &line.to_ascii_lowercase(), *kind),
+            Matcher::LiteralCaseInsensitive { patterns, kind } => {
+                //DATA
+                //folding can change the length of characters (like ß to ss), so the matches are mapped back to where they came from
+                let (folded, origins) = fold_case_with_origins(line);
+
+                map_to_origins(line, find_matches(patterns, &folded, *kind), &origins, false)
+            },
+            Matcher::LiteralIgnoreAccents { patterns, case_insensitive, kind } if line.is_ascii() => match case_insensitive {
+                true => find_matches(patterns, &line.to_ascii_lowercase(), *kind),
+                false => find_matches(patterns, line, *kind),
+            },
+            Matcher::LiteralIgnoreAccents { patterns, case_insensitive, kind } => {
+                //DATA
+                //stripping accents (then folding) changes where everything is, so the matches are mapped back through both
+                let (stripped, stripped_origins) = strip_accents_with_origins(line);
+                let (searched, origins) = match case_insensitive {
+                    true => {
+                        //DATA
+                        let (folded, folded_origins) = fold_case_with_origins(&stripped);
+
+                        (folded, folded_origins.iter().map(|origin| stripped_origins[*origin]).collect())
+                    },
+                    false => (stripped, stripped_origins),
+                };
+
+                map_to_origins(line, find_matches(patterns, &searched, *kind), &origins, true)
+            },
+            Matcher::Regex(regexes) => find_regex_matches(regexes, line),
+            Matcher::Fuzzy { patterns, max_distance, case_insensitive, kind } => find_fuzzy_matches(patterns, line, *max_distance, *case_insensitive, *kind),
+        };
+    }
+
+    /**
+     * returns what the match at range in line should be replaced with
+     * for regexes, $N (or ${N}) in replacement is what group N matched ($0 is the whole match), and $$ is a $, for plain text, replacement is used as is
+     */
+    pub fn replacement(&self, line: &str, range: &Range<usize>, replacement: &str) -> String {
+        let Matcher::Regex(regexes) = self else {
+            return replacement.to_string();
+        };
+
+        //DATA
+        //the regex that found this match is the one that matches the same text from the same place
+        let groups: Vec<Option<Range<usize>>> = regexes.iter()
+            .filter_map(|regex| regex.captures_at(line, range.start))
+            .find(|groups| groups[0].as_ref() == Some(range))
+            .unwrap_or_default();
+        let group = |number: usize| groups.get(number).cloned().flatten().map_or("", |group| &line[group]);
+        let mut expanded = String::with_capacity(replacement.len());
+        let mut rest: &str = replacement;
+
+        //copy the replacement over, swapping each $ reference for what it refers to
+        while let Some(dollar) = rest.find('$') {
+            expanded.push_str(&rest[..dollar]);
+            rest = &rest[dollar + 1..];
+            if let Some(after) = rest.strip_prefix('$') {
+                expanded.push('$');
+                rest = after;
+            } else if let Some((number, after)) = rest.strip_prefix('{').and_then(|braced| braced.split_once('}')).and_then(|(number, after)| Some((number.parse::<usize>().ok()?, after))) {
+                expanded.push_str(group(number));
+                rest = after;
+            } else {
+                let digits: usize = rest.bytes().take_while(u8::is_ascii_digit).count();
+                match rest[..digits].parse::<usize>() {
+                    Ok(number) => expanded.push_str(group(number)),
+                    //a $ that isn't a reference is just a $
+                    Err(_) => expanded.push('$'),
+                }
+                rest = &rest[digits..];
+            }
+        }
+        expanded.push_str(rest);
+
+        //return
+        return expanded;
+    }
+
+    /**
+     * returns true if there are any matches in line
+     */
+    pub fn is_match(&self, line: &str) -> bool {
+        return match self {
+            Matcher::Regex(regexes) => regexes.iter().any(|regex| regex.is_match(line)),
+            _ => !self.find_matches(line).is_empty(),
+        };
+    }
+}
+
+/**
+ * returns the matches found in a changed (case folded, or stripped of accents) copy of line as byte ranges of line itself,
+ * origins is where each byte of the copy came from in line, and with marks, a match also covers the combining marks right after it (its accents)
+ */
+fn map_to_origins(line: &str, found: Vec<Range<usize>>, origins: &[usize], marks: bool) -> Vec<Range<usize>> {
+    //DATA
+    let mut matches: Vec<Range<usize>> = Vec::new();
+
+    for found in found {
+        //a match that starts or ends partway through what a character was changed into covers all of that character
+        let start: usize = origins[found.start];
+        let last: usize = origins[found.end - 1];
+        let mut end: usize = last + line[last..].chars().next().map_or(0, char::len_utf8);
+        if marks {
+            end += line[end..].chars().take_while(|c| is_combining_mark(*c)).map(char::len_utf8).sum::<usize>();
+        }
+        match matches.last_mut() {
+            Some(previous) if previous.end > start => previous.end = previous.end.max(end),
+            _ => matches.push(start..end),
+        }
+    }
+
+    //return
+    return matches;
+}
+
+/**
+ * returns the byte ranges of every non-overlapping stretch of line that's within max_distance edits of one of the patterns, and fits kind
+ * an edit is inserting, deleting, or changing a character, or swapping two characters next to each other (so recieve is one edit from receive)
+ * when stretches overlap, the one with the fewest edits wins, then the one that starts first
+ * this fills in a (pattern length × line length) table for each pattern, for each line, so it's a lot slower than exact matching
+ */
+fn find_fuzzy_matches(patterns: &[Vec<char>], line: &str, max_distance: usize, case_insensitive: bool, kind: MatchKind) -> Vec<Range<usize>> {
+    //DATA
+    //the byte offset of each character, and of the end of the line
+    let offsets: Vec<usize> = line.char_indices().map(|(offset, _)| offset).chain([line.len()]).collect();
+    let text: Vec<char> = line.chars().map(|c| if case_insensitive {fold_one(c)} else {c}).collect();
+    let mut candidates: Vec<(usize, Range<usize>)> = Vec::new(); //the number of edits, and the characters it covers
+
+    for pattern in patterns.iter().filter(|pattern| !pattern.is_empty()) {
+        //DATA
+        //each column has, for each length of the start of the pattern, the fewest edits it takes to match it to text ending here, and where that starts
+        //a match can start anywhere, so matching none of the pattern takes no edits
+        let column = |end: usize| -> Vec<(usize, usize)> {(0..=pattern.len()).map(|length| (length, end)).collect()};
+        let mut before_previous: Vec<(usize, usize)> = column(0);
+        let mut previous: Vec<(usize, usize)> = column(0);
+
+        for end in 1..=text.len() {
+            //DATA
+            let mut current: Vec<(usize, usize)> = Vec::with_capacity(pattern.len() + 1);
+
+            current.push((0, end));
+            for length in 1..=pattern.len() {
+                //DATA
+                let changed: usize = if pattern[length - 1] == text[end - 1] {0} else {1};
+                //fewer edits win, then later starts (so matches don't pick up extra characters at the front)
+                let better = |a: (usize, usize), b: (usize, usize)| if (a.0, usize::MAX - a.1) <= (b.0, usize::MAX - b.1) {a} else {b};
+                let mut best: (usize, usize) = (previous[length - 1].0 + changed, previous[length - 1].1);
+
+                best = better(best, (previous[length].0 + 1, previous[length].1));    //an extra character in the text
+                best = better(best, (current[length - 1].0 + 1, current[length - 1].1)); //a character missing from the text
+                if length > 1 && end > 1 && pattern[length - 1] == text[end - 2] && pattern[length - 2] == text[end - 1] {
+                    best = better(best, (before_previous[length - 2].0 + 1, before_previous[length - 2].1)); //two characters swapped
+                }
+                current.push(best);
+            }
+
+            if let Some(&(distance, start)) = current.last().filter(|(distance, start)| *distance <= max_distance && *start < end) {
+                candidates.push((distance, start..end));
+            }
+            before_previous = std::mem::replace(&mut previous, current);
+        }
+    }
+
+    //the closest matches are picked first, so a worse one that overlaps them can't push them out
+    candidates.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.start.cmp(&b.1.start)).then(b.1.end.cmp(&a.1.end)));
+    let mut picked: Vec<Range<usize>> = Vec::new();
+    for (_, characters) in candidates {
+        //DATA
+        let occurrence: Range<usize> = offsets[characters.start]..offsets[characters.end];
+
+        if kind.fits(line, &occurrence) && !picked.iter().any(|other| other.start < occurrence.end && occurrence.start < other.end) {
+            picked.push(occurrence);
+        }
+    }
+
+    //return
+    return select_matches(picked);
+}
+
+/**
+ * returns the lowercase version of c, when that's one character, which is what fuzzy matching compares case insensitively (instead of full case folding)
+ */
+fn fold_one(c: char) -> char {
+    //DATA
+    let mut lowercase = c.to_lowercase();
+
+    return match (lowercase.next(), lowercase.next()) {
+        (Some(lower), None) => lower,
+        _ => c,
+    };
+}
+
+/**
+ * pushes the case folded version of c onto folded, which is what case insensitive matching compares
+ * that's c.to_lowercase(), plus the full foldings lowercasing misses: ß (and ẞ) fold to ss, and ς folds to σ
+ * there's no special casing for turkish: İ lowercases to i followed by a combining dot (so it doesn't match a plain i),
+ * and the dotless ı only matches itself (so I doesn't match it)
+ */
+fn fold_char(c: char, folded: &mut String) {
+    match c {
+        'ß' | 'ẞ' => folded.push_str("ss"),
+        'ς' => folded.push('σ'),
+        c => folded.extend(c.to_lowercase()),
+    }
+}
+
+/**
+ * returns the case folded version of text, which is what case insensitive matching compares
+ */
+pub fn fold_case(text: &str) -> String {
+    //DATA
+    let mut folded = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        fold_char(c, &mut folded);
+    }
+    return folded;
+}
+
+/**
+ * returns the case folded version of text, along with where each of its bytes came from in text (the start of the character it was folded from)
+ */
+fn fold_case_with_origins(text: &str) -> (String, Vec<usize>) {
+    //DATA
+    let mut folded = String::with_capacity(text.len());
+    let mut origins: Vec<usize> = Vec::with_capacity(text.len());
+
+    for (start, c) in text.char_indices() {
+        fold_char(c, &mut folded);
+        origins.resize(folded.len(), start);
+    }
+    return (folded, origins);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Anchor;
+
+    /**
+     * returns the text of each match of matcher in line
+     */
+    fn matched<'a>(matcher: &Matcher, line: &'a str) -> Vec<&'a str> {
+        return matcher.find_matches(line).into_iter().map(|range| &line[range]).collect();
+    }
+
+    #[test]
+    fn literal_finds_every_occurrence() {
+        //DATA
+        let matcher: Matcher = Matcher::literal(&["ab"], CaseMode::Sensitive, MatchKind::Substring);
+
+        assert_eq!(matcher.find_matches("ab xab abab"), vec![0..2, 4..6, 7..9, 9..11]);
+        assert!(matches!(matcher, Matcher::Literal { .. }));
+        assert!(!matcher.is_match("AB"));
+    }
+
+    #[test]
+    fn literal_finds_any_of_its_patterns() {
+        //DATA
+        let matcher: Matcher = Matcher::literal(&["fn", "let"], CaseMode::Sensitive, MatchKind::Substring);
+
+        assert_eq!(matched(&matcher, "let f = fn() {}"), vec!["let", "fn"]);
+    }
+
+    #[test]
+    fn literal_case_insensitive_ignores_case() {
+        //DATA
+        let matcher: Matcher = Matcher::literal(&["Hello"], CaseMode::Insensitive, MatchKind::Substring);
+
+        assert!(matches!(matcher, Matcher::LiteralCaseInsensitive { .. }));
+        assert_eq!(matched(&matcher, "hello HELLO hElLo"), vec!["hello", "HELLO", "hElLo"]);
+        assert_eq!(matched(&matcher, "é HELLO"), vec!["HELLO"]);
+    }
+
+    #[test]
+    fn literal_case_insensitive_maps_folded_matches_back() {
+        //DATA
+        //'ẞ' is 3 bytes but folds to "ss", so the match has to be mapped back to the original line
+        let matcher: Matcher = Matcher::literal(&["strasse"], CaseMode::Insensitive, MatchKind::Substring);
+
+        assert_eq!(matched(&matcher, "die STRAẞE"), vec!["STRAẞE"]);
+    }
+
+    #[test]
+    fn sharp_s_matches_ss_both_ways() {
+        //DATA
+        let sharp: Matcher = Matcher::literal(&["straße"], CaseMode::Insensitive, MatchKind::Substring);
+        let double: Matcher = Matcher::literal(&["STRASSE"], CaseMode::Insensitive, MatchKind::Substring);
+
+        assert_eq!(matched(&sharp, "STRASSE, strasse, Straße, STRAẞE"), vec!["STRASSE", "strasse", "Straße", "STRAẞE"]);
+        assert_eq!(matched(&double, "straße STRASSE"), vec!["straße", "STRASSE"]);
+        //a match that starts halfway through a folded ß covers all of it
+        assert_eq!(matched(&Matcher::literal(&["se"], CaseMode::Insensitive, MatchKind::Substring), "straße"), vec!["ße"]);
+    }
+
+    #[test]
+    fn dotted_and_dotless_i_arent_special_cased() {
+        //DATA
+        let plain: Matcher = Matcher::literal(&["i"], CaseMode::Insensitive, MatchKind::Substring);
+        let dotless: Matcher = Matcher::literal(&["ı"], CaseMode::Insensitive, MatchKind::Substring);
+
+        //I and i match each other, but not the turkish İ (which folds to i and a combining dot, so its i matches) or ı
+        assert_eq!(matched(&plain, "I i ı"), vec!["I", "i"]);
+        assert_eq!(matched(&plain, "İ"), vec!["İ"]);
+        assert_eq!(matched(&dotless, "I i ı"), vec!["ı"]);
+        assert_eq!(fold_case("İ"), "i\u{307}");
+    }
+
+    #[test]
+    fn accented_and_non_latin_letters_ignore_case() {
+        //DATA
+        let accented: Matcher = Matcher::literal(&["café"], CaseMode::Insensitive, MatchKind::Substring);
+        let greek: Matcher = Matcher::literal(&["ΟΔΟΣ"], CaseMode::Insensitive, MatchKind::Substring);
+        let cyrillic: Matcher = Matcher::literal(&["привет"], CaseMode::Insensitive, MatchKind::Substring);
+
+        assert_eq!(matched(&accented, "CAFÉ Café cafe"), vec!["CAFÉ", "Café"]);
+        //the final sigma folds like any other sigma
+        assert_eq!(matched(&greek, "οδος οδοσ"), vec!["οδος", "οδοσ"]);
+        assert_eq!(matched(&cyrillic, "ПРИВЕТ Привет"), vec!["ПРИВЕТ", "Привет"]);
+        assert!(Matcher::literal(&["café"], CaseMode::Sensitive, MatchKind::Substring).find_matches("CAFÉ").is_empty());
+    }
+
+    #[test]
+    fn match_kinds_are_respected() {
+        //DATA
+        let word: Matcher = Matcher::literal(&["cat"], CaseMode::Sensitive, MatchKind::Word);
+        let line: Matcher = Matcher::literal(&["cat"], CaseMode::Sensitive, MatchKind::Line);
+
+        assert_eq!(word.find_matches("cat concat cat_ (cat)"), vec![0..3, 17..20]);
+        assert!(line.is_match("cat"));
+        assert!(!line.is_match("cat "));
+    }
+
+    #[test]
+    fn anchored_matches_skip_the_occurrences_that_arent_anchored() {
+        //DATA
+        let end: Matcher = Matcher::literal(&["needle"], CaseMode::Insensitive, MatchKind::Anchored { anchor: Anchor::End, word: false, ignore_whitespace: false });
+        let start: Matcher = Matcher::literal(&["needle"], CaseMode::Insensitive, MatchKind::Anchored { anchor: Anchor::Start, word: true, ignore_whitespace: true });
+
+        assert_eq!(matched(&end, "Needle and NEEDLE"), vec!["NEEDLE"]);
+        assert_eq!(matched(&end, "Needle and NEEDLE "), Vec::<&str>::new());
+        assert_eq!(matched(&start, "  NEEDLE and needle"), vec!["NEEDLE"]);
+        assert_eq!(matched(&start, "  NEEDLES and needle"), Vec::<&str>::new());
+        assert!(!end.is_match(""));
+    }
+
+    #[test]
+    fn regex_finds_matches_and_rejects_bad_patterns() {
+        //DATA
+        let matcher: Matcher = Matcher::new(&[r"fn\s+\w+\("], CaseMode::Sensitive, MatchKind::Substring, true).unwrap();
+        let insensitive: Matcher = Matcher::new(&["a+b"], CaseMode::Insensitive, MatchKind::Substring, true).unwrap();
+
+        assert_eq!(matched(&matcher, "pub fn  main() {}"), vec!["fn  main("]);
+        assert_eq!(matched(&insensitive, "xAaB"), vec!["AaB"]);
+        assert!(Matcher::new(&["(unclosed"], CaseMode::Sensitive, MatchKind::Substring, true).is_err());
+        //without regex, the same pattern is plain text
+        assert!(Matcher::new(&["(unclosed"], CaseMode::Sensitive, MatchKind::Substring, false).unwrap().is_match("a (unclosed b"));
+    }
+
+    #[test]
+    fn regex_replacements_fill_in_groups() {
+        //DATA
+        let matcher: Matcher = Matcher::new(&[r"(\w+)=(\w+)"], CaseMode::Sensitive, MatchKind::Substring, true).unwrap();
+        let line: &str = "set a=b now";
+        let range: Range<usize> = matcher.find_matches(line)[0].clone();
+
+        assert_eq!(matcher.replacement(line, &range, "$2=$1"), "b=a");
+        assert_eq!(matcher.replacement(line, &range, "${1}x $$ $9 $"), "ax $  $");
+        //plain text replacements are used as is
+        assert_eq!(Matcher::literal(&["a"], CaseMode::Sensitive, MatchKind::Substring).replacement("a", &(0..1), "$1"), "$1");
+    }
+
+    #[test]
+    fn fuzzy_allows_a_few_edits() {
+        //DATA
+        let matcher: Matcher = Matcher::fuzzy(&["receive"], CaseMode::Sensitive, MatchKind::Substring, 1);
+
+        assert!(matcher.is_match("we recieve it"));
+        assert!(matcher.is_match("we receive it"));
+        assert!(!matcher.is_match("we rcieve it"));
+        assert!(!Matcher::fuzzy(&["receive"], CaseMode::Sensitive, MatchKind::Substring, 0).is_match("recieve"));
+        assert!(Matcher::fuzzy(&["receive"], CaseMode::Insensitive, MatchKind::Substring, 1).is_match("RECIEVE"));
+    }
+
+    #[test]
+    fn fuzzy_matches_cover_the_text_that_matched() {
+        //DATA
+        let matcher: Matcher = Matcher::fuzzy(&["receive"], CaseMode::Sensitive, MatchKind::Substring, 1);
+
+        //one swapped, one missing, one extra, and one changed character
+        assert_eq!(matched(&matcher, "recieve, recive, receeive, receeve"), vec!["recieve", "recive", "receeive", "receeve"]);
+        //two edits away is too far
+        assert!(matched(&matcher, "récieve rcieve").is_empty());
+    }
+
+    #[test]
+    fn fuzzy_matches_are_byte_ranges_of_the_line() {
+        //DATA
+        let matcher: Matcher = Matcher::fuzzy(&["café"], CaseMode::Sensitive, MatchKind::Substring, 1);
+
+        assert_eq!(matcher.find_matches("é cafè"), vec![3..8]);
+        assert_eq!(matched(&matcher, "日本 cafe"), vec!["cafe"]);
+    }
+
+    #[test]
+    fn fuzzy_prefers_the_closest_match() {
+        //DATA
+        let matcher: Matcher = Matcher::fuzzy(&["abcdef"], CaseMode::Sensitive, MatchKind::Substring, 2);
+
+        //abcdef is exact, so the fuzzier ones that overlap it don't get in its way
+        assert_eq!(matched(&matcher, "xabcdefx"), vec!["abcdef"]);
+        assert_eq!(matched(&matcher, "abcxef abcdef"), vec!["abcxef", "abcdef"]);
+    }
+
+    #[test]
+    fn fuzzy_respects_the_match_kind() {
+        //DATA
+        let word: Matcher = Matcher::fuzzy(&["receive"], CaseMode::Sensitive, MatchKind::Word, 1);
+        let line: Matcher = Matcher::fuzzy(&["receive"], CaseMode::Sensitive, MatchKind::Line, 1);
+
+        assert_eq!(matched(&word, "recieve unrecieved"), vec!["recieve"]);
+        assert!(line.is_match("recieve"));
+        assert!(!line.is_match("we recieve"));
+    }
+
+    #[test]
+    fn ignoring_accents_matches_either_way() {
+        //DATA
+        let matcher: Matcher = Matcher::ignoring_accents(&["resume"], CaseMode::Sensitive, MatchKind::Substring);
+
+        assert_eq!(matched(&matcher, "my résumé, my resume"), vec!["résumé", "resume"]);
+        assert_eq!(matched(&matcher, "re\u{301}sume\u{301}!"), vec!["re\u{301}sume\u{301}"]);
+        assert!(!matcher.is_match("RÉSUMÉ"));
+        assert!(Matcher::ignoring_accents(&["resume"], CaseMode::Insensitive, MatchKind::Substring).is_match("RÉSUMÉ"));
+    }
+
+    #[test]
+    fn accented_queries_match_either_form_too() {
+        //DATA
+        let precomposed: Matcher = Matcher::ignoring_accents(&["résumé"], CaseMode::Sensitive, MatchKind::Substring);
+        let decomposed: Matcher = Matcher::ignoring_accents(&["re\u{301}sume\u{301}"], CaseMode::Sensitive, MatchKind::Substring);
+
+        for matcher in [&precomposed, &decomposed] {
+            assert_eq!(matched(matcher, "résumé, re\u{301}sume\u{301}, resume"), vec!["résumé", "re\u{301}sume\u{301}", "resume"]);
+        }
+    }
+
+    #[test]
+    fn ignoring_accents_spans_are_in_the_original_text() {
+        //DATA
+        let matcher: Matcher = Matcher::ignoring_accents(&["muller"], CaseMode::Insensitive, MatchKind::Substring);
+
+        //the marks after the last letter are part of the match, so highlighting it doesn't split the letter from its accent
+        assert_eq!(matcher.find_matches("Müller and Mu\u{308}ller"), vec![0..7, 12..20]);
+        assert_eq!(Matcher::ignoring_accents(&["cafe"], CaseMode::Sensitive, MatchKind::Substring).find_matches("un cafe\u{301}!"), vec![3..9]);
+    }
+
+    #[test]
+    fn ignoring_accents_keeps_the_match_kind() {
+        //DATA
+        let word: Matcher = Matcher::ignoring_accents(&["resume"], CaseMode::Insensitive, MatchKind::Word);
+        let line: Matcher = Matcher::ignoring_accents(&["resume"], CaseMode::Sensitive, MatchKind::Line);
+
+        assert_eq!(matched(&word, "RÉSUMÉS and résumé"), vec!["résumé"]);
+        assert!(line.is_match("re\u{301}sume\u{301}"));
+        assert!(!line.is_match("my résumé"));
+    }
+}
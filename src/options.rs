@@ -0,0 +1,204 @@
+use std::io::{self, Write};
+
+/**
+ * what kind of value an option takes, so shell completions can suggest values for it
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hint {
+    None,                           //anything (or no value at all)
+    File,                           //a file
+    Dir,                            //a directory
+    Path,                           //a file or a directory
+    Type,                           //the name of a file type, like rust
+    Choices(&'static [&'static str]),   //one of these
+}
+
+/**
+ * an option, how it's written, and what it does, everything that parses, documents, or completes options works from these
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OptionSpec {
+    pub short: Option<&'static str>,    //like "-i"
+    pub long: &'static str,             //like "--ignore-case"
+    pub value: Option<&'static str>,    //the name of the value it takes, like "<N>", None if it's a flag, and in brackets, like "[=path]", if it's optional (and has to be given after an =)
+    pub hint: Hint,
+    pub description: &'static [&'static str], //the first line, then any more lines there are
+}
+
+//every option, in the order the help lists them
+pub const OPTIONS: &[OptionSpec] = &[
+    OptionSpec { short: Some("-i"), long: "--ignore-case", value: None, hint: Hint::None, description: &["Case insensitive search"] },
+    OptionSpec { short: Some("-s"), long: "--case-sensitive", value: None, hint: Hint::None, description: &["Case sensitive search (the default)"] },
+    OptionSpec { short: None, long: "--case-insensitive", value: None, hint: Hint::None, description: &["Same as --ignore-case (deprecated)"] },
+    OptionSpec { short: None, long: "--smart-case", value: None, hint: Hint::None, description: &["Case insensitive if the query is all lowercase, case sensitive otherwise (-i and -s win over this)"] },
+    OptionSpec { short: Some("-E"), long: "--regex", value: None, hint: Hint::None, description: &["Treat the query and patterns as regular expressions, like \"fn\\s+\\w+\\(\" (the default is plain text)", "supports . [...] \\d \\w \\s * + ? {n,m} | (...) ^ $ \\b and (?i)"] },
+    OptionSpec { short: Some("-U"), long: "--multiline", value: None, hint: Hint::None, description: &["Search the whole contents of each file at once, so the query can span lines, e.g. $'#[test]\\nfn '"] },
+    OptionSpec { short: Some("-F"), long: "--fixed-strings", value: None, hint: Hint::None, description: &["Treat the query and patterns as plain text, undoing an earlier --regex"] },
+    OptionSpec { short: Some("-w"), long: "--word-regexp", value: None, hint: Hint::None, description: &["Only match whole words, the query can't touch any other letters, digits, or underscores"] },
+    OptionSpec { short: Some("-x"), long: "--line-regexp", value: None, hint: Hint::None, description: &["Only match whole lines, the query has to be everything on the line except the line ending"] },
+    OptionSpec { short: None, long: "--ignore-accents", value: None, hint: Hint::None, description: &["Ignore accents on letters, so resume matches résumé, and Muller matches Müller (with -i, the case is ignored too), plain text only"] },
+    OptionSpec { short: None, long: "--anchor", value: Some("<WHERE>"), hint: Hint::Choices(&["start", "end", "both"]), description: &["Only match the query at the start of the line, the end of it (not counting the line ending), or both, e.g. --anchor start 'use ' (works with -i and -w)"] },
+    OptionSpec { short: None, long: "--anchor-ignore-whitespace", value: None, hint: Hint::None, description: &["With --anchor, whitespace at the start or end of the line doesn't count, so indented lines (or ones with trailing spaces) can still match"] },
+    OptionSpec { short: None, long: "--fuzzy", value: Some("<N>"), hint: Hint::None, description: &["Also match text up to N edits away from the query (an edit is adding, removing, or changing a character, or swapping two next to each other)", "e.g. recieve matches receive with --fuzzy 1, it's plain text and a line at a time only, and much slower than exact matching"] },
+    OptionSpec { short: Some("-l"), long: "--files-with-matches", value: None, hint: Hint::None, description: &["List the files that contain the query, instead of their lines (the default output mode with --compat-v0)"] },
+    OptionSpec { short: Some("-L"), long: "--files-without-match", value: None, hint: Hint::None, description: &["List the files that don't contain the query (an output mode, can't be used with -l)"] },
+    OptionSpec { short: Some("-c"), long: "--count", value: None, hint: Hint::None, description: &["List the files that contain the query, with how many times they contain it, as \"path: N\""] },
+    OptionSpec { short: Some("-n"), long: "--line-number", value: None, hint: Hint::None, description: &["Print every line that contains the query, as \"path:line number:line\" (the default output mode)"] },
+    OptionSpec { short: None, long: "--compat-v0", value: None, hint: Hint::None, description: &["List the files that contain the query by default, like older versions did, instead of printing their lines"] },
+    OptionSpec { short: None, long: "--column", value: None, hint: Hint::None, description: &["Also print the column of the first match on each line (in characters, starting from 1), implies -n"] },
+    OptionSpec { short: Some("-b"), long: "--byte-offset", value: None, hint: Hint::None, description: &["Also print the byte offset of the first match on each line (or in each file, when listing files), from the start of the file"] },
+    OptionSpec { short: Some("-o"), long: "--only-matching", value: None, hint: Hint::None, description: &["Print just the matched text, each match on a line of its own, implies -n"] },
+    OptionSpec { short: Some("-A"), long: "--after-context", value: Some("<N>"), hint: Hint::None, description: &["Also print N lines after each matching line, implies -n"] },
+    OptionSpec { short: Some("-B"), long: "--before-context", value: Some("<N>"), hint: Hint::None, description: &["Also print N lines before each matching line, implies -n"] },
+    OptionSpec { short: Some("-C"), long: "--context", value: Some("<N>"), hint: Hint::None, description: &["Also print N lines before and after each matching line, implies -n"] },
+    OptionSpec { short: None, long: "--replace", value: Some("<TEXT>"), hint: Hint::None, description: &["Print matching lines with the matches replaced by TEXT (files aren't changed), implies -n", "with --regex, $1 (or ${1}) in TEXT is what the first group matched, $0 is the whole match, and $$ is a $"] },
+    OptionSpec { short: None, long: "--passthru", value: None, hint: Hint::None, description: &["Print every line of the files that have matches, with the matches highlighted, implies -n", "context lines are printed as \"path-line number-line\", and separate groups of lines are split up by a \"--\""] },
+    OptionSpec { short: Some("-H"), long: "--with-filename", value: None, hint: Hint::None, description: &["Start every line (and count) with its file's name, even when searching just one file"] },
+    OptionSpec { short: None, long: "--no-filename", value: None, hint: Hint::None, description: &["Don't print file names next to lines and counts (the default when the only PATH is a file)"] },
+    OptionSpec { short: None, long: "--absolute-paths", value: None, hint: Hint::None, description: &["Print the absolute path of each file, with symlinks resolved, instead of the path it was found at"] },
+    OptionSpec { short: None, long: "--relative-to", value: Some("<DIR>"), hint: Hint::Dir, description: &["Print the path of each file relative to DIR (with symlinks resolved), or its absolute path if it isn't under DIR"] },
+    OptionSpec { short: None, long: "--heading", value: None, hint: Hint::None, description: &["When printing lines, print each file's name once above its lines (the default in a terminal)"] },
+    OptionSpec { short: None, long: "--no-heading", value: None, hint: Hint::None, description: &["When printing lines, start every line with its file's name (the default outside of a terminal)"] },
+    OptionSpec { short: None, long: "--plain", value: None, hint: Hint::None, description: &["Print just the results, one per line like grep's, without the banner, headers, or indentation (the default outside of a terminal)"] },
+    OptionSpec { short: None, long: "--no-plain", value: None, hint: Hint::None, description: &["Print the banner, headers, and indentation, even outside of a terminal"] },
+    OptionSpec { short: Some("-0"), long: "--null", value: None, hint: Hint::None, description: &["When listing files, end each file name with a null instead of a newline, and don't print anything else (for xargs -0)"] },
+    OptionSpec { short: None, long: "--json", value: None, hint: Hint::None, description: &["Print the results as one json document, {\"results\":[...],\"stats\":{...}}, with an object for each file (or line)"] },
+    OptionSpec { short: None, long: "--jsonl", value: None, hint: Hint::None, description: &["Print each result as a json object on a line of its own, as soon as it's found, like {\"type\":\"match\",...}", "errors are {\"type\":\"error\",...}, and the last line is {\"type\":\"summary\",\"stats\":{...}}"] },
+    OptionSpec { short: None, long: "--vimgrep", value: None, hint: Hint::None, description: &["Print a \"path:line:column:line\" line for each match (two matches on a line are two lines), for vim's quickfix list", "the path is always printed, and there's nothing else, like headings, color, or a banner"] },
+    OptionSpec { short: None, long: "--format", value: Some("<TEMPLATE>"), hint: Hint::None, description: &["Print each result as TEMPLATE, with {path}, {line}, {column}, {byte_offset}, {text}, {match}, and {count} filled in, e.g. '{path}\\t{line}\\t{text}'", "\\t and \\n are a tab and a line break, and {{ and }} are literal braces, what a result doesn't have (like {line} when listing files) is left empty"] },
+    OptionSpec { short: None, long: "--csv", value: None, hint: Hint::None, description: &["Print the results as csv, with a header row, then \"path,match_count\" for each file, or \"path,line_number,column,match_count,line_text\" for each line"] },
+    OptionSpec { short: None, long: "--output", value: Some("<FILE>"), hint: Hint::File, description: &["Write the results to FILE (it's never searched itself) instead of stdout, errors still go to stderr"] },
+    OptionSpec { short: None, long: "--stats", value: None, hint: Hint::None, description: &["Print how many files were searched, matched, and skipped, how many matches there were, and how long it took at the end"] },
+    OptionSpec { short: None, long: "--count-matches", value: None, hint: Hint::None, description: &["Print the total number of times the query is in all the files put together (with -m, each file's count is capped first)"] },
+    OptionSpec { short: None, long: "--include-zero", value: None, hint: Hint::None, description: &["With --count, also list the files that don't contain the query"] },
+    OptionSpec { short: None, long: "--files", value: None, hint: Hint::None, description: &["List the files that would be searched, without searching them (no query is needed)"] },
+    OptionSpec { short: None, long: "--filenames", value: Some("[=path]"), hint: Hint::None, description: &["List the files whose names match the query (with the same case, -w, -x, and --regex rules), without opening them, like a quick find", "with --filenames=path, the query is matched against the whole path the file was found at instead, filters like --include still apply either way"] },
+    OptionSpec { short: None, long: "--also-match-names", value: None, hint: Hint::None, description: &["Also match files whose names match the query, even if their contents don't (or can't be read), these are tagged with [name]"] },
+    OptionSpec { short: Some("-I"), long: "--invert-match", value: None, hint: Hint::None, description: &["List the files that don't contain the query instead, files that can't be read are never listed"] },
+    OptionSpec { short: Some("-f"), long: "--filter", value: Some("<EXTENSIONS>..."), hint: Hint::None, description: &["Comma separated list of extensions, will only search files with these extensions", "use \"noext\" to also search files without an extension"] },
+    OptionSpec { short: None, long: "--include", value: Some("<GLOB>"), hint: Hint::None, description: &["Only search files whose names match GLOB (*, ?, and [...] are supported), can be given multiple times"] },
+    OptionSpec { short: None, long: "--exclude", value: Some("<GLOB>"), hint: Hint::None, description: &["Don't search files whose names match GLOB, can be given multiple times", "excludes win over includes, and neither can contain a directory separator"] },
+    OptionSpec { short: Some("-t"), long: "--type", value: Some("<TYPE>"), hint: Hint::Type, description: &["Only search files of type TYPE (like rust, or web), the same as an --include for each of its globs"] },
+    OptionSpec { short: Some("-T"), long: "--type-not", value: Some("<TYPE>"), hint: Hint::Type, description: &["Don't search files of type TYPE, the same as an --exclude for each of its globs"] },
+    OptionSpec { short: None, long: "--type-list", value: None, hint: Hint::None, description: &["List the file types, and the globs for each"] },
+    OptionSpec { short: None, long: "--type-add", value: Some("<NAME:GLOB>"), hint: Hint::None, description: &["Add GLOB to the file type NAME (making it if it's new), e.g. 'proto:*.proto', can be given multiple times"] },
+    OptionSpec { short: None, long: "--exclude-dir", value: Some("<NAME>"), hint: Hint::None, description: &["With -r, don't go into directories named NAME (like target or node_modules), can be given multiple times"] },
+    OptionSpec { short: Some("-r"), long: "--recursive", value: None, hint: Hint::None, description: &["Search through subdirectories"] },
+    OptionSpec { short: None, long: "--max-depth", value: Some("<N>"), hint: Hint::None, description: &["With -r, don't go more than N levels of subdirectories deep (0 is just PATH itself)"] },
+    OptionSpec { short: None, long: "--min-depth", value: Some("<N>"), hint: Hint::None, description: &["With -r, skip files less than N levels of subdirectories deep"] },
+    OptionSpec { short: None, long: "--hidden", value: None, hint: Hint::None, description: &["Also search hidden files and directories (ones starting with a '.'), these are skipped by default"] },
+    OptionSpec { short: None, long: "--follow", value: None, hint: Hint::None, description: &["With -r, go into symlinked directories and search symlinked files (each directory is only searched once, so loops are fine)"] },
+    OptionSpec { short: None, long: "--no-ignore", value: None, hint: Hint::None, description: &["With -r, also search files that .gitignore files say to ignore"] },
+    OptionSpec { short: None, long: "--ignore-file", value: Some("<FILE>"), hint: Hint::File, description: &["With -r, also skip what FILE (in .gitignore syntax, relative to PATH) ignores, can be given multiple times", "these win over .gitignore files, and still apply with --no-ignore"] },
+    OptionSpec { short: None, long: "--one-file-system", value: None, hint: Hint::None, description: &["With -r, don't go into directories on other filesystems, like mounts"] },
+    OptionSpec { short: None, long: "--no-dedupe", value: None, hint: Hint::None, description: &["Search a file once for every path it can be reached by (through links, or PATHs given twice), instead of just once"] },
+    OptionSpec { short: Some("-v"), long: "--verbose", value: None, hint: Hint::None, description: &["include all error messages in output"] },
+    OptionSpec { short: Some("-q"), long: "--quiet", value: None, hint: Hint::None, description: &["Don't print anything, just exit with the status (errors still go to stderr with -v)"] },
+    OptionSpec { short: Some("-m"), long: "--max-count", value: Some("<N>"), hint: Hint::None, description: &["Stop reading a file after N matches"] },
+    OptionSpec { short: Some("-a"), long: "--text", value: None, hint: Hint::None, description: &["Search binary files (ones with a NUL byte near the start) as text, instead of skipping them"] },
+    OptionSpec { short: None, long: "--binary", value: None, hint: Hint::None, description: &["Search binary files, but just print \"binary file <path> matches\" instead of their lines"] },
+    OptionSpec { short: None, long: "--binary-context", value: Some("<N>"), hint: Hint::None, description: &["With --binary, also print a hex dump (like xxd's) of each match in a binary file, and N bytes before and after it, matches close together share one"] },
+    OptionSpec { short: Some("-z"), long: "--search-zip", value: None, hint: Hint::None, description: &["Search inside gzip files (.gz), decompressing them as they're read, results are reported with the path of the .gz", "--max-filesize is the most they can decompress to (without it, 1G), so a tiny file can't decompress to something huge",
+        "and search each file in zip and tar archives (.zip, .jar, .docx, .tar, .tar.gz, .tgz, and the like) as archive.zip!path/in/zip, with -f, --include, --exclude, and -t applied to their names",
+        "archives in archives aren't opened, they're searched as they are, and --max-filesize is the most each file in an archive can be (links and devices in tar archives are skipped)"] },
+    OptionSpec { short: None, long: "--pre", value: Some("<COMMAND>"), hint: Hint::File, description: &["Run COMMAND with the path of each file as its argument, and search what it prints instead of the file (for pdfs, databases, and the like)", "it failing is an error for that file, with its exit code, and stdin isn't run through it"] },
+    OptionSpec { short: None, long: "--pre-glob", value: Some("<GLOB>"), hint: Hint::None, description: &["With --pre, only run COMMAND on files whose names match GLOB (the rest are searched as they are), can be given multiple times"] },
+    OptionSpec { short: None, long: "--pre-timeout", value: Some("<SECONDS>"), hint: Hint::None, description: &["With --pre, kill COMMAND if it takes longer than SECONDS on a file (e.g. 10, or 2.5), which is an error for that file"] },
+    OptionSpec { short: None, long: "--strict-utf8", value: None, hint: Hint::None, description: &["Skip files that aren't valid utf-8, instead of searching them with the invalid bytes replaced"] },
+    OptionSpec { short: None, long: "--allow-blank-query", value: None, hint: Hint::None, description: &["Allow a query (or pattern) that's just whitespace, like a tab, these are errors by default"] },
+    OptionSpec { short: None, long: "--encoding", value: Some("<ENCODING>"), hint: Hint::Choices(&["auto", "utf-8", "utf-16le", "utf-16be", "latin1"]), description: &["Read files as auto (the default, utf-8 unless there's a utf-16 byte order mark), utf-8, utf-16le, utf-16be, or latin1"] },
+    OptionSpec { short: None, long: "--max-filesize", value: Some("<SIZE>"), hint: Hint::None, description: &["Skip files larger than SIZE, e.g. 512, 10K, 10M, or 1G"] },
+    OptionSpec { short: None, long: "--min-size", value: Some("<SIZE>"), hint: Hint::None, description: &["Only search files at least SIZE big (like --max-filesize's SIZE), to leave out tiny stubs, with --files, only list those"] },
+    OptionSpec { short: None, long: "--max-size", value: Some("<SIZE>"), hint: Hint::None, description: &["Only search files at most SIZE big, with --files, only list those, files skipped by either are counted in --stats"] },
+    OptionSpec { short: None, long: "--newer-than", value: Some("<TIME>"), hint: Hint::None, description: &["Only search files modified at or after TIME, which is how long ago (30m, 12h, 2d, or 1w), or a date, 2024-01-01 (or 2024-01-01T12:30), in UTC", "files modified in the future (like after the clock was changed) are newer than any TIME, files skipped like this are counted in --stats"] },
+    OptionSpec { short: None, long: "--older-than", value: Some("<TIME>"), hint: Hint::None, description: &["Only search files modified before TIME (like --newer-than's), both can be given to search the files modified in between"] },
+    OptionSpec { short: None, long: "--threads", value: Some("<N>"), hint: Hint::None, description: &["Search N files at a time (default: the number of CPUs), results are still printed in order"] },
+    OptionSpec { short: None, long: "--sort", value: Some("<BY>"), hint: Hint::Choices(&["path", "mtime", "size", "none"]), description: &["The order to search and print files in: path (the default), mtime (oldest first), size (smallest first), or none (fastest)"] },
+    OptionSpec { short: None, long: "--color", value: Some("<WHEN>"), hint: Hint::Choices(&["auto", "always", "never"]), description: &["When to color file names and matches: auto (only in a terminal, and without NO_COLOR), always, or never"] },
+    OptionSpec { short: None, long: "--hyperlinks", value: Some("<WHEN>"), hint: Hint::Choices(&["auto", "on", "off"]), description: &["When to make file names clickable links to the files, in terminals that support it: auto (whenever there's color), on, or off"] },
+    OptionSpec { short: Some("-e"), long: "--regexp", value: Some("<PATTERN>"), hint: Hint::None, description: &["Also search for PATTERN, can be given multiple times", "files containing any of the patterns match, when given, the query is optional"] },
+    OptionSpec { short: None, long: "--pattern-file", value: Some("<FILE>"), hint: Hint::File, description: &["Also search for every pattern in FILE, one per line, lines starting with '#' are ignored"] },
+    OptionSpec { short: None, long: "--all-of", value: None, hint: Hint::None, description: &["Only match files that contain every one of the patterns (and the query), instead of any of them", "when printing lines, the lines with any of them are printed, but only from those files, which have to be read until all of them are found"] },
+    OptionSpec { short: None, long: "--not", value: Some("<PATTERN>"), hint: Hint::None, description: &["Don't match files that contain PATTERN anywhere (matched like the query, with the same case rules), can be given multiple times", "files have to be read all the way through to be sure they don't, even after the query is found, so this is slower"] },
+    OptionSpec { short: Some("-p"), long: "--path", value: Some("<PATH>"), hint: Hint::Path, description: &["Also search in PATH, can be given multiple times", "when given, all arguments without a '-' are the query"] },
+    OptionSpec { short: None, long: "--no-config", value: None, hint: Hint::None, description: &["Don't load default options from the config file"] },
+    OptionSpec { short: None, long: "--generate-man", value: None, hint: Hint::None, description: &["Print the man page (in roff), e.g. grep-directory --generate-man > grep-directory.1"] },
+    OptionSpec { short: None, long: "--completions", value: Some("<SHELL>"), hint: Hint::Choices(&["bash", "zsh", "fish"]), description: &["Print a completion script for SHELL (bash, zsh, or fish), e.g. grep-directory --completions bash > ~/.local/share/bash-completion/completions/grep-directory"] },
+    OptionSpec { short: Some("-h"), long: "--help", value: None, hint: Hint::None, description: &["Prints help information, so does a bare \"help\""] },
+    OptionSpec { short: Some("-V"), long: "--version", value: None, hint: Hint::None, description: &["Prints version information, so does a bare \"version\""] },
+];
+
+//the column the descriptions start at in the help, tabs are 8 wide
+const DESCRIPTION_COLUMN: usize = 56;
+
+/**
+ * returns the option written as option (short like "-i", or long like "--ignore-case"), if there is one
+ */
+pub fn find(option: &str) -> Option<&'static OptionSpec> {
+    return OPTIONS.iter().find(|spec| spec.long == option || spec.short == Some(option));
+}
+
+/**
+ * returns true if option is the short or long form of an option
+ */
+pub fn is_valid(option: &str) -> bool {
+    return find(option).is_some();
+}
+
+/**
+ * returns true if option is an option that takes a value (as the next argument, or after an =)
+ */
+pub fn takes_value(option: &str) -> bool {
+    return find(option).is_some_and(|spec| spec.value.is_some_and(|value| !is_optional(value)));
+}
+
+/**
+ * returns true if option is an option that can be given a value after an =, but doesn't have to be
+ */
+pub fn takes_optional_value(option: &str) -> bool {
+    return find(option).is_some_and(|spec| spec.value.is_some_and(is_optional));
+}
+
+/**
+ * returns true if value (the name of the value an option takes) is optional, like "[=path]"
+ */
+pub fn is_optional(value: &str) -> bool {
+    return value.starts_with("[=");
+}
+
+/**
+ * returns every way options can be written, short and long
+ */
+pub fn names() -> impl Iterator<Item = &'static str> {
+    return OPTIONS.iter().flat_map(|spec| spec.short.into_iter().chain([spec.long]));
+}
+
+/**
+ * writes the options as they're listed in the help, a line for each, with the descriptions lined up (and any more lines of them under the first)
+ */
+pub fn write_options<W: Write>(out: &mut W) -> io::Result<()> {
+    for spec in OPTIONS {
+        //DATA
+        let short: String = spec.short.map(|short| format!("{},", short)).unwrap_or_default();
+        let long: String = match spec.value {
+            Some(value) if is_optional(value) => format!("{}{}", spec.long, value),
+            Some(value) => format!("{} {}", spec.long, value),
+            None => spec.long.to_string(),
+        };
+        //the short flag is in the first tab stop, and the long one starts at the third
+        let mut column: usize = 16 + long.chars().count();
+        let mut padding: String = String::from("\t");
+
+        column = (column / 8 + 1) * 8;
+        while column < DESCRIPTION_COLUMN {
+            padding.push('\t');
+            column += 8;
+        }
+
+        writeln!(out, "\t{}\t{}{}{}", short, long, padding, spec.description[0])?;
+        for more in &spec.description[1..] {
+            writeln!(out, "\t\t\t\t\t\t\t{}", more)?;
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,221 @@
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Child, ChildStdout, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+//how often a running preprocessor is checked on, to see if it's done (or out of time)
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/**
+ * how a preprocessor ended
+ */
+enum Outcome {
+    Exited(ExitStatus),
+    TimedOut,   //it was killed for taking longer than the timeout
+    Stopped,    //it was killed because the rest of its output wasn't needed
+}
+
+/**
+ * a preprocessor (--pre) running on a file, reading from it reads what it prints, which is what's searched instead of the file
+ * it's watched on a thread of its own, so one that hangs can be killed once it's out of time, even while its output is being waited on
+ */
+pub struct Preprocessor {
+    stdout: ChildStdout,
+    eof: bool,                          //whether all of its output has been read
+    stop: Sender<()>,                   //tells the watcher to kill it, since the rest of its output isn't needed
+    watcher: JoinHandle<io::Result<Outcome>>,
+    stderr: JoinHandle<String>,         //the first line it printed to stderr, for the error if it fails
+    timeout: Option<Duration>,
+}
+impl Preprocessor {
+    /**
+     * starts command, with path as its argument, it's killed if it takes longer than timeout
+     */
+    pub fn spawn(command: &str, path: &Path, timeout: Option<Duration>) -> io::Result<Preprocessor> {
+        //DATA
+        let mut preprocessor: Command = Command::new(command);
+        preprocessor.arg(path).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+        //in a process group of its own, so whatever it starts (like the commands in a shell script) can be killed along with it
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut preprocessor, 0);
+        //a missing command isn't a missing file, which would be skipped like it was deleted before it was searched
+        let mut child: Child = preprocessor.spawn()
+            .map_err(|error| {
+                let kind: io::ErrorKind = if error.kind() == io::ErrorKind::NotFound {io::ErrorKind::Other} else {error.kind()};
+                io::Error::new(kind, format!("couldn't run the preprocessor '{}': {}", command, error))
+            })?;
+        let stdout: ChildStdout = child.stdout.take().expect("the preprocessor's stdout is piped");
+        let stderr = child.stderr.take().expect("the preprocessor's stderr is piped");
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        //stderr is read as it comes, so a preprocessor that prints a lot of it can't fill the pipe and get stuck
+        let stderr: JoinHandle<String> = thread::spawn(move || {
+            //DATA
+            let mut reader = BufReader::new(stderr);
+            let mut first_line: String = String::new();
+
+            let _ = reader.read_line(&mut first_line);
+            let _ = io::copy(&mut reader, &mut io::sink());
+            return first_line.trim_end().to_string();
+        });
+        let watcher: JoinHandle<io::Result<Outcome>> = thread::spawn(move || watch(child, stopped, timeout));
+
+        //return
+        return Ok(Preprocessor { stdout, eof: false, stop, watcher, stderr, timeout });
+    }
+
+    /**
+     * waits for the preprocessor to end (killing it if its output wasn't all read, since it isn't needed), and returns an error if it failed
+     * the error has its exit code, and the first line it printed to stderr
+     */
+    pub fn finish(self) -> io::Result<()> {
+        //DATA
+        let Preprocessor { stdout, eof, stop, watcher, stderr, timeout } = self;
+
+        if !eof {
+            let _ = stop.send(());
+        }
+        drop(stdout);
+        let outcome: Outcome = watcher.join().map_err(|_| io::Error::other("the preprocessor's watcher panicked"))??;
+        let stderr: String = stderr.join().unwrap_or_default();
+        let stderr: String = if stderr.is_empty() {stderr} else {format!(": {}", stderr)};
+
+        //return
+        return match outcome {
+            Outcome::Exited(status) if status.success() => Ok(()),
+            Outcome::Exited(status) => Err(io::Error::other(match status.code() {
+                Some(code) => format!("the preprocessor exited with code {}{}", code, stderr),
+                None => format!("the preprocessor was killed by a signal{}", stderr),
+            })),
+            Outcome::TimedOut => Err(io::Error::new(io::ErrorKind::TimedOut, format!("the preprocessor was killed after {:?}, it took too long{}", timeout.unwrap_or_default(), stderr))),
+            Outcome::Stopped => Ok(()),
+        };
+    }
+}
+impl Read for Preprocessor {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        //DATA
+        let read: usize = self.stdout.read(buf)?;
+
+        if read == 0 && !buf.is_empty() {
+            self.eof = true;
+        }
+        return Ok(read);
+    }
+}
+
+/**
+ * waits for child to exit, killing it if it takes longer than timeout, or when told to stop
+ */
+fn watch(mut child: Child, stopped: Receiver<()>, timeout: Option<Duration>) -> io::Result<Outcome> {
+    //DATA
+    let started: Instant = Instant::now();
+
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Outcome::Exited(status));
+        }
+
+        //DATA
+        let out_of_time: bool = timeout.is_some_and(|timeout| started.elapsed() >= timeout);
+
+        if out_of_time || !matches!(stopped.recv_timeout(POLL_INTERVAL), Err(RecvTimeoutError::Timeout)) {
+            kill(&mut child);
+            child.wait()?;
+            return Ok(if out_of_time {Outcome::TimedOut} else {Outcome::Stopped});
+        }
+    }
+}
+
+/**
+ * kills child, and everything it started that's still running, which could otherwise keep its output open after it's gone
+ */
+#[cfg(unix)]
+fn kill(child: &mut Child) {
+    //SIGKILL is 9 everywhere
+    const SIGKILL: i32 = 9;
+    extern "C" {
+        #[link_name = "kill"]
+        fn kill_process(pid: i32, signal: i32) -> i32;
+    }
+    //a negative pid is the whole process group, which the child leads
+    if unsafe { kill_process(-(child.id() as i32), SIGKILL) } != 0 {
+        let _ = child.kill();
+    }
+}
+#[cfg(not(unix))]
+fn kill(child: &mut Child) {
+    let _ = child.kill();
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /**
+     * returns what command prints for path (reading at most limit bytes of it), and how it ended
+     */
+    fn preprocess(command: &str, path: &str, timeout: Option<Duration>, limit: u64) -> (String, io::Result<()>) {
+        //DATA
+        let mut preprocessor: Preprocessor = Preprocessor::spawn(command, Path::new(path), timeout).unwrap();
+        let mut output: String = String::new();
+
+        (&mut preprocessor).take(limit).read_to_string(&mut output).unwrap();
+        return (output, preprocessor.finish());
+    }
+
+    #[test]
+    fn what_it_prints_is_read() {
+        //DATA
+        let (output, finished) = preprocess("echo", "hello", None, u64::MAX);
+
+        assert_eq!(output, "hello\n");
+        assert!(finished.is_ok());
+    }
+
+    #[test]
+    fn failing_is_an_error_with_the_exit_code() {
+        //DATA
+        let (output, finished) = preprocess("false", "a.pdf", None, u64::MAX);
+
+        assert_eq!(output, "");
+        assert_eq!(finished.err().unwrap().to_string(), "the preprocessor exited with code 1");
+        //with the first line it printed to stderr
+        let error: String = preprocess("ls", "/nonexistent", None, u64::MAX).1.err().unwrap().to_string();
+        assert!(error.starts_with("the preprocessor exited with code ") && error.contains(": ls: ") && error.contains("/nonexistent"), "{}", error);
+    }
+
+    #[test]
+    fn a_missing_preprocessor_isnt_a_missing_file() {
+        //DATA
+        let error: io::Error = Preprocessor::spawn("/nonexistent/to-text", Path::new("a.pdf"), None).err().unwrap();
+
+        assert_eq!(error.kind(), io::ErrorKind::Other);
+        assert!(error.to_string().starts_with("couldn't run the preprocessor '/nonexistent/to-text': "), "{}", error);
+    }
+
+    #[test]
+    fn one_that_takes_too_long_is_killed() {
+        //DATA
+        let started: Instant = Instant::now();
+        //sleep's argument is the path, so this is sleep 10
+        let (output, finished) = preprocess("sleep", "10", Some(Duration::from_millis(100)), u64::MAX);
+        let error: io::Error = finished.err().unwrap();
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(output, "");
+        assert_eq!((error.kind(), error.to_string()), (io::ErrorKind::TimedOut, "the preprocessor was killed after 100ms, it took too long".to_string()));
+    }
+
+    #[test]
+    fn one_stopped_early_isnt_an_error() {
+        //DATA
+        //yes never stops printing its argument
+        let (output, finished) = preprocess("yes", "needle", None, 14);
+
+        assert_eq!(output, "needle\nneedle\n");
+        assert!(finished.is_ok());
+    }
+}
@@ -0,0 +1,898 @@
+use std::error::Error;  //allows for some better errors
+use std::ops::Range;
+use std::rc::Rc;
+
+use crate::{Anchor, MatchKind};
+
+//the most instructions a compiled regex can have, so things like "(a{1000}){1000}" can't eat all the memory
+const MAX_PROGRAM_SIZE: usize = 100_000;
+
+/**
+ * a set of characters, like [a-z] or \d
+ */
+#[derive(Debug, Clone, PartialEq)]
+struct Class {
+    negated: bool,
+    ranges: Vec<(char,char)>,
+}
+impl Class {
+    fn new(negated: bool, ranges: &[(char,char)]) -> Class {
+        return Class { negated, ranges: ranges.to_vec() };
+    }
+
+    fn matches(&self, c: char, case_insensitive: bool) -> bool {
+        //DATA
+        let contains = |c: char| self.ranges.iter().any(|(start,end)| *start <= c && c <= *end);
+
+        //with case insensitivity, the other cases of c count too
+        let found: bool = contains(c) || (case_insensitive && other_cases(c).any(contains));
+        return found != self.negated;
+    }
+}
+
+/**
+ * a place in the text that has to be a certain way for the regex to match, without matching any characters
+ */
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Assertion {
+    Start,          //^
+    End,            //$
+    WordBoundary,   //\b
+    NotWordBoundary,//\B
+    NotAfterWord,   //used for whole word matching, the character before isn't a word character
+    NotBeforeWord,  //used for whole word matching, the character after isn't a word character
+}
+
+/**
+ * a piece of a parsed regex
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Empty,
+    Literal(char, bool),    //the character, and whether it's case insensitive
+    Any,                    //. (anything but a newline)
+    Class(Class, bool),     //the class, and whether it's case insensitive
+    Assert(Assertion),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize>, greedy: bool },
+    Group(Box<Node>, usize),    //a capturing group, and its number (starting from 1)
+}
+
+/**
+ * an instruction of a compiled regex, run by the pike vm in Regex::find_at
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Inst {
+    Char(char, bool),
+    Any,
+    Class(Class, bool),
+    Assert(Assertion),
+    Split(usize, usize),    //try both, the first one has priority
+    Jmp(usize),
+    Save(usize),            //remember the position in this slot, slots 2n and 2n+1 are the start and end of group n
+    Match,
+}
+
+/**
+ * a compiled regular expression, supporting
+ * - `.`, `[...]` classes (with ranges and `^` negation), and the ascii `\d`, `\w`, `\s` classes (and their negations `\D`, `\W`, `\S`)
+ * - `*`, `+`, `?`, `{n}`, `{n,}`, and `{n,m}` repetition, lazy if followed by a `?`
+ * - `|` alternation, `(...)` capturing groups, and `(?:...)` groups that don't capture
+ * - `^` and `$` anchors (the start and end of the line), and `\b` and `\B` word boundaries
+ * - the `(?i)` flag, and `(?i:...)` groups, for case insensitivity
+ * - `\` to escape any other character, and `\n`, `\r`, and `\t`
+ *
+ * when there's more than one way to match, the leftmost match wins, then the one the pattern prefers (like perl)
+ */
+#[derive(Debug, Clone)]
+pub struct Regex {
+    pattern: String,
+    program: Vec<Inst>,
+    groups: usize,      //how many capturing groups there are
+}
+//the positions each thread has saved, shared between threads until one of them saves something
+type Slots = Rc<Vec<Option<usize>>>;
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, Box<dyn Error>> {
+        return Regex::with_options(pattern, false, MatchKind::Substring);
+    }
+
+    /**
+     * same as new, but case_insensitive is the same as starting the pattern with (?i),
+     * and kind limits the matches to whole words or lines, like for literal queries
+     */
+    pub fn with_options(pattern: &str, case_insensitive: bool, kind: MatchKind) -> Result<Regex, Box<dyn Error>> {
+        //DATA
+        let mut parser = Parser { pattern, chars: pattern.chars().collect(), position: 0, case_insensitive, groups: 0 };
+        let mut program: Vec<Inst> = Vec::new();
+
+        //parse the pattern
+        let mut node = parser.parse_alternation()?;
+        if parser.position < parser.chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+        node = match kind {
+            MatchKind::Substring => node,
+            MatchKind::Word => Node::Concat(vec![Node::Assert(Assertion::NotAfterWord), node, Node::Assert(Assertion::NotBeforeWord)]),
+            MatchKind::Line => Node::Concat(vec![Node::Assert(Assertion::Start), node, Node::Assert(Assertion::End)]),
+            MatchKind::Anchored { anchor, word, ignore_whitespace } => {
+                //DATA
+                //there's no looking behind, so whitespace that's ignored is part of the match
+                let whitespace = || Node::Repeat { node: Box::new(Node::Class(parser.escaped_class('s').expect("\\s is a class"), false)), min: 0, max: None, greedy: true };
+                let mut nodes: Vec<Node> = Vec::new();
+
+                if anchor != Anchor::End {
+                    nodes.push(Node::Assert(Assertion::Start));
+                    nodes.extend(ignore_whitespace.then(whitespace));
+                }
+                nodes.extend(word.then_some(Node::Assert(Assertion::NotAfterWord)));
+                nodes.push(node);
+                nodes.extend(word.then_some(Node::Assert(Assertion::NotBeforeWord)));
+                if anchor != Anchor::Start {
+                    nodes.extend(ignore_whitespace.then(whitespace));
+                    nodes.push(Node::Assert(Assertion::End));
+                }
+                Node::Concat(nodes)
+            },
+        };
+
+        //compile it
+        compile(&node, &mut program).map_err(|err| format!("Invalid regex '{}': {}.", pattern, err))?;
+        program.push(Inst::Match);
+
+        //return
+        return Ok(Regex { pattern: pattern.to_string(), program, groups: parser.groups });
+    }
+
+    /**
+     * returns the pattern this regex was compiled from
+     */
+    pub fn as_str(&self) -> &str {
+        return &self.pattern;
+    }
+
+    /**
+     * returns how many capturing groups the regex has
+     */
+    pub fn group_count(&self) -> usize {
+        return self.groups;
+    }
+
+    /**
+     * returns true if the regex matches anywhere in text
+     */
+    pub fn is_match(&self, text: &str) -> bool {
+        return self.find_at(text, 0).is_some();
+    }
+
+    /**
+     * returns the byte range of every non-overlapping match in text, from left to right
+     */
+    pub fn find_iter(&self, text: &str) -> Vec<Range<usize>> {
+        //DATA
+        let mut matches: Vec<Range<usize>> = Vec::new();
+        let mut start: usize = 0;
+
+        while start <= text.len() {
+            let Some(found) = self.find_at(text, start) else {
+                break;
+            };
+            //an empty match can't be followed by another one in the same place, so move forward one character
+            start = if found.is_empty() {found.end + text[found.end..].chars().next().map_or(1, char::len_utf8)} else {found.end};
+            matches.push(found);
+        }
+
+        return matches;
+    }
+
+    /**
+     * returns the byte range of the leftmost match in text that starts at or after start
+     * text before start still counts for ^ and \b
+     */
+    pub fn find_at(&self, text: &str, start: usize) -> Option<Range<usize>> {
+        return self.captures_at(text, start).and_then(|groups| groups[0].clone());
+    }
+
+    /**
+     * same as find_at, but also returns where each capturing group matched, the whole match is group 0
+     * groups that didn't take part in the match are None
+     */
+    pub fn captures_at(&self, text: &str, start: usize) -> Option<Vec<Option<Range<usize>>>> {
+        //DATA
+        let mut current: Vec<(usize, Slots)> = Vec::new(); //threads at this position, as (instruction, what it's saved), in order of priority
+        let mut next: Vec<(usize, Slots)> = Vec::new();
+        let mut added: Vec<usize> = vec![usize::MAX; self.program.len()]; //the generation each instruction was last added to a list in
+        let mut generation: usize = 0;
+        let mut found: Option<Vec<Option<usize>>> = None;
+        let mut position: usize = start;
+
+        loop {
+            //a match could start here too, unless one has already been found (which would be further left)
+            if found.is_none() {
+                let mut slots: Vec<Option<usize>> = vec![None; 2 * (self.groups + 1)];
+                slots[0] = Some(position);
+                self.add_thread(&mut current, &mut added, generation, 0, Rc::new(slots), text, position);
+            }
+            //with no threads left, there's nothing more to find once there's a match, or the text has run out,
+            //otherwise a match could still start further on (a thread started here can die right away, like on a \b that doesn't hold)
+            if current.is_empty() && (found.is_some() || position == text.len()) {
+                break;
+            }
+
+            //step every thread forward over the next character
+            let c: Option<char> = text[position..].chars().next();
+            let next_position: usize = position + c.map_or(0, char::len_utf8);
+            for (pc, slots) in current.drain(..) {
+                let matched: bool = match (&self.program[pc], c) {
+                    (Inst::Match, _) => {
+                        //this beats every thread after it, so they can all be dropped
+                        let mut slots: Vec<Option<usize>> = slots.to_vec();
+                        slots[1] = Some(position);
+                        found = Some(slots);
+                        break;
+                    },
+                    (Inst::Char(expected, case_insensitive), Some(c)) => c == *expected || (*case_insensitive && other_cases(c).any(|other| other == *expected)),
+                    (Inst::Any, Some(c)) => c != '\n',
+                    (Inst::Class(class, case_insensitive), Some(c)) => class.matches(c, *case_insensitive),
+                    _ => false,
+                };
+                if matched {
+                    self.add_thread(&mut next, &mut added, generation + 1, pc + 1, slots, text, next_position);
+                }
+            }
+
+            //move on to the next position
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+            generation += 1;
+            if c.is_none() {
+                break;
+            }
+            position = next_position;
+        }
+
+        //pair the slots up into the range each group matched
+        return found.map(|slots| slots.chunks(2).map(|pair| match pair {
+            [Some(start), Some(end)] => Some(*start..*end),
+            _ => None,
+        }).collect());
+    }
+
+    /**
+     * adds the thread at instruction pc to list, following jumps, splits, saves, and assertions right away, so the list only has instructions that match characters
+     */
+    #[allow(clippy::too_many_arguments)]
+    fn add_thread(&self, list: &mut Vec<(usize, Slots)>, added: &mut [usize], generation: usize, pc: usize, slots: Slots, text: &str, position: usize) {
+        //a thread that's already here has priority over this one
+        if added[pc] == generation {
+            return;
+        }
+        added[pc] = generation;
+
+        match &self.program[pc] {
+            Inst::Jmp(to) => self.add_thread(list, added, generation, *to, slots, text, position),
+            Inst::Split(first, second) => {
+                self.add_thread(list, added, generation, *first, slots.clone(), text, position);
+                self.add_thread(list, added, generation, *second, slots, text, position);
+            },
+            Inst::Save(slot) => {
+                let mut slots = slots;
+                Rc::make_mut(&mut slots)[*slot] = Some(position);
+                self.add_thread(list, added, generation, pc + 1, slots, text, position);
+            },
+            Inst::Assert(assertion) => if assertion_holds(*assertion, text, position) {
+                self.add_thread(list, added, generation, pc + 1, slots, text, position);
+            },
+            _ => list.push((pc, slots)),
+        }
+    }
+}
+
+/**
+ * returns true if the assertion is true at position (a byte index) in text
+ */
+fn assertion_holds(assertion: Assertion, text: &str, position: usize) -> bool {
+    //DATA
+    let word_before: bool = text[..position].chars().next_back().is_some_and(is_word_char);
+    let word_after: bool = text[position..].chars().next().is_some_and(is_word_char);
+
+    return match assertion {
+        Assertion::Start => position == 0,
+        Assertion::End => position == text.len(),
+        Assertion::WordBoundary => word_before != word_after,
+        Assertion::NotWordBoundary => word_before == word_after,
+        Assertion::NotAfterWord => !word_before,
+        Assertion::NotBeforeWord => !word_after,
+    };
+}
+
+fn is_word_char(c: char) -> bool {
+    return c.is_alphanumeric() || c == '_';
+}
+
+/**
+ * returns the other cases of c (like 'A' for 'a'), only the ones that are a single character
+ */
+fn other_cases(c: char) -> impl Iterator<Item = char> {
+    //DATA
+    let mut lower = c.to_lowercase();
+    let mut upper = c.to_uppercase();
+    let lower: Option<char> = if lower.len() == 1 {lower.next()} else {None};
+    let upper: Option<char> = if upper.len() == 1 {upper.next()} else {None};
+
+    return lower.into_iter().chain(upper).filter(move |other| *other != c);
+}
+
+/**
+ * turns a regex pattern into nodes
+ */
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: Vec<char>,
+    position: usize,        //index into chars
+    case_insensitive: bool, //whether (?i) is on right now
+    groups: usize,          //how many capturing groups have been opened so far
+}
+impl Parser<'_> {
+    fn error(&self, message: &str) -> Box<dyn Error> {
+        return format!("Invalid regex '{}': {} at position {}.", self.pattern, message, self.position + 1).into();
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).copied();
+    }
+
+    /**
+     * parses pieces separated by '|', up to the end of the pattern or the ')' closing the group it's in
+     */
+    fn parse_alternation(&mut self) -> Result<Node, Box<dyn Error>> {
+        //DATA
+        let mut alternatives: Vec<Node> = vec![self.parse_concat()?];
+
+        while self.peek() == Some('|') {
+            self.position += 1;
+            alternatives.push(self.parse_concat()?);
+        }
+
+        return Ok(if alternatives.len() == 1 {alternatives.remove(0)} else {Node::Alternate(alternatives)});
+    }
+
+    /**
+     * parses a sequence of (possibly repeated) atoms
+     */
+    fn parse_concat(&mut self) -> Result<Node, Box<dyn Error>> {
+        //DATA
+        let mut nodes: Vec<Node> = Vec::new();
+
+        while let Some(c) = self.peek() {
+            match c {
+                '|' | ')' => break,
+                '*' | '+' | '?' => return Err(self.error(&format!("nothing to repeat before '{}'", c))),
+                '{' if self.repetition_ahead() => return Err(self.error("nothing to repeat before '{'")),
+                _ => {},
+            }
+            let atom = self.parse_atom()?;
+            let repeated = self.parse_repetitions(atom)?;
+            nodes.push(repeated);
+        }
+
+        return Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.remove(0),
+            _ => Node::Concat(nodes),
+        });
+    }
+
+    /**
+     * returns true if the '{' at position starts a repetition like {2} or {2,5}, otherwise it's just a '{'
+     */
+    fn repetition_ahead(&self) -> bool {
+        //DATA
+        let rest: String = self.chars[self.position..].iter().collect();
+
+        return match rest.find('}') {
+            Some(end) => {
+                let inside = &rest[1..end];
+                !inside.is_empty() && inside.chars().all(|c| c.is_ascii_digit() || c == ',') && inside.chars().next().is_some_and(|c| c.is_ascii_digit())
+            },
+            None => false,
+        };
+    }
+
+    /**
+     * wraps node in any repetitions that come after it
+     */
+    fn parse_repetitions(&mut self, mut node: Node) -> Result<Node, Box<dyn Error>> {
+        loop {
+            //DATA
+            let (min, max): (usize, Option<usize>) = match self.peek() {
+                Some('{') if self.repetition_ahead() => self.parse_counts()?,
+                Some(c @ ('*' | '+' | '?')) => {
+                    self.position += 1;
+                    match c {
+                        '*' => (0, None),
+                        '+' => (1, None),
+                        _ => (0, Some(1)),
+                    }
+                },
+                _ => return Ok(node),
+            };
+            //a '?' after a repetition makes it lazy
+            let greedy: bool = if self.peek() == Some('?') {
+                self.position += 1;
+                false
+            } else {
+                true
+            };
+
+            node = Node::Repeat { node: Box::new(node), min, max, greedy };
+        }
+    }
+
+    /**
+     * parses a {n}, {n,}, or {n,m} repetition, leaving position just after its closing '}'
+     */
+    fn parse_counts(&mut self) -> Result<(usize, Option<usize>), Box<dyn Error>> {
+        //DATA
+        let start: usize = self.position;
+        let end: usize = start + self.chars[start..].iter().position(|c| *c == '}').unwrap_or(0);
+        let inside: String = self.chars[start + 1..end].iter().collect();
+        let parse = |number: &str| number.parse::<usize>().ok();
+
+        let (min, max) = match inside.split_once(',') {
+            None => (parse(&inside), parse(&inside)),
+            Some((min, "")) => (parse(min), None),
+            Some((min, max)) => match parse(max) {
+                Some(max) => (parse(min), Some(max)),
+                None => return Err(self.error("invalid repetition count")),
+            },
+        };
+        let Some(min) = min else {
+            return Err(self.error("invalid repetition count"));
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error(&format!("repetition {{{}}} has a max smaller than its min", inside)));
+        }
+
+        self.position = end + 1;
+        return Ok((min, max));
+    }
+
+    /**
+     * parses a single thing that can be repeated: a character, class, group, anchor, or escape
+     */
+    fn parse_atom(&mut self) -> Result<Node, Box<dyn Error>> {
+        //DATA
+        let c = self.chars[self.position];
+
+        self.position += 1;
+        return match c {
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Assert(Assertion::Start)),
+            '$' => Ok(Node::Assert(Assertion::End)),
+            '[' => self.parse_class(),
+            '(' => self.parse_group(),
+            '\\' => self.parse_escape(),
+            c => Ok(Node::Literal(c, self.case_insensitive)),
+        };
+    }
+
+    /**
+     * parses everything after a '(', up to and including the matching ')'
+     */
+    fn parse_group(&mut self) -> Result<Node, Box<dyn Error>> {
+        //DATA
+        let opened_at: usize = self.position - 1;
+        let case_insensitive_before: bool = self.case_insensitive;
+        let mut group: Option<usize> = None; //the group's number, if it captures
+
+        //(?i) turns on case insensitivity for the rest of the group it's in, (?i:...) just for what's inside it, (?:...) doesn't capture anything
+        if self.peek() == Some('?') {
+            self.position += 1;
+            let mut flag_on: bool = false;
+            while self.peek() == Some('i') {
+                self.position += 1;
+                flag_on = true;
+            }
+            match self.peek() {
+                Some(')') if flag_on => {
+                    self.position += 1;
+                    self.case_insensitive = true;
+                    return Ok(Node::Empty);
+                },
+                Some(':') => {
+                    self.position += 1;
+                    self.case_insensitive = self.case_insensitive || flag_on;
+                },
+                _ => return Err(self.error("unsupported group flag, only (?:...), (?i), and (?i:...) are supported")),
+            }
+        } else {
+            //groups are numbered in the order they're opened
+            self.groups += 1;
+            group = Some(self.groups);
+        }
+
+        let node = self.parse_alternation()?;
+        if self.peek() != Some(')') {
+            self.position = opened_at;
+            return Err(self.error("unclosed '('"));
+        }
+        self.position += 1;
+        self.case_insensitive = case_insensitive_before;
+
+        return Ok(match group {
+            Some(group) => Node::Group(Box::new(node), group),
+            None => node,
+        });
+    }
+
+    /**
+     * parses everything after a '\' outside of a class
+     */
+    fn parse_escape(&mut self) -> Result<Node, Box<dyn Error>> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("nothing to escape after the trailing '\\'"));
+        };
+        self.position += 1;
+
+        return match c {
+            'b' => Ok(Node::Assert(Assertion::WordBoundary)),
+            'B' => Ok(Node::Assert(Assertion::NotWordBoundary)),
+            _ => match self.escaped_class(c) {
+                Some(class) => Ok(Node::Class(class, false)),
+                None => Ok(Node::Literal(self.escaped_char(c)?, self.case_insensitive)),
+            },
+        };
+    }
+
+    /**
+     * returns the class for \d, \w, \s, and their negations
+     */
+    fn escaped_class(&self, c: char) -> Option<Class> {
+        //DATA
+        let digits: &[(char,char)] = &[('0','9')];
+        let word: &[(char,char)] = &[('0','9'), ('A','Z'), ('_','_'), ('a','z')];
+        let space: &[(char,char)] = &[('\t','\r'), (' ',' ')];
+
+        return match c {
+            'd' => Some(Class::new(false, digits)),
+            'D' => Some(Class::new(true, digits)),
+            'w' => Some(Class::new(false, word)),
+            'W' => Some(Class::new(true, word)),
+            's' => Some(Class::new(false, space)),
+            'S' => Some(Class::new(true, space)),
+            _ => None,
+        };
+    }
+
+    /**
+     * returns the character an escape like \n or \. stands for
+     */
+    fn escaped_char(&mut self, c: char) -> Result<char, Box<dyn Error>> {
+        return match c {
+            'n' => Ok('\n'),
+            'r' => Ok('\r'),
+            't' => Ok('\t'),
+            c if c.is_alphanumeric() => {
+                self.position -= 1;
+                Err(self.error(&format!("unknown escape '\\{}'", c)))
+            },
+            c => Ok(c),
+        };
+    }
+
+    /**
+     * parses everything after a '[', up to and including the matching ']'
+     */
+    fn parse_class(&mut self) -> Result<Node, Box<dyn Error>> {
+        //DATA
+        let opened_at: usize = self.position - 1;
+        let mut negated: bool = false;
+        let mut ranges: Vec<(char,char)> = Vec::new();
+        let mut first: bool = true;
+
+        if self.peek() == Some('^') {
+            negated = true;
+            self.position += 1;
+        }
+        loop {
+            //DATA
+            let Some(c) = self.peek() else {
+                self.position = opened_at;
+                return Err(self.error("unclosed '['"));
+            };
+            self.position += 1;
+
+            //a ']' right at the start of the class is just a character in the class
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+
+            //the start of the range (or the only character in it)
+            let start: char = match c {
+                '\\' => {
+                    let Some(escaped) = self.peek() else {
+                        return Err(self.error("nothing to escape after the trailing '\\'"));
+                    };
+                    self.position += 1;
+                    if let Some(class) = self.escaped_class(escaped) {
+                        //negated classes like \D can't be merged into the ranges, so spell them out
+                        if class.negated {
+                            ranges.extend(negate_ranges(&class.ranges));
+                        } else {
+                            ranges.extend(class.ranges);
+                        }
+                        continue;
+                    }
+                    self.escaped_char(escaped)?
+                },
+                c => c,
+            };
+            //a '-' between two characters makes a range, otherwise it's just a '-'
+            if self.peek() == Some('-') && self.chars.get(self.position + 1).is_some_and(|next| *next != ']') {
+                self.position += 1;
+                let mut end: char = self.chars[self.position];
+                self.position += 1;
+                if end == '\\' {
+                    let Some(escaped) = self.peek() else {
+                        return Err(self.error("nothing to escape after the trailing '\\'"));
+                    };
+                    self.position += 1;
+                    end = self.escaped_char(escaped)?;
+                }
+                if end < start {
+                    return Err(self.error(&format!("range '{}-{}' is backwards", start, end)));
+                }
+                ranges.push((start, end));
+            } else {
+                ranges.push((start, start));
+            }
+        }
+
+        return Ok(Node::Class(Class { negated, ranges }, self.case_insensitive));
+    }
+}
+
+/**
+ * returns the ranges of every character that isn't in ranges
+ */
+fn negate_ranges(ranges: &[(char,char)]) -> Vec<(char,char)> {
+    //DATA
+    let mut sorted: Vec<(char,char)> = ranges.to_vec();
+    let mut negated: Vec<(char,char)> = Vec::new();
+    let mut next: u32 = 0; //the first character that might not be in a range
+
+    sorted.sort();
+    for (start, end) in sorted {
+        if (start as u32) > next {
+            negated.extend(char::from_u32(next).zip(char::from_u32(start as u32 - 1)));
+        }
+        next = next.max(end as u32 + 1);
+    }
+    negated.extend(char::from_u32(next).map(|start| (start, char::MAX)));
+
+    return negated;
+}
+
+/**
+ * adds the instructions for node to program
+ */
+fn compile(node: &Node, program: &mut Vec<Inst>) -> Result<(), String> {
+    //error handling
+    if program.len() > MAX_PROGRAM_SIZE {
+        return Err("it's too big, try using fewer or smaller repetitions".to_string());
+    }
+
+    match node {
+        Node::Empty => {},
+        Node::Literal(c, case_insensitive) => program.push(Inst::Char(*c, *case_insensitive)),
+        Node::Any => program.push(Inst::Any),
+        Node::Class(class, case_insensitive) => program.push(Inst::Class(class.clone(), *case_insensitive)),
+        Node::Assert(assertion) => program.push(Inst::Assert(*assertion)),
+        Node::Concat(nodes) => {
+            for node in nodes {
+                compile(node, program)?;
+            }
+        },
+        Node::Alternate(alternatives) => {
+            //each alternative but the last is tried before the ones after it, and jumps to the end when it's done
+            let mut jumps_to_end: Vec<usize> = Vec::new();
+            for (index, alternative) in alternatives.iter().enumerate() {
+                if index + 1 < alternatives.len() {
+                    let split = program.len();
+                    program.push(Inst::Split(split + 1, 0));
+                    compile(alternative, program)?;
+                    jumps_to_end.push(program.len());
+                    program.push(Inst::Jmp(0));
+                    program[split] = Inst::Split(split + 1, program.len());
+                } else {
+                    compile(alternative, program)?;
+                }
+            }
+            let end = program.len();
+            for jump in jumps_to_end {
+                program[jump] = Inst::Jmp(end);
+            }
+        },
+        Node::Group(node, group) => {
+            program.push(Inst::Save(2 * group));
+            compile(node, program)?;
+            program.push(Inst::Save(2 * group + 1));
+        },
+        Node::Repeat { node, min, max, greedy } => {
+            //the required copies
+            for _ in 0..*min {
+                compile(node, program)?;
+            }
+            match max {
+                //then as many more as there can be
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Split(0, 0));
+                    compile(node, program)?;
+                    program.push(Inst::Jmp(split));
+                    let end = program.len();
+                    program[split] = if *greedy {Inst::Split(split + 1, end)} else {Inst::Split(end, split + 1)};
+                },
+                //then up to max - min optional copies, each one only tried if the one before it matched
+                Some(max) => {
+                    let mut splits: Vec<usize> = Vec::new();
+                    for _ in *min..*max {
+                        splits.push(program.len());
+                        program.push(Inst::Split(0, 0));
+                        compile(node, program)?;
+                        if program.len() > MAX_PROGRAM_SIZE {
+                            return Err("it's too big, try using fewer or smaller repetitions".to_string());
+                        }
+                    }
+                    let end = program.len();
+                    for split in splits {
+                        program[split] = if *greedy {Inst::Split(split + 1, end)} else {Inst::Split(end, split + 1)};
+                    }
+                },
+            }
+        },
+    }
+
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * returns the text of every match of pattern in text
+     */
+    fn matched<'a>(pattern: &str, text: &'a str) -> Vec<&'a str> {
+        return Regex::new(pattern).unwrap().find_iter(text).into_iter().map(|range| &text[range]).collect();
+    }
+
+    /**
+     * returns the text each group of pattern matched in its first match in text, None for the groups that didn't take part
+     */
+    fn captured<'a>(pattern: &str, text: &'a str) -> Vec<Option<&'a str>> {
+        return Regex::new(pattern).unwrap().captures_at(text, 0).unwrap().into_iter().map(|group| group.map(|range| &text[range])).collect();
+    }
+
+    /**
+     * returns the error for pattern
+     */
+    fn error(pattern: &str) -> String {
+        return Regex::new(pattern).err().unwrap().to_string();
+    }
+
+    #[test]
+    fn literals_and_classes_match_characters() {
+        assert_eq!(matched("cat", "concat cat"), vec!["cat", "cat"]);
+        assert_eq!(matched("c.t", "cat cot c\nt"), vec!["cat", "cot"]);
+        assert_eq!(matched("[a-c]+", "abcd cab"), vec!["abc", "cab"]);
+        assert_eq!(matched("[^a-c ]+", "abcd cab xyz"), vec!["d", "xyz"]);
+        assert_eq!(matched("[.]", "a.b"), vec!["."]);
+        assert_eq!(matched(r"\.", "a.b"), vec!["."]);
+        assert_eq!(matched("é", "café"), vec!["é"]);
+    }
+
+    #[test]
+    fn escaped_classes_are_digits_words_and_whitespace() {
+        assert_eq!(matched(r"\d+", "a12 b345"), vec!["12", "345"]);
+        assert_eq!(matched(r"\w+", "foo_bar, baz9!"), vec!["foo_bar", "baz9"]);
+        assert_eq!(matched(r"\s+", "a \t b"), vec![" \t "]);
+        assert_eq!(matched(r"\D+", "a12b"), vec!["a", "b"]);
+        assert_eq!(matched(r"\W", "a-b"), vec!["-"]);
+        assert_eq!(matched(r"\S+", " ab  c "), vec!["ab", "c"]);
+        assert_eq!(matched(r"[\d_]+", "a1_2b"), vec!["1_2"]);
+    }
+
+    #[test]
+    fn repetitions_are_greedy_unless_asked_not_to_be() {
+        assert_eq!(matched("ab*", "a ab abbb"), vec!["a", "ab", "abbb"]);
+        assert_eq!(matched("ab+", "a ab abbb"), vec!["ab", "abbb"]);
+        assert_eq!(matched("colou?r", "color colour colouur"), vec!["color", "colour"]);
+        assert_eq!(matched("a{3}", "aa aaa aaaa"), vec!["aaa", "aaa"]);
+        assert_eq!(matched("a{2,}", "a aa aaaaa"), vec!["aa", "aaaaa"]);
+        assert_eq!(matched("a{1,2}", "aaa"), vec!["aa", "a"]);
+        assert_eq!(matched("<.+?>", "<a><b>"), vec!["<a>", "<b>"]);
+        //a { that isn't a repetition is just a character
+        assert_eq!(matched("a{x}", "a{x}"), vec!["a{x}"]);
+    }
+
+    #[test]
+    fn alternation_takes_the_first_that_matches() {
+        assert_eq!(matched("cat|dog", "dog cat bird"), vec!["dog", "cat"]);
+        assert_eq!(matched("a|ab", "ab"), vec!["a"]);
+        assert_eq!(matched("ab|a", "ab"), vec!["ab"]);
+        assert_eq!(matched("x(a|b)+y", "xabay xy"), vec!["xabay"]);
+    }
+
+    #[test]
+    fn groups_capture_what_they_matched() {
+        assert_eq!(captured(r"(\w+)@(\w+)", "mail me@host now"), vec![Some("me@host"), Some("me"), Some("host")]);
+        //a group that didn't take part is None, and a repeated one has its last match
+        assert_eq!(captured("(a)|(b)", "b"), vec![Some("b"), None, Some("b")]);
+        assert_eq!(captured("(?:(x)y)+", "xyxy"), vec![Some("xyxy"), Some("x")]);
+        assert_eq!(Regex::new("(a)(?:b)(c)").unwrap().group_count(), 2);
+    }
+
+    #[test]
+    fn case_can_be_ignored_for_all_or_part_of_a_pattern() {
+        assert_eq!(matched("(?i)hello", "Hello HELLO"), vec!["Hello", "HELLO"]);
+        assert_eq!(matched("(?i:he)llo", "HEllo HELLO"), vec!["HEllo"]);
+        assert_eq!(matched("(?i)[a-c]+", "ABCabc"), vec!["ABCabc"]);
+        assert!(Regex::with_options("hello", true, MatchKind::Substring).unwrap().is_match("HeLLo"));
+        assert!(!Regex::new("hello").unwrap().is_match("HELLO"));
+    }
+
+    #[test]
+    fn line_anchors_hold_only_at_the_ends() {
+        //DATA
+        let start: Regex = Regex::new("^foo").unwrap();
+        let end: Regex = Regex::new("bar$").unwrap();
+
+        assert_eq!(start.find_at("foo foo", 0), Some(0..3));
+        //text before start still counts, so ^ doesn't hold anywhere after 0
+        assert_eq!(start.find_at("foo foo", 1), None);
+        assert_eq!(end.find_at("bar bar", 0), Some(4..7));
+        assert!(!Regex::new("a$b").unwrap().is_match("a$b"));
+    }
+
+    #[test]
+    fn word_boundaries_hold_in_the_middle_of_the_text() {
+        assert_eq!(Regex::new(r"\bbar").unwrap().find_at("foo bar", 0), Some(4..7));
+        assert_eq!(matched(r"\bcat\b", "cat concat cat_ (cat)"), vec!["cat", "cat"]);
+        assert_eq!(Regex::new(r"\Bat").unwrap().find_at("at that", 0), Some(5..7));
+        assert_eq!(matched(r"\B", "ab"), vec![""]);
+        //starting partway through a word, the text before still counts
+        assert_eq!(Regex::new(r"\bbar").unwrap().find_at("foobar bar", 3), Some(7..10));
+    }
+
+    #[test]
+    fn match_kinds_compose_with_assertions_in_the_middle() {
+        //DATA
+        let word: Regex = Regex::with_options("cat", false, MatchKind::Word).unwrap();
+        let line: Regex = Regex::with_options("c.t", false, MatchKind::Line).unwrap();
+        let end: Regex = Regex::with_options("x", false, MatchKind::Anchored { anchor: Anchor::End, word: true, ignore_whitespace: true }).unwrap();
+
+        assert_eq!(word.find_iter("the cat concat cat"), vec![4..7, 15..18]);
+        assert!(line.is_match("cat") && !line.is_match("a cat"));
+        assert_eq!(end.find_at("use x  ", 0), Some(4..7));
+        assert_eq!(end.find_at("use ax", 0), None);
+    }
+
+    #[test]
+    fn parse_errors_say_where_they_are() {
+        assert_eq!(error("*a"), "Invalid regex '*a': nothing to repeat before '*' at position 1.");
+        assert_eq!(error("a|+"), "Invalid regex 'a|+': nothing to repeat before '+' at position 3.");
+        assert_eq!(error("(ab"), "Invalid regex '(ab': unclosed '(' at position 1.");
+        assert_eq!(error("ab)"), "Invalid regex 'ab)': unmatched ')' at position 3.");
+        assert_eq!(error("[ab"), "Invalid regex '[ab': unclosed '[' at position 1.");
+        assert_eq!(error("[z-a]"), "Invalid regex '[z-a]': range 'z-a' is backwards at position 5.");
+        assert_eq!(error("a{3,2}"), "Invalid regex 'a{3,2}': repetition {3,2} has a max smaller than its min at position 2.");
+        assert_eq!(error(r"a\q"), "Invalid regex 'a\\q': unknown escape '\\q' at position 3.");
+        assert_eq!(error("ab\\"), "Invalid regex 'ab\\': nothing to escape after the trailing '\\' at position 4.");
+        assert_eq!(error("(?x)a"), "Invalid regex '(?x)a': unsupported group flag, only (?:...), (?i), and (?i:...) are supported at position 3.");
+        assert_eq!(error("a{1000}{1000}"), "Invalid regex 'a{1000}{1000}': it's too big, try using fewer or smaller repetitions.");
+    }
+}
@@ -0,0 +1,408 @@
+use std::io::{self, Read};
+use std::path::Path;
+
+//tar archives are made of 512 byte blocks, a header for each entry, then its contents padded out to a whole block
+const BLOCK_SIZE: u64 = 512;
+//the most an extended header (a long name, or pax records) can be, they're just names and sizes, so more than this is corrupt
+const MAX_EXTENDED_HEADER_SIZE: u64 = 1 << 20;
+//the extensions of files that are tar archives, compressed or not
+const TAR_EXTENSIONS: [&str; 3] = [".tar", ".tar.gz", ".tgz"];
+
+/**
+ * returns true if path is a tar archive (.tar), or a gzipped one (.tar.gz or .tgz), going by its name
+ */
+pub fn is_tar_path(path: &Path) -> bool {
+    //DATA
+    let name: String = path.file_name().map(|name| name.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+
+    return TAR_EXTENSIONS.iter().any(|extension| name.len() > extension.len() && name.ends_with(extension));
+}
+
+/**
+ * returns true if path is a gzipped tar archive (.tar.gz or .tgz), going by its name
+ */
+pub fn is_gzipped(path: &Path) -> bool {
+    return is_tar_path(path) && path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gz") || extension.eq_ignore_ascii_case("tgz"));
+}
+
+/**
+ * an entry of a tar archive, as its header describes it
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,   //its path in the archive, with / separators
+    pub size: u64,      //how big its contents are
+    kind: u8,           //the type flag from its header
+}
+impl Entry {
+    /**
+     * returns true if the entry is a regular file, rather than a directory, a link, a device, or a fifo
+     */
+    pub fn is_file(&self) -> bool {
+        return matches!(self.kind, 0 | b'0' | b'7') && !self.name.ends_with('/');
+    }
+
+    /**
+     * returns true if the entry is a directory
+     */
+    pub fn is_dir(&self) -> bool {
+        return self.kind == b'5' || self.name.ends_with('/');
+    }
+}
+
+/**
+ * returns the error for a tar archive that doesn't make sense
+ */
+fn invalid(problem: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, format!("corrupt tar archive: {}", problem));
+}
+
+/**
+ * returns the number in a header field, which is octal text (ending in a space or a NUL), or big endian binary if its first bit is set
+ */
+fn number(field: &[u8]) -> io::Result<u64> {
+    if field[0] & 0x80 != 0 {
+        return Ok(field[1..].iter().fold((field[0] & 0x7F) as u64, |number, byte| number << 8 | *byte as u64));
+    }
+
+    //DATA
+    let text: String = String::from_utf8_lossy(field).trim_matches(|c: char| c == ' ' || c == '\0').to_string();
+
+    if text.is_empty() {
+        return Ok(0);
+    }
+    return u64::from_str_radix(&text, 8).map_err(|_| invalid("a header has a number that isn't a number"));
+}
+
+/**
+ * returns the text in a header field, which ends at its first NUL (or the end of the field)
+ */
+fn text(field: &[u8]) -> String {
+    return String::from_utf8_lossy(field.split(|byte| *byte == 0).next().unwrap_or_default()).into_owned();
+}
+
+/**
+ * reads a tar archive from inner in one pass, an entry at a time, without needing to seek (so it can be decompressed as it's read)
+ * reading from it reads the contents of the entry next_entry last returned, and whatever's left of them is skipped by the next call
+ */
+pub struct Archive<R: Read> {
+    inner: R,
+    unread: u64,    //how much of the current entry's contents haven't been read yet
+    padding: u64,   //how much padding comes after them
+    done: bool,     //whether the end of the archive (or an error) has been reached
+}
+impl<R: Read> Archive<R> {
+    pub fn new(inner: R) -> Archive<R> {
+        return Archive { inner, unread: 0, padding: 0, done: false };
+    }
+
+    /**
+     * returns the next entry of the archive, or None at the end of it
+     * once there's been an error, it's the end, since there's no knowing where the next header is
+     */
+    pub fn next_entry(&mut self) -> io::Result<Option<Entry>> {
+        if self.done {
+            return Ok(None);
+        }
+        return self.read_entry().inspect_err(|_| self.done = true);
+    }
+
+    fn read_entry(&mut self) -> io::Result<Option<Entry>> {
+        //DATA
+        //gnu long names and pax records come as entries of their own, just before the entry they're about
+        let mut long_name: Option<String> = None;
+        let mut long_size: Option<u64> = None;
+
+        loop {
+            //DATA
+            let mut header: [u8; BLOCK_SIZE as usize] = [0; BLOCK_SIZE as usize];
+
+            self.skip_rest()?;
+            //the archive ends with two blocks of zeroes, but some just end
+            if !self.read_header(&mut header)? || header.iter().all(|byte| *byte == 0) {
+                self.done = true;
+                return Ok(None);
+            }
+            //the checksum is the sum of the header's bytes, with its own field counted as spaces
+            let checksum: u64 = header.iter().enumerate().map(|(at, byte)| if (148..156).contains(&at) {b' ' as u64} else {*byte as u64}).sum();
+            if number(&header[148..156])? != checksum {
+                return Err(invalid("a header doesn't match its checksum"));
+            }
+            self.start_contents(number(&header[124..136])?);
+
+            match header[156] {
+                b'L' => long_name = Some(self.read_extended_header()?.trim_end_matches('\0').to_string()),
+                b'x' => for record in self.read_extended_header()?.lines() {
+                    //each record is "length key=value"
+                    match record.split_once(' ').and_then(|(_, record)| record.split_once('=')) {
+                        Some(("path", path)) => long_name = Some(path.to_string()),
+                        Some(("size", size)) => long_size = Some(size.parse().map_err(|_| invalid("a pax header has a size that isn't a number"))?),
+                        _ => {},
+                    }
+                },
+                //global pax headers, and long link names, don't matter for searching, they're skipped with the next header
+                b'g' | b'K' => {},
+                kind => {
+                    //DATA
+                    //ustar archives can have the start of a long name in a prefix field
+                    let name: String = match (long_name, &header[257..262] == b"ustar", text(&header[345..500])) {
+                        (Some(name), _, _) => name,
+                        (None, true, prefix) if !prefix.is_empty() => format!("{}/{}", prefix, text(&header[0..100])),
+                        _ => text(&header[0..100]),
+                    };
+
+                    if let Some(size) = long_size {
+                        self.start_contents(size);
+                    }
+                    return Ok(Some(Entry { name, size: self.unread, kind }));
+                },
+            }
+        }
+    }
+
+    /**
+     * reads a header block into header, returns false if the archive ended before it
+     */
+    fn read_header(&mut self, header: &mut [u8]) -> io::Result<bool> {
+        //DATA
+        let mut filled: usize = 0;
+
+        while filled < header.len() {
+            match self.inner.read(&mut header[filled..])? {
+                0 if filled == 0 => return Ok(false),
+                0 => return Err(invalid("it ends in the middle of a header")),
+                read => filled += read,
+            }
+        }
+        return Ok(true);
+    }
+
+    /**
+     * sets up reading the contents of the entry whose header was just read, which are size bytes long, then padded out to a whole block
+     */
+    fn start_contents(&mut self, size: u64) {
+        self.unread = size;
+        self.padding = (BLOCK_SIZE - size % BLOCK_SIZE) % BLOCK_SIZE;
+    }
+
+    /**
+     * returns the contents of an extended header (one that's about the next entry)
+     */
+    fn read_extended_header(&mut self) -> io::Result<String> {
+        //DATA
+        let mut contents: Vec<u8> = Vec::new();
+
+        if self.unread > MAX_EXTENDED_HEADER_SIZE {
+            return Err(invalid("an extended header is too big"));
+        }
+        self.read_to_end(&mut contents)?;
+        return Ok(String::from_utf8_lossy(&contents).into_owned());
+    }
+
+    /**
+     * skips whatever's left of the current entry's contents, and the padding after them
+     */
+    fn skip_rest(&mut self) -> io::Result<()> {
+        //DATA
+        let rest: u64 = self.unread + self.padding;
+
+        if io::copy(&mut (&mut self.inner).take(rest), &mut io::sink())? < rest {
+            return Err(invalid("it ends in the middle of a file"));
+        }
+        (self.unread, self.padding) = (0, 0);
+        return Ok(());
+    }
+}
+impl<R: Read> Read for Archive<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.unread == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+
+        //DATA
+        let limit: usize = buf.len().min(self.unread.min(usize::MAX as u64) as usize);
+        let read: io::Result<usize> = match self.inner.read(&mut buf[..limit]) {
+            Ok(0) => Err(invalid("it ends in the middle of a file")),
+            result => result,
+        };
+
+        match read {
+            Ok(read) => self.unread -= read as u64,
+            Err(_) => self.done = true,
+        }
+        return read;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * returns a ustar header for an entry called name, of the kind, with size bytes of contents
+     */
+    fn header(name: &str, kind: u8, size: usize) -> Vec<u8> {
+        //DATA
+        let mut header: Vec<u8> = vec![0; BLOCK_SIZE as usize];
+
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+        header[156] = kind;
+        header[257..263].copy_from_slice(b"ustar\0");
+        set_checksum(&mut header);
+        return header;
+    }
+
+    /**
+     * fills in the checksum of header, the sum of its bytes with the checksum field counted as spaces
+     */
+    fn set_checksum(header: &mut [u8]) {
+        //DATA
+        let checksum: u32 = header.iter().enumerate().map(|(at, byte)| if (148..156).contains(&at) {b' ' as u32} else {*byte as u32}).sum();
+
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    }
+
+    /**
+     * returns a tar archive of entries, each a header and its contents padded out to a whole block, and then the two blocks of zeroes it ends with
+     */
+    fn archive(entries: &[(Vec<u8>, &[u8])]) -> Vec<u8> {
+        //DATA
+        let mut archive: Vec<u8> = Vec::new();
+
+        for (header, contents) in entries {
+            archive.extend_from_slice(header);
+            archive.extend_from_slice(contents);
+            archive.resize(archive.len().next_multiple_of(BLOCK_SIZE as usize), 0);
+        }
+        archive.resize(archive.len() + 2 * BLOCK_SIZE as usize, 0);
+        return archive;
+    }
+
+    /**
+     * returns a regular file entry for archive
+     */
+    fn file<'a>(name: &str, contents: &'a [u8]) -> (Vec<u8>, &'a [u8]) {
+        return (header(name, b'0', contents.len()), contents);
+    }
+
+    /**
+     * returns the name of each entry of archive, with its contents if it's a regular file
+     */
+    fn read_all(archive: &[u8]) -> io::Result<Vec<(String, Option<String>)>> {
+        //DATA
+        let mut reader: Archive<&[u8]> = Archive::new(archive);
+        let mut entries: Vec<(String, Option<String>)> = Vec::new();
+
+        while let Some(entry) = reader.next_entry()? {
+            //contents that aren't read are skipped by the next call
+            let contents: Option<String> = if entry.is_file() {Some(io::read_to_string(&mut reader)?)} else {None};
+            entries.push((entry.name, contents));
+        }
+        return Ok(entries);
+    }
+
+    #[test]
+    fn tar_paths_go_by_extension() {
+        assert!(is_tar_path(Path::new("backup.tar")));
+        assert!(is_tar_path(Path::new("backup.TAR.GZ")));
+        assert!(is_tar_path(Path::new("dir/backup.tgz")));
+        assert!(!is_tar_path(Path::new(".tar")));
+        assert!(!is_tar_path(Path::new("backup.gz")));
+        assert!(is_gzipped(Path::new("backup.tar.gz")) && is_gzipped(Path::new("backup.tgz")));
+        assert!(!is_gzipped(Path::new("backup.tar")));
+    }
+
+    #[test]
+    fn header_numbers_are_octal_or_binary() {
+        assert_eq!(number(b"00000000017\0").unwrap(), 15);
+        assert_eq!(number(b"     17 ").unwrap(), 15);
+        assert_eq!(number(b"\0\0\0\0").unwrap(), 0);
+        assert_eq!(number(&[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00]).unwrap(), 256);
+        assert!(number(b"0000009\0").is_err());
+    }
+
+    #[test]
+    fn entries_are_read_in_order() {
+        //DATA
+        let archive: Vec<u8> = archive(&[
+            (header("docs/", b'5', 0), b""),
+            file("docs/notes.txt", b"first\nthe needle\nlast\n"),
+            (header("docs/link.txt", b'2', 0), b""),
+            file("readme.md", &[b'x'; 600]),
+        ]);
+
+        assert_eq!(read_all(&archive).unwrap(), vec![
+            ("docs/".to_string(), None),
+            ("docs/notes.txt".to_string(), Some("first\nthe needle\nlast\n".to_string())),
+            ("docs/link.txt".to_string(), None),
+            ("readme.md".to_string(), Some("x".repeat(600))),
+        ]);
+    }
+
+    #[test]
+    fn only_regular_files_are_files() {
+        //DATA
+        let kinds: Vec<(bool, bool)> = [b'0', 0, b'7', b'1', b'2', b'3', b'4', b'5', b'6'].iter()
+            .map(|kind| Entry { name: "a".to_string(), size: 0, kind: *kind })
+            .map(|entry| (entry.is_file(), entry.is_dir()))
+            .collect();
+
+        assert_eq!(kinds, vec![(true, false), (true, false), (true, false), (false, false), (false, false), (false, false), (false, false), (false, true), (false, false)]);
+        //and a name ending in / is a directory, whatever kind it says it is
+        assert!(Entry { name: "a/".to_string(), size: 0, kind: b'0' }.is_dir());
+    }
+
+    #[test]
+    fn long_names_come_from_extended_headers_or_the_prefix() {
+        //DATA
+        let long_name: String = format!("{}/needle.txt", "deep/".repeat(30));
+        let pax_record: String = format!("{} path={}\n", long_name.len() + 11, long_name);
+        let mut prefixed: Vec<u8> = header("needle.txt", b'0', 3);
+
+        prefixed[345..357].copy_from_slice(b"project/src\0");
+        set_checksum(&mut prefixed);
+        let archive: Vec<u8> = archive(&[
+            (header("././@LongLink", b'L', long_name.len() + 1), format!("{}\0", long_name).as_bytes()),
+            file("deep/deep/cut", b"gnu"),
+            (header("PaxHeaders/needle.txt", b'x', pax_record.len()), pax_record.as_bytes()),
+            file("deep/deep/cut", b"pax"),
+            (prefixed, b"ust"),
+        ]);
+
+        assert_eq!(read_all(&archive).unwrap(), vec![
+            (long_name.clone(), Some("gnu".to_string())),
+            (long_name, Some("pax".to_string())),
+            ("project/src/needle.txt".to_string(), Some("ust".to_string())),
+        ]);
+    }
+
+    #[test]
+    fn archives_that_just_end_are_fine() {
+        //DATA
+        let mut archive: Vec<u8> = archive(&[file("a.txt", b"needle\n")]);
+
+        archive.truncate(2 * BLOCK_SIZE as usize);
+        assert_eq!(read_all(&archive).unwrap(), vec![("a.txt".to_string(), Some("needle\n".to_string()))]);
+        assert_eq!(read_all(b"").unwrap(), vec![]);
+    }
+
+    #[test]
+    fn corrupt_archives_end_with_an_error() {
+        //DATA
+        let good: Vec<u8> = archive(&[file("a.txt", b"needle\n"), file("b.txt", b"needle\n")]);
+        let mut bad_checksum: Vec<u8> = good.clone();
+        let mut reader: Archive<&[u8]>;
+
+        bad_checksum[BLOCK_SIZE as usize * 2] = b'c';
+        reader = Archive::new(&bad_checksum);
+        assert_eq!(reader.next_entry().unwrap().unwrap().name, "a.txt");
+        assert_eq!(reader.next_entry().err().unwrap().to_string(), "corrupt tar archive: a header doesn't match its checksum");
+        //there's no knowing where the next header is after that
+        assert!(reader.next_entry().unwrap().is_none());
+
+        assert_eq!(read_all(&good[..100]).err().unwrap().to_string(), "corrupt tar archive: it ends in the middle of a header");
+        assert_eq!(read_all(&good[..BLOCK_SIZE as usize + 3]).err().unwrap().to_string(), "corrupt tar archive: it ends in the middle of a file");
+        assert_eq!(read_all(&good[..BLOCK_SIZE as usize + 10]).err().unwrap().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,197 @@
+use std::error::Error;  //allows for some better errors
+
+/**
+ * something about a result that a template can print
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Path,       //{path}, the file the result is from
+    Line,       //{line}, the line number, starting from 1
+    Column,     //{column}, the column of the (first) match, in characters, starting from 1
+    ByteOffset, //{byte_offset}, the byte offset of the (first) match, from the start of the file
+    Text,       //{text}, the whole line
+    Match,      //{match}, the matched text (with --replace, its replacement)
+    Count,      //{count}, how many matches there are on the line (or in the file, when listing files)
+}
+impl Field {
+    //every field, by the name it has in a template
+    const NAMES: [(&'static str, Field); 7] = [
+        ("path", Field::Path), ("line", Field::Line), ("column", Field::Column), ("byte_offset", Field::ByteOffset),
+        ("text", Field::Text), ("match", Field::Match), ("count", Field::Count),
+    ];
+
+    /**
+     * returns the field with this name, if there is one
+     */
+    fn from_name(name: &str) -> Option<Field> {
+        return Field::NAMES.iter().find(|(field_name, _)| *field_name == name).map(|(_, field)| *field);
+    }
+}
+
+/**
+ * a piece of a compiled template
+ */
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/**
+ * a compiled --format template, like '{path}\t{line}\t{text}', that each result is printed with
+ * placeholders are names in braces, \t and \n are a tab and a line break, \\ is a backslash, and {{ and }} are literal braces
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+impl Template {
+    pub fn new(template: &str) -> Result<Template, Box<dyn Error>> {
+        //DATA
+        let mut parts: Vec<Part> = Vec::new();
+        let mut literal: String = String::new();
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('\\') => literal.push('\\'),
+                    Some(other) => return Err(format!("Invalid --format template '{}': unknown escape '\\{}', expected \\t, \\n, or \\\\.", template, other).into()),
+                    None => return Err(format!("Invalid --format template '{}': nothing to escape after the trailing '\\'.", template).into()),
+                },
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                },
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                },
+                '}' => return Err(format!("Invalid --format template '{}': unmatched '}}', use '}}}}' for a literal one.", template).into()),
+                '{' => {
+                    //DATA
+                    let mut name: String = String::new();
+                    let mut closed: bool = false;
+
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+                    if !closed {
+                        return Err(format!("Invalid --format template '{}': the '{{{}' is never closed, use '{{{{' for a literal '{{'.", template, name).into());
+                    }
+                    match Field::from_name(&name) {
+                        Some(field) => {
+                            if !literal.is_empty() {
+                                parts.push(Part::Literal(std::mem::take(&mut literal)));
+                            }
+                            parts.push(Part::Field(field));
+                        },
+                        None => return Err(format!("Invalid --format template '{}': unknown placeholder '{{{}}}', expected one of {}.", template, name,
+                            Field::NAMES.iter().map(|(field_name, _)| format!("{{{}}}", field_name)).collect::<Vec<String>>().join(", ")).into()),
+                    }
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        //return
+        return Ok(Template { parts });
+    }
+
+    /**
+     * returns true if the template has a placeholder for field
+     */
+    pub fn uses(&self, field: Field) -> bool {
+        return self.parts.contains(&Part::Field(field));
+    }
+
+    /**
+     * returns the template filled in, with value giving what each placeholder is replaced by
+     */
+    pub fn render<F: Fn(Field) -> String>(&self, value: F) -> String {
+        return self.parts.iter().map(|part| match part {
+            Part::Literal(text) => text.clone(),
+            Part::Field(field) => value(*field),
+        }).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn placeholders_and_literals_are_split_up() {
+        //DATA
+        let template: Template = Template::new("{path}:{line} - {text}").unwrap();
+
+        assert_eq!(template.parts, vec![Part::Field(Field::Path), Part::Literal(":".to_string()), Part::Field(Field::Line), Part::Literal(" - ".to_string()), Part::Field(Field::Text)]);
+    }
+
+    #[test]
+    fn every_field_has_a_placeholder() {
+        for (name, field) in Field::NAMES {
+            assert!(Template::new(&format!("{{{}}}", name)).unwrap().uses(field), "{}", name);
+        }
+        assert!(!Template::new("{path}").unwrap().uses(Field::Count));
+    }
+
+    #[test]
+    fn escapes_and_doubled_braces_are_literal() {
+        //DATA
+        let template: Template = Template::new(r"a\tb\nc\\d{{e}}").unwrap();
+
+        assert_eq!(template.parts, vec![Part::Literal("a\tb\nc\\d{e}".to_string())]);
+        //{{path}} is a literal {path}, not a placeholder
+        assert_eq!(Template::new("{{path}}").unwrap().parts, vec![Part::Literal("{path}".to_string())]);
+        assert_eq!(Template::new("{{{path}}}").unwrap().parts, vec![Part::Literal("{".to_string()), Part::Field(Field::Path), Part::Literal("}".to_string())]);
+    }
+
+    #[test]
+    fn an_empty_template_has_nothing_in_it() {
+        assert!(Template::new("").unwrap().parts.is_empty());
+    }
+
+    #[test]
+    fn unknown_placeholders_are_named() {
+        //DATA
+        let error: String = Template::new("{path} {nope}").unwrap_err().to_string();
+
+        assert!(error.contains("unknown placeholder '{nope}'"), "{}", error);
+        assert!(error.contains("expected one of {path}, {line}, {column}, {byte_offset}, {text}, {match}, {count}."), "{}", error);
+        assert!(Template::new("{}").unwrap_err().to_string().contains("unknown placeholder '{}'"));
+        //names are exact
+        assert!(Template::new("{Path}").is_err());
+        assert!(Template::new("{ path }").is_err());
+    }
+
+    #[test]
+    fn bad_escapes_and_unbalanced_braces_are_errors() {
+        assert!(Template::new(r"\x").unwrap_err().to_string().contains("unknown escape '\\x'"));
+        assert!(Template::new("trailing\\").unwrap_err().to_string().contains("nothing to escape after the trailing '\\'"));
+        assert!(Template::new("{path").unwrap_err().to_string().contains("the '{path' is never closed"));
+        assert!(Template::new("path}").unwrap_err().to_string().contains("unmatched '}'"));
+    }
+
+    #[test]
+    fn rendering_fills_in_each_placeholder() {
+        //DATA
+        let template: Template = Template::new(r"{path}\t{line}\t{path}").unwrap();
+        let value = |field: Field| match field {
+            Field::Path => "a.txt".to_string(),
+            Field::Line => "3".to_string(),
+            _ => unreachable!(),
+        };
+
+        assert_eq!(template.render(value), "a.txt\t3\ta.txt");
+    }
+}
@@ -0,0 +1,187 @@
+use std::error::Error;  //allows for some better errors
+
+use crate::glob::Glob;
+use crate::levenshtein;
+
+//the built in file types, and the globs for the names of the files that are that type
+const BUILTIN_TYPES: [(&str, &[&str]); 23] = [
+    ("c", &["*.c", "*.h"]),
+    ("cpp", &["*.cpp", "*.cc", "*.cxx", "*.hpp", "*.hh", "*.hxx", "*.h"]),
+    ("csharp", &["*.cs"]),
+    ("css", &["*.css", "*.scss", "*.sass", "*.less"]),
+    ("go", &["*.go"]),
+    ("html", &["*.html", "*.htm"]),
+    ("java", &["*.java"]),
+    ("js", &["*.js", "*.jsx", "*.mjs", "*.cjs"]),
+    ("json", &["*.json"]),
+    ("make", &["Makefile", "makefile", "GNUmakefile", "*.mk"]),
+    ("markdown", &["*.md", "*.markdown"]),
+    ("py", &["*.py", "*.pyi"]),
+    ("ruby", &["*.rb"]),
+    ("rust", &["*.rs"]),
+    ("sh", &["*.sh", "*.bash", "*.zsh"]),
+    ("sql", &["*.sql"]),
+    ("toml", &["*.toml"]),
+    ("ts", &["*.ts", "*.tsx"]),
+    ("txt", &["*.txt"]),
+    ("web", &["*.html", "*.htm", "*.css", "*.js"]),
+    ("xml", &["*.xml"]),
+    ("yaml", &["*.yaml", "*.yml"]),
+    ("zig", &["*.zig"]),
+];
+
+/**
+ * the file types -t and -T can use, by name, each with the globs for the names of the files that are that type
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeTable {
+    types: Vec<(String, Vec<String>)>,  //sorted by name
+}
+impl TypeTable {
+    /**
+     * returns the table of the built in types
+     */
+    pub fn new() -> TypeTable {
+        return TypeTable { types: BUILTIN_TYPES.iter().map(|(name, globs)| (name.to_string(), globs.iter().map(|glob| glob.to_string()).collect())).collect() };
+    }
+
+    /**
+     * adds a type from a definition like "proto:*.proto", a definition for a type that's already in the table adds the glob to it
+     */
+    pub fn add(&mut self, definition: &str) -> Result<(), Box<dyn Error>> {
+        //DATA
+        let Some((name, glob)) = definition.split_once(':') else {
+            return Err(format!("Invalid type definition '{}', expected NAME:GLOB, like 'proto:*.proto'.", definition).into());
+        };
+
+        //error handling
+        if name.is_empty() {
+            return Err(format!("Invalid type definition '{}': the type name is empty.", definition).into());
+        }
+        if glob.is_empty() {
+            return Err(format!("Invalid type definition '{}': the glob is empty.", definition).into());
+        }
+        //globs are matched against file names, like --include
+        if glob.contains('/') {
+            return Err(format!("Invalid type definition '{}': globs are matched against file names, so they can't contain directory separators.", definition).into());
+        }
+        Glob::new(glob)?;
+
+        //add the glob to the type, or add the type (keeping the table sorted) if it's new
+        match self.types.binary_search_by(|(type_name, _)| type_name.as_str().cmp(name)) {
+            Ok(index) => if !self.types[index].1.iter().any(|existing| existing == glob) {
+                self.types[index].1.push(glob.to_string());
+            },
+            Err(index) => self.types.insert(index, (name.to_string(), vec![glob.to_string()])),
+        }
+        return Ok(());
+    }
+
+    /**
+     * returns the globs for the type with this name, throwing an error (suggesting the closest type) if there isn't one
+     */
+    pub fn globs(&self, name: &str) -> Result<&[String], Box<dyn Error>> {
+        if let Some((_, globs)) = self.types.iter().find(|(type_name, _)| type_name == name) {
+            return Ok(globs);
+        }
+        return Err(match self.closest(name) {
+            Some(closest) => format!("Unknown file type '{}', did you mean '{}'? --type-list shows them all.", name, closest),
+            None => format!("Unknown file type '{}', --type-list shows them all.", name),
+        }.into());
+    }
+
+    /**
+     * returns the name of the type closest to name, as long as it's close enough to plausibly be a typo
+     */
+    fn closest(&self, name: &str) -> Option<&str> {
+        //DATA
+        let max_distance = 2.max(name.chars().count() / 3);
+
+        return self.types.iter()
+            .map(|(type_name, _)| (levenshtein(name, type_name), type_name.as_str()))
+            .filter(|(distance,_)| *distance <= max_distance)
+            .min_by_key(|(distance,_)| *distance)
+            .map(|(_,type_name)| type_name);
+    }
+
+    /**
+     * returns every type, with its globs, in order of name
+     */
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &[String])> {
+        return self.types.iter().map(|(name, globs)| (name.as_str(), globs.as_slice()));
+    }
+}
+impl Default for TypeTable {
+    fn default() -> Self {
+        return TypeTable::new();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn built_in_types_have_globs() {
+        //DATA
+        let table = TypeTable::new();
+
+        assert_eq!(table.globs("rust").unwrap(), ["*.rs"]);
+        assert_eq!(table.globs("web").unwrap(), ["*.html", "*.htm", "*.css", "*.js"]);
+    }
+
+    #[test]
+    fn built_in_types_are_sorted_by_name() {
+        //DATA
+        let table = TypeTable::new();
+        let names: Vec<&str> = table.iter().map(|(name, _)| name).collect();
+        let mut sorted: Vec<&str> = names.clone();
+
+        sorted.sort();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn unknown_types_suggest_the_closest_one() {
+        //DATA
+        let table = TypeTable::new();
+
+        assert_eq!(table.globs("rusty").unwrap_err().to_string(), "Unknown file type 'rusty', did you mean 'rust'? --type-list shows them all.");
+        assert_eq!(table.globs("zzzzzzzz").unwrap_err().to_string(), "Unknown file type 'zzzzzzzz', --type-list shows them all.");
+    }
+
+    #[test]
+    fn added_types_are_kept_in_order() {
+        //DATA
+        let mut table = TypeTable::new();
+
+        table.add("proto:*.proto").unwrap();
+        table.add("aaa:*.aaa").unwrap();
+        assert_eq!(table.globs("proto").unwrap(), ["*.proto"]);
+        assert_eq!(table.iter().next(), Some(("aaa", ["*.aaa".to_string()].as_slice())));
+    }
+
+    #[test]
+    fn added_globs_extend_a_type_once() {
+        //DATA
+        let mut table = TypeTable::new();
+
+        table.add("rust:*.rs.in").unwrap();
+        table.add("rust:*.rs.in").unwrap();
+        table.add("rust:*.rs").unwrap();
+        assert_eq!(table.globs("rust").unwrap(), ["*.rs", "*.rs.in"]);
+    }
+
+    #[test]
+    fn bad_definitions_are_errors() {
+        //DATA
+        let mut table = TypeTable::new();
+
+        assert_eq!(table.add("proto").unwrap_err().to_string(), "Invalid type definition 'proto', expected NAME:GLOB, like 'proto:*.proto'.");
+        assert_eq!(table.add(":*.x").unwrap_err().to_string(), "Invalid type definition ':*.x': the type name is empty.");
+        assert_eq!(table.add("proto:").unwrap_err().to_string(), "Invalid type definition 'proto:': the glob is empty.");
+        assert!(table.add("proto:src/*.proto").unwrap_err().to_string().contains("can't contain directory separators"));
+        assert!(table.add("proto:[*.proto").is_err());
+        assert_eq!(table, TypeTable::new());
+    }
+}
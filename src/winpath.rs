@@ -0,0 +1,157 @@
+use std::borrow::Cow;
+
+//the prefix that tells windows to use a path as is, without parsing it (or holding it to the max path length)
+pub const VERBATIM_PREFIX: &str = r"\\?\";
+//a verbatim path to a network share, like \\?\UNC\server\share
+const VERBATIM_UNC_PREFIX: &str = r"\\?\UNC\";
+//how long paths can be on windows, without the verbatim prefix
+pub const MAX_PATH: usize = 260;
+
+/**
+ * returns the verbatim form of an absolute windows path (like C:\dir, or \\server\share\dir), which can be longer than MAX_PATH
+ * returns None for relative paths, and paths with . or .. in them, since windows won't resolve those in a verbatim path
+ * paths that are already verbatim are returned as is
+ */
+pub fn to_verbatim(path: &str) -> Option<String> {
+    if path.starts_with(VERBATIM_PREFIX) {
+        return Some(path.to_string());
+    }
+
+    //DATA
+    //verbatim paths can only use backslashes
+    let path: String = path.replace('/', "\\");
+    let bytes: &[u8] = path.as_bytes();
+
+    if path.split('\\').any(|component| component == "." || component == "..") {
+        return None;
+    }
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return Some(format!("{}{}", VERBATIM_UNC_PREFIX, share));
+    }
+    if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' {
+        return Some(format!("{}{}", VERBATIM_PREFIX, path));
+    }
+    return None;
+}
+
+/**
+ * returns path without its verbatim prefix (like canonicalizing adds), as long as it means the same thing without it
+ * \\?\C:\dir becomes C:\dir, and \\?\UNC\server\share becomes \\server\share, anything else (or anything too long without it) is returned as is
+ */
+pub fn strip_verbatim(path: &str) -> Cow<'_, str> {
+    //without the prefix, the path has to fit in the max path length again
+    if let Some(share) = path.strip_prefix(VERBATIM_UNC_PREFIX) {
+        if share.len() + 2 < MAX_PATH {
+            return Cow::Owned(format!(r"\\{}", share));
+        }
+    } else if let Some(rest) = path.strip_prefix(VERBATIM_PREFIX) {
+        //DATA
+        let bytes: &[u8] = rest.as_bytes();
+
+        if bytes.len() >= 3 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':' && bytes[2] == b'\\' && rest.len() < MAX_PATH {
+            return Cow::Borrowed(rest);
+        }
+    }
+    return Cow::Borrowed(path);
+}
+
+/**
+ * returns the separator the user typed path with, / or \, or None if it doesn't have one (or has both)
+ */
+pub fn typed_separator(path: &str) -> Option<char> {
+    return match (path.contains('/'), path.contains('\\')) {
+        (true, false) => Some('/'),
+        (false, true) => Some('\\'),
+        _ => None,
+    };
+}
+
+/**
+ * returns path with every separator (/ or \) swapped for separator
+ * verbatim paths are returned as is, the only separator they can have is \
+ */
+pub fn with_separators(path: &str, separator: char) -> Cow<'_, str> {
+    if path.starts_with(VERBATIM_PREFIX) || !path.contains(['/', '\\']) {
+        return Cow::Borrowed(path);
+    }
+    return Cow::Owned(path.chars().map(|c| if c == '/' || c == '\\' {separator} else {c}).collect());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drive_paths_become_verbatim() {
+        assert_eq!(to_verbatim(r"C:\dir\file.txt").unwrap(), r"\\?\C:\dir\file.txt");
+        assert_eq!(to_verbatim("C:/dir/file.txt").unwrap(), r"\\?\C:\dir\file.txt");
+    }
+
+    #[test]
+    fn shares_become_verbatim_unc_paths() {
+        assert_eq!(to_verbatim(r"\\server\share\dir").unwrap(), r"\\?\UNC\server\share\dir");
+        assert_eq!(to_verbatim("//server/share/dir").unwrap(), r"\\?\UNC\server\share\dir");
+    }
+
+    #[test]
+    fn verbatim_paths_stay_as_they_are() {
+        assert_eq!(to_verbatim(r"\\?\C:\dir").unwrap(), r"\\?\C:\dir");
+        assert_eq!(to_verbatim(r"\\?\UNC\server\share").unwrap(), r"\\?\UNC\server\share");
+    }
+
+    #[test]
+    fn relative_and_dotted_paths_cant_be_verbatim() {
+        assert_eq!(to_verbatim(r"dir\file.txt"), None);
+        assert_eq!(to_verbatim(r"\dir\file.txt"), None);
+        assert_eq!(to_verbatim("C:dir"), None);
+        assert_eq!(to_verbatim(r"C:\dir\..\file.txt"), None);
+        assert_eq!(to_verbatim(r"C:\.\file.txt"), None);
+        assert_eq!(to_verbatim(r"\\server\share\..\other"), None);
+    }
+
+    #[test]
+    fn stripping_undoes_to_verbatim() {
+        for path in [r"C:\dir\file.txt", r"\\server\share\dir"] {
+            assert_eq!(strip_verbatim(&to_verbatim(path).unwrap()), path);
+        }
+    }
+
+    #[test]
+    fn stripping_leaves_other_paths_alone() {
+        for path in [r"C:\dir", r"\\server\share", r"dir\file.txt", r"\\?\Volume{1234}\dir", r"\\?\C:"] {
+            assert!(matches!(strip_verbatim(path), Cow::Borrowed(p) if p == path));
+        }
+    }
+
+    #[test]
+    fn paths_too_long_without_the_prefix_keep_it() {
+        //DATA
+        let long_drive: String = format!(r"\\?\C:\{}", "d".repeat(MAX_PATH));
+        let long_share: String = format!(r"\\?\UNC\server\{}", "s".repeat(MAX_PATH));
+        let short_enough: String = format!(r"\\?\C:\{}", "d".repeat(MAX_PATH - 4));
+
+        assert_eq!(strip_verbatim(&long_drive), long_drive);
+        assert_eq!(strip_verbatim(&long_share), long_share);
+        assert_eq!(strip_verbatim(&short_enough).len(), MAX_PATH - 1);
+    }
+
+    #[test]
+    fn typed_separator_needs_exactly_one_kind() {
+        assert_eq!(typed_separator("dir/file.txt"), Some('/'));
+        assert_eq!(typed_separator(r"dir\file.txt"), Some('\\'));
+        assert_eq!(typed_separator(r"dir/sub\file.txt"), None);
+        assert_eq!(typed_separator("file.txt"), None);
+    }
+
+    #[test]
+    fn separators_are_swapped_for_the_typed_one() {
+        assert_eq!(with_separators(r"dir/sub\file.txt", '/'), "dir/sub/file.txt");
+        assert_eq!(with_separators(r"dir/sub\file.txt", '\\'), r"dir\sub\file.txt");
+        assert!(matches!(with_separators("file.txt", '/'), Cow::Borrowed("file.txt")));
+    }
+
+    #[test]
+    fn verbatim_paths_keep_their_backslashes() {
+        assert_eq!(with_separators(r"\\?\C:\dir\file.txt", '/'), r"\\?\C:\dir\file.txt");
+    }
+}
@@ -0,0 +1,315 @@
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::gzip::crc32;
+use crate::inflate::Inflate;
+
+//the signatures the records of a zip archive start with
+const END_SIGNATURE: u32 = 0x06054B50;
+const CENTRAL_SIGNATURE: u32 = 0x02014B50;
+const LOCAL_SIGNATURE: u32 = 0x04034B50;
+//the end record is 22 bytes, and can be followed by a comment of up to 64K
+const END_SIZE: u64 = 22;
+const MAX_COMMENT_SIZE: u64 = 0xFFFF;
+//the extra field with the real sizes and offset, when they don't fit in the regular ones
+const ZIP64_EXTRA: u16 = 0x0001;
+//the extensions of files that are zip archives, whatever they're called (java archives, office documents, ebooks)
+const ZIP_EXTENSIONS: [&str; 11] = ["zip", "jar", "war", "ear", "apk", "docx", "xlsx", "pptx", "odt", "ods", "epub"];
+
+/**
+ * returns true if path is a zip archive, going by its name
+ */
+pub fn is_zip_path(path: &Path) -> bool {
+    return path.extension().is_some_and(|extension| ZIP_EXTENSIONS.iter().any(|zip| extension.eq_ignore_ascii_case(zip)));
+}
+
+/**
+ * a file in a zip archive, as its central directory describes it
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,       //its path in the archive, with / separators
+    pub size: u64,          //how big it is, decompressed
+    pub encrypted: bool,
+    method: u16,            //0 for stored, 8 for deflated
+    crc: u32,
+    compressed_size: u64,
+    header_offset: u64,     //where its local header is in the archive
+}
+impl Entry {
+    /**
+     * returns true if the entry is a directory, rather than a file
+     */
+    pub fn is_dir(&self) -> bool {
+        return self.name.ends_with('/');
+    }
+}
+
+/**
+ * returns the error for a zip archive that doesn't make sense
+ */
+fn invalid(problem: &str) -> io::Error {
+    return io::Error::new(io::ErrorKind::InvalidData, format!("corrupt zip archive: {}", problem));
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    return u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    return u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]]);
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    return u32_at(bytes, at) as u64 | (u32_at(bytes, at + 4) as u64) << 32;
+}
+
+/**
+ * returns the entries of the zip archive, from its central directory at the end
+ */
+pub fn read_entries<R: Read + Seek>(archive: &mut R) -> io::Result<Vec<Entry>> {
+    //DATA
+    let length: u64 = archive.seek(SeekFrom::End(0))?;
+    let tail_length: u64 = length.min(END_SIZE + MAX_COMMENT_SIZE);
+    let mut tail: Vec<u8> = vec![0; tail_length as usize];
+
+    //the end record is the last thing in the archive, except for its comment, which can't be found without finding the end first
+    archive.seek(SeekFrom::Start(length - tail_length))?;
+    archive.read_exact(&mut tail)?;
+    if tail_length < END_SIZE {
+        return Err(invalid("it's too short to be a zip archive"));
+    }
+    let Some(end) = (0..=tail.len() - END_SIZE as usize).rev().find(|at| u32_at(&tail, *at) == END_SIGNATURE) else {
+        return Err(invalid("it doesn't have a central directory"));
+    };
+    let entry_count: u16 = u16_at(&tail, end + 10);
+    let directory_size: u32 = u32_at(&tail, end + 12);
+    let directory_offset: u32 = u32_at(&tail, end + 16);
+    if entry_count == 0xFFFF || directory_offset == 0xFFFFFFFF {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "zip64 archives with this many entries (or this big) aren't supported"));
+    }
+    if directory_offset as u64 + directory_size as u64 > length {
+        return Err(invalid("the central directory is past the end of the archive"));
+    }
+    let mut directory: Vec<u8> = vec![0; directory_size as usize];
+    archive.seek(SeekFrom::Start(directory_offset as u64))?;
+    archive.read_exact(&mut directory)?;
+
+    //each entry is 46 bytes, then its name, extra fields, and comment
+    let mut entries: Vec<Entry> = Vec::with_capacity(entry_count as usize);
+    let mut at: usize = 0;
+    for _ in 0..entry_count {
+        if at + 46 > directory.len() || u32_at(&directory, at) != CENTRAL_SIGNATURE {
+            return Err(invalid("an entry in the central directory is cut off, or missing"));
+        }
+        //DATA
+        let name_length: usize = u16_at(&directory, at + 28) as usize;
+        let extra_length: usize = u16_at(&directory, at + 30) as usize;
+        let comment_length: usize = u16_at(&directory, at + 32) as usize;
+        if at + 46 + name_length + extra_length + comment_length > directory.len() {
+            return Err(invalid("an entry in the central directory is cut off"));
+        }
+        let name: String = String::from_utf8_lossy(&directory[at + 46..at + 46 + name_length]).into_owned();
+        let mut entry = Entry {
+            name,
+            size: u32_at(&directory, at + 24) as u64,
+            encrypted: u16_at(&directory, at + 8) & 1 == 1,
+            method: u16_at(&directory, at + 10),
+            crc: u32_at(&directory, at + 16),
+            compressed_size: u32_at(&directory, at + 20) as u64,
+            header_offset: u32_at(&directory, at + 42) as u64,
+        };
+
+        //sizes and offsets too big for 32 bits are in a zip64 extra field instead, in this order, but only the ones that didn't fit
+        let mut extra: &[u8] = &directory[at + 46 + name_length..at + 46 + name_length + extra_length];
+        while extra.len() >= 4 {
+            //DATA
+            let (id, length) = (u16_at(extra, 0), u16_at(extra, 2) as usize);
+            let mut field: &[u8] = &extra[4..(4 + length).min(extra.len())];
+
+            if id == ZIP64_EXTRA {
+                for value in [&mut entry.size, &mut entry.compressed_size, &mut entry.header_offset] {
+                    if *value == 0xFFFFFFFF && field.len() >= 8 {
+                        *value = u64_at(field, 0);
+                        field = &field[8..];
+                    }
+                }
+            }
+            extra = &extra[(4 + length).min(extra.len())..];
+        }
+        entries.push(entry);
+        at += 46 + name_length + extra_length + comment_length;
+    }
+
+    //return
+    return Ok(entries);
+}
+
+/**
+ * returns a reader of what entry decompresses to, which fails if that doesn't match its checksum
+ */
+pub fn open_entry<'a, R: Read + Seek>(archive: &'a mut R, entry: &Entry) -> io::Result<Box<dyn Read + 'a>> {
+    //DATA
+    let mut header: [u8; 30] = [0; 30];
+
+    if entry.encrypted {
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "it's encrypted"));
+    }
+    //the local header has its own name and extra field, which can be different from the central directory's, the data is after them
+    archive.seek(SeekFrom::Start(entry.header_offset))?;
+    archive.read_exact(&mut header).map_err(|_| invalid("an entry's header is cut off"))?;
+    if u32_at(&header, 0) != LOCAL_SIGNATURE {
+        return Err(invalid("an entry isn't where the central directory says it is"));
+    }
+    archive.seek(SeekFrom::Current(u16_at(&header, 26) as i64 + u16_at(&header, 28) as i64))?;
+    let data = archive.take(entry.compressed_size);
+
+    //return
+    return match entry.method {
+        0 => Ok(Box::new(Checked { inner: data, crc: 0, size: 0, entry_crc: entry.crc, entry_size: entry.size })),
+        8 => Ok(Box::new(Checked { inner: Inflate::new(BufReader::new(data)), crc: 0, size: 0, entry_crc: entry.crc, entry_size: entry.size })),
+        method => Err(io::Error::new(io::ErrorKind::Unsupported, format!("it's compressed with a method (number {}) other than deflate", method))),
+    };
+}
+
+/**
+ * reads from inner, and checks what it read against the entry's checksum and size once it runs out
+ */
+struct Checked<R: Read> {
+    inner: R,
+    crc: u32,       //the crc-32 of what's been read so far
+    size: u64,      //how much has been read so far
+    entry_crc: u32,
+    entry_size: u64,
+}
+impl<R: Read> Read for Checked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        //DATA
+        let read: usize = self.inner.read(buf)?;
+
+        if read == 0 && !buf.is_empty() && (self.crc != self.entry_crc || self.size != self.entry_size) {
+            return Err(invalid("an entry doesn't match its checksum"));
+        }
+        self.crc = crc32(self.crc, &buf[..read]);
+        self.size += read as u64;
+        return Ok(read);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    //a zip archive with a docs/ directory, docs/notes.txt ("first\nthe needle\nlast\n", deflated), readme.md ("no match here\nneedle\n", stored),
+    //and secret.txt ("needle\n", stored, but marked as encrypted), made with python's zipfile
+    const ARCHIVE: [u8; 452] = [
+        0x50, 0x4B, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x50, 0x4B, 0x03, 0x04, 0x14,
+        0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x50, 0x3D, 0x14, 0x09, 0x59, 0x16, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00,
+        0x00, 0x0E, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x6E, 0x6F, 0x74, 0x65, 0x73, 0x2E, 0x74, 0x78, 0x74, 0x4B,
+        0xCB, 0x2C, 0x2A, 0x2E, 0xE1, 0x2A, 0xC9, 0x48, 0x55, 0xC8, 0x4B, 0x4D, 0x4D, 0xC9, 0x49, 0xE5, 0xCA, 0x49, 0x04, 0xF2,
+        0x01, 0x50, 0x4B, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xA8, 0x1E, 0xD1, 0x95, 0x15,
+        0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x72, 0x65, 0x61, 0x64, 0x6D, 0x65, 0x2E, 0x6D, 0x64,
+        0x6E, 0x6F, 0x20, 0x6D, 0x61, 0x74, 0x63, 0x68, 0x20, 0x68, 0x65, 0x72, 0x65, 0x0A, 0x6E, 0x65, 0x65, 0x64, 0x6C, 0x65,
+        0x0A, 0x50, 0x4B, 0x03, 0x04, 0x14, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xFE, 0xAA, 0xD8, 0x42, 0x07,
+        0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x73, 0x65, 0x63, 0x72, 0x65, 0x74, 0x2E, 0x74, 0x78,
+        0x74, 0x6E, 0x65, 0x65, 0x64, 0x6C, 0x65, 0x0A, 0x50, 0x4B, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x21, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0xED, 0x41, 0x00, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x50,
+        0x4B, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x50, 0x3D, 0x14, 0x09, 0x59, 0x16,
+        0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00, 0x0E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA4,
+        0x81, 0x23, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x6E, 0x6F, 0x74, 0x65, 0x73, 0x2E, 0x74, 0x78, 0x74, 0x50,
+        0x4B, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xA8, 0x1E, 0xD1, 0x95, 0x15,
+        0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA4,
+        0x81, 0x65, 0x00, 0x00, 0x00, 0x72, 0x65, 0x61, 0x64, 0x6D, 0x65, 0x2E, 0x6D, 0x64, 0x50, 0x4B, 0x01, 0x02, 0x14, 0x03,
+        0x14, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xFE, 0xAA, 0xD8, 0x42, 0x07, 0x00, 0x00, 0x00, 0x07, 0x00,
+        0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA4, 0x81, 0xA1, 0x00, 0x00, 0x00,
+        0x73, 0x65, 0x63, 0x72, 0x65, 0x74, 0x2E, 0x74, 0x78, 0x74, 0x50, 0x4B, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00,
+        0x04, 0x00, 0xDE, 0x00, 0x00, 0x00, 0xD0, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    //where readme.md's data starts, and where the central directory starts
+    const README_DATA: usize = 140;
+    const DIRECTORY: usize = 208;
+
+    /**
+     * returns what the entry called name in archive decompresses to
+     */
+    fn read_entry(archive: &[u8], name: &str) -> io::Result<String> {
+        //DATA
+        let mut archive: Cursor<&[u8]> = Cursor::new(archive);
+        let entries: Vec<Entry> = read_entries(&mut archive)?;
+        let entry: &Entry = entries.iter().find(|entry| entry.name == name).unwrap();
+
+        return io::read_to_string(open_entry(&mut archive, entry)?);
+    }
+
+    #[test]
+    fn zip_paths_go_by_extension() {
+        assert!(is_zip_path(Path::new("archive.zip")));
+        assert!(is_zip_path(Path::new("lib/app.JAR")));
+        assert!(is_zip_path(Path::new("report.docx")));
+        assert!(!is_zip_path(Path::new("archive.tar.gz")));
+        assert!(!is_zip_path(Path::new("zip")));
+    }
+
+    #[test]
+    fn entries_are_read_from_the_central_directory() {
+        //DATA
+        let entries: Vec<Entry> = read_entries(&mut Cursor::new(&ARCHIVE[..])).unwrap();
+
+        assert_eq!(entries.iter().map(|entry| entry.name.as_str()).collect::<Vec<&str>>(), vec!["docs/", "docs/notes.txt", "readme.md", "secret.txt"]);
+        assert_eq!(entries.iter().map(|entry| entry.size).collect::<Vec<u64>>(), vec![0, 22, 21, 7]);
+        assert_eq!(entries.iter().map(|entry| entry.is_dir()).collect::<Vec<bool>>(), vec![true, false, false, false]);
+        assert_eq!(entries.iter().map(|entry| entry.encrypted).collect::<Vec<bool>>(), vec![false, false, false, true]);
+    }
+
+    #[test]
+    fn stored_and_deflated_entries_are_read() {
+        assert_eq!(read_entry(&ARCHIVE, "docs/notes.txt").unwrap(), "first\nthe needle\nlast\n");
+        assert_eq!(read_entry(&ARCHIVE, "readme.md").unwrap(), "no match here\nneedle\n");
+        assert_eq!(read_entry(&ARCHIVE, "docs/").unwrap(), "");
+    }
+
+    #[test]
+    fn encrypted_entries_cant_be_opened() {
+        //DATA
+        let error: io::Error = read_entry(&ARCHIVE, "secret.txt").err().unwrap();
+
+        assert_eq!((error.kind(), error.to_string()), (io::ErrorKind::Unsupported, "it's encrypted".to_string()));
+    }
+
+    #[test]
+    fn entries_that_dont_match_their_checksum_are_corrupt() {
+        //DATA
+        let mut archive: Vec<u8> = ARCHIVE.to_vec();
+
+        archive[README_DATA] = b'N';
+        let error: io::Error = read_entry(&archive, "readme.md").err().unwrap();
+        assert_eq!((error.kind(), error.to_string()), (io::ErrorKind::InvalidData, "corrupt zip archive: an entry doesn't match its checksum".to_string()));
+        //the other entries are still fine
+        assert_eq!(read_entry(&archive, "docs/notes.txt").unwrap(), "first\nthe needle\nlast\n");
+    }
+
+    #[test]
+    fn archives_without_a_central_directory_are_corrupt() {
+        //DATA
+        let mut missing: Vec<u8> = ARCHIVE.to_vec();
+        let errors: Vec<io::Error>;
+
+        //the first entry in the central directory is where the end record says, but it doesn't start like one
+        missing[DIRECTORY] = 0;
+        errors = vec![
+            read_entries(&mut Cursor::new(&ARCHIVE[..10])).err().unwrap(),
+            read_entries(&mut Cursor::new(&ARCHIVE[..ARCHIVE.len() - 1])).err().unwrap(),
+            read_entries(&mut Cursor::new(&missing[..])).err().unwrap(),
+        ];
+
+        assert!(errors.iter().all(|error| error.kind() == io::ErrorKind::InvalidData));
+        assert_eq!(errors.iter().map(|error| error.to_string()).collect::<Vec<String>>(), vec![
+            "corrupt zip archive: it's too short to be a zip archive",
+            "corrupt zip archive: it doesn't have a central directory",
+            "corrupt zip archive: an entry in the central directory is cut off, or missing",
+        ]);
+    }
+}
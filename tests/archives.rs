@@ -0,0 +1,265 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+mod common;
+
+use common::Fixture;
+
+//"first\nthe needle\nlast\n" gzipped
+const GZIPPED: [u8; 40] = [
+    0x1F, 0x8B, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0x4B, 0xCB, 0x2C, 0x2A, 0x2E, 0xE1, 0x2A, 0xC9, 0x48, 0x55,
+    0xC8, 0x4B, 0x4D, 0x4D, 0xC9, 0x49, 0xE5, 0xCA, 0x49, 0x04, 0xF2, 0x01, 0x3D, 0x14, 0x09, 0x59, 0x16, 0x00, 0x00, 0x00,
+];
+//a zip archive with a docs/ directory, docs/notes.txt ("first\nthe needle\nlast\n", deflated), readme.md ("no match here\nneedle\n", stored),
+//and secret.txt ("needle\n", stored, but marked as encrypted), made with python's zipfile
+const ZIPPED: [u8; 452] = [
+    0x50, 0x4B, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x50, 0x4B, 0x03, 0x04, 0x14,
+    0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x50, 0x3D, 0x14, 0x09, 0x59, 0x16, 0x00, 0x00, 0x00, 0x16, 0x00, 0x00,
+    0x00, 0x0E, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x6E, 0x6F, 0x74, 0x65, 0x73, 0x2E, 0x74, 0x78, 0x74, 0x4B,
+    0xCB, 0x2C, 0x2A, 0x2E, 0xE1, 0x2A, 0xC9, 0x48, 0x55, 0xC8, 0x4B, 0x4D, 0x4D, 0xC9, 0x49, 0xE5, 0xCA, 0x49, 0x04, 0xF2,
+    0x01, 0x50, 0x4B, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xA8, 0x1E, 0xD1, 0x95, 0x15,
+    0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x72, 0x65, 0x61, 0x64, 0x6D, 0x65, 0x2E, 0x6D, 0x64,
+    0x6E, 0x6F, 0x20, 0x6D, 0x61, 0x74, 0x63, 0x68, 0x20, 0x68, 0x65, 0x72, 0x65, 0x0A, 0x6E, 0x65, 0x65, 0x64, 0x6C, 0x65,
+    0x0A, 0x50, 0x4B, 0x03, 0x04, 0x14, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xFE, 0xAA, 0xD8, 0x42, 0x07,
+    0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x73, 0x65, 0x63, 0x72, 0x65, 0x74, 0x2E, 0x74, 0x78,
+    0x74, 0x6E, 0x65, 0x65, 0x64, 0x6C, 0x65, 0x0A, 0x50, 0x4B, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x21, 0x50, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, 0xED, 0x41, 0x00, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x50,
+    0x4B, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x50, 0x3D, 0x14, 0x09, 0x59, 0x16,
+    0x00, 0x00, 0x00, 0x16, 0x00, 0x00, 0x00, 0x0E, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA4,
+    0x81, 0x23, 0x00, 0x00, 0x00, 0x64, 0x6F, 0x63, 0x73, 0x2F, 0x6E, 0x6F, 0x74, 0x65, 0x73, 0x2E, 0x74, 0x78, 0x74, 0x50,
+    0x4B, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xA8, 0x1E, 0xD1, 0x95, 0x15,
+    0x00, 0x00, 0x00, 0x15, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA4,
+    0x81, 0x65, 0x00, 0x00, 0x00, 0x72, 0x65, 0x61, 0x64, 0x6D, 0x65, 0x2E, 0x6D, 0x64, 0x50, 0x4B, 0x01, 0x02, 0x14, 0x03,
+    0x14, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x50, 0xFE, 0xAA, 0xD8, 0x42, 0x07, 0x00, 0x00, 0x00, 0x07, 0x00,
+    0x00, 0x00, 0x0A, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xA4, 0x81, 0xA1, 0x00, 0x00, 0x00,
+    0x73, 0x65, 0x63, 0x72, 0x65, 0x74, 0x2E, 0x74, 0x78, 0x74, 0x50, 0x4B, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00,
+    0x04, 0x00, 0xDE, 0x00, 0x00, 0x00, 0xD0, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+//a gzipped tar archive of project/, project/src/app/handlers/deep/nested/needle.txt ("first\nthe needle\nlast\n"), project/big.log ("needle\n" and a line of 1000 dots),
+//project/link.txt (a symlink to big.log), and project/README ("nothing\n"), made with python's tarfile
+const TARRED: [u8; 255] = [
+    0x1F, 0x8B, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xED, 0xD6, 0x3D, 0x0E, 0x82, 0x30, 0x18, 0x06, 0xE0, 0xCE,
+    0x9C, 0x82, 0x13, 0x50, 0x5A, 0x4A, 0x99, 0x4D, 0x74, 0x74, 0xF1, 0x06, 0x28, 0x15, 0x50, 0x02, 0x4D, 0x5B, 0x13, 0x8F,
+    0x6F, 0xA3, 0x2E, 0xA2, 0x38, 0x60, 0xC0, 0x44, 0xDF, 0x67, 0x69, 0xCB, 0xC2, 0xF0, 0xE6, 0xFB, 0xD1, 0xA6, 0x3B, 0xA8,
+    0x9D, 0xA3, 0x64, 0x42, 0xB1, 0x97, 0xA5, 0xE9, 0xF5, 0xF4, 0xFA, 0xE7, 0x8B, 0x7B, 0x26, 0x58, 0x42, 0xC2, 0x94, 0xCC,
+    0xE0, 0x64, 0x5D, 0x6E, 0xFC, 0x2F, 0xC9, 0x7F, 0xD2, 0xF7, 0xFC, 0xAD, 0xD9, 0xD1, 0x5C, 0x6B, 0x5A, 0xE5, 0x6D, 0xD1,
+    0x28, 0x63, 0x69, 0xA1, 0x94, 0xA6, 0xAD, 0xB2, 0x4E, 0x15, 0xFE, 0x50, 0xFE, 0x63, 0xE4, 0xCE, 0x6E, 0x74, 0xFE, 0x52,
+    0x88, 0xE1, 0xFC, 0xB9, 0x7C, 0xCC, 0x9F, 0xC9, 0x2C, 0x15, 0x24, 0x8C, 0x91, 0xFF, 0xE4, 0xF6, 0xB5, 0xB1, 0x2E, 0x70,
+    0x95, 0x0A, 0x6F, 0x21, 0x07, 0x4D, 0xEE, 0xDF, 0x04, 0xFE, 0xAC, 0xFE, 0xB7, 0x75, 0x19, 0x35, 0x5D, 0x39, 0x59, 0xFF,
+    0x7F, 0x53, 0xFF, 0x2C, 0x93, 0xBD, 0xFE, 0xCF, 0x62, 0x29, 0x13, 0xD4, 0xFF, 0x1C, 0xEE, 0x55, 0x1F, 0x01, 0xC0, 0xCF,
+    0x0B, 0x86, 0xFA, 0x7F, 0x53, 0xB7, 0xC7, 0xD1, 0x0B, 0xDE, 0xA7, 0xFB, 0x5F, 0xFF, 0xCE, 0x78, 0x22, 0x7C, 0xFF, 0xE7,
+    0x53, 0x0E, 0x25, 0xF4, 0xFF, 0x87, 0xFC, 0x37, 0xAB, 0xC5, 0x72, 0xBD, 0x22, 0xE4, 0x2B, 0xF9, 0xB3, 0xA7, 0xF9, 0xCF,
+    0x25, 0xC7, 0xFC, 0x9F, 0x65, 0xFE, 0x77, 0xAE, 0xAA, 0xDB, 0x12, 0x1B, 0x3F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0xC0, 0x0F, 0xB9, 0x00, 0x5B, 0x02, 0x12, 0x2E, 0x00, 0x28, 0x00, 0x00,
+];
+
+/**
+ * returns a fixture with app.log.gz, which has "needle" on its second line once it's decompressed, bad.gz, which is cut off after its header,
+ * and plain.txt, which has "needle" in it too
+ */
+fn gzipped_logs() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("app.log.gz", GZIPPED);
+    fixture.file("bad.gz", b"\x1f\x8b\x08\x00junk");
+    fixture.file("plain.txt", "needle\n");
+    return fixture;
+}
+
+#[test]
+fn gzip_files_are_only_searched_with_search_zip() {
+    //DATA
+    let fixture = gzipped_logs();
+
+    assert_eq!(fixture.run(&[".", "needle"]).lines(), vec!["./plain.txt:1:needle"]);
+    assert_eq!(fixture.run(&["-z", ".", "needle"]).lines(), vec!["./app.log.gz:2:the needle", "./plain.txt:1:needle"]);
+    assert_eq!(fixture.run(&["--search-zip", "-l", ".", "needle"]).lines(), vec!["./app.log.gz", "./plain.txt"]);
+}
+
+#[test]
+fn gzip_offsets_are_in_what_it_decompresses_to() {
+    assert_eq!(gzipped_logs().run(&["-z", "-b", "--column", "app.log.gz", "needle"]).lines(), vec!["2:5:10:the needle"]);
+}
+
+#[test]
+fn corrupt_gzip_files_are_skipped_with_an_error() {
+    //DATA
+    let fixture = gzipped_logs();
+    let output = fixture.run(&["-z", ".", "needle"]);
+
+    //the rest are still searched
+    assert_eq!((output.code, output.lines().len()), (0, 2));
+    assert_eq!(output.stderr, "1 file couldn't be read (invalid data: 1), rerun with --verbose for details\n");
+    assert!(fixture.run(&["-z", "-v", ".", "needle"]).stderr.contains("Error searching \"./bad.gz\": corrupt gzip file: it's too short to be gzip"));
+}
+
+#[test]
+fn max_filesize_limits_what_gzip_files_decompress_to() {
+    //DATA
+    let fixture = gzipped_logs();
+    let output = fixture.run(&["-z", "-v", "--max-filesize", "10", "app.log.gz", "needle"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Error searching \"app.log.gz\": it decompresses to more than 10 bytes"), "{}", output.stderr);
+    //the limit is on the 22 bytes it decompresses to, not on the 40 bytes of the file
+    assert_eq!(fixture.run(&["-z", "--max-filesize", "22", "app.log.gz", "needle"]).lines(), vec!["2:the needle"]);
+}
+
+/**
+ * returns a fixture with archive.zip (see ZIPPED), and plain.txt, which has "needle" in it
+ */
+fn zipped_docs() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("archive.zip", ZIPPED);
+    fixture.file("plain.txt", "needle\n");
+    return fixture;
+}
+
+#[test]
+fn zip_entries_are_searched_as_archive_bang_name() {
+    //DATA
+    let fixture = zipped_docs();
+
+    assert_eq!(fixture.run(&[".", "needle"]).lines(), vec!["./plain.txt:1:needle"]);
+    assert_eq!(fixture.run(&["-z", ".", "needle"]).lines(), vec!["./archive.zip!docs/notes.txt:2:the needle", "./archive.zip!readme.md:2:needle", "./plain.txt:1:needle"]);
+    assert_eq!(fixture.run(&["-z", "-l", "archive.zip", "needle"]).lines(), vec!["archive.zip!docs/notes.txt", "archive.zip!readme.md"]);
+    //an archive is a lot of files, so even when it's the only path, the results say which entry they're from
+    assert_eq!(fixture.run(&["-z", "archive.zip", "first"]).lines(), vec!["archive.zip!docs/notes.txt:1:first"]);
+    assert_eq!(fixture.run(&["-z", "--no-filename", "archive.zip", "first"]).lines(), vec!["1:first"]);
+}
+
+#[test]
+fn encrypted_zip_entries_are_errors_of_their_own() {
+    //DATA
+    let fixture = zipped_docs();
+    let output = fixture.run(&["-z", "--stats", ".", "needle"]);
+
+    //the rest of the archive is still searched
+    assert_eq!(output.code, 0);
+    assert!(output.stdout.contains("files matched: 3\n") && output.stdout.contains("files skipped: 1\n"), "{}", output.stdout);
+    assert_eq!(output.stderr, "1 file couldn't be read (unsupported: 1), rerun with --verbose for details\n");
+    assert!(fixture.run(&["-z", "-v", ".", "needle"]).stderr.contains("Error searching \"./archive.zip!secret.txt\": it's encrypted\n"));
+}
+
+#[test]
+fn corrupt_zip_archives_are_an_error_for_the_archive() {
+    //DATA
+    let fixture = zipped_docs();
+    fixture.file("cut.zip", &ZIPPED[..ZIPPED.len() - 1]);
+    let output = fixture.run(&["-z", "-v", ".", "needle"]);
+
+    assert!(output.stderr.contains("Error searching \"./cut.zip\": corrupt zip archive: it doesn't have a central directory\n"), "{}", output.stderr);
+    assert_eq!(output.lines().len(), 3);
+}
+
+#[test]
+fn filters_apply_to_zip_entry_names() {
+    //DATA
+    let fixture = zipped_docs();
+
+    //the archive's own name doesn't have to match, only the names in it (and the encrypted entry is filtered out before it's an error)
+    let output = fixture.run(&["-z", "--include", "*.md", ".", "needle"]);
+    assert_eq!((output.lines(), output.stderr.as_str()), (vec!["./archive.zip!readme.md:2:needle"], ""));
+    assert_eq!(fixture.run(&["-z", "--exclude", "notes.*", ".", "needle"]).lines(), vec!["./archive.zip!readme.md:2:needle", "./plain.txt:1:needle"]);
+    assert_eq!(fixture.run(&["-z", "-t", "markdown", ".", "needle"]).lines(), vec!["./archive.zip!readme.md:2:needle"]);
+}
+
+#[test]
+fn zip_entry_paths_are_in_json_and_vimgrep_too() {
+    //DATA
+    let fixture = zipped_docs();
+    let json: String = fixture.run(&["-z", "--json", "archive.zip", "needle"]).stdout;
+
+    assert!(json.contains("{\"kind\":\"match\",\"path\":\"archive.zip!docs/notes.txt\",\"line_number\":2,\"column\":5,\"byte_offset\":10,"), "{}", json);
+    assert!(json.contains("{\"kind\":\"match\",\"path\":\"archive.zip!readme.md\",\"line_number\":2,\"column\":1,\"byte_offset\":14,"), "{}", json);
+    assert_eq!(fixture.run(&["-z", "--vimgrep", "archive.zip", "needle"]).lines(), vec!["archive.zip!docs/notes.txt:2:5:the needle", "archive.zip!readme.md:2:1:needle"]);
+}
+
+/**
+ * returns a tar archive of files, each a header and its contents padded out to a whole block, and then the two blocks of zeroes it ends with
+ */
+fn tar(files: &[(&str, &str)]) -> Vec<u8> {
+    //DATA
+    let mut archive: Vec<u8> = Vec::new();
+
+    for (name, contents) in files {
+        //DATA
+        let mut header: Vec<u8> = vec![0; 512];
+
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..136].copy_from_slice(format!("{:011o}\0", contents.len()).as_bytes());
+        header[156] = b'0';
+        header[148..156].copy_from_slice(b"        ");
+        let checksum: u32 = header.iter().map(|byte| *byte as u32).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+        archive.extend_from_slice(&header);
+        archive.extend_from_slice(contents.as_bytes());
+        archive.resize(archive.len().next_multiple_of(512), 0);
+    }
+    archive.resize(archive.len() + 1024, 0);
+    return archive;
+}
+
+/**
+ * returns a fixture with project.tar.gz (see TARRED), and notes.tar, which has notes/needle.txt and notes/other.txt in it
+ */
+fn tarred_project() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("project.tar.gz", TARRED);
+    fixture.file("notes.tar", tar(&[("notes/needle.txt", "a needle\n"), ("notes/other.txt", "nothing\n")]));
+    return fixture;
+}
+
+#[test]
+fn tar_entries_are_searched_as_archive_bang_name() {
+    //DATA
+    let fixture = tarred_project();
+
+    assert_eq!(fixture.run(&[".", "needle"]).lines(), Vec::<&str>::new());
+    //in the order they're in the archive, since it's read in one pass
+    assert_eq!(fixture.run(&["-z", ".", "needle"]).lines(), vec![
+        "./notes.tar!notes/needle.txt:1:a needle",
+        "./project.tar.gz!project/src/app/handlers/deep/nested/needle.txt:2:the needle",
+        "./project.tar.gz!project/big.log:1:needle",
+    ]);
+    assert_eq!(fixture.run(&["-z", "-l", "project.tar.gz", "the needle"]).lines(), vec!["project.tar.gz!project/src/app/handlers/deep/nested/needle.txt"]);
+    assert_eq!(fixture.run(&["-z", "--include", "other.*", ".", "nothing"]).lines(), vec!["./notes.tar!notes/other.txt:1:nothing"]);
+}
+
+#[test]
+fn only_regular_files_in_tar_archives_are_searched() {
+    //DATA
+    let output = tarred_project().run(&["-z", "-v", "project.tar.gz", "big"]);
+
+    //the symlink isn't followed, or searched as its target's name
+    assert_eq!((output.code, output.lines()), (1, Vec::<&str>::new()));
+    assert_eq!(output.stderr, "Skipping \"project.tar.gz!project/link.txt\": not a regular file\n");
+}
+
+#[test]
+fn max_filesize_skips_big_tar_entries() {
+    //DATA
+    let output = tarred_project().run(&["-z", "-v", "--max-filesize", "100", "project.tar.gz", "needle"]);
+
+    //the file after it is still searched, and it's skipped rather than an error
+    assert_eq!((output.code, output.lines()), (0, vec!["project.tar.gz!project/src/app/handlers/deep/nested/needle.txt:2:the needle"]));
+    assert!(output.stderr.contains("Skipping \"project.tar.gz!project/big.log\": larger than the max filesize\n"), "{}", output.stderr);
+}
+
+#[test]
+fn corrupt_tar_archives_stop_at_the_error() {
+    //DATA
+    let fixture = tarred_project();
+    let mut archive: Vec<u8> = tar(&[("a.txt", "needle\n"), ("b.txt", "needle\n")]);
+
+    //b.txt's header doesn't match its checksum any more
+    archive[1024] = b'c';
+    fixture.file("bad.tar", archive);
+    let output = fixture.run(&["-z", "-v", "bad.tar", "needle"]);
+
+    assert_eq!((output.code, output.lines()), (0, vec!["bad.tar!a.txt:1:needle"]));
+    assert!(output.stderr.contains("Error searching \"bad.tar\": corrupt tar archive: a header doesn't match its checksum\n"), "{}", output.stderr);
+}
@@ -0,0 +1,419 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+mod common;
+
+use std::process::Stdio;
+
+use common::Fixture;
+
+#[test]
+fn double_dash_makes_the_rest_positional() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("flags.txt", "cc -DFOO main.c\n");
+    fixture.file("other.txt", "nothing\n");
+
+    let output = fixture.run(&[".", "--", "-DFOO"]);
+    assert_eq!(output.code, 0, "{:?}", output);
+    assert_eq!(output.lines(), vec!["./flags.txt:1:cc -DFOO main.c"]);
+}
+
+#[test]
+fn double_dash_allows_a_path_starting_with_a_dash() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("-dashed/a.txt", "arrow -> here\n");
+
+    let output = fixture.run(&["--", "-dashed", "->"]);
+    assert_eq!(output.code, 0, "{:?}", output);
+    assert_eq!(output.lines(), vec!["-dashed/a.txt:1:arrow -> here"]);
+}
+
+#[test]
+fn without_double_dash_a_dashed_query_is_an_option() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("flags.txt", "cc -DFOO main.c\n");
+
+    let output = fixture.run(&[".", "-DFOO"]);
+    assert_eq!(output.code, 2);
+}
+
+#[test]
+fn errors_are_short_and_keep_the_suggestion() {
+    //DATA
+    let fixture = Fixture::new();
+    let output = fixture.run(&["--recusive", ".", "x"]);
+
+    assert_eq!(output.code, 2);
+    assert_eq!(output.stdout, "");
+    assert_eq!(output.stderr.lines().collect::<Vec<&str>>(), vec![
+        "Problem parsing arguments: unknown option '--recusive', did you mean '--recursive'?",
+        "Run with --help for usage.",
+    ]);
+}
+
+#[test]
+fn version_succeeds() {
+    //DATA
+    let output = Fixture::new().run(&["--version"]);
+
+    assert_eq!(output.code, 0);
+    assert!(output.stdout.starts_with(&format!("grep-directory {}\n", env!("CARGO_PKG_VERSION"))), "{}", output.stdout);
+}
+
+#[test]
+fn dash_searches_stdin() {
+    //DATA
+    let fixture = Fixture::new();
+
+    let output = fixture.run_with_stdin(&["-", "ERROR"], b"ok\nERROR one\nfine\n");
+    assert_eq!((output.code, output.lines()), (0, vec!["2:ERROR one"]));
+
+    let output = fixture.run_with_stdin(&["-H", "-", "ERROR"], b"ok\nERROR one\nfine\n");
+    assert_eq!((output.code, output.lines()), (0, vec!["<stdin>:2:ERROR one"]));
+
+    let output = fixture.run_with_stdin(&["-l", "-", "ERROR"], b"ERROR\n");
+    assert_eq!((output.code, output.lines()), (0, vec!["<stdin>"]));
+
+    let output = fixture.run_with_stdin(&["-", "ERROR"], b"ok\n");
+    assert_eq!((output.code, output.stdout.as_str()), (1, ""));
+}
+
+#[test]
+fn pattern_file_skips_blank_lines_and_comments_and_trims_trailing_whitespace() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("patterns", "# banned identifiers\n\nunwrap  \n   \n\t\n  # not a comment, it doesn't start the line\npanic!\t\n");
+    fixture.file("src/a.rs", "x.unwrap();\n");
+    fixture.file("src/b.rs", "panic!(\"no\");\n");
+    fixture.file("src/c.rs", "  # not a comment, it doesn't start the line\n");
+    fixture.file("src/d.rs", "unwrap  \n");
+    fixture.file("src/e.rs", "banned identifiers\n");
+
+    let output = fixture.run(&["-l", "--pattern-file", "patterns", "src"]);
+    assert_eq!(output.code, 0, "{:?}", output);
+    //whitespace only lines aren't patterns, or they'd match everything (like e.rs)
+    assert_eq!(output.lines(), vec!["src/a.rs", "src/b.rs", "src/c.rs", "src/d.rs"]);
+}
+
+#[test]
+fn pattern_file_that_is_missing_or_empty_is_an_error() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("empty", "# just a comment\n\n");
+
+    let output = fixture.run(&["--pattern-file", "missing", "."]);
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Couldn't read pattern file 'missing'"), "{}", output.stderr);
+
+    let output = fixture.run(&["--pattern-file", "empty", "."]);
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Pattern file 'empty' doesn't contain any patterns."), "{}", output.stderr);
+}
+
+#[test]
+fn exit_code_is_0_with_matches_and_1_without() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hello\n");
+
+    assert_eq!(fixture.run(&[".", "hello"]).code, 0);
+    assert_eq!(fixture.run(&["-l", ".", "hello"]).code, 0);
+    assert_eq!(fixture.run(&[".", "goodbye"]).code, 1);
+    assert_eq!(fixture.run(&["-c", ".", "goodbye"]).code, 1);
+}
+
+#[test]
+fn exit_code_is_2_for_bad_arguments() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hello\n");
+
+    assert_eq!(fixture.run(&["--no-such-option", ".", "hello"]).code, 2);
+    assert_eq!(fixture.run(&["missing", "hello"]).code, 2);
+    assert_eq!(fixture.run(&["."]).code, 2);
+    assert_eq!(fixture.run::<&str>(&[]).code, 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn exit_code_is_2_when_nothing_matched_but_files_could_not_be_read() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hello\n");
+    fixture.file("b.bad", "hello\n");
+
+    //false fails on every file, so none of them could be searched
+    let output = fixture.run(&["--pre", "false", ".", "hello"]);
+    assert_eq!(output.code, 2, "{:?}", output);
+    assert!(output.stderr.contains("couldn't be read"), "{}", output.stderr);
+
+    //a match is still a match, even if another file couldn't be read
+    assert_eq!(fixture.run(&["--pre", "false", "--pre-glob", "*.bad", ".", "hello"]).code, 0);
+    assert_eq!(fixture.run(&["--pre", "false", "--pre-glob", "*.bad", ".", "goodbye"]).code, 2);
+}
+
+#[test]
+fn bare_help_prints_the_help() {
+    //DATA
+    let fixture = Fixture::new();
+
+    for args in [&["help"][..], &["-h"], &["--help", "extra"]] {
+        //DATA
+        let output = fixture.run(args);
+
+        assert_eq!(output.code, 0, "{:?}", args);
+        assert!(output.stdout.contains("OPTIONS:"), "{:?}", args);
+        assert_eq!(output.stderr, "", "{:?}", args);
+    }
+}
+
+#[test]
+fn help_can_still_be_searched_for() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("help/a.txt", "needle\n");
+    fixture.file("b.txt", "help\n");
+
+    assert_eq!(fixture.run(&["help", "needle"]).lines(), vec!["help/a.txt:1:needle"]);
+    assert_eq!(fixture.run(&["b.txt", "help"]).lines(), vec!["1:help"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn file_names_that_arent_utf8_are_found() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    //DATA
+    let fixture = Fixture::new();
+    let name: &OsStr = OsStr::from_bytes(b"bad\xFF.txt");
+    std::fs::write(fixture.root.join(name), "needle\n").unwrap();
+    fixture.file("ok.txt", "needle\n");
+
+    //printed lossily by default, but exactly with -0 and in json
+    assert_eq!(fixture.run(&["-l", ".", "needle"]).lines(), vec!["./bad\u{FFFD}.txt", "./ok.txt"]);
+    assert_eq!(fixture.run(&["-l", "-0", ".", "needle"]).stdout_bytes, b"./bad\xFF.txt\0./ok.txt\0");
+    assert!(fixture.run(&["--json", "-l", ".", "needle"]).lines().contains(&"{\"path_bytes\":[46,47,98,97,100,255,46,116,120,116]},"));
+    //and they can be the path to search
+    assert_eq!(fixture.run(&[OsStr::new("-H"), name, OsStr::new("needle")]).lines(), vec!["bad\u{FFFD}.txt:1:needle"]);
+}
+
+/**
+ * returns a fixture with real/sub/a.txt, which has "needle" in it, and link, a symlink to real (on unix)
+ */
+fn linked_tree() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("real/sub/a.txt", "needle\n");
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("real", fixture.path("link")).unwrap();
+    return fixture;
+}
+
+#[test]
+fn paths_are_printed_as_found_by_default() {
+    //DATA
+    let fixture = linked_tree();
+
+    assert_eq!(fixture.run(&["-l", "-r", "real", "needle"]).lines(), vec!["real/sub/a.txt"]);
+}
+
+#[test]
+fn absolute_paths_start_from_the_root() {
+    //DATA
+    let fixture = linked_tree();
+    let expected: String = std::fs::canonicalize(fixture.path("real/sub/a.txt")).unwrap().to_string_lossy().into_owned();
+
+    assert_eq!(fixture.run(&["-l", "-r", "--absolute-paths", "real", "needle"]).lines(), vec![expected.as_str()]);
+    assert_eq!(fixture.run(&["-r", "--absolute-paths", "real", "needle"]).lines(), vec![format!("{}:1:needle", expected)]);
+    assert_eq!(fixture.run(&["-l", "--absolute-paths", "real/sub/a.txt", "needle"]).lines(), vec![expected.as_str()]);
+}
+
+#[cfg(unix)]
+#[test]
+fn absolute_paths_resolve_symlinks() {
+    //DATA
+    let fixture = linked_tree();
+    let expected: String = std::fs::canonicalize(fixture.path("real/sub/a.txt")).unwrap().to_string_lossy().into_owned();
+
+    assert_eq!(fixture.run(&["-l", "-r", "link", "needle"]).lines(), vec!["link/sub/a.txt"]);
+    assert_eq!(fixture.run(&["-l", "-r", "--absolute-paths", "link", "needle"]).lines(), vec![expected.as_str()]);
+}
+
+#[test]
+fn output_closing_early_exits_quietly() {
+    //DATA
+    let fixture = Fixture::new();
+    //more than a pipe holds, so it can't all be written before the pipe closes
+    fixture.file("big.txt", "needle\n".repeat(50_000));
+    let mut child = common::command_in(&fixture.root, &["big.txt", "needle"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    //like piping into head, which closes its end once it has enough
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8_lossy(&output.stderr), "");
+}
+
+#[test]
+fn errors_are_summed_up_at_the_end() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("good.txt", "needle\n");
+    fixture.file("bad.txt", b"needle \xff\n");
+    let locked = fixture.unreadable_file("locked.txt", "needle\n");
+    let output = fixture.run(&["--strict-utf8", ".", "needle"]);
+
+    //the readable file still matches
+    assert_eq!(output.code, 0);
+    if locked {
+        assert_eq!(output.lines(), vec!["./good.txt:1:needle"]);
+        assert_eq!(output.stderr, "2 files couldn't be read (invalid data: 1, permission denied: 1), rerun with --verbose for details\n");
+    } else {
+        //running as root, so it could be read anyway
+        assert_eq!(output.lines(), vec!["./good.txt:1:needle", "./locked.txt:1:needle"]);
+        assert_eq!(output.stderr, "1 file couldn't be read (invalid data: 1), rerun with --verbose for details\n");
+    }
+}
+
+#[test]
+fn verbose_reports_each_error_instead_of_the_summary() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("good.txt", "needle\n");
+    fixture.file("bad.txt", b"needle \xff\n");
+    let output = fixture.run(&["--strict-utf8", "--verbose", ".", "needle"]);
+
+    assert!(output.stderr.contains("Error searching \"./bad.txt\": stream did not contain valid UTF-8"), "{}", output.stderr);
+    assert!(!output.stderr.contains("rerun with --verbose"), "{}", output.stderr);
+}
+
+/**
+ * returns a fixture with a.txt, which has "hit" on its first line, and b.txt, which is just "hit"
+ */
+fn two_hits() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "one hit\nno\n");
+    fixture.file("b.txt", "hit\n");
+    return fixture;
+}
+
+#[test]
+fn matching_lines_are_printed_by_default_and_l_lists_files() {
+    //DATA
+    let fixture = two_hits();
+
+    assert_eq!(fixture.run(&[".", "hit"]).lines(), vec!["./a.txt:1:one hit", "./b.txt:1:hit"]);
+    for flag in ["-l", "--files-with-matches"] {
+        assert_eq!(fixture.run(&[flag, ".", "hit"]).lines(), vec!["./a.txt", "./b.txt"]);
+    }
+}
+
+#[test]
+fn compat_v0_lists_files_by_default() {
+    //DATA
+    let fixture = two_hits();
+    let with_env = |value: &str, args: &[&str]| -> String {
+        //DATA
+        let output = common::command_in(&fixture.root, args).env("GREP_DIRECTORY_COMPAT_V0", value).stdin(Stdio::null()).output().unwrap();
+
+        return String::from_utf8(output.stdout).unwrap();
+    };
+
+    assert_eq!(fixture.run(&["--compat-v0", ".", "hit"]).lines(), vec!["./a.txt", "./b.txt"]);
+    assert_eq!(with_env("1", &[".", "hit"]), "./a.txt\n./b.txt\n");
+    //an empty variable is the same as not having it
+    assert_eq!(with_env("", &[".", "hit"]), "./a.txt:1:one hit\n./b.txt:1:hit\n");
+    //picking an output mode still works
+    assert_eq!(with_env("1", &["-n", ".", "hit"]), "./a.txt:1:one hit\n./b.txt:1:hit\n");
+}
+
+/**
+ * returns a fixture with proj/sub/a.txt and b.txt, which both have "hit" in them, and the canonical path of b.txt
+ */
+fn project() -> (Fixture, String) {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("proj/sub/a.txt", "hit\n");
+    let outside: String = std::fs::canonicalize(fixture.file("b.txt", "hit\n")).unwrap().to_string_lossy().into_owned();
+
+    return (fixture, outside);
+}
+
+#[test]
+fn relative_to_rebases_the_paths_under_it() {
+    //DATA
+    let (fixture, outside) = project();
+
+    //what isn't under it is absolute instead
+    assert_eq!(fixture.run(&["-r", "--relative-to", "proj", ".", "hit"]).lines(), vec![format!("{}:1:hit", outside), "sub/a.txt:1:hit".to_string()]);
+    assert_eq!(fixture.run(&["-r", "-l", "--relative-to", "proj/", "proj", "hit"]).lines(), vec!["sub/a.txt"]);
+}
+
+#[test]
+fn relative_to_keeps_the_raw_path_in_csv_and_json() {
+    //DATA
+    let (fixture, outside) = project();
+
+    assert_eq!(fixture.run(&["-r", "-l", "--csv", "--relative-to", "proj", ".", "hit"]).stdout,
+        format!("path,raw_path,match_count\n{},./b.txt,1\nsub/a.txt,./proj/sub/a.txt,1\n", outside));
+    assert_eq!(fixture.run(&["-r", "-l", "--jsonl", "--relative-to", "proj", ".", "hit"]).lines()[..2],
+        [format!("{{\"type\":\"file\",\"path\":\"{}\",\"raw_path\":\"./b.txt\"}}", outside), "{\"type\":\"file\",\"path\":\"sub/a.txt\",\"raw_path\":\"./proj/sub/a.txt\"}".to_string()]);
+}
+
+#[test]
+fn relative_to_cant_be_used_with_absolute_paths() {
+    //DATA
+    let (fixture, _) = project();
+    let output = fixture.run(&["--relative-to", "proj", "--absolute-paths", ".", "hit"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("--absolute-paths can't be used with --relative-to"), "{}", output.stderr);
+}
+
+#[test]
+fn completions_match_the_golden_files() {
+    //DATA
+    let fixture = Fixture::new();
+
+    //after changing the options, regenerate these with grep-directory --completions <shell> > tests/golden/completions.<shell>
+    for (shell, golden) in [("bash", include_str!("golden/completions.bash")), ("zsh", include_str!("golden/completions.zsh")), ("fish", include_str!("golden/completions.fish"))] {
+        //DATA
+        let output = fixture.run(&["--completions", shell]);
+
+        assert_eq!(output.code, 0, "{}", shell);
+        assert_eq!(output.stdout, golden, "{}", shell);
+    }
+}
+
+#[test]
+fn bash_completions_are_valid_bash() {
+    //DATA
+    let fixture = Fixture::new();
+    let path = fixture.file("completions.bash", fixture.run(&["--completions", "bash"]).stdout);
+
+    //only checked where there's a bash to check it with
+    if let Ok(status) = std::process::Command::new("bash").arg("-n").arg(path).stderr(Stdio::null()).status() {
+        assert!(status.success());
+    }
+}
+
+#[test]
+fn man_page_matches_the_golden_file() {
+    //DATA
+    let output = Fixture::new().run(&["--generate-man"]);
+
+    //after changing the options, regenerate it with grep-directory --generate-man > tests/golden/grep-directory.1
+    assert_eq!(output.code, 0);
+    assert_eq!(output.stdout, include_str!("golden/grep-directory.1"));
+}
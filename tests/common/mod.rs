@@ -0,0 +1,196 @@
+//shared by the integration tests, not every test file uses all of it
+#![allow(dead_code)]
+
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+//how many fixtures this test binary has made, so they each get a directory of their own
+static FIXTURES: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * a temporary directory of files to search, that's deleted once the test is done with it
+ */
+pub struct Fixture {
+    pub root: PathBuf,
+}
+impl Fixture {
+    pub fn new() -> Fixture {
+        //DATA
+        let number: usize = FIXTURES.fetch_add(1, Ordering::SeqCst);
+        let root: PathBuf = std::env::temp_dir().join(format!("grep-directory-test-{}-{}", std::process::id(), number));
+
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        return Fixture { root };
+    }
+
+    /**
+     * returns the full path of path in the fixture
+     */
+    pub fn path(&self, path: &str) -> PathBuf {
+        return self.root.join(path);
+    }
+
+    /**
+     * writes a file at path (making the directories it's in), and returns its full path
+     */
+    pub fn file<C: AsRef<[u8]>>(&self, path: &str, contents: C) -> PathBuf {
+        //DATA
+        let full_path: PathBuf = self.path(path);
+
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(&full_path, contents).unwrap();
+        return full_path;
+    }
+
+    /**
+     * makes a directory at path (and the ones it's in), and returns its full path
+     */
+    pub fn dir(&self, path: &str) -> PathBuf {
+        //DATA
+        let full_path: PathBuf = self.path(path);
+
+        fs::create_dir_all(&full_path).unwrap();
+        return full_path;
+    }
+
+    /**
+     * writes a file at path, then takes away permission to read it, and returns whether that worked
+     * (it doesn't when the tests are run as root, or on platforms without unix permissions)
+     */
+    pub fn unreadable_file<C: AsRef<[u8]>>(&self, path: &str, contents: C) -> bool {
+        //DATA
+        let full_path: PathBuf = self.file(path, contents);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&full_path, fs::Permissions::from_mode(0o000)).unwrap();
+        }
+        return fs::read(&full_path).is_err();
+    }
+
+    /**
+     * makes a directory at path, then takes away permission to read it, and returns whether that worked
+     * (it doesn't when the tests are run as root, or on platforms without unix permissions)
+     */
+    pub fn unreadable_dir(&self, path: &str) -> bool {
+        //DATA
+        let full_path: PathBuf = self.dir(path);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&full_path, fs::Permissions::from_mode(0o000)).unwrap();
+        }
+        return fs::read_dir(&full_path).is_err();
+    }
+
+    /**
+     * writes a file at path, last modified at modified, and returns its full path
+     */
+    pub fn file_modified_at<C: AsRef<[u8]>>(&self, path: &str, contents: C, modified: SystemTime) -> PathBuf {
+        //DATA
+        let full_path: PathBuf = self.file(path, contents);
+
+        fs::File::options().write(true).open(&full_path).unwrap().set_modified(modified).unwrap();
+        return full_path;
+    }
+
+    /**
+     * runs grep-directory with args, from the root of the fixture
+     */
+    pub fn run<S: AsRef<OsStr>>(&self, args: &[S]) -> Output {
+        return run_in(&self.root, args, None);
+    }
+
+    /**
+     * runs grep-directory with args, from the root of the fixture, with input as its stdin
+     */
+    pub fn run_with_stdin<S: AsRef<OsStr>>(&self, args: &[S], input: &[u8]) -> Output {
+        return run_in(&self.root, args, Some(input));
+    }
+}
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        //files made unreadable by a test have to be readable again to be deleted
+        #[cfg(unix)]
+        let _ = Command::new("chmod").arg("-R").arg("u+rwx").arg(&self.root).status();
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/**
+ * what running grep-directory did
+ */
+#[derive(Debug)]
+pub struct Output {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String,
+    pub stdout_bytes: Vec<u8>,  //stdout as it was, for output that isn't utf-8
+}
+impl Output {
+    /**
+     * returns the lines it printed, in order
+     */
+    pub fn lines(&self) -> Vec<&str> {
+        return self.stdout.lines().collect();
+    }
+
+    /**
+     * returns the lines it printed, sorted, for comparing results that can be in any order
+     */
+    pub fn sorted_lines(&self) -> Vec<&str> {
+        //DATA
+        let mut lines: Vec<&str> = self.lines();
+
+        lines.sort();
+        return lines;
+    }
+}
+
+/**
+ * runs grep-directory with args from dir, without the default options from the environment or a config file
+ */
+pub fn run_in<S: AsRef<OsStr>>(dir: &Path, args: &[S], input: Option<&[u8]>) -> Output {
+    //DATA
+    let mut child = command_in(dir, args)
+        .stdin(if input.is_some() {Stdio::piped()} else {Stdio::null()})
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    if let Some(input) = input {
+        use std::io::Write;
+        child.stdin.take().unwrap().write_all(input).unwrap();
+    }
+    let output = child.wait_with_output().unwrap();
+    return Output {
+        code: output.status.code().unwrap_or(-1),
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        stdout_bytes: output.stdout,
+    };
+}
+
+/**
+ * returns the command to run grep-directory with args from dir, the same way run_in does, for tests that need to handle the process themselves
+ */
+pub fn command_in<S: AsRef<OsStr>>(dir: &Path, args: &[S]) -> Command {
+    //DATA
+    let mut command = Command::new(env!("CARGO_BIN_EXE_grep-directory"));
+
+    command.args(args)
+        .current_dir(dir)
+        .env_remove("GREP_DIRECTORY_OPTS")
+        .env_remove("GREP_DIRECTORY_COMPAT_V0")
+        .env_remove("NO_COLOR")
+        .env("XDG_CONFIG_HOME", dir.join("no-such-config-dir"));
+    return command;
+}
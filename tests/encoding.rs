@@ -0,0 +1,367 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+mod common;
+
+use common::Fixture;
+
+//the same text in each fixture, with "needle" on lines 2 and 3, after an 'é' on line 3
+const TEXT: &str = "first line\nthe needle here\nlast é needle\n";
+
+/**
+ * returns TEXT encoded as utf-16, little endian or big endian, without a byte order mark
+ */
+fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+    return text.encode_utf16().flat_map(|unit| if big_endian {unit.to_be_bytes()} else {unit.to_le_bytes()}).collect();
+}
+
+/**
+ * returns a fixture with TEXT in each encoding: utf8.txt, bom8.txt (utf-8 with a byte order mark), le.txt and be.txt (utf-16 with byte order marks),
+ * le-no-bom.txt, and latin1.txt
+ */
+fn every_encoding() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("utf8.txt", TEXT);
+    fixture.file("bom8.txt", [b"\xEF\xBB\xBF".as_slice(), TEXT.as_bytes()].concat());
+    fixture.file("le.txt", [b"\xFF\xFE".to_vec(), utf16(TEXT, false)].concat());
+    fixture.file("be.txt", [b"\xFE\xFF".to_vec(), utf16(TEXT, true)].concat());
+    fixture.file("le-no-bom.txt", utf16(TEXT, false));
+    fixture.file("latin1.txt", TEXT.chars().map(|c| c as u8).collect::<Vec<u8>>());
+    return fixture;
+}
+
+#[test]
+fn auto_finds_the_query_in_utf8_and_utf16_with_a_bom() {
+    //DATA
+    let fixture = every_encoding();
+
+    for name in ["utf8.txt", "bom8.txt", "le.txt", "be.txt"] {
+        assert_eq!(fixture.run(&["-n", name, "needle"]).lines(), vec!["2:the needle here", "3:last é needle"], "{}", name);
+    }
+}
+
+#[test]
+fn explicit_encodings_decode_files_without_a_bom() {
+    //DATA
+    let fixture = every_encoding();
+
+    assert_eq!(fixture.run(&["-n", "--encoding", "utf-16le", "le-no-bom.txt", "needle"]).lines(), vec!["2:the needle here", "3:last é needle"]);
+    assert_eq!(fixture.run(&["-n", "--encoding", "latin1", "latin1.txt", "é needle"]).lines(), vec!["3:last é needle"]);
+    //an explicit encoding still skips its own byte order mark
+    assert_eq!(fixture.run(&["-n", "--encoding", "utf-16be", "be.txt", "first"]).lines(), vec!["1:first line"]);
+}
+
+#[test]
+fn columns_are_counted_in_the_decoded_text() {
+    //DATA
+    let fixture = every_encoding();
+
+    for name in ["utf8.txt", "le.txt", "be.txt"] {
+        assert_eq!(fixture.run(&["--column", name, "needle"]).lines(), vec!["2:5:the needle here", "3:8:last é needle"], "{}", name);
+    }
+}
+
+#[test]
+fn byte_offsets_are_counted_in_the_file() {
+    //DATA
+    let fixture = every_encoding();
+
+    assert_eq!(fixture.run(&["-b", "utf8.txt", "needle"]).lines(), vec!["2:15:the needle here", "3:35:last é needle"]);
+    assert_eq!(fixture.run(&["-b", "bom8.txt", "needle"]).lines(), vec!["2:18:the needle here", "3:38:last é needle"]);
+    //utf-16 takes 2 bytes for each character, after the 2 byte order mark bytes
+    assert_eq!(fixture.run(&["-b", "le.txt", "needle"]).lines(), vec!["2:32:the needle here", "3:70:last é needle"]);
+    assert_eq!(fixture.run(&["-b", "be.txt", "needle"]).lines(), vec!["2:32:the needle here", "3:70:last é needle"]);
+    assert_eq!(fixture.run(&["-b", "--encoding", "utf-16le", "le-no-bom.txt", "needle"]).lines(), vec!["2:30:the needle here", "3:68:last é needle"]);
+    //latin1 takes 1 byte for 'é', where utf-8 takes 2
+    assert_eq!(fixture.run(&["-b", "--encoding", "latin1", "latin1.txt", "needle"]).lines(), vec!["2:15:the needle here", "3:34:last é needle"]);
+}
+
+#[test]
+fn byte_offsets_are_counted_in_the_file_with_multiline() {
+    //DATA
+    let fixture = every_encoding();
+
+    assert_eq!(fixture.run(&["-b", "-U", "le.txt", "here\nlast"]).lines(), vec!["2:46:the needle here", "3:56:last é needle"]);
+    assert_eq!(fixture.run(&["-b", "-U", "--encoding", "latin1", "latin1.txt", "é needle"]).lines(), vec!["3:32:last é needle"]);
+}
+
+#[test]
+fn decode_errors_are_reported_for_each_file() {
+    //DATA
+    let fixture = Fixture::new();
+    //an unpaired surrogate, then half a character
+    fixture.file("bad.txt", b"\xFF\xFEn\x00\x00\xD8e\x00");
+    fixture.file("odd.txt", b"\xFF\xFEn\x00e");
+    fixture.file("good.txt", "needle\n");
+    let output = fixture.run(&["--verbose", ".", "n"]);
+
+    assert_eq!(output.lines(), vec!["./good.txt:1:needle"]);
+    assert!(output.stderr.contains("unpaired surrogate 0xd800"), "{}", output.stderr);
+    assert!(output.stderr.contains("it ends halfway through a character"), "{}", output.stderr);
+}
+
+#[test]
+fn unknown_encodings_are_rejected() {
+    //DATA
+    let fixture = every_encoding();
+
+    assert!(fixture.run(&["--encoding", "ebcdic", ".", "needle"]).stderr.contains("Invalid encoding 'ebcdic', expected auto, utf-8, utf-16le, utf-16be, or latin1."));
+}
+
+/**
+ * returns a fixture with obj.o, which has NUL bytes around "needle", and t.txt, which is plain text with "needle" in it
+ */
+fn with_binary() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("obj.o", b"ELF\0\0needle\0junk\nneedle two\n");
+    fixture.file("t.txt", "needle\n");
+    return fixture;
+}
+
+#[test]
+fn binary_files_are_skipped_by_default() {
+    //DATA
+    let fixture = with_binary();
+    let output = fixture.run(&["--verbose", "--stats", ".", "needle"]);
+
+    assert_eq!(output.lines()[0], "./t.txt:1:needle");
+    assert!(output.stderr.contains("Skipping \"./obj.o\": binary file"), "{}", output.stderr);
+    assert!(output.stdout.contains("\tfiles skipped: 1\n"), "{}", output.stdout);
+    //without --verbose, they're skipped silently
+    assert_eq!(fixture.run(&[".", "needle"]).stderr, "");
+}
+
+#[test]
+fn nul_bytes_after_the_start_of_a_file_dont_make_it_binary() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("late.txt", "a".repeat(9000) + "\0needle\n");
+
+    assert_eq!(fixture.run(&["-l", ".", "needle"]).lines(), vec!["./late.txt"]);
+}
+
+#[test]
+fn text_searches_binary_files_as_text() {
+    //DATA
+    let fixture = with_binary();
+
+    assert_eq!(fixture.run(&["--text", ".", "needle"]).stdout, "./obj.o:1:ELF\0\0needle\0junk\n./obj.o:2:needle two\n./t.txt:1:needle\n");
+}
+
+#[test]
+fn binary_reports_matches_without_printing_them() {
+    //DATA
+    let fixture = with_binary();
+
+    assert_eq!(fixture.run(&["--binary", ".", "needle"]).lines(), vec!["binary file ./obj.o matches", "./t.txt:1:needle"]);
+    assert_eq!(fixture.run(&["--binary", "-l", ".", "needle"]).lines(), vec!["./obj.o", "./t.txt"]);
+    assert_eq!(fixture.run(&["--binary", "-c", ".", "needle"]).lines(), vec!["./obj.o: 2", "./t.txt: 1"]);
+}
+
+/**
+ * returns a fixture with bin.dat, which is the bytes 0 to 31, "needle" at offset 32, 10 0xFF bytes, "needle" again at offset 48,
+ * the bytes 200 to 239, "far needle" at offset 94, and 8 NUL bytes, 112 bytes in all
+ */
+fn with_hex() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    let mut bytes: Vec<u8> = (0..32).collect();
+
+    bytes.extend_from_slice(b"needle");
+    bytes.extend_from_slice(&[0xFF; 10]);
+    bytes.extend_from_slice(b"needle");
+    bytes.extend(200..240);
+    bytes.extend_from_slice(b"far needle");
+    bytes.extend_from_slice(&[0; 8]);
+    fixture.file("bin.dat", bytes);
+    return fixture;
+}
+
+#[test]
+fn binary_context_dumps_the_bytes_around_each_match() {
+    assert_eq!(with_hex().run(&["--binary", "--binary-context", "4", "bin.dat", "needle"]).stdout, concat!(
+        "binary file bin.dat matches\n",
+        "0000001c: 1c1d 1e1f 6e65 6564 6c65 ffff ffff       ....needle....\n",
+        "--\n",
+        "0000002c: ffff ffff 6e65 6564 6c65 c8c9 cacb       ....needle....\n",
+        "--\n",
+        "0000005e: 6661 7220 6e65 6564 6c65 0000 0000       far needle....\n",
+    ));
+}
+
+#[test]
+fn binary_context_merges_dumps_that_touch() {
+    assert_eq!(with_hex().run(&["--binary", "--binary-context", "5", "bin.dat", "needle"]).lines(), vec![
+        "binary file bin.dat matches",
+        "0000001b: 1b1c 1d1e 1f6e 6565 646c 65ff ffff ffff  .....needle.....",
+        "0000002b: ffff ffff ff6e 6565 646c 65c8 c9ca cbcc  .....needle.....",
+        "--",
+        "0000005d: ef66 6172 206e 6565 646c 6500 0000 0000  .far needle.....",
+    ]);
+}
+
+#[test]
+fn binary_context_stops_at_the_end_of_the_file() {
+    assert_eq!(with_hex().run(&["--binary", "--binary-context", "40", "bin.dat", "far needle"]).lines(), vec![
+        "binary file bin.dat matches",
+        "00000036: c8c9 cacb cccd cecf d0d1 d2d3 d4d5 d6d7  ................",
+        "00000046: d8d9 dadb dcdd dedf e0e1 e2e3 e4e5 e6e7  ................",
+        "00000056: e8e9 eaeb eced eeef 6661 7220 6e65 6564  ........far need",
+        "00000066: 6c65 0000 0000 0000 0000                 le........",
+    ]);
+}
+
+#[test]
+fn binary_context_offsets_are_from_the_start_of_big_files() {
+    //DATA
+    let fixture = Fixture::new();
+    //far enough in that it's not in the first chunk that's read
+    fixture.file("big.bin", [vec![0; 100_000], b"needle".to_vec(), vec![1; 100_000]].concat());
+
+    assert_eq!(fixture.run(&["--binary", "--binary-context", "3", "big.bin", "needle"]).lines(), vec![
+        "binary file big.bin matches",
+        "0001869d: 0000 006e 6565 646c 6501 0101            ...needle...",
+    ]);
+}
+
+#[test]
+fn binary_context_needs_binary() {
+    //DATA
+    let fixture = with_hex();
+
+    assert!(fixture.run(&["--binary-context", "4", "bin.dat", "needle"]).stderr.contains("--binary-context is about how --binary prints the matches in binary files"));
+    //and there's nothing to dump when the lines aren't being printed
+    assert_eq!(fixture.run(&["--binary", "--binary-context", "4", "-l", "bin.dat", "needle"]).lines(), vec!["bin.dat"]);
+}
+
+/**
+ * returns a fixture with bad.txt, where "needle" is surrounded by bytes that aren't valid utf-8, and a line with an upper case NEEDLE after one
+ */
+fn invalid_utf8() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("bad.txt", b"ok\n\xFF\xFEneedle\xC3(\nNEEDLE \xFF\n");
+    return fixture;
+}
+
+#[test]
+fn invalid_utf8_is_searched_around() {
+    //DATA
+    let fixture = invalid_utf8();
+
+    assert_eq!(fixture.run(&["-l", ".", "needle"]).lines(), vec!["./bad.txt"]);
+    //the invalid bytes are printed as U+FFFD
+    assert_eq!(fixture.run(&["bad.txt", "needle"]).stdout, "2:\u{FFFD}\u{FFFD}needle\u{FFFD}(\n");
+    assert_eq!(fixture.run(&["-c", "-i", "bad.txt", "needle"]).stdout, "2\n");
+}
+
+#[test]
+fn invalid_utf8_doesnt_move_byte_offsets() {
+    //DATA
+    let fixture = invalid_utf8();
+
+    assert_eq!(fixture.run(&["-b", "-o", "bad.txt", "needle"]).lines(), vec!["2:5:needle"]);
+    assert_eq!(fixture.run(&["-b", "-o", "-i", "bad.txt", "needle"]).lines(), vec!["2:5:needle", "3:14:NEEDLE"]);
+    assert_eq!(fixture.run(&["-b", "-U", "bad.txt", "NEEDLE"]).lines(), vec!["3:14:NEEDLE \u{FFFD}"]);
+}
+
+#[test]
+fn strict_utf8_skips_invalid_files() {
+    //DATA
+    let fixture = invalid_utf8();
+    let output = fixture.run(&["--strict-utf8", "-l", ".", "needle"]);
+
+    assert_eq!((output.code, output.stdout.as_str()), (2, ""));
+    assert_eq!(output.stderr, "1 file couldn't be read (invalid data: 1), rerun with --verbose for details\n");
+    assert!(fixture.run(&["--strict-utf8", "-v", ".", "needle"]).stderr.contains("Error searching \"./bad.txt\": stream did not contain valid UTF-8"));
+}
+
+/**
+ * returns a fixture with the same four lines, with "foo" on lines 2 and 4, in lf.txt, crlf.txt, and mixed.txt (with both, and no line ending at the end)
+ */
+fn line_endings() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("lf.txt", "a\nfoo\n  foo bar\nfoo\n");
+    fixture.file("crlf.txt", "a\r\nfoo\r\n  foo bar\r\nfoo\r\n");
+    fixture.file("mixed.txt", "a\r\nfoo\n  foo bar\r\nfoo");
+    return fixture;
+}
+
+#[test]
+fn line_endings_dont_change_lines_or_columns() {
+    //DATA
+    let fixture = line_endings();
+
+    for name in ["lf.txt", "crlf.txt", "mixed.txt"] {
+        assert_eq!(fixture.run(&["--column", name, "foo"]).stdout, "2:1:foo\n3:3:  foo bar\n4:1:foo\n", "{}", name);
+    }
+}
+
+#[test]
+fn line_endings_dont_get_in_the_way_of_line_regexp() {
+    //DATA
+    let fixture = line_endings();
+
+    for name in ["lf.txt", "crlf.txt", "mixed.txt"] {
+        assert_eq!(fixture.run(&["-x", "-n", name, "foo"]).stdout, "2:foo\n4:foo\n", "{}", name);
+        assert_eq!(fixture.run(&["-E", "-n", name, "foo$"]).stdout, "2:foo\n4:foo\n", "{}", name);
+    }
+}
+
+#[test]
+fn line_endings_arent_printed_with_context() {
+    //DATA
+    let fixture = line_endings();
+
+    for name in ["lf.txt", "crlf.txt", "mixed.txt"] {
+        assert_eq!(fixture.run(&["-C1", name, "bar"]).stdout, "2-foo\n3:  foo bar\n4-foo\n", "{}", name);
+    }
+}
+
+#[test]
+fn line_endings_still_count_in_byte_offsets() {
+    //DATA
+    let fixture = line_endings();
+
+    assert_eq!(fixture.run(&["-b", "lf.txt", "foo"]).lines(), vec!["2:2:foo", "3:8:  foo bar", "4:16:foo"]);
+    assert_eq!(fixture.run(&["-b", "crlf.txt", "foo"]).lines(), vec!["2:3:foo", "3:10:  foo bar", "4:19:foo"]);
+    assert_eq!(fixture.run(&["-b", "mixed.txt", "foo"]).lines(), vec!["2:3:foo", "3:9:  foo bar", "4:18:foo"]);
+}
+
+#[test]
+fn multiline_queries_can_have_carriage_returns() {
+    //DATA
+    let fixture = line_endings();
+
+    assert_eq!(fixture.run(&["-U", "-l", ".", "foo\r\n  foo"]).lines(), vec!["./crlf.txt"]);
+    assert_eq!(fixture.run(&["-U", "-n", "crlf.txt", "foo\r\n  foo"]).lines(), vec!["2:foo", "3:  foo bar"]);
+}
+
+#[test]
+fn a_utf8_bom_doesnt_get_in_the_way_of_the_first_line() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("bom.rs", b"\xEF\xBB\xBFuse std;\nuse x;\n");
+
+    assert_eq!(fixture.run(&["-x", "-n", "bom.rs", "use std;"]).lines(), vec!["1:use std;"]);
+    assert_eq!(fixture.run(&["-E", "-n", "bom.rs", "^use std"]).lines(), vec!["1:use std;"]);
+    assert_eq!(fixture.run(&["--column", "bom.rs", "std"]).lines(), vec!["1:5:use std;"]);
+    //byte offsets still count it
+    assert_eq!(fixture.run(&["-b", "bom.rs", "use"]).lines(), vec!["1:3:use std;", "2:12:use x;"]);
+}
+
+#[test]
+fn a_file_thats_just_a_bom_is_empty() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("bom.txt", b"\xEF\xBB\xBF");
+
+    assert_eq!(fixture.run(&["-c", "--include-zero", "bom.txt", "x"]).lines(), vec!["0"]);
+    assert_eq!(fixture.run(&["--allow-blank-query", "-c", "--include-zero", "bom.txt", " "]).lines(), vec!["0"]);
+}
@@ -0,0 +1,51 @@
+# bash completion for grep-directory, generated by grep-directory --completions bash
+_grep_directory() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "$prev" in
+        --anchor) COMPREPLY=($(compgen -W "start end both" -- "$cur")); return;;
+        --fuzzy) return;;
+        -A|--after-context) return;;
+        -B|--before-context) return;;
+        -C|--context) return;;
+        --replace) return;;
+        --relative-to) COMPREPLY=($(compgen -d -- "$cur")); return;;
+        --format) return;;
+        --output) COMPREPLY=($(compgen -f -- "$cur")); return;;
+        -f|--filter) return;;
+        --include) return;;
+        --exclude) return;;
+        -t|--type) COMPREPLY=($(compgen -W "c cpp csharp css go html java js json make markdown py ruby rust sh sql toml ts txt web xml yaml zig" -- "$cur")); return;;
+        -T|--type-not) COMPREPLY=($(compgen -W "c cpp csharp css go html java js json make markdown py ruby rust sh sql toml ts txt web xml yaml zig" -- "$cur")); return;;
+        --type-add) return;;
+        --exclude-dir) return;;
+        --max-depth) return;;
+        --min-depth) return;;
+        --ignore-file) COMPREPLY=($(compgen -f -- "$cur")); return;;
+        -m|--max-count) return;;
+        --binary-context) return;;
+        --pre) COMPREPLY=($(compgen -f -- "$cur")); return;;
+        --pre-glob) return;;
+        --pre-timeout) return;;
+        --encoding) COMPREPLY=($(compgen -W "auto utf-8 utf-16le utf-16be latin1" -- "$cur")); return;;
+        --max-filesize) return;;
+        --min-size) return;;
+        --max-size) return;;
+        --newer-than) return;;
+        --older-than) return;;
+        --threads) return;;
+        --sort) COMPREPLY=($(compgen -W "path mtime size none" -- "$cur")); return;;
+        --color) COMPREPLY=($(compgen -W "auto always never" -- "$cur")); return;;
+        --hyperlinks) COMPREPLY=($(compgen -W "auto on off" -- "$cur")); return;;
+        -e|--regexp) return;;
+        --pattern-file) COMPREPLY=($(compgen -f -- "$cur")); return;;
+        --not) return;;
+        -p|--path) COMPREPLY=($(compgen -f -- "$cur")); return;;
+        --completions) COMPREPLY=($(compgen -W "bash zsh fish" -- "$cur")); return;;
+    esac
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "-i --ignore-case -s --case-sensitive --case-insensitive --smart-case -E --regex -U --multiline -F --fixed-strings -w --word-regexp -x --line-regexp --ignore-accents --anchor --anchor-ignore-whitespace --fuzzy -l --files-with-matches -L --files-without-match -c --count -n --line-number --compat-v0 --column -b --byte-offset -o --only-matching -A --after-context -B --before-context -C --context --replace --passthru -H --with-filename --no-filename --absolute-paths --relative-to --heading --no-heading --plain --no-plain -0 --null --json --jsonl --vimgrep --format --csv --output --stats --count-matches --include-zero --files --filenames --also-match-names -I --invert-match -f --filter --include --exclude -t --type -T --type-not --type-list --type-add --exclude-dir -r --recursive --max-depth --min-depth --hidden --follow --no-ignore --ignore-file --one-file-system --no-dedupe -v --verbose -q --quiet -m --max-count -a --text --binary --binary-context -z --search-zip --pre --pre-glob --pre-timeout --strict-utf8 --allow-blank-query --encoding --max-filesize --min-size --max-size --newer-than --older-than --threads --sort --color --hyperlinks -e --regexp --pattern-file --all-of --not -p --path --no-config --generate-man --completions -h --help -V --version" -- "$cur"))
+    else
+        COMPREPLY=($(compgen -f -- "$cur"))
+    fi
+}
+complete -o filenames -F _grep_directory grep-directory
@@ -0,0 +1,98 @@
+# fish completion for grep-directory, generated by grep-directory --completions fish
+complete -c grep-directory -s i -l ignore-case -d 'Case insensitive search'
+complete -c grep-directory -s s -l case-sensitive -d 'Case sensitive search (the default)'
+complete -c grep-directory -l case-insensitive -d 'Same as --ignore-case (deprecated)'
+complete -c grep-directory -l smart-case -d 'Case insensitive if the query is all lowercase, case sensitive otherwise (-i and -s win over this)'
+complete -c grep-directory -s E -l regex -d 'Treat the query and patterns as regular expressions, like "fn\\s+\\w+\\(" (the default is plain text)'
+complete -c grep-directory -s U -l multiline -d 'Search the whole contents of each file at once, so the query can span lines, e.g. $\'#[test]\\nfn \''
+complete -c grep-directory -s F -l fixed-strings -d 'Treat the query and patterns as plain text, undoing an earlier --regex'
+complete -c grep-directory -s w -l word-regexp -d 'Only match whole words, the query can\'t touch any other letters, digits, or underscores'
+complete -c grep-directory -s x -l line-regexp -d 'Only match whole lines, the query has to be everything on the line except the line ending'
+complete -c grep-directory -l ignore-accents -d 'Ignore accents on letters, so resume matches résumé, and Muller matches Müller (with -i, the case is ignored too), plain text only'
+complete -c grep-directory -l anchor -r -f -a 'start end both' -d 'Only match the query at the start of the line, the end of it (not counting the line ending), or both, e.g. --anchor start \'use \' (works with -i and -w)'
+complete -c grep-directory -l anchor-ignore-whitespace -d 'With --anchor, whitespace at the start or end of the line doesn\'t count, so indented lines (or ones with trailing spaces) can still match'
+complete -c grep-directory -l fuzzy -r -f -d 'Also match text up to N edits away from the query (an edit is adding, removing, or changing a character, or swapping two next to each other)'
+complete -c grep-directory -s l -l files-with-matches -d 'List the files that contain the query, instead of their lines (the default output mode with --compat-v0)'
+complete -c grep-directory -s L -l files-without-match -d 'List the files that don\'t contain the query (an output mode, can\'t be used with -l)'
+complete -c grep-directory -s c -l count -d 'List the files that contain the query, with how many times they contain it, as "path: N"'
+complete -c grep-directory -s n -l line-number -d 'Print every line that contains the query, as "path:line number:line" (the default output mode)'
+complete -c grep-directory -l compat-v0 -d 'List the files that contain the query by default, like older versions did, instead of printing their lines'
+complete -c grep-directory -l column -d 'Also print the column of the first match on each line (in characters, starting from 1), implies -n'
+complete -c grep-directory -s b -l byte-offset -d 'Also print the byte offset of the first match on each line (or in each file, when listing files), from the start of the file'
+complete -c grep-directory -s o -l only-matching -d 'Print just the matched text, each match on a line of its own, implies -n'
+complete -c grep-directory -s A -l after-context -r -f -d 'Also print N lines after each matching line, implies -n'
+complete -c grep-directory -s B -l before-context -r -f -d 'Also print N lines before each matching line, implies -n'
+complete -c grep-directory -s C -l context -r -f -d 'Also print N lines before and after each matching line, implies -n'
+complete -c grep-directory -l replace -r -f -d 'Print matching lines with the matches replaced by TEXT (files aren\'t changed), implies -n'
+complete -c grep-directory -l passthru -d 'Print every line of the files that have matches, with the matches highlighted, implies -n'
+complete -c grep-directory -s H -l with-filename -d 'Start every line (and count) with its file\'s name, even when searching just one file'
+complete -c grep-directory -l no-filename -d 'Don\'t print file names next to lines and counts (the default when the only PATH is a file)'
+complete -c grep-directory -l absolute-paths -d 'Print the absolute path of each file, with symlinks resolved, instead of the path it was found at'
+complete -c grep-directory -l relative-to -r -f -a '(__fish_complete_directories)' -d 'Print the path of each file relative to DIR (with symlinks resolved), or its absolute path if it isn\'t under DIR'
+complete -c grep-directory -l heading -d 'When printing lines, print each file\'s name once above its lines (the default in a terminal)'
+complete -c grep-directory -l no-heading -d 'When printing lines, start every line with its file\'s name (the default outside of a terminal)'
+complete -c grep-directory -l plain -d 'Print just the results, one per line like grep\'s, without the banner, headers, or indentation (the default outside of a terminal)'
+complete -c grep-directory -l no-plain -d 'Print the banner, headers, and indentation, even outside of a terminal'
+complete -c grep-directory -s 0 -l null -d 'When listing files, end each file name with a null instead of a newline, and don\'t print anything else (for xargs -0)'
+complete -c grep-directory -l json -d 'Print the results as one json document, {"results":[...],"stats":{...}}, with an object for each file (or line)'
+complete -c grep-directory -l jsonl -d 'Print each result as a json object on a line of its own, as soon as it\'s found, like {"type":"match",...}'
+complete -c grep-directory -l vimgrep -d 'Print a "path:line:column:line" line for each match (two matches on a line are two lines), for vim\'s quickfix list'
+complete -c grep-directory -l format -r -f -d 'Print each result as TEMPLATE, with {path}, {line}, {column}, {byte_offset}, {text}, {match}, and {count} filled in, e.g. \'{path}\\t{line}\\t{text}\''
+complete -c grep-directory -l csv -d 'Print the results as csv, with a header row, then "path,match_count" for each file, or "path,line_number,column,match_count,line_text" for each line'
+complete -c grep-directory -l output -r -F -d 'Write the results to FILE (it\'s never searched itself) instead of stdout, errors still go to stderr'
+complete -c grep-directory -l stats -d 'Print how many files were searched, matched, and skipped, how many matches there were, and how long it took at the end'
+complete -c grep-directory -l count-matches -d 'Print the total number of times the query is in all the files put together (with -m, each file\'s count is capped first)'
+complete -c grep-directory -l include-zero -d 'With --count, also list the files that don\'t contain the query'
+complete -c grep-directory -l files -d 'List the files that would be searched, without searching them (no query is needed)'
+complete -c grep-directory -l filenames -d 'List the files whose names match the query (with the same case, -w, -x, and --regex rules), without opening them, like a quick find'
+complete -c grep-directory -l also-match-names -d 'Also match files whose names match the query, even if their contents don\'t (or can\'t be read), these are tagged with [name]'
+complete -c grep-directory -s I -l invert-match -d 'List the files that don\'t contain the query instead, files that can\'t be read are never listed'
+complete -c grep-directory -s f -l filter -r -f -d 'Comma separated list of extensions, will only search files with these extensions'
+complete -c grep-directory -l include -r -f -d 'Only search files whose names match GLOB (*, ?, and [...] are supported), can be given multiple times'
+complete -c grep-directory -l exclude -r -f -d 'Don\'t search files whose names match GLOB, can be given multiple times'
+complete -c grep-directory -s t -l type -r -f -a 'c cpp csharp css go html java js json make markdown py ruby rust sh sql toml ts txt web xml yaml zig' -d 'Only search files of type TYPE (like rust, or web), the same as an --include for each of its globs'
+complete -c grep-directory -s T -l type-not -r -f -a 'c cpp csharp css go html java js json make markdown py ruby rust sh sql toml ts txt web xml yaml zig' -d 'Don\'t search files of type TYPE, the same as an --exclude for each of its globs'
+complete -c grep-directory -l type-list -d 'List the file types, and the globs for each'
+complete -c grep-directory -l type-add -r -f -d 'Add GLOB to the file type NAME (making it if it\'s new), e.g. \'proto:*.proto\', can be given multiple times'
+complete -c grep-directory -l exclude-dir -r -f -d 'With -r, don\'t go into directories named NAME (like target or node_modules), can be given multiple times'
+complete -c grep-directory -s r -l recursive -d 'Search through subdirectories'
+complete -c grep-directory -l max-depth -r -f -d 'With -r, don\'t go more than N levels of subdirectories deep (0 is just PATH itself)'
+complete -c grep-directory -l min-depth -r -f -d 'With -r, skip files less than N levels of subdirectories deep'
+complete -c grep-directory -l hidden -d 'Also search hidden files and directories (ones starting with a \'.\'), these are skipped by default'
+complete -c grep-directory -l follow -d 'With -r, go into symlinked directories and search symlinked files (each directory is only searched once, so loops are fine)'
+complete -c grep-directory -l no-ignore -d 'With -r, also search files that .gitignore files say to ignore'
+complete -c grep-directory -l ignore-file -r -F -d 'With -r, also skip what FILE (in .gitignore syntax, relative to PATH) ignores, can be given multiple times'
+complete -c grep-directory -l one-file-system -d 'With -r, don\'t go into directories on other filesystems, like mounts'
+complete -c grep-directory -l no-dedupe -d 'Search a file once for every path it can be reached by (through links, or PATHs given twice), instead of just once'
+complete -c grep-directory -s v -l verbose -d 'include all error messages in output'
+complete -c grep-directory -s q -l quiet -d 'Don\'t print anything, just exit with the status (errors still go to stderr with -v)'
+complete -c grep-directory -s m -l max-count -r -f -d 'Stop reading a file after N matches'
+complete -c grep-directory -s a -l text -d 'Search binary files (ones with a NUL byte near the start) as text, instead of skipping them'
+complete -c grep-directory -l binary -d 'Search binary files, but just print "binary file <path> matches" instead of their lines'
+complete -c grep-directory -l binary-context -r -f -d 'With --binary, also print a hex dump (like xxd\'s) of each match in a binary file, and N bytes before and after it, matches close together share one'
+complete -c grep-directory -s z -l search-zip -d 'Search inside gzip files (.gz), decompressing them as they\'re read, results are reported with the path of the .gz'
+complete -c grep-directory -l pre -r -F -d 'Run COMMAND with the path of each file as its argument, and search what it prints instead of the file (for pdfs, databases, and the like)'
+complete -c grep-directory -l pre-glob -r -f -d 'With --pre, only run COMMAND on files whose names match GLOB (the rest are searched as they are), can be given multiple times'
+complete -c grep-directory -l pre-timeout -r -f -d 'With --pre, kill COMMAND if it takes longer than SECONDS on a file (e.g. 10, or 2.5), which is an error for that file'
+complete -c grep-directory -l strict-utf8 -d 'Skip files that aren\'t valid utf-8, instead of searching them with the invalid bytes replaced'
+complete -c grep-directory -l allow-blank-query -d 'Allow a query (or pattern) that\'s just whitespace, like a tab, these are errors by default'
+complete -c grep-directory -l encoding -r -f -a 'auto utf-8 utf-16le utf-16be latin1' -d 'Read files as auto (the default, utf-8 unless there\'s a utf-16 byte order mark), utf-8, utf-16le, utf-16be, or latin1'
+complete -c grep-directory -l max-filesize -r -f -d 'Skip files larger than SIZE, e.g. 512, 10K, 10M, or 1G'
+complete -c grep-directory -l min-size -r -f -d 'Only search files at least SIZE big (like --max-filesize\'s SIZE), to leave out tiny stubs, with --files, only list those'
+complete -c grep-directory -l max-size -r -f -d 'Only search files at most SIZE big, with --files, only list those, files skipped by either are counted in --stats'
+complete -c grep-directory -l newer-than -r -f -d 'Only search files modified at or after TIME, which is how long ago (30m, 12h, 2d, or 1w), or a date, 2024-01-01 (or 2024-01-01T12:30), in UTC'
+complete -c grep-directory -l older-than -r -f -d 'Only search files modified before TIME (like --newer-than\'s), both can be given to search the files modified in between'
+complete -c grep-directory -l threads -r -f -d 'Search N files at a time (default: the number of CPUs), results are still printed in order'
+complete -c grep-directory -l sort -r -f -a 'path mtime size none' -d 'The order to search and print files in: path (the default), mtime (oldest first), size (smallest first), or none (fastest)'
+complete -c grep-directory -l color -r -f -a 'auto always never' -d 'When to color file names and matches: auto (only in a terminal, and without NO_COLOR), always, or never'
+complete -c grep-directory -l hyperlinks -r -f -a 'auto on off' -d 'When to make file names clickable links to the files, in terminals that support it: auto (whenever there\'s color), on, or off'
+complete -c grep-directory -s e -l regexp -r -f -d 'Also search for PATTERN, can be given multiple times'
+complete -c grep-directory -l pattern-file -r -F -d 'Also search for every pattern in FILE, one per line, lines starting with \'#\' are ignored'
+complete -c grep-directory -l all-of -d 'Only match files that contain every one of the patterns (and the query), instead of any of them'
+complete -c grep-directory -l not -r -f -d 'Don\'t match files that contain PATTERN anywhere (matched like the query, with the same case rules), can be given multiple times'
+complete -c grep-directory -s p -l path -r -F -d 'Also search in PATH, can be given multiple times'
+complete -c grep-directory -l no-config -d 'Don\'t load default options from the config file'
+complete -c grep-directory -l generate-man -d 'Print the man page (in roff), e.g. grep-directory --generate-man > grep-directory.1'
+complete -c grep-directory -l completions -r -f -a 'bash zsh fish' -d 'Print a completion script for SHELL (bash, zsh, or fish), e.g. grep-directory --completions bash > ~/.local/share/bash-completion/completions/grep-directory'
+complete -c grep-directory -s h -l help -d 'Prints help information, so does a bare "help"'
+complete -c grep-directory -s V -l version -d 'Prints version information, so does a bare "version"'
@@ -0,0 +1,959 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+mod common;
+
+use common::Fixture;
+use std::fs::File;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/**
+ * returns a fixture with m.txt, where the first line starts with a 2-byte 'é', so columns and byte offsets differ
+ */
+fn multibyte() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("m.txt", "éab ab\nab\n");
+    return fixture;
+}
+
+#[test]
+fn column_counts_characters_from_one() {
+    //DATA
+    let fixture = multibyte();
+
+    assert_eq!(fixture.run(&["--column", "m.txt", "ab"]).lines(), vec!["1:2:éab ab", "2:1:ab"]);
+}
+
+#[test]
+fn column_works_when_the_match_differs_in_case() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("i.txt", "xİAb\n");
+
+    assert_eq!(fixture.run(&["--column", "-i", "i.txt", "ab"]).lines(), vec!["1:3:xİAb"]);
+}
+
+#[test]
+fn byte_offset_counts_bytes_from_the_start_of_the_file() {
+    //DATA
+    let fixture = multibyte();
+
+    assert_eq!(fixture.run(&["-b", "m.txt", "ab"]).lines(), vec!["1:2:éab ab", "2:8:ab"]);
+    assert_eq!(fixture.run(&["-b", "--column", "m.txt", "ab"]).lines(), vec!["1:2:2:éab ab", "2:1:8:ab"]);
+}
+
+#[test]
+fn byte_offset_counts_carriage_returns() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("crlf.txt", "a\r\nab\r\n");
+
+    assert_eq!(fixture.run(&["-b", "crlf.txt", "ab"]).lines(), vec!["2:3:ab"]);
+    assert_eq!(fixture.run(&["-b", "-i", "crlf.txt", "AB"]).lines(), vec!["2:3:ab"]);
+}
+
+#[test]
+fn byte_offset_uses_the_file_not_the_lowercased_copy() {
+    //DATA
+    let fixture = Fixture::new();
+    //'İ' is 2 bytes, but lowercases to 3 ("i" and a combining dot)
+    fixture.file("i.txt", "İİab\n");
+
+    assert_eq!(fixture.run(&["-b", "-i", "i.txt", "AB"]).lines(), vec!["1:4:İİab"]);
+}
+
+#[test]
+fn byte_offset_with_files_is_the_first_match_in_each_file() {
+    //DATA
+    let fixture = multibyte();
+    fixture.file("crlf.txt", "a\r\nab\r\n");
+
+    assert_eq!(fixture.run(&["-l", "-b", ".", "ab"]).lines(), vec!["./crlf.txt:3", "./m.txt:2"]);
+}
+
+/**
+ * returns a fixture with c.txt, which has lines 1 to 20, except that lines 5, 8 (3 lines after 5), and 16 are "match" and the line number
+ */
+fn context() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    let lines: Vec<String> = (1..=20).map(|n| if [5, 8, 16].contains(&n) { format!("match{}", n) } else { n.to_string() }).collect();
+
+    fixture.file("c.txt", &(lines.join("\n") + "\n"));
+    return fixture;
+}
+
+#[test]
+fn context_merges_overlapping_regions() {
+    //DATA
+    let fixture = context();
+
+    assert_eq!(fixture.run(&["-C", "2", "c.txt", "match"]).lines(), vec![
+        "3-3", "4-4", "5:match5", "6-6", "7-7", "8:match8", "9-9", "10-10",
+        "--",
+        "14-14", "15-15", "16:match16", "17-17", "18-18",
+    ]);
+}
+
+#[test]
+fn context_before_and_after_separate_groups() {
+    //DATA
+    let fixture = context();
+
+    assert_eq!(fixture.run(&["-A", "1", "c.txt", "match"]).lines(), vec!["5:match5", "6-6", "--", "8:match8", "9-9", "--", "16:match16", "17-17"]);
+    assert_eq!(fixture.run(&["-B", "1", "c.txt", "match"]).lines(), vec!["4-4", "5:match5", "--", "7-7", "8:match8", "--", "15-15", "16:match16"]);
+}
+
+#[test]
+fn context_stops_at_the_ends_of_the_file() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("c.txt", "match\n2\n3\nmatch");
+
+    assert_eq!(fixture.run(&["-C", "5", "c.txt", "match"]).lines(), vec!["1:match", "2-2", "3-3", "4:match"]);
+}
+
+#[test]
+fn context_is_prefixed_with_the_path() {
+    //DATA
+    let fixture = context();
+
+    assert_eq!(fixture.run(&["-C1", ".", "match16"]).lines(), vec!["./c.txt-15-15", "./c.txt:16:match16", "./c.txt-17-17"]);
+}
+
+#[test]
+fn context_needs_a_number() {
+    //DATA
+    let fixture = context();
+    let output = fixture.run(&["-A", "foo", "c.txt", "match"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Invalid value 'foo' for '-A', expected a non-negative whole number."), "{}", output.stderr);
+}
+
+#[test]
+fn only_matching_prints_every_occurrence() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("o.txt", "x ab y ab z ab\nnone\n");
+
+    assert_eq!(fixture.run(&["-o", "o.txt", "ab"]).lines(), vec!["1:ab", "1:ab", "1:ab"]);
+}
+
+#[test]
+fn only_matching_splits_back_to_back_occurrences() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("o.txt", "ababab\n");
+
+    assert_eq!(fixture.run(&["-o", "o.txt", "ab"]).lines(), vec!["1:ab", "1:ab", "1:ab"]);
+}
+
+#[test]
+fn only_matching_handles_a_match_at_the_end_of_the_file() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("o.txt", "none\nend ab");
+
+    assert_eq!(fixture.run(&["-o", "o.txt", "ab"]).stdout, "2:ab\n");
+}
+
+#[test]
+fn only_matching_prints_the_text_from_the_file() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("o.txt", "aB Ab\n");
+
+    assert_eq!(fixture.run(&["-o", "-i", "o.txt", "ab"]).lines(), vec!["1:aB", "1:Ab"]);
+}
+
+#[test]
+fn null_separates_file_names_with_nul_bytes() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("has space.txt", "hit\n");
+    fixture.file("plain.txt", "hit\n");
+    let output = fixture.run(&["-l", "-0", ".", "hit"]);
+
+    assert_eq!(output.stdout, "./has space.txt\0./plain.txt\0");
+    //the way xargs -0 splits it
+    assert_eq!(output.stdout.split_terminator('\0').collect::<Vec<&str>>(), vec!["./has space.txt", "./plain.txt"]);
+}
+
+#[test]
+fn null_names_can_be_searched_again() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("has space.txt", "hit\n");
+    let output = fixture.run(&["-l", "-0", ".", "hit"]);
+    let name: &str = output.stdout.split_terminator('\0').next().unwrap();
+
+    assert_eq!(fixture.run(&[name, "hit"]).lines(), vec!["1:hit"]);
+}
+
+/**
+ * returns a fixture with a.txt, which has "hit" on lines 1 and 3, and d/b.txt, which has it on line 1
+ */
+fn two_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("a.txt", "hit 1\nno\nhit 2\n");
+    fixture.file("d/b.txt", "hit 3\n");
+    return fixture;
+}
+
+#[test]
+fn heading_groups_lines_under_each_path() {
+    //DATA
+    let fixture = two_files();
+
+    assert_eq!(fixture.run(&["-r", "--heading", ".", "hit"]).stdout, "./a.txt\n1:hit 1\n3:hit 2\n\n./d/b.txt\n1:hit 3\n");
+}
+
+#[test]
+fn no_heading_repeats_the_path_on_each_line() {
+    //DATA
+    let fixture = two_files();
+
+    assert_eq!(fixture.run(&["-r", "--no-heading", ".", "hit"]).stdout, "./a.txt:1:hit 1\n./a.txt:3:hit 2\n./d/b.txt:1:hit 3\n");
+    //not a terminal, so no headings by default
+    assert_eq!(fixture.run(&["-r", ".", "hit"]).stdout, fixture.run(&["-r", "--no-heading", ".", "hit"]).stdout);
+}
+
+#[test]
+fn filenames_are_hidden_for_a_single_file_by_default() {
+    //DATA
+    let fixture = two_files();
+
+    assert_eq!(fixture.run(&["a.txt", "hit"]).lines(), vec!["1:hit 1", "3:hit 2"]);
+}
+
+#[test]
+fn filenames_are_shown_for_several_files_by_default() {
+    //DATA
+    let fixture = two_files();
+
+    assert_eq!(fixture.run(&["-r", ".", "hit"]).lines(), vec!["./a.txt:1:hit 1", "./a.txt:3:hit 2", "./d/b.txt:1:hit 3"]);
+    assert_eq!(fixture.run(&["-p", "a.txt", "-p", "d/b.txt", "hit"]).lines(), vec!["a.txt:1:hit 1", "a.txt:3:hit 2", "d/b.txt:1:hit 3"]);
+}
+
+#[test]
+fn with_filename_shows_it_for_a_single_file() {
+    //DATA
+    let fixture = two_files();
+
+    assert_eq!(fixture.run(&["-H", "a.txt", "hit"]).lines(), vec!["a.txt:1:hit 1", "a.txt:3:hit 2"]);
+    assert_eq!(fixture.run(&["--with-filename", "a.txt", "hit"]).lines(), vec!["a.txt:1:hit 1", "a.txt:3:hit 2"]);
+}
+
+#[test]
+fn no_filename_hides_it_for_several_files() {
+    //DATA
+    let fixture = two_files();
+
+    assert_eq!(fixture.run(&["--no-filename", "-r", ".", "hit"]).lines(), vec!["1:hit 1", "3:hit 2", "1:hit 3"]);
+}
+
+/**
+ * returns a fixture with 2 files that match, 1 that doesn't, 1 binary, 1 bigger than 1K, and 1 empty
+ */
+fn stats_tree() -> Fixture {
+    //DATA
+    let fixture = two_files();
+
+    fixture.file("c.txt", "none\n");
+    fixture.file("bin.dat", "hit\0bin");
+    fixture.file("big.txt", "h".repeat(3000));
+    fixture.file("empty.txt", "");
+    return fixture;
+}
+
+/**
+ * returns the stats block from output, without the elapsed line, which changes from run to run
+ */
+fn stats_block(output: &str) -> Vec<&str> {
+    return output.lines().skip_while(|line| *line != "Stats:").filter(|line| !line.starts_with("\telapsed: ")).collect();
+}
+
+#[test]
+fn stats_counts_each_decision() {
+    //DATA
+    let fixture = stats_tree();
+    let output = fixture.run(&["-r", "--stats", "--max-filesize", "1K", ".", "hit"]);
+
+    assert_eq!(stats_block(&output.stdout), vec![
+        "Stats:",
+        "\tfiles searched: 3",
+        "\tfiles matched: 2",
+        "\ttotal matches: 3",
+        "\tfiles skipped: 2",
+        "\tskipped by date: 0",
+        "\tskipped by size: 0",
+        "\tempty files: 1",
+        "\tunreadable directories: 0",
+    ]);
+    assert!(output.stdout.lines().any(|line| line.starts_with("\telapsed: ")), "{}", output.stdout);
+}
+
+#[test]
+fn stats_come_after_the_results() {
+    //DATA
+    let fixture = two_files();
+    let output = fixture.run(&["-r", "--stats", ".", "hit"]);
+
+    assert_eq!(output.lines()[..4], ["./a.txt:1:hit 1", "./a.txt:3:hit 2", "./d/b.txt:1:hit 3", "Stats:"]);
+}
+
+#[test]
+fn stats_go_to_stderr_when_quiet() {
+    //DATA
+    let fixture = stats_tree();
+    let output = fixture.run(&["-q", "-r", "--stats", "--max-filesize", "1K", ".", "hit"]);
+
+    assert_eq!(output.stdout, "");
+    assert_eq!(stats_block(&output.stderr)[1..4], ["\tfiles searched: 3", "\tfiles matched: 2", "\ttotal matches: 3"]);
+}
+
+/**
+ * returns a fixture where the order by path is a, b, c, by age (oldest first) is c, a, b, and by size (smallest first) is b, c, a
+ */
+fn sortable() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    for (name, contents, year_offset) in [("b.txt", "x\n", 2), ("a.txt", "xxx\n", 1), ("c.txt", "xx\n", 0)] {
+        //DATA
+        let path = fixture.file(name, contents);
+        let modified: SystemTime = UNIX_EPOCH + Duration::from_secs(1_577_836_800 + year_offset * 31_536_000);
+
+        File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+    return fixture;
+}
+
+#[test]
+fn sort_by_path_is_the_default() {
+    //DATA
+    let fixture = sortable();
+
+    assert_eq!(fixture.run(&["-l", "--sort", "path", ".", "x"]).lines(), vec!["./a.txt", "./b.txt", "./c.txt"]);
+    assert_eq!(fixture.run(&["-l", ".", "x"]).lines(), vec!["./a.txt", "./b.txt", "./c.txt"]);
+}
+
+#[test]
+fn sort_by_mtime_is_oldest_first() {
+    //DATA
+    let fixture = sortable();
+
+    assert_eq!(fixture.run(&["-l", "--sort", "mtime", ".", "x"]).lines(), vec!["./c.txt", "./a.txt", "./b.txt"]);
+}
+
+#[test]
+fn sort_by_size_is_smallest_first() {
+    //DATA
+    let fixture = sortable();
+
+    assert_eq!(fixture.run(&["-l", "--sort", "size", ".", "x"]).lines(), vec!["./b.txt", "./c.txt", "./a.txt"]);
+}
+
+#[test]
+fn order_by_path_doesnt_depend_on_creation_order() {
+    //DATA
+    let names: [&str; 7] = ["z.txt", "b.txt", "a.txt", "b/c.txt", "b/a/d.txt", "ba/x.txt", "b-c.txt"];
+    //a directory's files come before the next name in its parent, even ones that sort before the directory's name would with its separator
+    let expected: Vec<&str> = vec!["./a.txt", "./b/a/d.txt", "./b/c.txt", "./b-c.txt", "./b.txt", "./ba/x.txt", "./z.txt"];
+
+    //as written, reversed, and interleaved, so the directory entries come back in a different order each time
+    for order in [[0, 1, 2, 3, 4, 5, 6], [6, 5, 4, 3, 2, 1, 0], [3, 0, 6, 2, 5, 1, 4]] {
+        //DATA
+        let fixture = Fixture::new();
+        fixture.dir("b/a");
+        fixture.dir("ba");
+
+        for index in order {
+            fixture.file(names[index], "hit\n");
+        }
+        assert_eq!(fixture.run(&["-l", "-r", ".", "hit"]).lines(), expected);
+        for threads in ["1", "4"] {
+            assert_eq!(fixture.run(&["-r", "--threads", threads, ".", "hit"]).lines(), expected.iter().map(|path| format!("{}:1:hit", path)).collect::<Vec<String>>());
+        }
+    }
+}
+
+#[test]
+fn sort_by_size_breaks_ties_by_path() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("b.txt", "x\n");
+    fixture.file("a.txt", "x\n");
+    fixture.file("c.txt", "x\n");
+
+    assert_eq!(fixture.run(&["-l", "--sort", "size", ".", "x"]).lines(), vec!["./a.txt", "./b.txt", "./c.txt"]);
+}
+
+#[test]
+fn sort_none_still_finds_everything() {
+    //DATA
+    let fixture = sortable();
+
+    assert_eq!(fixture.run(&["-l", "--sort", "none", ".", "x"]).sorted_lines(), vec!["./a.txt", "./b.txt", "./c.txt"]);
+}
+
+#[test]
+fn sort_rejects_other_orders() {
+    //DATA
+    let fixture = sortable();
+
+    assert!(fixture.run(&["--sort", "foo", ".", "x"]).stderr.contains("Invalid value 'foo' for '--sort', expected path, mtime, size, or none."));
+}
+
+/**
+ * returns a fixture with log.txt, which has "error" on the second of its three lines, and quiet.txt, which doesn't have it at all
+ */
+fn log() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("log.txt", "one\nerror two\nthree\n");
+    fixture.file("quiet.txt", "nothing\n");
+    return fixture;
+}
+
+#[test]
+fn passthru_prints_every_line_of_matching_files() {
+    //DATA
+    let fixture = log();
+
+    assert_eq!(fixture.run(&["--passthru", ".", "error"]).lines(), vec!["./log.txt-1-one", "./log.txt:2:error two", "./log.txt-3-three"]);
+    assert_eq!(fixture.run(&["--passthru", "log.txt", "error"]).lines(), vec!["1-one", "2:error two", "3-three"]);
+}
+
+#[test]
+fn passthru_skips_files_without_matches_even_when_named() {
+    //DATA
+    let fixture = log();
+    let output = fixture.run(&["--passthru", "quiet.txt", "error"]);
+
+    assert_eq!((output.code, output.stdout.as_str()), (1, ""));
+    assert_eq!(fixture.run(&["--passthru", "-p", "quiet.txt", "-p", "log.txt", "error"]).lines(), vec!["log.txt-1-one", "log.txt:2:error two", "log.txt-3-three"]);
+}
+
+#[test]
+fn passthru_only_highlights_the_matches() {
+    //DATA
+    let fixture = log();
+
+    assert_eq!(fixture.run(&["--passthru", "--color", "always", "log.txt", "error"]).stdout, "1-one\n2:\x1b[1;31merror\x1b[0m two\n3-three\n");
+}
+
+#[test]
+fn passthru_needs_line_output() {
+    //DATA
+    let output = log().run(&["--passthru", "-l", ".", "error"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("they only work when printing lines"), "{}", output.stderr);
+}
+
+#[test]
+fn replace_swaps_every_occurrence_on_a_line() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("r.txt", "foo foo é foo\n");
+
+    assert_eq!(fixture.run(&["--replace", "bar", "r.txt", "foo"]).lines(), vec!["1:bar bar é bar"]);
+    //multibyte replacements don't get in the way of the rest of the line
+    assert_eq!(fixture.run(&["--replace", "ü", "r.txt", "foo"]).lines(), vec!["1:ü ü é ü"]);
+}
+
+#[test]
+fn replace_can_contain_the_query() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("r.txt", "foo foo é foo\n");
+
+    assert_eq!(fixture.run(&["--replace", "foofoo", "r.txt", "foo"]).lines(), vec!["1:foofoo foofoo é foofoo"]);
+}
+
+#[test]
+fn replace_can_be_empty() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("r.txt", "foo foo é foo\n");
+
+    assert_eq!(fixture.run(&["--replace", "", "r.txt", "foo"]).lines(), vec!["1:  é "]);
+}
+
+#[test]
+fn replace_fills_in_regex_groups() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("r.txt", "let a=1; let b=2;\n");
+
+    assert_eq!(fixture.run(&["-E", "--replace", "$2=$1", "r.txt", r"(\w)=(\d)"]).lines(), vec!["1:let 1=a; let 2=b;"]);
+    assert_eq!(fixture.run(&["-E", "-o", "--replace", "<$1>", "r.txt", r"(\w)=\d"]).lines(), vec!["1:<a>", "1:<b>"]);
+}
+
+#[test]
+fn replace_leaves_the_file_alone() {
+    //DATA
+    let fixture = Fixture::new();
+    let path = fixture.file("r.txt", "foo\n");
+
+    fixture.run(&["--replace", "bar", "r.txt", "foo"]);
+    assert_eq!(std::fs::read_to_string(path).unwrap(), "foo\n");
+}
+
+/**
+ * returns a fixture with a.txt, which has "hi" on lines 2 and 3 (with quotes, a backslash, a tab, a control character, and an é around them),
+ * d/b.txt, which has "hi" followed by invalid utf-8, and c.txt, which doesn't have it at all
+ */
+fn json_tree() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "first\nsay \"hi\"\\there\tnow \u{1}\nlast hi, é hi\n");
+    fixture.file("d/b.txt", b"x hi \xff\n");
+    fixture.file("c.txt", "nope\n");
+    return fixture;
+}
+
+/**
+ * returns json output with how long the search took zeroed, since that's the only thing that changes from run to run
+ */
+fn without_elapsed(json: &str) -> String {
+    //DATA
+    let (before, after) = json.split_once("\"elapsed_seconds\":").unwrap();
+    let rest: &str = after.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == 'e' || c == '-');
+
+    return format!("{}\"elapsed_seconds\":0{}", before, rest);
+}
+
+#[test]
+fn json_matches_the_golden_file() {
+    //DATA
+    let output = json_tree().run(&["--json", "-r", "-C1", ".", "hi"]);
+
+    assert_eq!(output.code, 0);
+    assert_eq!(without_elapsed(&output.stdout), include_str!("golden/json.json"));
+}
+
+#[test]
+fn json_byte_offsets_are_where_the_first_match_is() {
+    //DATA
+    let fixture = json_tree();
+    let output = fixture.run(&["--json", "-r", "-C1", ".", "hi"]);
+    let offsets: Vec<&str> = output.lines().iter().filter_map(|line| line.split_once("\"byte_offset\":")).map(|(_, rest)| rest.split(',').next().unwrap()).collect();
+
+    //the context line's is where it starts, the rest are the same as -b
+    assert_eq!(offsets, vec!["0", "11", "32", "2"]);
+    assert_eq!(fixture.run(&["-b", "-r", ".", "hi"]).lines(), vec!["./a.txt:2:11:say \"hi\"\\there\tnow \u{1}", "./a.txt:3:32:last hi, é hi", "./d/b.txt:1:2:x hi \u{FFFD}"]);
+}
+
+#[test]
+fn json_isnt_changed_by_heading_or_color() {
+    //DATA
+    let fixture = json_tree();
+    let plain = fixture.run(&["--json", "-r", ".", "hi"]);
+    let styled = fixture.run(&["--json", "--heading", "--color", "always", "-r", ".", "hi"]);
+
+    assert_eq!(without_elapsed(&styled.stdout), without_elapsed(&plain.stdout));
+    assert!(!styled.stdout.contains('\u{1b}'));
+    assert!(!styled.stderr.contains("Searching for"), "{}", styled.stderr);
+}
+
+#[test]
+fn json_lists_just_the_paths_without_lines() {
+    //DATA
+    let output = json_tree().run(&["--json", "-l", "-r", ".", "hi"]);
+
+    assert_eq!(output.lines()[..3], ["{\"results\":[", "{\"path\":\"./a.txt\"},", "{\"path\":\"./d/b.txt\"}"]);
+    assert!(output.lines()[3].contains("\"files_searched\":3,\"files_matched\":2,\"total_matches\":"), "{}", output.stdout);
+}
+
+/**
+ * returns the fields of a json object, each with its value as it was written, or None if text isn't exactly one valid json object
+ * just enough of a parser to check each json lines record on its own
+ */
+fn json_object(text: &str) -> Option<Vec<(String, String)>> {
+    //DATA
+    let bytes: &[u8] = text.as_bytes();
+    let mut fields: Vec<(String, String)> = Vec::new();
+    let mut at: usize = 1;
+
+    if bytes.first() != Some(&b'{') {
+        return None;
+    }
+    if bytes.get(at) == Some(&b'}') {
+        return (at + 1 == bytes.len()).then_some(fields);
+    }
+    loop {
+        //DATA
+        let key_end: usize = json_value(bytes, at).filter(|_| bytes[at] == b'"')?;
+        if bytes.get(key_end) != Some(&b':') {
+            return None;
+        }
+        let value_end: usize = json_value(bytes, key_end + 1)?;
+
+        fields.push((text[at + 1..key_end - 1].to_string(), text[key_end + 1..value_end].to_string()));
+        match bytes.get(value_end) {
+            Some(b',') => at = value_end + 1,
+            Some(b'}') if value_end + 1 == bytes.len() => return Some(fields),
+            _ => return None,
+        }
+    }
+}
+
+/**
+ * returns where the json value starting at bytes[at] ends, or None if it isn't one
+ */
+fn json_value(bytes: &[u8], at: usize) -> Option<usize> {
+    match bytes.get(at)? {
+        b'"' => {
+            //DATA
+            let mut end: usize = at + 1;
+
+            loop {
+                match bytes.get(end)? {
+                    b'"' => return Some(end + 1),
+                    b'\\' if bytes.get(end + 1) == Some(&b'u') => {
+                        bytes.get(end + 2..end + 6).filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))?;
+                        end += 6;
+                    },
+                    b'\\' if matches!(bytes.get(end + 1)?, b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => end += 2,
+                    b'\\' => return None,
+                    c if *c < b' ' => return None,
+                    _ => end += 1,
+                }
+            }
+        },
+        open @ (b'[' | b'{') => {
+            //DATA
+            let close: u8 = if *open == b'[' {b']'} else {b'}'};
+            let mut end: usize = at + 1;
+
+            if bytes.get(end) == Some(&close) {
+                return Some(end + 1);
+            }
+            loop {
+                if *open == b'{' {
+                    end = json_value(bytes, end).filter(|_| bytes[end] == b'"')?;
+                    if bytes.get(end) != Some(&b':') {
+                        return None;
+                    }
+                    end += 1;
+                }
+                end = json_value(bytes, end)?;
+                match bytes.get(end)? {
+                    b',' => end += 1,
+                    c if *c == close => return Some(end + 1),
+                    _ => return None,
+                }
+            }
+        },
+        b't' => bytes[at..].starts_with(b"true").then_some(at + 4),
+        b'f' => bytes[at..].starts_with(b"false").then_some(at + 5),
+        b'n' => bytes[at..].starts_with(b"null").then_some(at + 4),
+        b'-' | b'0'..=b'9' => {
+            //DATA
+            let length: usize = bytes[at + 1..].iter().take_while(|c| c.is_ascii_digit() || matches!(c, b'.' | b'e' | b'E' | b'+' | b'-')).count();
+
+            return Some(at + 1 + length);
+        },
+        _ => None,
+    }
+}
+
+/**
+ * returns the type and path of each json lines record in output, checking that every line is a json object by itself
+ */
+fn record_types(output: &str) -> Vec<(String, String)> {
+    return output.lines().map(|line| {
+        //DATA
+        let fields: Vec<(String, String)> = json_object(line).unwrap_or_else(|| panic!("not a json object: {}", line));
+        let field = |name: &str| fields.iter().find(|(key, _)| key == name).map(|(_, value)| value.trim_matches('"').to_string()).unwrap_or_default();
+
+        (field("type"), field("path"))
+    }).collect();
+}
+
+#[test]
+fn json_lines_records_are_each_valid_json() {
+    //DATA
+    //d/b.txt has invalid utf-8 in it, so with --strict-utf8, it's an error
+    let fixture = json_tree();
+    fixture.file("bad.txt", b"hi \xff\n");
+    let output = fixture.run(&["--jsonl", "--strict-utf8", "-r", "-C1", ".", "hi"]);
+
+    assert_eq!(record_types(&output.stdout), vec![
+        ("context".to_string(), "./a.txt".to_string()),
+        ("match".to_string(), "./a.txt".to_string()),
+        ("match".to_string(), "./a.txt".to_string()),
+        ("error".to_string(), "./bad.txt".to_string()),
+        ("error".to_string(), "./d/b.txt".to_string()),
+        ("summary".to_string(), String::new()),
+    ]);
+}
+
+#[test]
+fn json_lines_keep_each_files_records_together_and_the_summary_last() {
+    //DATA
+    let fixture = Fixture::new();
+    for name in ["a", "b", "c", "d", "e", "f"] {
+        fixture.file(&format!("{}.txt", name), "hi\nno\nhi\nhi\n");
+    }
+
+    for mode in [&["-n"][..], &["-l"], &["-c"]] {
+        for threads in ["1", "4"] {
+            //DATA
+            let output = fixture.run(&[mode, &["--jsonl", "--threads", threads, ".", "hi"]].concat());
+            let records: Vec<(String, String)> = record_types(&output.stdout);
+            let mut paths: Vec<String> = records.iter().map(|(_, path)| path.clone()).collect();
+
+            assert_eq!(records.last().unwrap().0, "summary", "{}", output.stdout);
+            assert!(records[..records.len() - 1].iter().all(|(kind, _)| kind != "summary"), "{}", output.stdout);
+            //once a file's records stop, it doesn't come up again, and they're in order
+            paths.dedup();
+            paths.pop();
+            assert_eq!(paths, ["a", "b", "c", "d", "e", "f"].map(|name| format!("./{}.txt", name)), "{}", output.stdout);
+        }
+    }
+}
+
+#[test]
+fn json_object_rejects_what_isnt_json() {
+    assert!(json_object("{\"a\":[1,{\"b\":\"\\u00e9\"}],\"c\":true}").is_some());
+    for text in ["", "{", "{\"a\"}", "{\"a\":1,}", "{\"a\":\"\\x\"}", "{\"a\":1}}", "[1]", "{\"a\":\"\t\"}"] {
+        assert!(json_object(text).is_none(), "{}", text);
+    }
+}
+
+#[test]
+fn csv_has_a_header_then_a_row_per_line() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("x,y.txt", "a, \"hi\"\nhi hi\n");
+    fixture.file("z.txt", "hi\n");
+    let output = fixture.run(&["--csv", ".", "hi"]);
+
+    assert_eq!(output.stdout, "path,line_number,column,match_count,line_text\n\"./x,y.txt\",1,5,1,\"a, \"\"hi\"\"\"\n\"./x,y.txt\",2,1,2,hi hi\n./z.txt,1,1,1,hi\n");
+}
+
+#[test]
+fn csv_lists_files_with_their_match_counts() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("x,y.txt", "a, \"hi\"\nhi hi\n");
+    fixture.file("z.txt", "no\n");
+
+    assert_eq!(fixture.run(&["--csv", "-l", ".", "hi"]).stdout, "path,match_count\n\"./x,y.txt\",3\n");
+    assert_eq!(fixture.run(&["--csv", "-L", ".", "hi"]).stdout, "path,match_count\n./z.txt,0\n");
+    //the header is there even when nothing matched, and only once with several threads
+    assert_eq!(fixture.run(&["--csv", "-l", ".", "nothing"]).stdout, "path,match_count\n");
+    assert_eq!(fixture.run(&["--csv", "-l", "--threads", "4", ".", "o"]).stdout.matches("path,match_count").count(), 1);
+}
+
+#[test]
+fn csv_rejects_other_formats_and_decoration() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hi\n");
+
+    assert!(fixture.run(&["--csv", "--json", ".", "hi"]).stderr.contains("'--csv' and '--json' can't be used together, only one output format can be picked."));
+    for decoration in [&["--heading"][..], &["--color", "always"]] {
+        //DATA
+        let output = fixture.run(&[&["--csv"][..], decoration, &[".", "hi"]].concat());
+
+        assert_eq!(output.code, 2);
+        assert!(output.stderr.contains("--csv can't be used with --heading or --color always"), "{}", output.stderr);
+    }
+}
+
+#[test]
+fn vimgrep_has_an_entry_per_match() {
+    //DATA
+    let fixture = Fixture::new();
+    //colons in the line don't change how it's laid out
+    fixture.file("a.txt", "ab:cd ab\nxx\n");
+    fixture.file("c.txt", "ab\n");
+    let output = fixture.run(&["--vimgrep", ".", "ab"]);
+
+    assert_eq!(output.stdout, "./a.txt:1:1:ab:cd ab\n./a.txt:1:7:ab:cd ab\n./c.txt:1:1:ab\n");
+    assert_eq!(output.stderr, "");
+    //a single file still has its path, which the quickfix list needs
+    assert_eq!(fixture.run(&["--vimgrep", "c.txt", "ab"]).stdout, "c.txt:1:1:ab\n");
+}
+
+#[test]
+fn vimgrep_ignores_heading_and_rejects_context() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "one\nab\n");
+
+    assert_eq!(fixture.run(&["--vimgrep", "--heading", ".", "ab"]).stdout, "./a.txt:2:1:ab\n");
+    for option in [&["-C1"][..], &["-b"], &["--passthru"]] {
+        //DATA
+        let output = fixture.run(&[&["--vimgrep"][..], option, &[".", "ab"]].concat());
+
+        assert_eq!(output.code, 2);
+        assert!(output.stderr.contains("--vimgrep can't be used with --passthru, --byte-offset, or the context options"), "{}", output.stderr);
+    }
+}
+
+/**
+ * returns a fixture with a.txt, which has "hit" on its one line, and d/b.txt, which has it on both of its lines
+ */
+fn plain_tree() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hit\n");
+    fixture.file("d/b.txt", "hit\nhit\n");
+    return fixture;
+}
+
+#[test]
+fn piped_output_is_plain_like_grep() {
+    //DATA
+    let fixture = plain_tree();
+
+    //the tests' output is a pipe, not a terminal
+    for args in [&["-l", "-r", ".", "hit"][..], &["--plain", "-l", "-r", ".", "hit"]] {
+        assert_eq!(fixture.run(args).stdout, "./a.txt\n./d/b.txt\n");
+    }
+    assert_eq!(fixture.run(&["-c", "-r", ".", "hit"]).stdout, "./a.txt: 1\n./d/b.txt: 2\n");
+    assert_eq!(fixture.run(&["-n", "-r", ".", "hit"]).stdout, "./a.txt:1:hit\n./d/b.txt:1:hit\n./d/b.txt:2:hit\n");
+}
+
+#[test]
+fn no_plain_decorates_the_output_like_a_terminal() {
+    //DATA
+    let fixture = plain_tree();
+
+    assert_eq!(fixture.run(&["--no-plain", "-l", "-r", ".", "hit"]).stdout, "Searching for hit\nIn Path .\nFiles containing query: \n\t./a.txt\n\t./d/b.txt\n");
+    assert_eq!(fixture.run(&["--no-plain", "-c", "-r", ".", "hit"]).stdout, "Searching for hit\nIn Path .\nMatches per file: \n\t./a.txt: 1\n\t./d/b.txt: 2\n");
+    //lines are never indented, only the banner is added
+    assert_eq!(fixture.run(&["--no-plain", "-n", "-r", ".", "hit"]).stdout, "Searching for hit\nIn Path .\n./a.txt:1:hit\n./d/b.txt:1:hit\n./d/b.txt:2:hit\n");
+}
+
+#[test]
+fn quiet_and_other_formats_are_never_decorated() {
+    //DATA
+    let fixture = plain_tree();
+
+    assert!(!fixture.run(&["--no-plain", "-q", "-l", "-r", ".", "hit"]).stdout.contains("Searching for"));
+    assert_eq!(fixture.run(&["--no-plain", "-0", "-l", "-r", ".", "hit"]).stdout, "./a.txt\0./d/b.txt\0");
+    assert!(fixture.run(&["--no-plain", "--csv", "-l", "-r", ".", "hit"]).stdout.starts_with("path,match_count\n"));
+}
+
+#[test]
+fn output_file_is_left_out_of_its_own_results() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hit\n");
+    fixture.dir("sub");
+
+    //run twice, so the second run has a results file with "hit" in it to find, if it wasn't left out
+    for path in ["res.txt", "res.txt", "sub/../res.txt"] {
+        //DATA
+        let output = fixture.run(&["-r", "--output", path, ".", "hit"]);
+
+        assert_eq!((output.code, output.stdout.as_str()), (0, ""));
+        assert!(output.stderr.starts_with("Wrote 1 result to "), "{}", output.stderr);
+        assert_eq!(std::fs::read_to_string(fixture.path("res.txt")).unwrap(), "./a.txt:1:hit\n");
+    }
+}
+
+#[test]
+fn output_file_that_cant_be_created_is_an_error() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hit\n");
+    let output = fixture.run(&["--output", "nodir/res.txt", ".", "hit"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Couldn't create the output file 'nodir/res.txt'"), "{}", output.stderr);
+}
+
+#[test]
+fn output_counts_every_result_written() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "hit\nhit\n");
+    fixture.file("b.txt", "hit\n");
+
+    assert!(fixture.run(&["--output", "res.txt", ".", "hit"]).stderr.starts_with("Wrote 3 results to "));
+    assert!(fixture.run(&["-l", "--output", "res.txt", ".", "hit"]).stderr.starts_with("Wrote 2 results to "));
+    assert!(fixture.run(&["-q", "-l", "--output", "res.txt", ".", "hit"]).stderr.is_empty());
+}
+
+/**
+ * returns a fixture with a.txt, which has "hit" twice on its first line, and b.txt, which is just "hit"
+ */
+fn templated() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "x hit hit\nno\n");
+    fixture.file("b.txt", "hit\n");
+    return fixture;
+}
+
+#[test]
+fn format_fills_in_each_line() {
+    //DATA
+    let fixture = templated();
+
+    assert_eq!(fixture.run(&["--format", r"{path}\t{line}\t{column}\t{byte_offset}\t{count}\t{match}|{text}", ".", "hit"]).stdout,
+        "./a.txt\t1\t3\t2\t2\thit|x hit hit\n./b.txt\t1\t1\t0\t1\thit|hit\n");
+    //with -o, there's one for each match, and with --replace, the match is its replacement
+    assert_eq!(fixture.run(&["-o", "--format", "{match}@{column}", ".", "hit"]).lines(), vec!["hit@3", "hit@7", "hit@1"]);
+    assert_eq!(fixture.run(&["--replace", "HIT", "--format", "{match}", "a.txt", "hit"]).lines(), vec!["HIT"]);
+}
+
+#[test]
+fn format_leaves_out_what_a_result_doesnt_have() {
+    //DATA
+    let fixture = templated();
+
+    assert_eq!(fixture.run(&["-l", "--format", "{path} {count} [{line}]", ".", "hit"]).lines(), vec!["./a.txt 2 []", "./b.txt 1 []"]);
+}
+
+#[test]
+fn format_with_an_unknown_placeholder_is_an_error() {
+    //DATA
+    let output = templated().run(&["--format", "{{x}} {nope}", ".", "hit"]);
+
+    assert_eq!((output.code, output.stdout.as_str()), (2, ""));
+    assert!(output.stderr.contains("unknown placeholder '{nope}'"), "{}", output.stderr);
+}
+
+#[test]
+fn hyperlinks_link_paths_to_their_file_urls() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a b.txt", "hit\n");
+    let url: String = format!("file://{}/a%20b.txt", std::fs::canonicalize(&fixture.root).unwrap().display());
+    let link = |text: &str| format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text);
+
+    assert_eq!(fixture.run(&["--hyperlinks", "on", "-l", ".", "hit"]).stdout, format!("{}\n", link("./a b.txt")));
+    assert_eq!(fixture.run(&["--hyperlinks", "on", ".", "hit"]).stdout, format!("{}:1:hit\n", link("./a b.txt")));
+    //auto is off when the output isn't a terminal, like --color
+    assert_eq!(fixture.run(&["--hyperlinks", "auto", "-l", ".", "hit"]).stdout, "./a b.txt\n");
+    assert!(fixture.run(&["--hyperlinks", "maybe", ".", "hit"]).stderr.contains("Invalid value 'maybe' for '--hyperlinks', expected on, off, or auto."));
+}
+
+#[test]
+fn hyperlinks_are_left_out_of_machine_readable_output() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a b.txt", "hit\n");
+
+    for format in [&["--json"][..], &["--jsonl"], &["--csv"], &["-0"], &["--format", "{path}"]] {
+        //DATA
+        let output = fixture.run(&[&["--hyperlinks", "on", "-l"][..], format, &[".", "hit"]].concat());
+
+        assert!(output.stdout.contains("a b.txt"), "{:?}: {}", format, output.stdout);
+        assert!(!output.stdout.contains('\u{1b}'), "{:?}: {}", format, output.stdout);
+    }
+    assert!(!fixture.run(&["--hyperlinks", "on", "--vimgrep", ".", "hit"]).stdout.contains('\u{1b}'));
+}
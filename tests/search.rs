@@ -0,0 +1,817 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+mod common;
+
+use common::Fixture;
+
+/**
+ * returns a fixture with ten.txt, which has "needle" on each of its ten lines
+ */
+fn ten_needles() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("ten.txt", (1..=10).map(|line| format!("line {} needle\n", line)).collect::<String>());
+    return fixture;
+}
+
+#[test]
+fn max_count_stops_after_that_many_matches() {
+    //DATA
+    let fixture = ten_needles();
+
+    assert_eq!(fixture.run(&["-c", "-m", "1", ".", "needle"]).lines(), vec!["./ten.txt: 1"]);
+    assert_eq!(fixture.run(&["-c", "--max-count", "5", ".", "needle"]).lines(), vec!["./ten.txt: 5"]);
+    assert_eq!(fixture.run(&["-c", ".", "needle"]).lines(), vec!["./ten.txt: 10"]);
+    assert_eq!(fixture.run(&["-m", "2", ".", "needle"]).lines(), vec!["./ten.txt:1:line 1 needle", "./ten.txt:2:line 2 needle"]);
+    assert_eq!(fixture.run(&["-l", "-m", "1", ".", "needle"]).lines(), vec!["./ten.txt"]);
+}
+
+#[test]
+fn max_count_0_is_an_error() {
+    //DATA
+    let output = ten_needles().run(&["-m", "0", ".", "needle"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Option '-m' must be at least 1."), "{}", output.stderr);
+}
+
+#[test]
+fn max_filesize_skips_larger_files() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("big.txt", format!("{}\nneedle\n", "a".repeat(3000)));
+    fixture.file("small.txt", "needle\n");
+    fixture.file("exactly.txt", format!("{}\nneedle\n", "a".repeat(1024 - 8)));
+
+    assert_eq!(fixture.run(&["-l", "--max-filesize", "1K", ".", "needle"]).lines(), vec!["./exactly.txt", "./small.txt"]);
+    assert_eq!(fixture.run(&["-l", ".", "needle"]).lines(), vec!["./big.txt", "./exactly.txt", "./small.txt"]);
+
+    //the skip is only mentioned with --verbose
+    assert_eq!(fixture.run(&["-l", "--max-filesize", "1K", ".", "needle"]).stderr, "");
+    assert!(fixture.run(&["-l", "-v", "--max-filesize", "1K", ".", "needle"]).stderr.contains("Skipping \"./big.txt\": larger than the max filesize"));
+}
+
+/**
+ * returns a fixture with the same word in lowercase, uppercase, and title case, a file for each
+ */
+fn three_cases() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("lower.txt", "foo\n");
+    fixture.file("upper.txt", "FOO\n");
+    fixture.file("title.txt", "Foo\n");
+    return fixture;
+}
+
+#[test]
+fn smart_case_ignores_case_for_a_lowercase_query() {
+    assert_eq!(three_cases().run(&["-l", "--smart-case", ".", "foo"]).lines(), vec!["./lower.txt", "./title.txt", "./upper.txt"]);
+}
+
+#[test]
+fn smart_case_is_case_sensitive_for_a_query_with_uppercase() {
+    assert_eq!(three_cases().run(&["-l", "--smart-case", ".", "Foo"]).lines(), vec!["./title.txt"]);
+}
+
+#[test]
+fn explicit_case_flags_win_over_smart_case() {
+    //DATA
+    let fixture = three_cases();
+
+    assert_eq!(fixture.run(&["-l", "--smart-case", "-s", ".", "foo"]).lines(), vec!["./lower.txt"]);
+    assert_eq!(fixture.run(&["-l", "-i", "--smart-case", ".", "Foo"]).lines(), vec!["./lower.txt", "./title.txt", "./upper.txt"]);
+}
+
+#[test]
+fn word_regexp_only_matches_whole_words() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("w.txt", "id starts the file\nvoid identifier _id id_\n(id) x.id,\nthe file ends with id");
+
+    //at the start of the file, next to punctuation, and at the end of the file (without a line ending)
+    assert_eq!(fixture.run(&["-w", "w.txt", "id"]).lines(), vec!["1:id starts the file", "3:(id) x.id,", "4:the file ends with id"]);
+    assert_eq!(fixture.run(&["-w", "-o", "w.txt", "id"]).lines(), vec!["1:id", "3:id", "3:id", "4:id"]);
+}
+
+#[test]
+fn word_regexp_works_with_ignore_case() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("w.txt", "ID starts\nvoid IDENTIFIER\nends with Id");
+
+    assert_eq!(fixture.run(&["-w", "-i", "w.txt", "id"]).lines(), vec!["1:ID starts", "3:ends with Id"]);
+    assert_eq!(fixture.run(&["-w", "w.txt", "id"]).code, 1);
+}
+
+#[test]
+fn word_regexp_works_with_regex_in_the_middle_of_a_line() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("w.txt", "the cat sat
+concat
+foo bar
+foobar
+");
+
+    //the same as without -E
+    assert_eq!(fixture.run(&["-E", "-w", "w.txt", "cat"]).lines(), vec!["1:the cat sat"]);
+    assert_eq!(fixture.run(&["-w", "w.txt", "cat"]).lines(), vec!["1:the cat sat"]);
+    assert_eq!(fixture.run(&["-E", "-w", "-o", "--column", "w.txt", "[cs]at"]).lines(), vec!["1:5:cat", "1:9:sat"]);
+}
+
+#[test]
+fn regex_word_boundaries_hold_in_the_middle_of_a_line() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("w.txt", "the cat sat
+concat
+foo bar
+foobar
+");
+
+    assert_eq!(fixture.run(&["-E", "w.txt", r"\bbar"]).lines(), vec!["3:foo bar"]);
+    assert_eq!(fixture.run(&["-E", "w.txt", r"\bcat\b"]).lines(), vec!["1:the cat sat"]);
+    assert_eq!(fixture.run(&["-E", "-o", "w.txt", r"\Bbar"]).lines(), vec!["4:bar"]);
+    assert_eq!(fixture.run(&["-E", "--anchor", "end", "w.txt", r"\bb.r"]).lines(), vec!["3:foo bar"]);
+}
+
+#[test]
+fn line_regexp_only_matches_whole_lines() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("substring.txt", "const DEBUG = true;\nxDEBUG = true\n");
+    fixture.file("exact.txt", "other\nDEBUG = true\n");
+    fixture.file("crlf.txt", "DEBUG = true\r\n");
+    fixture.file("trailing_space.txt", "DEBUG = true \n");
+
+    //the line ending doesn't count, but other whitespace does
+    assert_eq!(fixture.run(&["-l", "-x", ".", "DEBUG = true"]).lines(), vec!["./crlf.txt", "./exact.txt"]);
+    assert_eq!(fixture.run(&["-x", "exact.txt", "DEBUG = true"]).lines(), vec!["2:DEBUG = true"]);
+    assert_eq!(fixture.run(&["-l", ".", "DEBUG = true"]).lines().len(), 4);
+}
+
+#[test]
+fn line_regexp_works_with_ignore_case() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("exact.txt", "DEBUG = true\n");
+
+    assert_eq!(fixture.run(&["-x", "-i", "exact.txt", "debug = TRUE"]).lines(), vec!["1:DEBUG = true"]);
+    assert_eq!(fixture.run(&["-x", "exact.txt", "debug = TRUE"]).code, 1);
+}
+
+/**
+ * returns a fixture with a file that has the query, one that doesn't, and c.bad, which --pre false (with --pre-glob *.bad) can't read
+ */
+fn with_a_failing_file() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("src/a.rs", "// SPDX-License-Identifier: MIT\n");
+    fixture.file("src/b.rs", "fn main() {}\n");
+    fixture.file("src/c.bad", "nothing\n");
+    return fixture;
+}
+
+#[cfg(unix)]
+#[test]
+fn invert_match_lists_files_without_the_query_but_not_unreadable_ones() {
+    //DATA
+    let fixture = with_a_failing_file();
+    let output = fixture.run(&["-I", "-l", "-r", "--pre", "false", "--pre-glob", "*.bad", "src", "SPDX-License-Identifier"]);
+
+    assert_eq!((output.code, output.lines()), (0, vec!["src/b.rs"]));
+    assert!(output.stderr.contains("1 file couldn't be read"), "{}", output.stderr);
+
+    //with --verbose, it says why
+    assert!(fixture.run(&["-I", "-l", "-r", "-v", "--pre", "false", "--pre-glob", "*.bad", "src", "SPDX-License-Identifier"]).stderr.contains("Error searching \"src/c.bad\""));
+}
+
+#[test]
+fn invert_match_skips_files_without_permission() {
+    //DATA
+    let fixture = with_a_failing_file();
+
+    if !fixture.unreadable_file("src/secret.rs", "fn secret() {}\n") {
+        return;
+    }
+    let output = fixture.run(&["--invert-match", "-l", "-r", "src", "SPDX-License-Identifier"]);
+    assert_eq!(output.lines(), vec!["src/b.rs", "src/c.bad"]);
+    assert!(output.stderr.contains("1 file couldn't be read"), "{}", output.stderr);
+}
+
+#[test]
+fn invert_match_works_with_ignore_case_and_filters() {
+    //DATA
+    let fixture = with_a_failing_file();
+    fixture.file("src/d.rs", "// spdx-license-identifier: MIT\n");
+
+    assert_eq!(fixture.run(&["-I", "-l", "-r", "-i", "--filter", "rs", "src", "SPDX-License-Identifier"]).lines(), vec!["src/b.rs"]);
+    assert_eq!(fixture.run(&["-I", "-l", "-r", "--filter", "rs", "src", "SPDX-License-Identifier"]).lines(), vec!["src/b.rs", "src/d.rs"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn a_file_that_fails_to_read_is_in_neither_list() {
+    //DATA
+    let fixture = with_a_failing_file();
+    let args = |mode: &'static str, verbose: bool| -> Vec<&'static str> {
+        //DATA
+        let mut args: Vec<&str> = vec![mode, "-r", "--pre", "false", "--pre-glob", "*.bad", "src", "SPDX-License-Identifier"];
+
+        if verbose {
+            args.insert(0, "--verbose");
+        }
+        return args;
+    };
+
+    assert_eq!(fixture.run(&args("-l", false)).lines(), vec!["src/a.rs"]);
+    assert_eq!(fixture.run(&args("-L", false)).lines(), vec!["src/b.rs"]);
+    for mode in ["-l", "-L"] {
+        assert!(fixture.run(&args(mode, true)).stderr.contains("Error searching \"src/c.bad\": the preprocessor exited with code 1"), "{}", mode);
+    }
+}
+
+#[test]
+fn output_modes_are_mutually_exclusive() {
+    //DATA
+    let fixture = with_a_failing_file();
+
+    for (first, second) in [("-l", "-L"), ("-L", "-c"), ("-c", "--count-matches"), ("-L", "-n")] {
+        //DATA
+        let output = fixture.run(&[first, second, "src", "x"]);
+
+        assert_eq!(output.code, 2);
+        assert!(output.stderr.contains(&format!("'{}' and '{}' can't be used together, only one output mode can be picked.", first, second)), "{}", output.stderr);
+    }
+}
+
+#[test]
+fn count_counts_every_match_even_on_the_same_line() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.rs", "unwrap( unwrap( unwrap(\nnone\nunwrap(\n");
+    fixture.file("b.rs", "none\n");
+    fixture.file("c.txt", "aaaa");
+
+    assert_eq!(fixture.run(&["-c", ".", "unwrap("]).lines(), vec!["./a.rs: 4"]);
+    //matches don't overlap
+    assert_eq!(fixture.run(&["-c", "c.txt", "aa"]).lines(), vec!["2"]);
+}
+
+#[test]
+fn count_leaves_out_files_without_matches_unless_asked() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.rs", "unwrap(\n");
+    fixture.file("b.rs", "none\n");
+
+    assert_eq!(fixture.run(&["-c", "--include-zero", ".", "unwrap("]).lines(), vec!["./a.rs: 1", "./b.rs: 0"]);
+}
+
+#[test]
+fn count_rejects_an_empty_query() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.rs", "unwrap(\n");
+
+    assert!(fixture.run(&["-c", ".", ""]).stderr.contains("No/invalid query given"));
+    assert!(fixture.run(&["-c", "-e", "", "."]).stderr.contains("Option '-e' requires a non-empty pattern."));
+}
+
+/**
+ * src/a.rs has 3 occurrences of "unwrap(" (2 on its first line), src/sub/b.rs and src/c.txt have 1 each
+ */
+fn known_totals() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("src/a.rs", "unwrap( unwrap(\nunwrap(\n");
+    fixture.file("src/sub/b.rs", "unwrap(\n");
+    fixture.file("src/c.txt", "unwrap(\n");
+    return fixture;
+}
+
+#[test]
+fn count_matches_totals_every_file() {
+    //DATA
+    let fixture = known_totals();
+
+    assert_eq!(fixture.run(&["-r", "--count-matches", "src", "unwrap("]).lines(), vec!["5"]);
+    assert_eq!(fixture.run(&["--count-matches", "src", "unwrap("]).lines(), vec!["4"]);
+    assert_eq!(fixture.run(&["-r", "--count-matches", "-f", "rs", "src", "unwrap("]).lines(), vec!["4"]);
+}
+
+#[test]
+fn count_matches_caps_each_file_before_summing() {
+    //DATA
+    let fixture = known_totals();
+
+    assert_eq!(fixture.run(&["-r", "--count-matches", "-m", "1", "src", "unwrap("]).lines(), vec!["3"]);
+    assert_eq!(fixture.run(&["-r", "--count-matches", "-m", "2", "src", "unwrap("]).lines(), vec!["4"]);
+}
+
+#[test]
+fn count_matches_prints_zero_when_nothing_matched() {
+    //DATA
+    let fixture = known_totals();
+    let output = fixture.run(&["-r", "--count-matches", "src", "nothing"]);
+
+    assert_eq!(output.lines(), vec!["0"]);
+    assert_eq!(output.code, 1);
+}
+
+#[test]
+fn multiline_matches_across_one_newline() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.rs", "use x;\n#[test]\nfn a() {}\n");
+    fixture.file("b.rs", "#[test]\n\nfn b() {}\n");
+
+    assert_eq!(fixture.run(&["-U", "-l", ".", "#[test]\nfn "]).lines(), vec!["./a.rs"]);
+    //the whole range is printed, both lines of it
+    assert_eq!(fixture.run(&["-U", "-n", "a.rs", "#[test]\nfn "]).lines(), vec!["2:#[test]", "3:fn a() {}"]);
+    assert_eq!(fixture.run(&["-U", "-C1", "a.rs", "#[test]\nfn "]).lines(), vec!["1-use x;", "2:#[test]", "3:fn a() {}"]);
+}
+
+#[test]
+fn multiline_matches_at_the_end_of_the_file() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("e.txt", "a\nb\nend");
+
+    assert_eq!(fixture.run(&["-U", "-n", "e.txt", "b\nend"]).stdout, "2:b\n3:end\n");
+    assert_eq!(fixture.run(&["-U", "--column", "-b", "e.txt", "b\nend"]).lines(), vec!["2:1:2:b", "3:1:4:end"]);
+}
+
+#[test]
+fn line_breaks_in_the_query_need_multiline() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.rs", "#[test]\nfn a() {}\n");
+    let output = fixture.run(&["a.rs", "#[test]\nfn "]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("The query contains a line break, which can only match across lines with --multiline."), "{}", output.stderr);
+}
+
+#[test]
+fn searches_are_case_sensitive_by_default() {
+    assert_eq!(three_cases().run(&["-l", ".", "Foo"]).lines(), vec!["./title.txt"]);
+}
+
+#[test]
+fn every_case_insensitive_flag_ignores_case() {
+    //DATA
+    let fixture = three_cases();
+
+    for flag in ["-i", "--ignore-case", "--case-insensitive"] {
+        assert_eq!(fixture.run(&["-l", flag, ".", "Foo"]).lines(), vec!["./lower.txt", "./title.txt", "./upper.txt"], "{}", flag);
+    }
+    assert_eq!(fixture.run(&["-l", "-i", "-s", ".", "Foo"]).lines(), vec!["./title.txt"]);
+}
+
+#[test]
+fn ignoring_case_folds_unicode() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("street.txt", "Die STRASSE ist lang\n");
+    fixture.file("cafe.txt", "LE CAFÉ\n");
+
+    //ß is shorter than the ss it folds to, and É is 2 bytes, so the columns come from the original line
+    assert_eq!(fixture.run(&["--column", "-o", "-i", "street.txt", "straße"]).lines(), vec!["1:5:STRASSE"]);
+    assert_eq!(fixture.run(&["--column", "-o", "-i", "cafe.txt", "café"]).lines(), vec!["1:4:CAFÉ"]);
+    assert_eq!(fixture.run(&["-l", "cafe.txt", "café"]).code, 1);
+}
+
+#[test]
+fn spaces_around_a_query_are_searched_for() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("spaced.txt", "a foo b\n");
+    fixture.file("tight.txt", "afoob\n");
+
+    assert_eq!(fixture.run(&["-l", ".", " foo "]).lines(), vec!["./spaced.txt"]);
+}
+
+#[test]
+fn blank_queries_are_errors_unless_allowed() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("tab.txt", "a\tb\n");
+    fixture.file("none.txt", "ab\n");
+
+    assert_eq!(fixture.run(&[".", "\t"]).code, 2);
+    assert_eq!(fixture.run(&["-l", "--allow-blank-query", ".", "\t"]).lines(), vec!["./tab.txt"]);
+}
+
+/**
+ * returns a fixture with a.txt, which has receive spelled a few ways, each one and two edits away from it
+ */
+fn misspelled() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "we recieve it\nwe receive it\nwe rcieve it\nrecive, receeve, récieve\n");
+    return fixture;
+}
+
+#[test]
+fn fuzzy_finds_text_a_few_edits_away() {
+    //DATA
+    let fixture = misspelled();
+
+    assert_eq!(fixture.run(&["-o", "--fuzzy", "1", "a.txt", "receive"]).lines(), vec!["1:recieve", "2:receive", "4:recive", "4:receeve"]);
+    assert_eq!(fixture.run(&["-o", "--fuzzy", "2", "a.txt", "receive"]).lines(), vec!["1:recieve", "2:receive", "3:rcieve", "4:recive", "4:receeve", "4:récieve"]);
+    //the color covers just what matched
+    assert_eq!(fixture.run(&["--fuzzy", "1", "--color", "always", "a.txt", "recieve"]).lines()[0], "1:we \u{1b}[1;31mrecieve\u{1b}[0m it");
+}
+
+#[test]
+fn fuzzy_0_is_exact_matching() {
+    //DATA
+    let fixture = misspelled();
+
+    assert_eq!(fixture.run(&["--fuzzy", "0", "a.txt", "recieve"]).stdout, fixture.run(&["a.txt", "recieve"]).stdout);
+    assert_eq!(fixture.run(&["--fuzzy", "0", "a.txt", "receive"]).lines(), vec!["2:we receive it"]);
+}
+
+#[test]
+fn fuzzy_rejects_queries_it_would_match_everything_with() {
+    //DATA
+    let fixture = misspelled();
+    let output = fixture.run(&["--fuzzy", "2", "a.txt", "abcd"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("The pattern 'abcd' is too short for --fuzzy 2, it has to be more than 4 characters long"), "{}", output.stderr);
+    assert_eq!(fixture.run(&["--fuzzy", "2", "a.txt", "abcde"]).code, 1);
+    assert!(fixture.run(&["--fuzzy", "1", "-E", "a.txt", "receive"]).stderr.contains("--fuzzy can't be used with --regex or --multiline"));
+}
+
+/**
+ * returns a fixture with a file with both "use tokio" and "block_on" in it, in each order, one with just the first, and one with neither
+ */
+fn tokio_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("both.rs", "use tokio\nx\nblock_on(f)\n");
+    fixture.file("reversed.rs", "block_on(compat)\nuse tokio::x\n");
+    fixture.file("one.rs", "use tokio\n");
+    fixture.file("neither.rs", "none\n");
+    return fixture;
+}
+
+#[test]
+fn all_of_needs_every_pattern_in_the_file() {
+    //DATA
+    let fixture = tokio_files();
+
+    assert_eq!(fixture.run(&["-l", "--all-of", "-e", "use tokio", "-e", "block_on", "."]).lines(), vec!["./both.rs", "./reversed.rs"]);
+    assert_eq!(fixture.run(&["-L", "--all-of", "-e", "use tokio", "-e", "block_on", "."]).lines(), vec!["./neither.rs", "./one.rs"]);
+    //without it, any of them is enough
+    assert_eq!(fixture.run(&["-l", "-e", "use tokio", "-e", "block_on", "."]).lines(), vec!["./both.rs", "./one.rs", "./reversed.rs"]);
+}
+
+#[test]
+fn all_of_prints_the_lines_that_match_any_of_them() {
+    //DATA
+    let fixture = tokio_files();
+
+    assert_eq!(fixture.run(&["--all-of", "-e", "use tokio", "-e", "block_on", "."]).lines(),
+        vec!["./both.rs:1:use tokio", "./both.rs:3:block_on(f)", "./reversed.rs:1:block_on(compat)", "./reversed.rs:2:use tokio::x"]);
+    assert_eq!(fixture.run(&["-c", "--all-of", "-e", "use tokio", "-e", "block_on", "."]).lines(), vec!["./both.rs: 2", "./reversed.rs: 2"]);
+}
+
+#[test]
+fn all_of_reads_to_the_end_for_a_late_pattern() {
+    //DATA
+    let fixture = Fixture::new();
+    //the first pattern is found right away, but the second isn't until the very end
+    fixture.file("late.txt", format!("first\n{}last\n", "filler\n".repeat(10_000)));
+    fixture.file("missing.txt", format!("first\n{}", "filler\n".repeat(10_000)));
+
+    assert_eq!(fixture.run(&["-l", "--all-of", "-e", "first", "-e", "last", "."]).lines(), vec!["./late.txt"]);
+    assert_eq!(fixture.run(&["-m", "1", "--all-of", "-e", "first", "-e", "last", "."]).lines(), vec!["./late.txt:1:first"]);
+}
+
+/**
+ * returns a fixture with files with "unsafe" in them, with "allow(unsafe_code)" before it, after it, in upper case, and not at all
+ */
+fn unsafe_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("before.rs", "#[allow(unsafe_code)]\nunsafe { x }\n");
+    fixture.file("after.rs", "unsafe { x }\n#[allow(unsafe_code)]\n");
+    fixture.file("upper.rs", "#[ALLOW(UNSAFE_CODE)]\nunsafe\n");
+    fixture.file("plain.rs", "unsafe { y }\n");
+    return fixture;
+}
+
+#[test]
+fn not_leaves_out_files_with_the_pattern_anywhere() {
+    //DATA
+    let fixture = unsafe_files();
+
+    //it doesn't matter whether it comes before or after the query
+    assert_eq!(fixture.run(&["-l", "--not", "#[allow(unsafe_code)]", ".", "unsafe"]).lines(), vec!["./plain.rs", "./upper.rs"]);
+    assert_eq!(fixture.run(&["-c", "--not", "allow", ".", "unsafe"]).lines(), vec!["./plain.rs: 1", "./upper.rs: 1"]);
+    assert_eq!(fixture.run(&["-L", "--not", "allow", ".", "unsafe"]).lines(), vec!["./after.rs", "./before.rs"]);
+}
+
+#[test]
+fn not_can_be_given_more_than_once() {
+    //DATA
+    let fixture = unsafe_files();
+
+    assert_eq!(fixture.run(&["--not", "allow(unsafe_code)", "--not", "{ y", ".", "unsafe"]).lines(), vec!["./upper.rs:2:unsafe"]);
+}
+
+#[test]
+fn not_follows_the_case_settings() {
+    //DATA
+    let fixture = unsafe_files();
+
+    assert_eq!(fixture.run(&["-l", "-i", "--not", "allow(unsafe_code)", ".", "unsafe"]).lines(), vec!["./plain.rs"]);
+    assert_eq!(fixture.run(&["-l", "--smart-case", "--not", "allow(unsafe_code)", ".", "unsafe"]).lines(), vec!["./plain.rs"]);
+}
+
+/**
+ * returns a fixture with src/config/main.rs, src/lib.rs (which has "config" in it), src/config.toml, and Config.md
+ */
+fn named_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("src/config/main.rs", "x\n");
+    fixture.file("src/lib.rs", "config\n");
+    fixture.file("src/config.toml", "x\n");
+    fixture.file("Config.md", "x\n");
+    return fixture;
+}
+
+#[test]
+fn filenames_matches_the_name_or_the_whole_path() {
+    //DATA
+    let fixture = named_files();
+
+    assert_eq!(fixture.run(&["--filenames", "-r", ".", "config"]).lines(), vec!["./src/config.toml"]);
+    assert_eq!(fixture.run(&["--filenames=path", "-r", ".", "config"]).lines(), vec!["./src/config/main.rs", "./src/config.toml"]);
+}
+
+#[test]
+fn filenames_still_uses_the_filters() {
+    //DATA
+    let fixture = named_files();
+
+    assert!(fixture.run(&["--filenames", "-r", "--include", "*.rs", ".", "config"]).lines().is_empty());
+    assert_eq!(fixture.run(&["--filenames=path", "-r", "--include", "*.rs", ".", "config"]).lines(), vec!["./src/config/main.rs"]);
+}
+
+#[test]
+fn filenames_follows_the_matching_options() {
+    //DATA
+    let fixture = named_files();
+
+    assert_eq!(fixture.run(&["--filenames", "-i", "-r", ".", "config"]).lines(), vec!["./Config.md", "./src/config.toml"]);
+    assert_eq!(fixture.run(&["--filenames", "-x", "-r", ".", "config"]).lines(), Vec::<&str>::new());
+    assert_eq!(fixture.run(&["--filenames", "-x", "-r", ".", "config.toml"]).lines(), vec!["./src/config.toml"]);
+    assert_eq!(fixture.run(&["--filenames", "-E", "-r", ".", r"^main\."]).lines(), vec!["./src/config/main.rs"]);
+}
+
+#[test]
+fn filenames_doesnt_open_the_files() {
+    //DATA
+    let fixture = Fixture::new();
+    //where it can't be read (not as root), it's still listed, without an error
+    fixture.unreadable_file("config.txt", "x\n");
+    let output = fixture.run(&["--filenames", ".", "config"]);
+
+    assert_eq!((output.code, output.lines()), (0, vec!["./config.txt"]));
+    assert_eq!(output.stderr, "");
+}
+
+/**
+ * returns a fixture with a file that matches "telemetry" by name only, by content only, by both, and by neither
+ */
+fn telemetry_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("telemetry.rs", "x\n");
+    fixture.file("main.rs", "use telemetry;\n");
+    fixture.file("telemetry.md", "telemetry\n");
+    fixture.file("other.rs", "x\n");
+    return fixture;
+}
+
+#[test]
+fn also_match_names_tags_the_files_that_matched_by_name() {
+    //DATA
+    let fixture = telemetry_files();
+
+    assert_eq!(fixture.run(&["-l", "--also-match-names", ".", "telemetry"]).lines(), vec!["./main.rs", "./telemetry.md [name]", "./telemetry.rs [name]"]);
+    assert_eq!(fixture.run(&["--also-match-names", ".", "telemetry"]).lines(), vec!["./main.rs:1:use telemetry;", "./telemetry.md [name]", "./telemetry.md:1:telemetry", "./telemetry.rs [name]"]);
+    assert_eq!(fixture.run(&["-c", "--also-match-names", ".", "telemetry"]).lines(), vec!["./main.rs: 1", "./telemetry.md: 1 [name]", "./telemetry.rs: 0 [name]"]);
+}
+
+#[test]
+fn also_match_names_lists_both_kinds_in_json() {
+    //DATA
+    let output = telemetry_files().run(&["-l", "--jsonl", "--also-match-names", ".", "telemetry"]);
+
+    assert_eq!(output.lines()[..3], [
+        "{\"type\":\"file\",\"path\":\"./main.rs\",\"matched_by\":[\"content\"]}",
+        "{\"type\":\"file\",\"path\":\"./telemetry.md\",\"matched_by\":[\"name\",\"content\"]}",
+        "{\"type\":\"file\",\"path\":\"./telemetry.rs\",\"matched_by\":[\"name\"]}",
+    ]);
+}
+
+#[test]
+fn also_match_names_reports_names_of_files_that_cant_be_read() {
+    //DATA
+    let fixture = Fixture::new();
+    //the name is checked before the file is opened (which works anyway as root)
+    fixture.unreadable_file("telemetry.rs", "x\n");
+    let output = fixture.run(&["-l", "--also-match-names", ".", "telemetry"]);
+
+    assert_eq!((output.code, output.lines()), (0, vec!["./telemetry.rs [name]"]));
+    assert!(!output.stderr.contains("couldn't be read"), "{}", output.stderr);
+}
+
+/**
+ * returns a fixture with docs/a.txt and docs/b.pdf, which have "needle" in them in lower case, and scripts in bin/ to preprocess them with:
+ * upper.sh prints the file in upper case, fail.sh fails with a message, and hang.sh starts a sleep that takes ten seconds
+ */
+#[cfg(unix)]
+fn preprocessed_docs() -> Fixture {
+    use std::os::unix::fs::PermissionsExt;
+
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("docs/a.txt", "a needle\nhay\n");
+    fixture.file("docs/b.pdf", "needle in a pdf\n");
+
+    for (name, script) in [("upper.sh", "tr a-z A-Z < \"$1\""), ("fail.sh", "echo \"can't convert $1\" >&2\nexit 3"), ("hang.sh", "sleep 10")] {
+        std::fs::set_permissions(fixture.file(&format!("bin/{}", name), format!("#!/bin/sh\n{}\n", script)), std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    return fixture;
+}
+
+#[cfg(unix)]
+#[test]
+fn pre_searches_what_the_preprocessor_prints() {
+    //DATA
+    let fixture = preprocessed_docs();
+
+    assert_eq!(fixture.run(&["--pre", "bin/upper.sh", "docs", "NEEDLE"]).lines(), vec!["docs/a.txt:1:A NEEDLE", "docs/b.pdf:1:NEEDLE IN A PDF"]);
+    //the file itself isn't searched
+    assert_eq!(fixture.run(&["--pre", "bin/upper.sh", "docs", "needle"]).code, 1);
+}
+
+#[cfg(unix)]
+#[test]
+fn pre_glob_picks_the_files_that_are_preprocessed() {
+    //DATA
+    let fixture = preprocessed_docs();
+
+    assert_eq!(fixture.run(&["--pre", "bin/upper.sh", "--pre-glob", "*.pdf", "docs", "NEEDLE"]).lines(), vec!["docs/b.pdf:1:NEEDLE IN A PDF"]);
+    assert_eq!(fixture.run(&["--pre", "bin/upper.sh", "--pre-glob", "*.pdf", "docs", "needle"]).lines(), vec!["docs/a.txt:1:a needle"]);
+    assert_eq!(fixture.run(&["--pre-glob", "*.pdf", "docs", "needle"]).code, 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn a_failing_preprocessor_is_an_error_for_the_file() {
+    //DATA
+    let fixture = preprocessed_docs();
+    let output = fixture.run(&["-v", "--pre", "bin/fail.sh", "--pre-glob", "*.pdf", "docs", "needle"]);
+
+    assert_eq!((output.code, output.lines()), (0, vec!["docs/a.txt:1:a needle"]));
+    assert!(output.stderr.contains("Error searching \"docs/b.pdf\": the preprocessor exited with code 3: can't convert docs/b.pdf\n"), "{}", output.stderr);
+    assert!(fixture.run(&["-v", "--pre", "bin/missing.sh", "docs", "needle"]).stderr.contains("Error searching \"docs/a.txt\": couldn't run the preprocessor 'bin/missing.sh': "));
+}
+
+#[cfg(unix)]
+#[test]
+fn a_hung_preprocessor_is_killed_after_the_timeout() {
+    //DATA
+    let fixture = preprocessed_docs();
+    let started = std::time::Instant::now();
+    let output = fixture.run(&["-v", "--pre", "bin/hang.sh", "--pre-timeout", "0.2", "--pre-glob", "*.pdf", "docs", "needle"]);
+
+    //the sleep it started is killed too, so it doesn't keep its output open for the ten seconds
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!((output.code, output.lines()), (0, vec!["docs/a.txt:1:a needle"]));
+    assert!(output.stderr.contains("Error searching \"docs/b.pdf\": the preprocessor was killed after 200ms, it took too long\n"), "{}", output.stderr);
+}
+
+/**
+ * returns a fixture with imports.rs, which has "use" at the start of some lines, after the indentation of another, and in the middle and at the end of others,
+ * and lines that end in ";", one with trailing whitespace after it, and an empty one
+ */
+fn imports() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("imports.rs", "use std::io;\n    use crate::a;\nfn f() { let x = 1; } // use\nreuse this;\n\nUSE IT;  \n");
+    return fixture;
+}
+
+#[test]
+fn anchor_start_only_matches_at_the_start_of_the_line() {
+    //DATA
+    let fixture = imports();
+
+    assert_eq!(fixture.run(&["imports.rs", "use"]).lines().len(), 4);
+    assert_eq!(fixture.run(&["--anchor", "start", "imports.rs", "use"]).lines(), vec!["1:use std::io;"]);
+    assert_eq!(fixture.run(&["--anchor", "start", "-i", "imports.rs", "use"]).lines(), vec!["1:use std::io;", "6:USE IT;  "]);
+    assert_eq!(fixture.run(&["--anchor", "start", "--anchor-ignore-whitespace", "imports.rs", "use"]).lines(), vec!["1:use std::io;", "2:    use crate::a;"]);
+}
+
+#[test]
+fn anchor_end_only_matches_at_the_end_of_the_line() {
+    //DATA
+    let fixture = imports();
+
+    //the line ending doesn't count, but whitespace before it does, unless it's ignored
+    assert_eq!(fixture.run(&["--anchor", "end", "imports.rs", ";"]).lines(), vec!["1:use std::io;", "2:    use crate::a;", "4:reuse this;"]);
+    assert_eq!(fixture.run(&["--anchor", "end", "--anchor-ignore-whitespace", "-i", "imports.rs", "it;"]).lines(), vec!["6:USE IT;  "]);
+    //"use" is on the third line twice, but only the last one is at the end
+    assert_eq!(fixture.run(&["--anchor", "end", "-o", "--column", "imports.rs", "use"]).lines(), vec!["3:26:use"]);
+}
+
+#[test]
+fn anchor_both_matches_whole_lines_and_words() {
+    //DATA
+    let fixture = imports();
+
+    assert_eq!(fixture.run(&["--anchor", "both", "imports.rs", "use std::io;"]).lines(), vec!["1:use std::io;"]);
+    assert_eq!(fixture.run(&["--anchor", "both", "imports.rs", "use"]).code, 1);
+    //with -w, "reuse" doesn't start with the word "use", even though it starts with use
+    assert_eq!(fixture.run(&["--anchor", "end", "-w", "-i", "imports.rs", "use"]).lines(), vec!["3:fn f() { let x = 1; } // use"]);
+    assert_eq!(fixture.run(&["--anchor", "start", "-w", "--anchor-ignore-whitespace", "imports.rs", "re"]).code, 1);
+}
+
+#[test]
+fn anchored_queries_dont_match_empty_lines() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("blank.txt", "x\n\n  \ny \n");
+
+    assert_eq!(fixture.run(&["--anchor", "start", "blank.txt", "x"]).lines(), vec!["1:x"]);
+    assert_eq!(fixture.run(&["--anchor", "end", "--allow-blank-query", "blank.txt", " "]).lines(), vec!["3:  ", "4:y "]);
+    assert_eq!(fixture.run(&["--anchor", "both", "--allow-blank-query", "blank.txt", "  "]).lines(), vec!["3:  "]);
+    //a line of whitespace is empty once it's ignored, so there's nothing for a query to be at the start of
+    assert_eq!(fixture.run(&["--anchor", "start", "--anchor-ignore-whitespace", "--allow-blank-query", "blank.txt", " "]).code, 1);
+}
+
+/**
+ * returns a fixture with cv.txt, which has résumé precomposed (with é as one character), decomposed (as e and a combining accent), and without accents,
+ * and Müller both ways on one line
+ */
+fn accented_cv() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("cv.txt", "résumé (precomposed)\nre\u{301}sume\u{301} (decomposed)\nresume (plain)\nMüller and Mu\u{308}ller\nRESUMES\n");
+    return fixture;
+}
+
+#[test]
+fn accents_have_to_match_without_ignore_accents() {
+    //DATA
+    let fixture = accented_cv();
+
+    //the two forms of résumé look the same, but they're different text
+    assert_eq!(fixture.run(&["cv.txt", "résumé"]).lines(), vec!["1:résumé (precomposed)"]);
+    assert_eq!(fixture.run(&["cv.txt", "re\u{301}sume\u{301}"]).lines(), vec!["2:re\u{301}sume\u{301} (decomposed)"]);
+}
+
+#[test]
+fn ignore_accents_matches_precomposed_and_decomposed_forms() {
+    //DATA
+    let fixture = accented_cv();
+    let every_form: Vec<&str> = vec!["1:résumé (precomposed)", "2:re\u{301}sume\u{301} (decomposed)", "3:resume (plain)"];
+
+    for query in ["resume", "résumé", "re\u{301}sume\u{301}", "résume\u{301}"] {
+        assert_eq!(fixture.run(&["--ignore-accents", "cv.txt", query]).lines(), every_form, "{}", query);
+    }
+}
+
+#[test]
+fn ignore_accents_prints_the_original_text() {
+    //DATA
+    let fixture = accented_cv();
+
+    assert_eq!(fixture.run(&["--ignore-accents", "-o", "cv.txt", "resume"]).lines(), vec!["1:résumé", "2:re\u{301}sume\u{301}", "3:resume"]);
+    //columns are in the characters of the original, so the second Müller, with its separate accent, is one character later than it looks
+    assert_eq!(fixture.run(&["--ignore-accents", "-i", "-o", "--column", "cv.txt", "MULLER"]).lines(), vec!["4:1:Müller", "4:12:Mu\u{308}ller"]);
+    assert_eq!(fixture.run(&["--ignore-accents", "--color", "always", "cv.txt", "resume"]).lines()[1], "2:\x1b[1;31mre\u{301}sume\u{301}\x1b[0m (decomposed)");
+}
+
+#[test]
+fn ignore_accents_goes_with_ignore_case_and_words() {
+    //DATA
+    let fixture = accented_cv();
+
+    assert_eq!(fixture.run(&["--ignore-accents", "-i", "-c", "cv.txt", "RESUME"]).lines(), vec!["4"]);
+    assert_eq!(fixture.run(&["--ignore-accents", "-i", "-w", "-c", "cv.txt", "RESUME"]).lines(), vec!["3"]);
+    assert!(fixture.run(&["--ignore-accents", "-E", "cv.txt", "r.sum"]).stderr.contains("--ignore-accents can't be used with --regex or --fuzzy, it only works for plain text."));
+}
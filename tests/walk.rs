@@ -0,0 +1,913 @@
+#![allow(clippy::needless_return, clippy::needless_late_init)]
+
+mod common;
+
+use common::Fixture;
+
+/**
+ * returns a fixture with a file of each extension (and one without any), all containing "needle"
+ */
+fn mixed_extensions() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    for name in ["a.rs", "b.TOML", "c.md", "noext", "e.txt"] {
+        fixture.file(name, "needle\n");
+    }
+    return fixture;
+}
+
+#[test]
+fn filter_keeps_listed_extensions_ignoring_case() {
+    //DATA
+    let fixture = mixed_extensions();
+
+    assert_eq!(fixture.run(&["-l", "--filter", "rs,toml", ".", "needle"]).sorted_lines(), vec!["./a.rs", "./b.TOML"]);
+}
+
+#[test]
+fn filter_matches_files_without_an_extension_only_when_asked() {
+    //DATA
+    let fixture = mixed_extensions();
+
+    assert_eq!(fixture.run(&["-l", "--filter", "noext", ".", "needle"]).sorted_lines(), vec!["./noext"]);
+    assert_eq!(fixture.run(&["-l", "--filter", ",.MD", ".", "needle"]).sorted_lines(), vec!["./c.md", "./noext"]);
+    assert_eq!(fixture.run(&["-l", "--filter", "md", ".", "needle"]).sorted_lines(), vec!["./c.md"]);
+}
+
+/**
+ * returns a fixture with a directory a, a directory b, and a file c.txt, each with a file containing "needle"
+ */
+fn two_roots() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("a/1.txt", "needle\n");
+    fixture.file("b/2.txt", "needle\n");
+    fixture.file("c.txt", "needle\n");
+    return fixture;
+}
+
+#[test]
+fn searches_every_path() {
+    //DATA
+    let fixture = two_roots();
+
+    assert_eq!(fixture.run(&["-p", "a", "-p", "b", "needle"]).lines(), vec!["a/1.txt:1:needle", "b/2.txt:1:needle"]);
+}
+
+#[test]
+fn searches_a_mix_of_files_and_directories() {
+    //DATA
+    let fixture = two_roots();
+
+    assert_eq!(fixture.run(&["-p", "c.txt", "-p", "a", "needle"]).lines(), vec!["a/1.txt:1:needle", "c.txt:1:needle"]);
+}
+
+#[test]
+fn names_the_path_that_does_not_exist() {
+    //DATA
+    let fixture = two_roots();
+    let output = fixture.run(&["-p", "a", "-p", "nope", "-p", "b", "needle"]);
+
+    assert_eq!(output.code, 2);
+    assert_eq!(output.stdout, "");
+    assert!(output.stderr.contains("Invalid path 'nope'."), "{}", output.stderr);
+}
+
+#[test]
+fn files_under_more_than_one_path_are_searched_once() {
+    //DATA
+    let fixture = two_roots();
+
+    assert_eq!(fixture.run(&["-p", "a", "-p", "a/1.txt", "-p", "a", "needle"]).lines(), vec!["a/1.txt:1:needle"]);
+}
+
+/**
+ * returns a fixture with a file at every depth, from f0.txt in the root, to d1/d2/d3/d4/f4.txt four directories down
+ */
+fn nested() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    for path in ["f0.txt", "d1/f1.txt", "d1/d2/f2.txt", "d1/d2/d3/f3.txt", "d1/d2/d3/d4/f4.txt"] {
+        fixture.file(path, "needle\n");
+    }
+    return fixture;
+}
+
+#[test]
+fn max_depth_limits_how_far_down_files_are_searched() {
+    //DATA
+    let fixture = nested();
+
+    assert_eq!(fixture.run(&["-l", "-r", "--max-depth", "0", ".", "needle"]).sorted_lines(), vec!["./f0.txt"]);
+    assert_eq!(fixture.run(&["-l", "-r", "--max-depth", "1", ".", "needle"]).sorted_lines(), vec!["./d1/f1.txt", "./f0.txt"]);
+    assert_eq!(fixture.run(&["-l", "-r", "--max-depth", "2", ".", "needle"]).sorted_lines(), vec!["./d1/d2/f2.txt", "./d1/f1.txt", "./f0.txt"]);
+    assert_eq!(fixture.run(&["-l", "-r", ".", "needle"]).sorted_lines(), vec!["./d1/d2/d3/d4/f4.txt", "./d1/d2/d3/f3.txt", "./d1/d2/f2.txt", "./d1/f1.txt", "./f0.txt"]);
+}
+
+#[test]
+fn max_depth_has_to_be_a_whole_number() {
+    //DATA
+    let fixture = nested();
+
+    for value in ["-1", "two", ""] {
+        //DATA
+        let output = fixture.run(&["-r", "--max-depth", value, ".", "needle"]);
+
+        assert_eq!(output.code, 2, "{}", value);
+        assert!(output.stderr.contains("expected a non-negative whole number"), "{}", output.stderr);
+    }
+}
+
+#[test]
+fn min_depth_skips_files_near_the_root() {
+    //DATA
+    let fixture = nested();
+
+    assert_eq!(fixture.run(&["-l", "-r", "--min-depth", "3", ".", "needle"]).sorted_lines(), vec!["./d1/d2/d3/d4/f4.txt", "./d1/d2/d3/f3.txt"]);
+    assert_eq!(fixture.run(&["-l", "-r", "--min-depth", "2", "--max-depth", "3", ".", "needle"]).sorted_lines(), vec!["./d1/d2/d3/f3.txt", "./d1/d2/f2.txt"]);
+}
+
+#[test]
+fn min_depth_above_0_never_matches_a_file_given_as_the_path() {
+    //DATA
+    let fixture = nested();
+
+    let output = fixture.run(&["-l", "--min-depth", "1", "f0.txt", "needle"]);
+    assert_eq!((output.code, output.stdout.as_str()), (1, ""));
+    assert_eq!(fixture.run(&["-l", "--min-depth", "0", "f0.txt", "needle"]).lines(), vec!["f0.txt"]);
+}
+
+#[test]
+fn min_depth_greater_than_max_depth_is_an_error() {
+    //DATA
+    let output = nested().run(&["-r", "--min-depth", "3", "--max-depth", "2", ".", "needle"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("--min-depth (3) can't be greater than --max-depth (2)."), "{}", output.stderr);
+}
+
+#[test]
+fn include_keeps_only_matching_file_names() {
+    //DATA
+    let fixture = mixed_extensions();
+    fixture.file("Makefile", "needle\n");
+    fixture.file("Makefile.am", "needle\n");
+    fixture.file("sub/nested.rs", "needle\n");
+
+    assert_eq!(fixture.run(&["-l", "-r", "--include", "*.rs", ".", "needle"]).sorted_lines(), vec!["./a.rs", "./sub/nested.rs"]);
+    //more than one --include matches any of them
+    assert_eq!(fixture.run(&["-l", "-r", "--include", "*.rs", "--include", "Makefile*", ".", "needle"]).sorted_lines(), vec!["./Makefile", "./Makefile.am", "./a.rs", "./sub/nested.rs"]);
+    assert_eq!(fixture.run(&["-l", "--include", "[bc].*", ".", "needle"]).sorted_lines(), vec!["./b.TOML", "./c.md"]);
+}
+
+#[test]
+fn exclude_removes_matching_file_names() {
+    //DATA
+    let fixture = mixed_extensions();
+    fixture.file("app.min.js", "needle\n");
+    fixture.file("Cargo.lock", "needle\n");
+
+    assert_eq!(fixture.run(&["-l", "--exclude", "*.min.js", "--exclude", "*.lock", "--exclude", "*.txt", ".", "needle"]).sorted_lines(), vec!["./a.rs", "./b.TOML", "./c.md", "./noext"]);
+}
+
+#[test]
+fn exclude_wins_over_include() {
+    //DATA
+    let fixture = mixed_extensions();
+    let output = fixture.run(&["-l", "-v", "--include", "*.rs", "--include", "*.md", "--exclude", "a.*", ".", "needle"]);
+
+    assert_eq!(output.sorted_lines(), vec!["./c.md"]);
+    //with --verbose, it says what excluded the file
+    assert!(output.stderr.contains("Skipping \"./a.rs\": excluded by 'a.*'"), "{}", output.stderr);
+}
+
+#[test]
+fn include_and_exclude_globs_cannot_have_separators() {
+    for option in ["--include", "--exclude"] {
+        //DATA
+        let output = mixed_extensions().run(&[option, "sub/*.rs", ".", "needle"]);
+
+        assert_eq!(output.code, 2);
+        assert!(output.stderr.contains(&format!("Invalid value 'sub/*.rs' for '{}', globs are matched against file names, so they can't contain directory separators.", option)), "{}", output.stderr);
+    }
+}
+
+#[test]
+fn exclude_dir_prunes_the_whole_subtree() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("src/main.rs", "needle\n");
+    fixture.file("target/debug/build.rs", "needle\n");
+    fixture.file("target/release/deep/more.rs", "needle\n");
+    fixture.file("node_modules/pkg/index.js", "needle\n");
+    fixture.file("src/target/inner.rs", "needle\n");
+
+    assert_eq!(fixture.run(&["-l", "-r", "--exclude-dir", "target", "--exclude-dir", "node_modules", ".", "needle"]).sorted_lines(), vec!["./src/main.rs"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn exclude_dir_never_reads_the_directory() {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("src/main.rs", "needle\n");
+    fixture.file("target/debug/build.rs", "needle\n");
+    fs::set_permissions(fixture.path("target"), fs::Permissions::from_mode(0o000)).unwrap();
+
+    //reading it would be an error (for anyone but root), which would be summed up on stderr, and change the exit code
+    let output = fixture.run(&["-l", "-r", "--exclude-dir", "target", ".", "needle"]);
+    assert_eq!((output.code, output.lines(), output.stderr.as_str()), (0, vec!["./src/main.rs"], ""));
+}
+
+/**
+ * returns a fixture with hidden files and directories, and normal ones, all containing "needle"
+ */
+fn with_hidden() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file(".hidden_file", "needle\n");
+    fixture.file(".hiddendir/inner.txt", "needle\n");
+    fixture.file("normal.txt", "needle\n");
+    fixture.file("dir/normal.txt", "needle\n");
+    fixture.file("dir/.env", "needle\n");
+    return fixture;
+}
+
+#[test]
+fn hidden_files_and_directories_are_skipped_by_default() {
+    assert_eq!(with_hidden().run(&["-l", "-r", ".", "needle"]).sorted_lines(), vec!["./dir/normal.txt", "./normal.txt"]);
+}
+
+#[test]
+fn hidden_includes_them() {
+    assert_eq!(with_hidden().run(&["-l", "-r", "--hidden", ".", "needle"]).sorted_lines(), vec!["./.hidden_file", "./.hiddendir/inner.txt", "./dir/.env", "./dir/normal.txt", "./normal.txt"]);
+}
+
+#[test]
+fn a_hidden_path_that_is_named_is_still_searched() {
+    //DATA
+    let fixture = with_hidden();
+
+    assert_eq!(fixture.run(&["-l", "-r", ".hiddendir", "needle"]).lines(), vec![".hiddendir/inner.txt"]);
+    assert_eq!(fixture.run(&["-l", ".hidden_file", "needle"]).lines(), vec![".hidden_file"]);
+}
+
+/**
+ * returns a fixture with two directories that link to each other, a -> b and b -> a, and a broken symlink
+ */
+#[cfg(unix)]
+fn symlink_cycle() -> Fixture {
+    use std::os::unix::fs::symlink;
+
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("a/1.txt", "needle\n");
+    fixture.file("b/2.txt", "needle\n");
+    symlink("../b", fixture.path("a/to_b")).unwrap();
+    symlink("../a", fixture.path("b/to_a")).unwrap();
+    symlink("nowhere", fixture.path("broken.txt")).unwrap();
+    return fixture;
+}
+
+#[cfg(unix)]
+#[test]
+fn symlinked_directories_are_not_followed_by_default() {
+    //DATA
+    let output = symlink_cycle().run(&["-l", "-r", ".", "needle"]);
+
+    assert_eq!((output.code, output.lines()), (0, vec!["./a/1.txt", "./b/2.txt"]));
+}
+
+#[cfg(unix)]
+#[test]
+fn follow_terminates_on_a_symlink_cycle() {
+    //DATA
+    let output = symlink_cycle().run(&["-l", "-r", "--follow", ".", "needle"]);
+
+    //each directory is only walked once, under the first path (in name order) that reaches it
+    assert_eq!((output.code, output.lines()), (0, vec!["./a/1.txt", "./a/to_b/2.txt"]));
+}
+
+#[cfg(unix)]
+#[test]
+fn broken_symlinks_are_only_mentioned_with_verbose() {
+    //DATA
+    let fixture = symlink_cycle();
+
+    assert_eq!(fixture.run(&["-l", "-r", "--follow", ".", "needle"]).stderr, "");
+    assert!(fixture.run(&["-l", "-r", "--follow", "-v", ".", "needle"]).stderr.contains("Skipping \"./broken.txt\": broken symlink"));
+}
+
+#[test]
+fn threads_give_the_same_output_as_one_thread() {
+    //DATA
+    let fixture = Fixture::new();
+
+    for file in 0..60 {
+        //every third file has no matches, the rest have a few, on different lines
+        let contents: String = (0..20).map(|line| if file % 3 != 0 && line % (file % 5 + 2) == 0 {format!("{} {} needle\n", file, line)} else {format!("{} {} hay\n", file, line)}).collect();
+        fixture.file(&format!("dir{}/file{}.txt", file % 4, file), contents);
+    }
+
+    for mode in [&["-n"][..], &["-l"], &["-c"], &["--count-matches"], &["-L"], &["--json"], &["-o", "--column"]] {
+        //DATA
+        let mut one_thread: Vec<&str> = vec!["-r", "--threads", "1"];
+        let mut eight_threads: Vec<&str> = vec!["-r", "--threads", "8"];
+        one_thread.extend(mode.iter().chain(&[".", "needle"]));
+        eight_threads.extend(mode.iter().chain(&[".", "needle"]));
+
+        let (one, eight) = (fixture.run(&one_thread), fixture.run(&eight_threads));
+        assert!(!one.stdout.is_empty(), "{:?}", mode);
+        //json has how long the search took in its stats, which is all that can differ
+        let without_elapsed = |output: &str| output.split("\"elapsed").next().unwrap().to_string();
+        assert_eq!((one.code, without_elapsed(&one.stdout)), (eight.code, without_elapsed(&eight.stdout)), "{:?}", mode);
+    }
+}
+
+#[test]
+fn files_lists_what_would_be_searched() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("src/main.rs", "");
+    fixture.file("src/lib.rs", "");
+    fixture.file("target/debug/out.rs", "");
+    fixture.file("README", "");
+    fixture.file(".hidden", "");
+
+    assert_eq!(fixture.run(&["--files", "-r", "--exclude-dir", "target", "."]).lines(), vec!["./README", "./src/lib.rs", "./src/main.rs"]);
+    assert_eq!(fixture.run(&["--files", "-r", "--include", "*.rs", "."]).lines(), vec!["./src/lib.rs", "./src/main.rs", "./target/debug/out.rs"]);
+}
+
+#[test]
+fn files_rejects_a_query() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "x\n");
+
+    for args in [&["--files", ".", "x"][..], &["--files", "-e", "x", "."]] {
+        //DATA
+        let output = fixture.run(args);
+
+        assert_eq!(output.code, 2);
+        assert!(output.stderr.contains("--files lists files without searching them, so it can't be given a query or patterns"), "{}", output.stderr);
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn one_file_system_stays_off_other_mounts() {
+    use std::fs;
+    use std::os::unix::fs::{symlink, MetadataExt};
+
+    //DATA
+    //a directory on /dev/shm (a tmpfs), linked to from the fixture, is on a different filesystem than the temp directory, when there is one
+    let mount = std::path::PathBuf::from(format!("/dev/shm/grep-directory-test-{}", std::process::id()));
+    let fixture = Fixture::new();
+    fixture.file("here.txt", "needle\n");
+    if fs::create_dir_all(&mount).is_err() || fs::metadata(&mount).unwrap().dev() == fs::metadata(fixture.path(".")).unwrap().dev() {
+        let _ = fs::remove_dir_all(&mount);
+        return;
+    }
+    fs::write(mount.join("there.txt"), "needle\n").unwrap();
+    symlink(&mount, fixture.path("shm")).unwrap();
+
+    let (staying, wandering) = (
+        fixture.run(&["-l", "-r", "--follow", "--one-file-system", "-v", ".", "needle"]),
+        fixture.run(&["-l", "-r", "--follow", ".", "needle"]),
+    );
+    fs::remove_dir_all(&mount).unwrap();
+
+    assert_eq!(staying.lines(), vec!["./here.txt"]);
+    assert!(staying.stderr.contains("Skipping \"./shm\": on a different filesystem"), "{}", staying.stderr);
+    assert_eq!(wandering.lines(), vec!["./here.txt", "./shm/there.txt"]);
+}
+
+/**
+ * returns a fixture with a file with "needle" in it for a few types, a.rs, b.html, c.css, d.js, e.toml, and f.proto
+ */
+fn typed_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    for name in ["a.rs", "b.html", "c.css", "d.js", "e.toml", "f.proto"] {
+        fixture.file(name, "needle\n");
+    }
+    return fixture;
+}
+
+#[test]
+fn types_include_their_globs() {
+    //DATA
+    let fixture = typed_files();
+
+    assert_eq!(fixture.run(&["-l", "-t", "rust", ".", "needle"]).lines(), vec!["./a.rs"]);
+    assert_eq!(fixture.run(&["-l", "-t", "web", ".", "needle"]).lines(), vec!["./b.html", "./c.css", "./d.js"]);
+    assert_eq!(fixture.run(&["-l", "-t", "rust", "-t", "toml", ".", "needle"]).lines(), vec!["./a.rs", "./e.toml"]);
+}
+
+#[test]
+fn types_exclude_their_globs() {
+    //DATA
+    let fixture = typed_files();
+
+    assert_eq!(fixture.run(&["-l", "-T", "rust", "-T", "web", ".", "needle"]).lines(), vec!["./e.toml", "./f.proto"]);
+}
+
+#[test]
+fn types_combine_with_include() {
+    //DATA
+    let fixture = typed_files();
+
+    assert_eq!(fixture.run(&["-l", "-t", "rust", "--include", "*.toml", ".", "needle"]).lines(), vec!["./a.rs", "./e.toml"]);
+    assert_eq!(fixture.run(&["-l", "-t", "web", "--exclude", "*.css", ".", "needle"]).lines(), vec!["./b.html", "./d.js"]);
+}
+
+#[test]
+fn unknown_types_are_errors() {
+    //DATA
+    let output = typed_files().run(&["-t", "rusty", ".", "needle"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Unknown file type 'rusty', did you mean 'rust'?"), "{}", output.stderr);
+}
+
+#[test]
+fn type_list_prints_the_table() {
+    //DATA
+    let output = Fixture::new().run(&["--type-list"]);
+
+    assert!(output.lines().contains(&"rust: *.rs"), "{}", output.stdout);
+    assert!(output.lines().contains(&"web: *.html, *.htm, *.css, *.js"), "{}", output.stdout);
+}
+
+#[test]
+fn type_add_defines_a_new_type() {
+    //DATA
+    let fixture = typed_files();
+
+    assert_eq!(fixture.run(&["-l", "--type-add", "proto:*.proto", "-t", "proto", ".", "needle"]).lines(), vec!["./f.proto"]);
+}
+
+#[test]
+fn type_add_extends_a_built_in_type() {
+    //DATA
+    let fixture = typed_files();
+    fixture.file("g.rs.in", "needle\n");
+
+    assert_eq!(fixture.run(&["-l", "--type-add", "rust:*.rs.in", "-t", "rust", ".", "needle"]).lines(), vec!["./a.rs", "./g.rs.in"]);
+}
+
+#[test]
+fn type_add_can_come_after_the_type_is_used() {
+    //DATA
+    let fixture = typed_files();
+
+    assert_eq!(fixture.run(&["-l", "-t", "proto", "--type-add", "proto:*.proto", ".", "needle"]).lines(), vec!["./f.proto"]);
+    assert_eq!(fixture.run(&["-l", "-T", "proto", "--type-add", "proto:*.proto", "-t", "web", ".", "needle"]).lines(), vec!["./b.html", "./c.css", "./d.js"]);
+}
+
+#[test]
+fn type_add_rejects_bad_definitions() {
+    //DATA
+    let fixture = typed_files();
+
+    for (definition, error) in [("proto", "expected NAME:GLOB"), ("proto:", "the glob is empty"), (":*.x", "the type name is empty")] {
+        //DATA
+        let output = fixture.run(&["--type-add", definition, ".", "needle"]);
+
+        assert_eq!(output.code, 2, "{}", definition);
+        assert!(output.stderr.contains(error), "{}", output.stderr);
+    }
+}
+
+/**
+ * returns a fixture laid out like a repository, with a .gitignore at the top (ignoring target/, *.log except keep.log, and /local.txt),
+ * another in vendor/ (ignoring v.rs), and "needle" in every file
+ */
+fn repository() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file(".gitignore", "# build output\ntarget/\n\n*.log\n!keep.log\n/local.txt\n");
+    fixture.file("vendor/.gitignore", "v.rs\n");
+    for name in ["src/main.rs", "src/target/x.rs", "target/debug/out.rs", "a.log", "keep.log", "local.txt", "src/local.txt", "vendor/v.rs", "vendor/w.rs"] {
+        fixture.file(name, "needle\n");
+    }
+    return fixture;
+}
+
+#[test]
+fn gitignores_are_respected_when_recursing() {
+    //DATA
+    let fixture = repository();
+
+    assert_eq!(fixture.run(&["-l", "-r", ".", "needle"]).lines(), vec!["./keep.log", "./src/local.txt", "./src/main.rs", "./vendor/w.rs"]);
+}
+
+#[test]
+fn gitignores_say_what_they_skipped_with_verbose() {
+    //DATA
+    let output = repository().run(&["-l", "-r", "-v", ".", "needle"]);
+
+    assert_eq!(output.stderr.lines().collect::<Vec<&str>>(), vec![
+        "Skipping \"./a.log\": ignored by '*.log' in \"./.gitignore\"",
+        "Skipping \"./local.txt\": ignored by '/local.txt' in \"./.gitignore\"",
+        "Skipping \"./src/target\": ignored by 'target/' in \"./.gitignore\"",
+        "Skipping \"./target\": ignored by 'target/' in \"./.gitignore\"",
+        "Skipping \"./vendor/v.rs\": ignored by 'v.rs' in \"./vendor/.gitignore\"",
+    ]);
+}
+
+#[test]
+fn no_ignore_searches_everything() {
+    //DATA
+    let fixture = repository();
+
+    assert_eq!(fixture.run(&["-l", "-r", "--no-ignore", ".", "needle"]).lines().len(), 9);
+}
+
+#[test]
+fn gitignores_dont_apply_without_recursing() {
+    //DATA
+    let fixture = repository();
+
+    assert_eq!(fixture.run(&["-l", ".", "needle"]).lines(), vec!["./a.log", "./keep.log", "./local.txt"]);
+}
+
+/**
+ * returns a fixture with keep.log, a.log, and a.txt, which all have "needle" in them, and two ignore files with conflicting rules:
+ * one.ignore ignores *.log, and two.ignore re-includes keep.log
+ */
+fn conflicting_ignore_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    fixture.file("one.ignore", "*.log\n");
+    fixture.file("two.ignore", "!keep.log\n");
+    for name in ["keep.log", "a.log", "a.txt"] {
+        fixture.file(name, "needle\n");
+    }
+    return fixture;
+}
+
+#[test]
+fn later_ignore_files_win() {
+    //DATA
+    let fixture = conflicting_ignore_files();
+
+    assert_eq!(fixture.run(&["-l", "-r", "--ignore-file", "one.ignore", "--ignore-file", "two.ignore", ".", "needle"]).lines(), vec!["./a.txt", "./keep.log"]);
+    assert_eq!(fixture.run(&["-l", "-r", "--ignore-file", "two.ignore", "--ignore-file", "one.ignore", ".", "needle"]).lines(), vec!["./a.txt"]);
+}
+
+#[test]
+fn ignore_files_win_over_gitignores() {
+    //DATA
+    let fixture = conflicting_ignore_files();
+    fixture.file(".gitignore", "!*.log\n*.txt\n");
+
+    assert_eq!(fixture.run(&["-l", "-r", "--ignore-file", "one.ignore", ".", "needle"]).lines(), Vec::<&str>::new());
+    assert_eq!(fixture.run(&["-l", "-r", ".", "needle"]).lines(), vec!["./a.log", "./keep.log"]);
+}
+
+#[test]
+fn ignore_files_are_relative_to_the_search_root() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("anchored.ignore", "/a.txt\n");
+    fixture.file("sub/a.txt", "needle\n");
+    fixture.file("sub/deeper/a.txt", "needle\n");
+
+    assert_eq!(fixture.run(&["-l", "-r", "--ignore-file", "anchored.ignore", "sub", "needle"]).lines(), vec!["sub/deeper/a.txt"]);
+}
+
+#[test]
+fn ignore_files_say_what_they_skipped_with_verbose() {
+    //DATA
+    let output = conflicting_ignore_files().run(&["-l", "-r", "-v", "--ignore-file", "one.ignore", ".", "needle"]);
+
+    assert!(output.stderr.contains("Skipping \"./a.log\": ignored by '*.log' in \"one.ignore\""), "{}", output.stderr);
+}
+
+#[test]
+fn missing_ignore_files_are_errors() {
+    //DATA
+    let output = conflicting_ignore_files().run(&["--ignore-file", "nope", ".", "needle"]);
+
+    assert_eq!(output.code, 2);
+    assert!(output.stderr.contains("Couldn't read ignore file 'nope'"), "{}", output.stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_directories_are_skipped() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a/before.txt", "needle\n");
+    fixture.file("z/after.txt", "needle\n");
+    fixture.file("locked/inside.txt", "needle\n");
+    let unreadable: bool = fixture.unreadable_dir("locked");
+    let output = fixture.run(&["-l", "-r", "-v", "--stats", ".", "needle"]);
+
+    //the walk carries on past it, without panicking
+    assert_eq!(output.code, 0, "{}", output.stderr);
+    assert!(!output.stderr.contains("panicked"), "{}", output.stderr);
+    assert!(output.lines().contains(&"./a/before.txt") && output.lines().contains(&"./z/after.txt"), "{}", output.stdout);
+    //(as root, the directory can still be read, so there's nothing to report)
+    if unreadable {
+        //DATA
+        let quiet = fixture.run(&["-l", "-r", ".", "needle"]);
+
+        assert!(output.stderr.contains("Error reading \"./locked\": Permission denied"), "{}", output.stderr);
+        assert!(output.stdout.contains("\tunreadable directories: 1\n"), "{}", output.stdout);
+        assert_eq!(quiet.stderr, "1 directory couldn't be read (permission denied: 1), rerun with --verbose for details\n");
+    }
+}
+
+/**
+ * runs grep-directory with args in fixture, failing the test if it takes longer than 10 seconds (like it would waiting on a fifo)
+ */
+#[cfg(unix)]
+fn run_promptly(fixture: &Fixture, args: &'static [&'static str]) -> common::Output {
+    //DATA
+    let root = fixture.root.clone();
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    std::thread::spawn(move || sender.send(common::run_in(&root, args, None)));
+    return receiver.recv_timeout(std::time::Duration::from_secs(10)).expect("grep-directory hung");
+}
+
+#[cfg(unix)]
+#[test]
+fn fifos_and_devices_are_skipped() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "needle\n");
+    assert!(std::process::Command::new("mkfifo").arg(fixture.path("pipe")).status().unwrap().success());
+    std::os::unix::fs::symlink("/dev/zero", fixture.path("zero")).unwrap();
+
+    for args in [&["-l", "-v", "--stats", ".", "needle"][..], &["-l", "-v", "-r", "--follow", "--stats", ".", "needle"]] {
+        //DATA
+        let output = run_promptly(&fixture, args);
+
+        assert_eq!((output.code, output.lines()[0]), (0, "./a.txt"), "{:?}", args);
+        assert!(output.stderr.contains("Skipping \"./pipe\": not a regular file"), "{}", output.stderr);
+        assert!(output.stderr.contains("Skipping \"./zero\": not a regular file"), "{}", output.stderr);
+        assert!(output.stdout.contains("\tfiles skipped: 2\n"), "{}", output.stdout);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn a_fifo_can_be_named_without_hanging() {
+    //DATA
+    let fixture = Fixture::new();
+    assert!(std::process::Command::new("mkfifo").arg(fixture.path("pipe")).status().unwrap().success());
+    let output = run_promptly(&fixture, &["-v", "pipe", "needle"]);
+
+    assert_eq!(output.code, 1);
+    assert_eq!(output.stderr, "Skipping \"pipe\": not a regular file\n");
+}
+
+/**
+ * returns a fixture with a.txt, which has "needle" in it, hard.txt, a hard link to it, and soft.txt, a symlink to it
+ */
+#[cfg(unix)]
+fn linked_file() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "needle\n");
+
+    std::fs::hard_link(fixture.path("a.txt"), fixture.path("hard.txt")).unwrap();
+    std::os::unix::fs::symlink("a.txt", fixture.path("soft.txt")).unwrap();
+    return fixture;
+}
+
+#[cfg(unix)]
+#[test]
+fn links_to_the_same_file_are_searched_once() {
+    //DATA
+    let fixture = linked_file();
+
+    assert_eq!(fixture.run(&["--follow", ".", "needle"]).lines(), vec!["./a.txt:1:needle"]);
+    assert_eq!(fixture.run(&["-r", "--follow", "--count-matches", ".", "needle"]).stdout, "1\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn no_dedupe_searches_every_link() {
+    //DATA
+    let fixture = linked_file();
+
+    assert_eq!(fixture.run(&["--follow", "--no-dedupe", ".", "needle"]).lines(), vec!["./a.txt:1:needle", "./hard.txt:1:needle", "./soft.txt:1:needle"]);
+}
+
+#[test]
+fn a_path_given_twice_is_searched_once() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("a.txt", "needle\n");
+
+    assert_eq!(fixture.run(&["-p", "a.txt", "-p", "./a.txt", "needle"]).lines(), vec!["a.txt:1:needle"]);
+    assert_eq!(fixture.run(&["--no-dedupe", "-p", "a.txt", "-p", "./a.txt", "needle"]).lines(), vec!["./a.txt:1:needle", "a.txt:1:needle"]);
+}
+
+#[test]
+fn an_empty_directory_says_theres_nothing_to_search() {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.dir("none");
+    fixture.dir("also_none");
+    let output = fixture.run(&["-r", "none", "needle"]);
+
+    assert_eq!((output.code, output.stdout.as_str()), (1, ""));
+    assert_eq!(output.stderr, "No files to search under 'none'\n");
+    assert_eq!(fixture.run(&["-p", "none", "-p", "also_none", "needle"]).stderr, "No files to search under 'none', 'also_none'\n");
+    assert_eq!(fixture.run(&["-q", "none", "needle"]).stderr, "");
+}
+
+/**
+ * returns a fixture with blank, a directory of empty files, a.txt and b.txt
+ */
+fn empty_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+    fixture.file("blank/a.txt", "");
+    fixture.file("blank/b.txt", "");
+    return fixture;
+}
+
+#[test]
+fn empty_files_are_skipped_and_counted() {
+    //DATA
+    let fixture = empty_files();
+    let output = fixture.run(&["--stats", "blank", "needle"]);
+
+    //there were files to search, they just can't match
+    assert_eq!(output.code, 1);
+    assert!(!output.stderr.contains("No files to search"), "{}", output.stderr);
+    assert!(output.stdout.contains("\tfiles searched: 0\n"), "{}", output.stdout);
+    assert!(output.stdout.contains("\tempty files: 2\n"), "{}", output.stdout);
+}
+
+#[test]
+fn empty_files_are_still_listed_when_nothing_is_searched_for() {
+    //DATA
+    let fixture = empty_files();
+
+    assert_eq!(fixture.run(&["--files", "blank"]).lines(), vec!["blank/a.txt", "blank/b.txt"]);
+    assert_eq!(fixture.run(&["-L", "blank", "needle"]).lines(), vec!["blank/a.txt", "blank/b.txt"]);
+}
+
+/**
+ * returns a fixture of files with "needle" in them, modified at different times: old.txt on 2020-06-01, edge.txt at the very start of 2024-01-01,
+ * recent.txt an hour ago, and future.txt ten days from now (like after a clock was wrong)
+ */
+fn modified_at_different_times() -> Fixture {
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    //DATA
+    let fixture = Fixture::new();
+    let now: SystemTime = SystemTime::now();
+
+    fixture.file_modified_at("old.txt", "needle\n", UNIX_EPOCH + Duration::from_secs(1_590_969_600));
+    fixture.file_modified_at("edge.txt", "needle\n", UNIX_EPOCH + Duration::from_secs(1_704_067_200));
+    fixture.file_modified_at("recent.txt", "needle\n", now - Duration::from_secs(60 * 60));
+    fixture.file_modified_at("future.txt", "needle\n", now + Duration::from_secs(10 * 24 * 60 * 60));
+    return fixture;
+}
+
+#[test]
+fn newer_than_takes_a_duration_or_a_date() {
+    //DATA
+    let fixture = modified_at_different_times();
+
+    assert_eq!(fixture.run(&["-l", "--newer-than", "2d", ".", "needle"]).lines(), vec!["./future.txt", "./recent.txt"]);
+    assert_eq!(fixture.run(&["-l", "--newer-than", "90m", ".", "needle"]).lines(), vec!["./future.txt", "./recent.txt"]);
+    assert_eq!(fixture.run(&["-l", "--newer-than", "30m", ".", "needle"]).lines(), vec!["./future.txt"]);
+    assert_eq!(fixture.run(&["-l", "--newer-than", "2021-01-01", ".", "needle"]).lines(), vec!["./edge.txt", "./future.txt", "./recent.txt"]);
+}
+
+#[test]
+fn older_than_takes_a_duration_or_a_date() {
+    //DATA
+    let fixture = modified_at_different_times();
+
+    assert_eq!(fixture.run(&["-l", "--older-than", "2024-01-01", ".", "needle"]).lines(), vec!["./old.txt"]);
+    assert_eq!(fixture.run(&["-l", "--older-than", "1w", ".", "needle"]).lines(), vec!["./edge.txt", "./old.txt"]);
+    assert_eq!(fixture.run(&["-l", "--older-than", "2024-01-01T00:00:01", ".", "needle"]).lines(), vec!["./edge.txt", "./old.txt"]);
+}
+
+#[test]
+fn the_window_includes_its_start_but_not_its_end() {
+    //DATA
+    let fixture = modified_at_different_times();
+
+    //edge.txt was modified exactly at the start of 2024-01-01
+    assert_eq!(fixture.run(&["-l", "--newer-than", "2024-01-01", "--older-than", "2025-01-01", ".", "needle"]).lines(), vec!["./edge.txt"]);
+    assert_eq!(fixture.run(&["-l", "--newer-than", "2020-01-01", "--older-than", "2024-01-01", ".", "needle"]).lines(), vec!["./old.txt"]);
+    assert!(fixture.run(&["--newer-than", "2024-01-01", "--older-than", "2024-01-01", ".", "needle"]).stderr.contains("--newer-than has to be before --older-than, or no file could be both."));
+}
+
+#[test]
+fn files_modified_in_the_future_are_newer_than_anything() {
+    //DATA
+    let fixture = modified_at_different_times();
+    let output = fixture.run(&["-l", "--newer-than", "0s", ".", "needle"]);
+
+    assert_eq!((output.code, output.lines()), (0, vec!["./future.txt"]));
+    //and not older than now
+    assert!(!fixture.run(&["-l", "--older-than", "0s", ".", "needle"]).lines().contains(&"./future.txt"));
+}
+
+#[test]
+fn files_outside_the_window_are_counted_in_stats() {
+    //DATA
+    let fixture = modified_at_different_times();
+    let output = fixture.run(&["-l", "-v", "--stats", "--newer-than", "1w", ".", "needle"]);
+
+    assert!(output.stdout.contains("\tfiles searched: 2\n") && output.stdout.contains("\tskipped by date: 2\n"), "{}", output.stdout);
+    assert!(output.stderr.contains("Skipping \"./old.txt\": not modified between --newer-than and --older-than\n"), "{}", output.stderr);
+    //they're skipped before they're read, so one that couldn't be read isn't an error
+    if fixture.unreadable_file("locked.txt", "needle\n") {
+        assert_eq!(fixture.run(&["-l", "--older-than", "2020-01-01", ".", "needle"]).stderr, "");
+    }
+}
+
+#[test]
+fn bad_times_are_rejected() {
+    //DATA
+    let fixture = modified_at_different_times();
+
+    //2023 wasn't a leap year
+    for value in ["5x", "2023-02-29", "2024-13-01", "2024-01-01T24:00", "yesterday"] {
+        assert!(fixture.run(&["--newer-than", value, ".", "needle"]).stderr.contains(&format!("Invalid value '{}' for '--newer-than', expected a duration like 30m, 12h, 2d, or 1w", value)), "{}", value);
+    }
+}
+
+/**
+ * returns a fixture of files with "needle" on their first line, of different sizes: stub.txt is 10 bytes, at1k.txt exactly 1K, over1k.txt 1025 bytes, and big.txt 2K
+ */
+fn sized_files() -> Fixture {
+    //DATA
+    let fixture = Fixture::new();
+
+    for (name, size) in [("stub.txt", 10), ("at1k.txt", 1024), ("over1k.txt", 1025), ("big.txt", 2048)] {
+        fixture.file(name, format!("needle\n{}\n", "x".repeat(size - 8)));
+    }
+    return fixture;
+}
+
+#[test]
+fn min_size_includes_files_exactly_at_the_limit() {
+    //DATA
+    let fixture = sized_files();
+
+    assert_eq!(fixture.run(&["-l", "--min-size", "1K", ".", "needle"]).lines(), vec!["./at1k.txt", "./big.txt", "./over1k.txt"]);
+    assert_eq!(fixture.run(&["-l", "--min-size", "1025", ".", "needle"]).lines(), vec!["./big.txt", "./over1k.txt"]);
+}
+
+#[test]
+fn max_size_includes_files_exactly_at_the_limit() {
+    //DATA
+    let fixture = sized_files();
+
+    assert_eq!(fixture.run(&["-l", "--max-size", "1K", ".", "needle"]).lines(), vec!["./at1k.txt", "./stub.txt"]);
+    assert_eq!(fixture.run(&["-l", "--max-size", "1023", ".", "needle"]).lines(), vec!["./stub.txt"]);
+}
+
+#[test]
+fn min_and_max_size_combine() {
+    //DATA
+    let fixture = sized_files();
+
+    assert_eq!(fixture.run(&["-l", "--min-size", "1K", "--max-size", "1025", ".", "needle"]).lines(), vec!["./at1k.txt", "./over1k.txt"]);
+    //a range of one size is fine, a range of none isn't
+    assert_eq!(fixture.run(&["-l", "--min-size", "1K", "--max-size", "1K", ".", "needle"]).lines(), vec!["./at1k.txt"]);
+    assert!(fixture.run(&["--min-size", "2K", "--max-size", "1K", ".", "needle"]).stderr.contains("--min-size (2048) can't be greater than --max-size (1024)."));
+    assert!(fixture.run(&["--max-size", "1X", ".", "needle"]).stderr.contains("Invalid value '1X' for '--max-size', expected a size like 512, 10K, 10M, or 1G."));
+}
+
+#[test]
+fn size_filters_apply_to_files_mode_too() {
+    //DATA
+    let fixture = sized_files();
+    let output = fixture.run(&["--files", "-v", "--stats", "--max-size", "10", "."]);
+
+    assert_eq!(fixture.run(&["--files", "--min-size", "1025", "."]).lines(), vec!["./big.txt", "./over1k.txt"]);
+    assert_eq!(output.lines()[0], "./stub.txt");
+    assert!(output.stdout.contains("\tskipped by size: 3\n"), "{}", output.stdout);
+    assert!(output.stderr.contains("Skipping \"./big.txt\": not between --min-size and --max-size\n"), "{}", output.stderr);
+}
+
+#[test]
+fn size_filters_dont_apply_to_stdin() {
+    assert_eq!(Fixture::new().run_with_stdin(&["--min-size", "5K", "-", "needle"], b"needle\n").lines(), vec!["1:needle"]);
+}